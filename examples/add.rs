@@ -1,25 +1,14 @@
 use onnx_helpers::prelude::*;
-use onnx_pb::{save_model, tensor_proto::DataType};
+use onnx_pb::save_model;
 
 fn main() {
-    let mut graph = builder::Graph::new("add");
+    let graph = onnx_graph! {
+        graph add(X: f32[1, 10], Y: f32[1, 10]) -> (Z: f32[1, 10]) {
+            Z = X + Y;
+        }
+    };
 
-    let x = graph
-        .input("X")
-        .typed(DataType::Float)
-        .dim(1)
-        .dim(10)
-        .node();
-    let y = graph
-        .input("Y")
-        .typed(DataType::Float)
-        .dim(1)
-        .dim(10)
-        .node();
-
-    let z = (x + y).with_name("Z");
-
-    let model = graph.outputs(z).model().build();
+    let model = graph.model().build();
 
     save_model("add.onnx", &model).unwrap();
 }
@@ -0,0 +1,445 @@
+//! Command-line tool for inspecting and transforming ONNX models.
+//!
+//! Models are read and written in binary protobuf format, or in textual
+//! syntax when a file name ends with `.txt`.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use clap::{Parser, Subcommand};
+use onnx_helpers::{checker, convert, dot, extract, fuse, optim, proto, stats, text, visit};
+use onnx_pb::{GraphProto, ModelProto, StringStringEntryProto, ValueInfoProto};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(
+    name = "onnx-helpers",
+    version,
+    about = "Inspects and transforms ONNX models."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints I/O signatures, opsets, operator histogram and parameter count.
+    Info {
+        /// Model file.
+        model: PathBuf,
+    },
+    /// Prints model in textual syntax.
+    Print {
+        /// Model file.
+        model: PathBuf,
+        /// Output file, defaults to standard output.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Renders model graph in Graphviz DOT format.
+    Dot {
+        /// Model file.
+        model: PathBuf,
+        /// Output file, defaults to standard output.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Prints FLOPs, parameter count and peak memory and checks them against budgets.
+    Stats {
+        /// Model file.
+        model: PathBuf,
+        /// Prints cost of every node.
+        #[arg(long)]
+        nodes: bool,
+        /// Fails when model has more parameters.
+        #[arg(long)]
+        max_parameters: Option<u64>,
+        /// Fails when model needs more floating point operations.
+        #[arg(long)]
+        max_flops: Option<u64>,
+        /// Fails when peak activation memory exceeds given number of bytes.
+        #[arg(long)]
+        max_peak_memory: Option<u64>,
+    },
+    /// Validates model structure.
+    Check {
+        /// Model file.
+        model: PathBuf,
+    },
+    /// Folds constants, eliminates common subexpressions and prunes unused nodes.
+    Optimize {
+        /// Model file.
+        model: PathBuf,
+        /// Output model file.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Disables constant folding.
+        #[arg(long)]
+        no_fold: bool,
+        /// Disables common subexpression elimination.
+        #[arg(long)]
+        no_cse: bool,
+        /// Disables removal of unused nodes.
+        #[arg(long)]
+        no_prune: bool,
+        /// Fuses common operator patterns, e.g. `MatMul` and `Add` into `Gemm`.
+        #[arg(long)]
+        fuse: bool,
+    },
+    /// Converts model to another standard operator set version.
+    Convert {
+        /// Model file.
+        model: PathBuf,
+        /// Output model file.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Target operator set version.
+        #[arg(long)]
+        opset: i64,
+    },
+    /// Renames graph inputs and outputs.
+    RenameIo {
+        /// Model file.
+        model: PathBuf,
+        /// Output model file.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Renamed value as `OLD=NEW`.
+        #[arg(
+            short,
+            long = "rename",
+            value_name = "OLD=NEW",
+            value_parser = parse_pair,
+            required = true
+        )]
+        renames: Vec<(String, String)>,
+    },
+    /// Sets model metadata.
+    SetMetadata {
+        /// Model file.
+        model: PathBuf,
+        /// Output model file.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Producer name.
+        #[arg(long)]
+        producer_name: Option<String>,
+        /// Producer version.
+        #[arg(long)]
+        producer_version: Option<String>,
+        /// Model domain.
+        #[arg(long)]
+        domain: Option<String>,
+        /// Model version.
+        #[arg(long)]
+        model_version: Option<i64>,
+        /// Model doc string.
+        #[arg(long)]
+        doc_string: Option<String>,
+        /// Metadata property as `KEY=VALUE`.
+        #[arg(long = "prop", value_name = "KEY=VALUE", value_parser = parse_pair)]
+        props: Vec<(String, String)>,
+        /// Removes metadata property.
+        #[arg(long = "remove-prop", value_name = "KEY")]
+        remove_props: Vec<String>,
+    },
+    /// Extracts subgraph between given values.
+    Extract {
+        /// Model file.
+        model: PathBuf,
+        /// Output model file.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Comma separated names of subgraph inputs.
+        #[arg(long, value_delimiter = ',', required = true)]
+        inputs: Vec<String>,
+        /// Comma separated names of subgraph outputs.
+        #[arg(long, value_delimiter = ',', required = true)]
+        outputs: Vec<String>,
+    },
+}
+
+fn main() {
+    if let Err(err) = run(Cli::parse().command) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Info { model } => info(&load(&model)?),
+        Command::Print { model, output } => write(output, text::print_model(&load(&model)?)),
+        Command::Dot { model, output } => write(output, dot::model_to_dot(&load(&model)?)),
+        Command::Stats {
+            model,
+            nodes,
+            max_parameters,
+            max_flops,
+            max_peak_memory,
+        } => {
+            let stats = stats::model_stats(&load(&model)?);
+            if nodes {
+                for node in stats.nodes.iter() {
+                    let flops = node.flops.map_or_else(|| "?".to_owned(), |f| f.to_string());
+                    println!("{:<32} {:<24} {}", node.name, node.op_type, flops);
+                }
+            }
+            println!("{}", stats);
+            let budgets = [
+                ("parameters", stats.parameters, max_parameters),
+                ("flops", stats.flops, max_flops),
+                ("peak memory", stats.peak_memory, max_peak_memory),
+            ];
+            let exceeded: Vec<String> = budgets
+                .iter()
+                .filter_map(|(name, value, max)| match max {
+                    Some(max) if value > max => {
+                        Some(format!("{} {} exceeds budget of {}", name, value, max))
+                    }
+                    _ => None,
+                })
+                .collect();
+            if exceeded.is_empty() {
+                Ok(())
+            } else {
+                Err(exceeded.join(", ").into())
+            }
+        }
+        Command::Check { model } => match checker::check_model(&load(&model)?) {
+            Ok(()) => {
+                println!("{}: ok", model.display());
+                Ok(())
+            }
+            Err(errors) => {
+                for error in errors.iter() {
+                    println!("{}", error);
+                }
+                Err(format!("{} errors found", errors.len()).into())
+            }
+        },
+        Command::Optimize {
+            model,
+            output,
+            no_fold,
+            no_cse,
+            no_prune,
+            fuse,
+        } => {
+            let mut model = load(&model)?;
+            let mut total = 0;
+            if fuse {
+                let fused = fuse::fuse_model(&mut model);
+                eprintln!("fused {} patterns", fused);
+            }
+            let graph = model.graph.get_or_insert_with(GraphProto::default);
+            loop {
+                let mut changed = 0;
+                if !no_fold {
+                    changed += optim::fold_constants(graph);
+                }
+                if !no_cse {
+                    changed += optim::eliminate_common_subexpressions(graph);
+                }
+                if !no_prune {
+                    changed += optim::prune(graph);
+                }
+                if changed == 0 {
+                    break;
+                }
+                total += changed;
+            }
+            eprintln!("removed {} nodes", total);
+            save(&output, &model)
+        }
+        Command::Convert {
+            model,
+            output,
+            opset,
+        } => save(&output, &convert::convert_version(&load(&model)?, opset)?),
+        Command::RenameIo {
+            model,
+            output,
+            renames,
+        } => {
+            let mut model = load(&model)?;
+            let graph = model.graph.get_or_insert_with(GraphProto::default);
+            for (from, to) in renames.iter() {
+                if !graph
+                    .input
+                    .iter()
+                    .chain(graph.output.iter())
+                    .any(|value| &value.name == from)
+                {
+                    return Err(format!("{:?} is not a graph input or output", from).into());
+                }
+                visit::rename_value(graph, from, to);
+            }
+            save(&output, &model)
+        }
+        Command::SetMetadata {
+            model,
+            output,
+            producer_name,
+            producer_version,
+            domain,
+            model_version,
+            doc_string,
+            props,
+            remove_props,
+        } => {
+            let mut model = load(&model)?;
+            if let Some(producer_name) = producer_name {
+                model.producer_name = producer_name;
+            }
+            if let Some(producer_version) = producer_version {
+                model.producer_version = producer_version;
+            }
+            if let Some(domain) = domain {
+                model.domain = domain;
+            }
+            if let Some(model_version) = model_version {
+                model.model_version = model_version;
+            }
+            if let Some(doc_string) = doc_string {
+                model.doc_string = doc_string;
+            }
+            model
+                .metadata_props
+                .retain(|prop| !remove_props.contains(&prop.key));
+            for (key, value) in props {
+                match model.metadata_props.iter_mut().find(|prop| prop.key == key) {
+                    Some(prop) => prop.value = value,
+                    None => model
+                        .metadata_props
+                        .push(StringStringEntryProto { key, value }),
+                }
+            }
+            save(&output, &model)
+        }
+        Command::Extract {
+            model,
+            output,
+            inputs,
+            outputs,
+        } => save(
+            &output,
+            &extract::extract_model(&load(&model)?, &inputs, &outputs)?,
+        ),
+    }
+}
+
+fn info(model: &ModelProto) -> Result<()> {
+    let graph = model.graph.clone().unwrap_or_default();
+    println!("ir_version: {}", model.ir_version);
+    if !model.producer_name.is_empty() {
+        println!(
+            "producer: {} {}",
+            model.producer_name, model.producer_version
+        );
+    }
+    for opset in model.opset_import.iter() {
+        println!("opset_import: {:?} {}", opset.domain, opset.version);
+    }
+    for prop in model.metadata_props.iter() {
+        println!("metadata: {} = {}", prop.key, prop.value);
+    }
+    let initializers: Vec<&str> = graph.initializer.iter().map(|t| t.name.as_str()).collect();
+    println!("inputs:");
+    for input in graph
+        .input
+        .iter()
+        .filter(|input| !initializers.contains(&input.name.as_str()))
+    {
+        println!("  {}", signature(input));
+    }
+    println!("outputs:");
+    for output in graph.output.iter() {
+        println!("  {}", signature(output));
+    }
+
+    let mut histogram = BTreeMap::new();
+    let count = count_ops(&graph, &mut histogram);
+    let mut histogram: Vec<(String, usize)> = histogram.into_iter().collect();
+    histogram.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    println!("nodes: {}", count);
+    for (op, count) in histogram {
+        println!("  {:<24} {}", op, count);
+    }
+    let parameters: i64 = graph
+        .initializer
+        .iter()
+        .map(|tensor| tensor.dims.iter().product::<i64>())
+        .sum();
+    println!(
+        "parameters: {} in {} initializers",
+        parameters,
+        graph.initializer.len()
+    );
+    Ok(())
+}
+
+/// Counts operators in a graph and its subgraphs.
+fn count_ops(graph: &GraphProto, histogram: &mut BTreeMap<String, usize>) -> usize {
+    let mut count = graph.node.len();
+    for node in graph.node.iter() {
+        let op = if node.domain.is_empty() {
+            node.op_type.clone()
+        } else {
+            format!("{}.{}", node.domain, node.op_type)
+        };
+        *histogram.entry(op).or_default() += 1;
+        for subgraph in visit::subgraphs(node) {
+            count += count_ops(subgraph, histogram);
+        }
+    }
+    count
+}
+
+fn signature(value: &ValueInfoProto) -> String {
+    match value.r#type.as_ref() {
+        Some(typ) => format!("{}: {}", value.name, text::print_type(typ)),
+        None => value.name.clone(),
+    }
+}
+
+fn parse_pair(text: &str) -> std::result::Result<(String, String), String> {
+    match text.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_owned(), value.to_owned())),
+        _ => Err(format!("expected KEY=VALUE, got {:?}", text)),
+    }
+}
+
+fn is_text(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "txt")
+}
+
+fn load(path: &Path) -> Result<ModelProto> {
+    if is_text(path) {
+        Ok(text::parse_model(&std::fs::read_to_string(path)?)?)
+    } else {
+        Ok(proto::open_model(path)?)
+    }
+}
+
+fn save(path: &Path, model: &ModelProto) -> Result<()> {
+    if is_text(path) {
+        Ok(std::fs::write(path, text::print_model(model))?)
+    } else {
+        Ok(proto::save_model(path, model)?)
+    }
+}
+
+fn write(path: Option<PathBuf>, contents: String) -> Result<()> {
+    match path {
+        Some(path) => Ok(std::fs::write(path, contents)?),
+        None => {
+            print!("{}", contents);
+            Ok(())
+        }
+    }
+}
@@ -3,7 +3,9 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use onnx_pb::{NodeProto, ValueInfoProto};
+use onnx_pb::{tensor_proto::DataType, type_proto, NodeProto, TensorProto, ValueInfoProto};
+
+use crate::schema;
 
 /// Bag marker.
 #[derive(Copy, Clone)]
@@ -43,6 +45,16 @@ impl Bag {
         }
     }
 
+    /// Records element type of a graph initializer.
+    pub fn initializer(&mut self, tensor: &TensorProto) {
+        if let Some(elem_type) = DataType::from_i32(tensor.data_type) {
+            self.inner
+                .borrow_mut()
+                .initializers
+                .push((tensor.name.clone(), elem_type));
+        }
+    }
+
     pub fn node(&mut self, node: Rc<RefCell<NodeProto>>) {
         self.inner.borrow_mut().nodes.push(node)
     }
@@ -50,6 +62,29 @@ impl Bag {
     pub fn rename(&mut self, name: &str, new_name: &str) {
         self.inner.borrow_mut().rename(name, new_name)
     }
+
+    /// Returns true if bag contains a node of given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.inner
+            .borrow()
+            .nodes
+            .iter()
+            .any(|node| node.try_borrow().is_ok_and(|node| node.name == name))
+    }
+
+    /// Returns true if a node of the bag takes given value as an input.
+    pub fn consumes(&self, value: &str) -> bool {
+        self.inner.borrow().nodes.iter().any(|node| {
+            node.try_borrow()
+                .is_ok_and(|node| node.input.iter().any(|input| input == value))
+        })
+    }
+
+    /// Returns element type of a value, resolving node outputs through
+    /// registered operator schemas.
+    pub fn elem_type(&self, value: &str) -> Option<DataType> {
+        self.inner.borrow().elem_type(value)
+    }
 }
 
 #[derive(Clone, Default)]
@@ -57,9 +92,36 @@ struct BagInner {
     nodes: Vec<Rc<RefCell<NodeProto>>>,
     inputs: Vec<ValueInfoProto>,
     outputs: Vec<ValueInfoProto>,
+    initializers: Vec<(String, DataType)>,
 }
 
 impl BagInner {
+    fn elem_type(&self, value: &str) -> Option<DataType> {
+        let info = self
+            .inputs
+            .iter()
+            .chain(self.outputs.iter())
+            .find(|info| info.name == value);
+        if let Some(info) = info {
+            if let Some(type_proto::Value::TensorType(tensor)) =
+                info.r#type.as_ref().and_then(|typ| typ.value.as_ref())
+            {
+                return DataType::from_i32(tensor.elem_type)
+                    .filter(|elem_type| *elem_type != DataType::Undefined);
+            }
+        }
+        if let Some((_, elem_type)) = self.initializers.iter().find(|(name, _)| name == value) {
+            return Some(*elem_type).filter(|elem_type| *elem_type != DataType::Undefined);
+        }
+        let (node, index) = self.nodes.iter().find_map(|node| {
+            let node = node.try_borrow().ok()?;
+            let index = node.output.iter().position(|output| output == value)?;
+            Some((node.clone(), index))
+        })?;
+        schema::lookup(&node.domain, &node.op_type, None)?
+            .output_elem_type(&node, index, |input| self.elem_type(input))
+    }
+
     pub fn rename(&mut self, name: &str, new_name: &str) {
         for node in self.nodes.iter_mut() {
             match node.try_borrow_mut() {
@@ -1,10 +1,16 @@
 //! Graph builder.
 
-use onnx_pb::{GraphProto, NodeProto, TensorProto, TypeProto, ValueInfoProto};
+use std::collections::HashSet;
+
+use onnx_pb::{
+    GraphProto, NodeProto, OperatorSetIdProto, StringStringEntryProto, TensorAnnotation,
+    TensorProto, TypeProto, ValueInfoProto,
+};
 
 use crate::{
     builder::{self, Bag, Marker},
     nodes::*,
+    opset,
 };
 
 /// Graph builder.
@@ -14,8 +20,11 @@ pub struct Graph {
     nodes: Vec<NodeProto>,
     inputs: Vec<ValueInfoProto>,
     outputs: Vec<ValueInfoProto>,
+    value_info: Vec<ValueInfoProto>,
+    annotations: Vec<TensorAnnotation>,
     initializers: Vec<TensorProto>,
     doc_string: Option<String>,
+    opset: Option<i64>,
     constants: i64,
     bag: Bag,
 }
@@ -44,6 +53,16 @@ impl Graph {
         self
     }
 
+    /// Sets version of the standard operator set.
+    ///
+    /// Operators are emitted in the form used by this version, e.g. `axes`
+    /// of `ReduceSum` become an input from version 13.
+    #[inline]
+    pub fn opset(mut self, version: i64) -> Self {
+        self.opset = Some(version);
+        self
+    }
+
     /// Creates constant node in a graph.
     #[inline]
     pub fn constant<S: Into<String>, T: Into<TensorProto>>(&mut self, name: S, tensor: T) -> Node {
@@ -67,6 +86,19 @@ impl Graph {
         node
     }
 
+    /// Creates a sequence construct node in a graph.
+    #[inline(always)]
+    pub fn sequence<I>(&mut self, inputs: I) -> Node
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let mut node: Node = ops::SequenceConstruct::new(inputs).into();
+        node.bag = Some(self.bag.clone());
+        self.bag.node(node.inner.clone());
+        node
+    }
+
     /// Inserts graph nodes.
     #[inline]
     pub fn nodes<T: Into<NodeProto>>(mut self, node: T) -> Self {
@@ -103,17 +135,67 @@ impl Graph {
         self
     }
 
+    /// Inserts type annotation of an intermediate value.
+    #[inline]
+    pub fn value_info<T: Into<ValueInfoProto>>(mut self, value: T) -> Self {
+        self.value_info.push(value.into());
+        self
+    }
+
+    /// Inserts quantization annotation naming scale and zero point tensors
+    /// of a value.
+    #[inline]
+    pub fn quantization_annotation<T, S, Z>(mut self, tensor: T, scale: S, zero_point: Z) -> Self
+    where
+        T: Into<String>,
+        S: Into<String>,
+        Z: Into<String>,
+    {
+        let entry = |key: &str, value: String| StringStringEntryProto {
+            key: key.to_owned(),
+            value,
+        };
+        self.annotations.push(TensorAnnotation {
+            tensor_name: tensor.into(),
+            quant_parameter_tensor_names: vec![
+                entry("SCALE_TENSOR", scale.into()),
+                entry("ZERO_POINT_TENSOR", zero_point.into()),
+            ],
+        });
+        self
+    }
+
     /// Inserts graph initializers.
     #[inline]
     pub fn initializer<T: Into<TensorProto>>(mut self, initializer: T) -> Self {
-        self.initializers.push(initializer.into());
+        let initializer = initializer.into();
+        self.bag.initializer(&initializer);
+        self.initializers.push(initializer);
         self
     }
 
+    /// Inserts named graph initializer, returning node referring to it.
+    ///
+    /// Initializers are inserted once per name, so modules applied more than
+    /// once share their weights.
+    pub fn parameter<S: Into<String>, T: Into<TensorProto>>(&mut self, name: S, tensor: T) -> Node {
+        let name = name.into();
+        if !self.initializers.iter().any(|tensor| tensor.name == name) {
+            let mut tensor = tensor.into();
+            tensor.name = name.clone();
+            self.bag.initializer(&tensor);
+            self.initializers.push(tensor);
+        }
+        let mut node = builder::Node::named(name).build();
+        node.bag = Some(self.bag.clone());
+        node
+    }
+
     /// Creates graph node builder.
     #[inline]
     pub fn node<T: Into<String>>(&mut self, name: T) -> builder::Node {
         let mut node = builder::Node::default().name(name);
+        node.opset = self.opset;
         node.bag = Some(self.bag.clone());
         node
     }
@@ -136,44 +218,118 @@ impl Graph {
         value
     }
 
+    /// Returns initializers inserted so far.
+    pub(crate) fn initializers_mut(&mut self) -> &mut Vec<TensorProto> {
+        &mut self.initializers
+    }
+
+    /// Returns inputs declared so far.
+    pub(crate) fn declared_inputs(&self) -> Vec<ValueInfoProto> {
+        let mut inputs = self.inputs.clone();
+        inputs.extend(self.bag.inputs());
+        inputs
+    }
+
+    /// Returns node name starting with `name` not used in the graph yet.
+    pub(crate) fn unique_name(&self, name: String) -> String {
+        if !self.bag.contains(&name) {
+            return name;
+        }
+        (1..)
+            .map(|index| format!("{}_{}", name, index))
+            .find(|name| !self.bag.contains(name))
+            .unwrap()
+    }
+
     /// Builds a model builder from graph.
     #[inline]
     pub fn model(self) -> builder::Model {
-        builder::Model::new(self.build())
+        let version = self.opset.unwrap_or(opset::DEFAULT_VERSION);
+        builder::Model::new(self.build()).opset_import(OperatorSetIdProto {
+            version,
+            ..OperatorSetIdProto::default()
+        })
+    }
+
+    /// Renders the graph to Graphviz DOT.
+    #[inline]
+    pub fn to_dot(&self) -> String {
+        crate::dot::graph_to_dot(&self.clone().build())
     }
 
     /// Builds the graph.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an operator is not available in the operator set version.
+    /// See [`try_build`](#method.try_build).
     #[inline]
     pub fn build(self) -> GraphProto {
+        self.try_build().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Builds the graph, failing if an operator is not available in the
+    /// operator set version.
+    pub fn try_build(self) -> Result<GraphProto, opset::Error> {
+        let version = self.opset.unwrap_or(opset::DEFAULT_VERSION);
+        if !(1..=opset::MAX_VERSION).contains(&version) {
+            return Err(opset::Error::UnknownVersion(version));
+        }
         let mut nodes = self.nodes;
-        nodes.extend(self.bag.nodes().into_iter().map(Into::into));
+        nodes.extend(self.bag.nodes());
         nodes.dedup_by(|a, b| a.name == b.name);
         sort_nodes(&mut nodes);
         let mut inputs = self.inputs;
-        inputs.extend(self.bag.inputs().into_iter().map(Into::into));
+        inputs.extend(self.bag.inputs());
         inputs.dedup_by(|a, b| a.name == b.name);
         let mut outputs = self.outputs;
-        outputs.extend(self.bag.outputs().into_iter().map(Into::into));
+        outputs.extend(self.bag.outputs());
         outputs.dedup_by(|a, b| a.name == b.name);
-        GraphProto {
+        let mut initializers = self.initializers;
+        let bag = &self.bag;
+        let mut names: HashSet<String> = inputs
+            .iter()
+            .chain(outputs.iter())
+            .chain(self.value_info.iter())
+            .map(|value| value.name.clone())
+            .chain(initializers.iter().map(|tensor| tensor.name.clone()))
+            .chain(
+                nodes
+                    .iter()
+                    .flat_map(|node| node.input.iter().chain(node.output.iter()).cloned()),
+            )
+            .collect();
+        for node in nodes
+            .iter_mut()
+            .filter(|node| opset::is_default_domain(node))
+        {
+            opset::op_version(&node.op_type, version)?;
+            let elem_type = node.input.first().and_then(|input| bag.elem_type(input));
+            initializers.extend(opset::attributes_to_inputs(
+                node, version, elem_type, &mut names,
+            ));
+        }
+        Ok(GraphProto {
             name: self.name,
             node: nodes,
             input: inputs,
             output: outputs,
             doc_string: self.doc_string.unwrap_or_default(),
-            initializer: self.initializers,
+            initializer: initializers,
+            value_info: self.value_info,
+            quantization_annotation: self.annotations,
             ..GraphProto::default()
-        }
+        })
     }
 }
 
-impl Into<GraphProto> for Graph {
-    fn into(self) -> GraphProto {
-        self.build()
+impl From<Graph> for GraphProto {
+    fn from(graph: Graph) -> GraphProto {
+        graph.build()
     }
 }
 
-fn sort_nodes(nodes: &mut Vec<NodeProto>) {
+fn sort_nodes(nodes: &mut [NodeProto]) {
     use std::collections::HashMap;
     use std::iter::FromIterator;
 
@@ -201,3 +357,57 @@ fn sort_nodes(nodes: &mut Vec<NodeProto>) {
         a.partial_cmp(b).unwrap()
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use onnx_pb::tensor_proto::DataType;
+
+    #[test]
+    fn opset_forms() {
+        let build = |version: i64| {
+            let mut graph = Graph::new("g").opset(version);
+            let x = graph.input("X").typed(DataType::Float).node();
+            graph.outputs(x.sum(1, true).clip(0.0, 6.0)).build()
+        };
+
+        let graph = build(11);
+        assert_eq!(graph.node[0].op_type, "ReduceSum");
+        assert_eq!(graph.node[0].input.len(), 1);
+        assert_eq!(graph.node[1].input.len(), 3);
+        assert_eq!(graph.initializer.len(), 2);
+
+        let graph = build(13);
+        assert_eq!(graph.node[0].input.len(), 2);
+        assert!(graph.node[0].attribute.iter().all(|a| a.name != "axes"));
+        let axes = &graph.initializer[0];
+        assert_eq!(graph.node[0].input[1], axes.name);
+        assert_eq!(axes.int64_data, vec![1]);
+
+        let graph = build(9);
+        assert_eq!(graph.node[1].input.len(), 1);
+        assert_eq!(graph.node[1].attribute.len(), 2);
+        assert!(graph.initializer.is_empty());
+
+        let model = Graph::new("g").opset(13).model().build();
+        assert_eq!(model.opset_import.len(), 1);
+        assert_eq!(model.opset_import[0].version, 13);
+    }
+
+    #[test]
+    fn unavailable_op() {
+        let mut graph = Graph::new("g").opset(9);
+        let n = graph.input("N").typed(DataType::Int64).node();
+        let range = graph
+            .node("range")
+            .op("Range")
+            .inputs(vec![&n, &n, &n])
+            .build();
+        let err = graph.outputs(range).try_build().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Range is not available in opset 9, it was introduced in opset 11"
+        );
+    }
+}
@@ -2,10 +2,12 @@ mod bag;
 mod graph;
 mod model;
 mod node;
+mod tensor;
 mod value;
 
 pub(crate) use self::bag::*;
 pub use self::graph::*;
 pub use self::model::*;
 pub use self::node::*;
+pub use self::tensor::*;
 pub use self::value::*;
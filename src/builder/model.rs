@@ -1,13 +1,24 @@
 //! Model builder.
 
-use onnx_pb::{GraphProto, ModelProto, OperatorSetIdProto, StringStringEntryProto, Version};
+use onnx_pb::{
+    tensor_shape_proto::{dimension, Dimension},
+    type_proto, GraphProto, ModelProto, OperatorSetIdProto, StringStringEntryProto, ValueInfoProto,
+    Version,
+};
 
-const DEFAULT_OPSET_ID_VERSION: i64 = 11;
+use crate::{
+    opset, schema,
+    shape::{self, Dim},
+};
 
 /// Model builder.
+///
+/// Training information and model local functions are not part of the
+/// bundled ONNX IR version 6 and can not be set.
 #[derive(Default, Clone)]
 pub struct Model {
     graph: GraphProto,
+    ir_version: Option<i64>,
     domain: Option<String>,
     model_version: Option<i64>,
     producer_name: Option<String>,
@@ -27,28 +38,28 @@ impl Model {
         }
     }
 
-    /// Sets model doc_string.
+    /// Sets model domain, e.g. `ai.onnx.ml`.
     #[inline]
     pub fn domain<S: Into<String>>(mut self, domain: S) -> Self {
         self.domain = Some(domain.into());
         self
     }
 
-    /// Sets model doc_string.
+    /// Sets version of the model itself.
     #[inline]
     pub fn model_version(mut self, model_version: i64) -> Self {
         self.model_version = Some(model_version);
         self
     }
 
-    /// Sets model doc_string.
+    /// Sets name of the tool producing the model.
     #[inline]
     pub fn producer_name<S: Into<String>>(mut self, producer_name: S) -> Self {
         self.producer_name = Some(producer_name.into());
         self
     }
 
-    /// Sets model doc_string.
+    /// Sets version of the tool producing the model.
     #[inline]
     pub fn producer_version<S: Into<String>>(mut self, producer_version: S) -> Self {
         self.producer_version = Some(producer_version.into());
@@ -62,6 +73,13 @@ impl Model {
         self
     }
 
+    /// Sets IR version, the bundled version by default.
+    #[inline]
+    pub fn ir_version(mut self, ir_version: i64) -> Self {
+        self.ir_version = Some(ir_version);
+        self
+    }
+
     /// Inserts model metadata.
     #[inline]
     pub fn metadata<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
@@ -69,6 +87,22 @@ impl Model {
         self
     }
 
+    /// Inserts model metadata entries.
+    #[inline]
+    pub fn metadata_props<I, K, V>(mut self, entries: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.metadata.extend(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into())),
+        );
+        self
+    }
+
     /// Inserts operator set import.
     #[inline]
     pub fn opset_import(mut self, opset: OperatorSetIdProto) -> Self {
@@ -80,25 +114,93 @@ impl Model {
         self
     }
 
+    /// Makes the leading dimension of graph inputs and outputs symbolic `N`.
+    ///
+    /// Inputs backed by initializers are left unchanged. Entries of
+    /// `value_info` computed from the rewritten inputs are made symbolic as
+    /// well. Entries whose shape can not be inferred and whose leading
+    /// dimension was one of the replaced dimensions have it cleared.
+    pub fn make_batch_dynamic(mut self) -> Self {
+        let batch = dimension::Value::DimParam("N".to_owned());
+        let initializers: Vec<String> = self
+            .graph
+            .initializer
+            .iter()
+            .map(|tensor| tensor.name.clone())
+            .collect();
+        let mut replaced = Vec::new();
+        for value in self
+            .graph
+            .input
+            .iter_mut()
+            .filter(|value| !initializers.contains(&value.name))
+            .chain(self.graph.output.iter_mut())
+        {
+            if let Some(dim) = leading_dim(value) {
+                if let Some(old) = dim.value.replace(batch.clone()) {
+                    replaced.push(old);
+                }
+            }
+        }
+        let shapes = {
+            let graph = GraphProto {
+                value_info: Vec::new(),
+                output: Vec::new(),
+                ..self.graph.clone()
+            };
+            shape::infer_shapes(&graph, &shape::integer_constants(&graph))
+        };
+        for value in self.graph.value_info.iter_mut() {
+            let inferred = shapes.get(&value.name).map(|dims| dims.first());
+            if let Some(dim) = leading_dim(value) {
+                match inferred {
+                    Some(Some(Dim::Param(param))) if param == "N" => {
+                        dim.value = Some(batch.clone())
+                    }
+                    Some(_) => {}
+                    None if dim.value.as_ref().is_some_and(|old| replaced.contains(old)) => {
+                        dim.value = None
+                    }
+                    None => {}
+                }
+            }
+        }
+        self
+    }
+
     /// Builds the model.
+    ///
+    /// Operator domains used by the graph which are not imported are
+    /// imported in their latest registered [`schema`](../schema/index.html)
+    /// version.
     #[inline]
     pub fn build(self) -> ModelProto {
-        let opset_import = self.opset_imports.unwrap_or_else(|| {
+        let mut opset_import = self.opset_imports.unwrap_or_else(|| {
             vec![OperatorSetIdProto {
-                version: DEFAULT_OPSET_ID_VERSION,
+                version: opset::DEFAULT_VERSION,
                 ..OperatorSetIdProto::default()
             }]
         });
+        let mut domains = Vec::new();
+        schema::used_domains(&self.graph, &mut domains);
+        for domain in domains {
+            if opset_import
+                .iter()
+                .all(|opset| schema::normalize_domain(&opset.domain) != domain)
+            {
+                opset_import.push(OperatorSetIdProto {
+                    version: schema::domain_version(&domain).unwrap_or(1),
+                    domain,
+                });
+            }
+        }
         let metadata_props = self
             .metadata
             .into_iter()
-            .map(|(k, v)| StringStringEntryProto {
-                key: k.into(),
-                value: v.into(),
-            })
+            .map(|(key, value)| StringStringEntryProto { key, value })
             .collect();
         ModelProto {
-            ir_version: Version::IrVersion as i64,
+            ir_version: self.ir_version.unwrap_or(Version::IrVersion as i64),
             graph: Some(self.graph),
             domain: self.domain.unwrap_or_default(),
             doc_string: self.doc_string.unwrap_or_default(),
@@ -107,13 +209,149 @@ impl Model {
             model_version: self.model_version.unwrap_or_default(),
             opset_import,
             metadata_props,
-            ..ModelProto::default()
         }
     }
 }
 
-impl Into<ModelProto> for Model {
-    fn into(self) -> ModelProto {
-        self.build()
+impl From<Model> for ModelProto {
+    fn from(model: Model) -> ModelProto {
+        model.build()
+    }
+}
+
+/// Returns the first dimension of a ranked tensor value.
+fn leading_dim(value: &mut ValueInfoProto) -> Option<&mut Dimension> {
+    match value.r#type.as_mut()?.value.as_mut()? {
+        type_proto::Value::TensorType(tensor) => tensor.shape.as_mut()?.dim.first_mut(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use onnx_pb::tensor_proto::DataType;
+
+    use super::*;
+    use crate::builder;
+
+    fn leading(value: &ValueInfoProto) -> Option<dimension::Value> {
+        leading_dim(&mut value.clone()).and_then(|dim| dim.value.clone())
+    }
+
+    #[test]
+    fn batch_dynamic() {
+        let mut graph = builder::Graph::new("batch");
+        let x = graph.input("x").typed(DataType::Float).dim(1).dim(3).node();
+        let w = graph.input("w").typed(DataType::Float).dim(3).node();
+        let y = graph.node("y").op("Mul").inputs(vec![&x, &w]).build();
+        let z = graph.node("z").op("Relu").input(&y).build();
+        let mut graph = graph
+            .outputs(builder::Value::new(&z).typed(DataType::Float).dim(1).dim(3))
+            .initializer(
+                builder::Tensor::from_slice(&[3], &[1f32, 2., 3.])
+                    .unwrap()
+                    .name("w"),
+            )
+            .build();
+        graph.value_info = vec![
+            builder::Value::new(&y).dim(1).dim(3).build(),
+            builder::Value::new("s").dim(3).build(),
+        ];
+        let model = builder::Model::new(graph).make_batch_dynamic().build();
+        let graph = model.graph.unwrap();
+        let symbolic = Some(dimension::Value::DimParam("N".to_owned()));
+        assert_eq!(leading(&graph.input[0]), symbolic);
+        assert_eq!(
+            leading(&graph.input[1]),
+            Some(dimension::Value::DimValue(3))
+        );
+        assert_eq!(leading(&graph.output[0]), symbolic);
+        assert_eq!(leading(&graph.value_info[0]), symbolic);
+        assert_eq!(
+            leading(&graph.value_info[1]),
+            Some(dimension::Value::DimValue(3))
+        );
+    }
+
+    #[test]
+    fn batch_dynamic_unrelated_values() {
+        let mut graph = crate::text::parse_graph(
+            r#"
+            g (float[1,3] x) => (float[1,3] z) <float[3] w = {1.0, 2.0, 3.0}> {
+                u = Unsqueeze <axes = [0]> (w)
+                y = Mul (x, u)
+                c = com.example.Custom (y)
+                z = Add (c, u)
+            }
+            "#,
+        )
+        .unwrap();
+        graph.value_info = vec![
+            builder::Value::new("u").dim(1).dim(3).build(),
+            builder::Value::new("y").dim(1).dim(3).build(),
+            builder::Value::new("c").dim(1).dim(3).build(),
+        ];
+        let model = builder::Model::new(graph).make_batch_dynamic().build();
+        let graph = model.graph.unwrap();
+        let leading: Vec<Option<dimension::Value>> = graph.value_info.iter().map(leading).collect();
+        assert_eq!(
+            leading,
+            vec![
+                Some(dimension::Value::DimValue(1)),
+                Some(dimension::Value::DimParam("N".to_owned())),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn metadata() {
+        let mut graph = builder::Graph::new("metadata");
+        let x = graph
+            .input("x")
+            .typed(DataType::Float)
+            .dim(3)
+            .doc_string("features")
+            .node();
+        let y = x.relu();
+        let model = graph
+            .value_info(
+                builder::Value::new(&y)
+                    .typed(DataType::Float)
+                    .dim(3)
+                    .doc_string("activations"),
+            )
+            .quantization_annotation(&y, "y_scale", "y_zero_point")
+            .outputs_typed(y.sqrt(), DataType::Float)
+            .model()
+            .ir_version(6)
+            .domain("com.example")
+            .producer_name("onnx-helpers")
+            .metadata("author", "me")
+            .metadata_props(vec![("license", "MIT"), ("task", "demo")])
+            .build();
+        assert_eq!(model.ir_version, 6);
+        assert_eq!(model.domain, "com.example");
+        let keys: Vec<&str> = model
+            .metadata_props
+            .iter()
+            .map(|entry| entry.key.as_str())
+            .collect();
+        assert_eq!(keys, vec!["author", "license", "task"]);
+        let graph = model.graph.unwrap();
+        assert_eq!(graph.input[0].doc_string, "features");
+        assert_eq!(graph.value_info.len(), 1);
+        assert_eq!(graph.value_info[0].name, graph.node[0].output[0]);
+        assert_eq!(graph.value_info[0].doc_string, "activations");
+        let annotation = &graph.quantization_annotation[0];
+        assert_eq!(annotation.tensor_name, graph.node[0].output[0]);
+        assert_eq!(
+            annotation.quant_parameter_tensor_names[0].key,
+            "SCALE_TENSOR"
+        );
+        assert_eq!(
+            annotation.quant_parameter_tensor_names[1].value,
+            "y_zero_point"
+        );
     }
 }
@@ -1,8 +1,10 @@
 //! Node builder.
 
+use std::collections::HashSet;
+
 use onnx_pb::{make_attribute, Attribute, NodeProto};
 
-use crate::{builder::Bag, nodes};
+use crate::{builder::Bag, nodes, opset, schema};
 
 /// Node builder.
 #[derive(Default, Clone)]
@@ -14,6 +16,7 @@ pub struct Node {
     doc_string: Option<String>,
     domain: Option<String>,
     attributes: Vec<(String, Attribute)>,
+    pub(crate) opset: Option<i64>,
     pub(crate) bag: Option<Bag>,
 }
 
@@ -112,8 +115,22 @@ impl Node {
     }
 
     /// Builds the node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node does not match its operator schema.
+    /// See [`try_build`](#method.try_build).
     #[inline]
     pub fn build(self) -> nodes::Node {
+        self.try_build().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Builds the node, failing if it does not match its operator
+    /// [`schema`](../schema/index.html).
+    ///
+    /// Standard operators are checked in the form of the graph operator set
+    /// version, custom operators against their latest registered schema.
+    pub fn try_build(self) -> Result<nodes::Node, schema::Error> {
         let name = if let Some(name) = self.name {
             name
         } else {
@@ -126,7 +143,7 @@ impl Node {
             if self.inputs.len() == 2 {
                 format!(
                     "{}_{}_{}_{}",
-                    self.inputs.get(0).unwrap(),
+                    self.inputs.first().unwrap(),
                     self.op_type,
                     self.inputs.get(1).unwrap(),
                     attrs
@@ -141,7 +158,7 @@ impl Node {
                 )
             }
         };
-        let output = if self.outputs.len() > 0 {
+        let output = if !self.outputs.is_empty() {
             self.outputs
         } else {
             vec![format!("{}O", name)]
@@ -157,23 +174,48 @@ impl Node {
             op_type: self.op_type,
             doc_string: self.doc_string.unwrap_or_default(),
             input: self.inputs,
-            output: output,
+            output,
             attribute: attributes,
         };
+        verify(&proto, self.opset)?;
         let mut node = nodes::Node::from_proto(proto);
         nodes::maybe_bag_node(self.bag.clone(), &mut node);
-        node
+        Ok(node)
+    }
+}
+
+/// Verifies node against its schema.
+fn verify(proto: &NodeProto, version: Option<i64>) -> Result<(), schema::Error> {
+    let (proto, version) = if opset::is_default_domain(proto) {
+        let version = version.unwrap_or(opset::DEFAULT_VERSION);
+        let mut proto = proto.clone();
+        opset::attributes_to_inputs(&mut proto, version, None, &mut HashSet::new());
+        (proto, Some(version))
+    } else {
+        (proto.clone(), None)
+    };
+    let errors = match schema::lookup(&proto.domain, &proto.op_type, version) {
+        Some(schema) => schema.verify(&proto),
+        None => return Ok(()),
+    };
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(schema::Error {
+            node: proto.name,
+            message: errors.join(", "),
+        })
     }
 }
 
-impl Into<nodes::Node> for Node {
-    fn into(self) -> nodes::Node {
-        self.build()
+impl From<Node> for nodes::Node {
+    fn from(node: Node) -> nodes::Node {
+        node.build()
     }
 }
 
-impl Into<NodeProto> for Node {
-    fn into(self) -> NodeProto {
-        self.build().into()
+impl From<Node> for NodeProto {
+    fn from(node: Node) -> NodeProto {
+        node.build().into()
     }
 }
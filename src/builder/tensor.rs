@@ -0,0 +1,165 @@
+//! Tensor builder.
+
+use onnx_pb::{tensor_proto::DataType, TensorProto};
+
+use crate::tensor::{self, Element, Encoding, Error};
+
+/// Tensor builder.
+#[derive(Default, Clone)]
+pub struct Tensor {
+    name: Option<String>,
+    doc_string: Option<String>,
+    shape: Vec<usize>,
+    data_type: DataType,
+    raw: Vec<u8>,
+    encoding: Encoding,
+}
+
+impl Tensor {
+    /// Creates a new builder from values of given shape.
+    pub fn from_slice<T: Element>(shape: &[usize], values: &[T]) -> Result<Self, Error> {
+        let raw = tensor::from_slice(shape, values, Encoding::Raw)?.raw_data;
+        Ok(Tensor {
+            shape: shape.to_vec(),
+            data_type: T::DATA_TYPE,
+            raw,
+            ..Tensor::default()
+        })
+    }
+
+    /// Sets tensor name.
+    #[inline]
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets tensor doc_string.
+    #[inline]
+    pub fn doc_string<S: Into<String>>(mut self, doc_string: S) -> Self {
+        self.doc_string = Some(doc_string.into());
+        self
+    }
+
+    /// Sets storage of tensor values, `raw_data` by default.
+    #[inline]
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Builds the tensor.
+    pub fn build(self) -> TensorProto {
+        let mut tensor = tensor::encode(self.data_type, &self.shape, self.raw, self.encoding);
+        tensor.name = self.name.unwrap_or_default();
+        tensor.doc_string = self.doc_string.unwrap_or_default();
+        tensor
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<S, D> From<ndarray::ArrayBase<S, D>> for Tensor
+where
+    S: ndarray::Data,
+    S::Elem: Element,
+    D: ndarray::Dimension,
+{
+    fn from(array: ndarray::ArrayBase<S, D>) -> Tensor {
+        let values: Vec<S::Elem> = array.iter().copied().collect();
+        Tensor::from_slice(array.shape(), &values).expect("array shape matches its length")
+    }
+}
+
+impl From<Tensor> for TensorProto {
+    fn from(tensor: Tensor) -> TensorProto {
+        tensor.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodings() {
+        let values = [1.5f32, -2.0, 0.25, 8.0, 0.0, 3.0];
+        let raw = Tensor::from_slice(&[2, 3], &values)
+            .unwrap()
+            .name("w")
+            .build();
+        assert_eq!(raw.name, "w");
+        assert_eq!(raw.dims, vec![2, 3]);
+        assert_eq!(raw.raw_data.len(), 24);
+        assert!(raw.float_data.is_empty());
+        let typed = Tensor::from_slice(&[2, 3], &values)
+            .unwrap()
+            .encoding(Encoding::Typed)
+            .build();
+        assert_eq!(typed.float_data, values.to_vec());
+        assert_eq!(tensor::to_vec::<f32>(&raw).unwrap(), values.to_vec());
+        assert_eq!(tensor::to_vec::<f32>(&typed).unwrap(), values.to_vec());
+
+        let flags = Tensor::from_slice(&[3], &[true, false, true])
+            .unwrap()
+            .encoding(Encoding::Typed)
+            .build();
+        assert_eq!(flags.int32_data, vec![1, 0, 1]);
+        assert_eq!(
+            tensor::to_vec::<bool>(&flags).unwrap(),
+            vec![true, false, true]
+        );
+        let big = Tensor::from_slice(&[1], &[u64::MAX]).unwrap().build();
+        assert_eq!(tensor::to_vec::<u64>(&big).unwrap(), vec![u64::MAX]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            Tensor::from_slice(&[2, 2], &[1i64, 2, 3]).err().unwrap(),
+            Error::ShapeMismatch {
+                dims: vec![2, 2],
+                len: 3
+            }
+        );
+        let tensor = Tensor::from_slice(&[1], &[1i32]).unwrap().build();
+        assert_eq!(
+            tensor::to_vec::<i64>(&tensor).unwrap_err().to_string(),
+            "expected tensor of type Int64, found Int32"
+        );
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn ndarray() {
+        let array =
+            ndarray::Array::from_shape_fn((2, 3, 4), |(i, j, k)| (i * 12 + j * 4 + k) as i16);
+        let tensor = Tensor::from(array.view()).build();
+        assert_eq!(tensor.dims, vec![2, 3, 4]);
+        assert_eq!(tensor::to_array::<i16>(&tensor).unwrap(), array.into_dyn());
+        let transposed = ndarray::arr2(&[[1.0f64, 2.0], [3.0, 4.0]]).reversed_axes();
+        let tensor = tensor::from_array(&transposed, Encoding::Typed);
+        assert_eq!(tensor.double_data, vec![1.0, 3.0, 2.0, 4.0]);
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn half() {
+        let values = [half::f16::from_f32(0.5), half::f16::from_f32(-3.0)];
+        let tensor = Tensor::from_slice(&[2], &values)
+            .unwrap()
+            .encoding(Encoding::Typed)
+            .build();
+        assert_eq!(tensor.data_type, DataType::Float16 as i32);
+        assert_eq!(tensor.int32_data, vec![0x3800, 0xc200]);
+        assert_eq!(
+            tensor::to_vec::<half::f16>(&tensor).unwrap(),
+            values.to_vec()
+        );
+        let values = [half::bf16::from_f32(1.0)];
+        let tensor = Tensor::from_slice(&[1], &values).unwrap().build();
+        assert_eq!(
+            tensor::to_vec::<half::bf16>(&tensor).unwrap(),
+            values.to_vec()
+        );
+    }
+}
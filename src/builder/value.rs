@@ -2,8 +2,8 @@
 
 use onnx_pb::{
     tensor_proto::DataType,
-    tensor_shape_proto::Dimension,
-    type_proto::{self, Tensor},
+    tensor_shape_proto::{dimension, Dimension},
+    type_proto::{self, Map, Sequence, Tensor},
     TensorShapeProto, TypeProto, ValueInfoProto,
 };
 
@@ -13,12 +13,18 @@ use crate::{
 };
 
 /// Value info builder.
+///
+/// Values are tensors unless wrapped with [`sequence`](#method.sequence) or
+/// [`map`](#method.map). Optional and sparse tensor types are not part of
+/// the bundled ONNX IR version 6 and can not be expressed.
 #[derive(Default, Clone)]
 pub struct Value {
     name: String,
     elem_type: DataType,
     shape: Vec<Dimension>,
     doc_string: Option<String>,
+    denotation: Option<String>,
+    wrappers: Vec<Wrapper>,
     pub(crate) bag: Option<Bag>,
     pub(crate) marker: Option<Marker>,
 }
@@ -40,6 +46,13 @@ impl Value {
         self
     }
 
+    /// Sets value doc_string.
+    #[inline]
+    pub fn doc_string<S: Into<String>>(mut self, doc_string: S) -> Self {
+        self.doc_string = Some(doc_string.into());
+        self
+    }
+
     /// Sets value element type.
     #[inline]
     pub fn typed<T: Into<DataType>>(mut self, elem_type: T) -> Self {
@@ -61,13 +74,64 @@ impl Value {
         self
     }
 
+    /// Inserts symbolic value dimension, e.g. `batch` or `seq_len`.
+    ///
+    /// Dimensions sharing a name are expected to be of equal size.
+    #[inline]
+    pub fn dim_param<S: Into<String>>(mut self, name: S) -> Self {
+        self.shape
+            .push(Dimension::from(dimension::Value::DimParam(name.into())));
+        self
+    }
+
+    /// Inserts value dimension of unknown size.
+    #[inline]
+    pub fn unknown_dim(mut self) -> Self {
+        self.shape.push(Dimension::default());
+        self
+    }
+
+    /// Sets denotation of the last inserted dimension, e.g. `DATA_BATCH`.
+    ///
+    /// Panics if no dimension was inserted yet.
+    #[inline]
+    pub fn dim_denotation<S: Into<String>>(mut self, denotation: S) -> Self {
+        let dim = self
+            .shape
+            .last_mut()
+            .expect("dimension denotation requires a dimension");
+        dim.denotation = denotation.into();
+        self
+    }
+
+    /// Sets value type denotation, e.g. `IMAGE`.
+    #[inline]
+    pub fn denotation<S: Into<String>>(mut self, denotation: S) -> Self {
+        self.denotation = Some(denotation.into());
+        self
+    }
+
+    /// Wraps value type in a sequence, e.g. `seq(tensor(float))`.
+    #[inline]
+    pub fn sequence(mut self) -> Self {
+        self.wrappers.push(Wrapper::Sequence);
+        self
+    }
+
+    /// Wraps value type in a map with keys of given type.
+    #[inline]
+    pub fn map<T: Into<DataType>>(mut self, key_type: T) -> Self {
+        self.wrappers.push(Wrapper::Map(key_type.into()));
+        self
+    }
+
     /// Creates node for input.
     /// Requires builder to be bagged.
     #[inline]
     pub fn node(self) -> nodes::Node {
         let mut node = Node::named(self.name.clone()).build();
         node.bag = self.bag.clone();
-        let marker = self.marker.as_ref().unwrap().clone();
+        let marker = self.marker.unwrap();
         let mut bag: Bag = self.bag.as_ref().unwrap().clone();
         let value = self.build();
         bag.value(value, marker);
@@ -77,22 +141,115 @@ impl Value {
     /// Builds the value info.
     #[inline]
     pub fn build(self) -> ValueInfoProto {
+        let tensor = type_proto::Value::TensorType(Tensor {
+            shape: Some(TensorShapeProto { dim: self.shape }),
+            elem_type: self.elem_type as i32,
+        });
+        let mut typ = self
+            .wrappers
+            .into_iter()
+            .fold(type_of(tensor), |typ, wrapper| {
+                type_of(match wrapper {
+                    Wrapper::Sequence => type_proto::Value::SequenceType(Box::new(Sequence {
+                        elem_type: Some(Box::new(typ)),
+                    })),
+                    Wrapper::Map(key_type) => type_proto::Value::MapType(Box::new(Map {
+                        key_type: key_type as i32,
+                        value_type: Some(Box::new(typ)),
+                    })),
+                })
+            });
+        typ.denotation = self.denotation.unwrap_or_default();
         ValueInfoProto {
             name: self.name,
-            r#type: Some(TypeProto {
-                denotation: String::default(),
-                value: Some(type_proto::Value::TensorType(Tensor {
-                    shape: Some(TensorShapeProto { dim: self.shape }),
-                    elem_type: self.elem_type as i32,
-                })),
-            }),
+            r#type: Some(typ),
             doc_string: self.doc_string.unwrap_or_default(),
         }
     }
 }
 
-impl Into<ValueInfoProto> for Value {
-    fn into(self) -> ValueInfoProto {
-        self.build()
+impl From<Value> for ValueInfoProto {
+    fn from(value: Value) -> ValueInfoProto {
+        value.build()
+    }
+}
+
+/// Creates type without denotation.
+fn type_of(value: type_proto::Value) -> TypeProto {
+    TypeProto {
+        denotation: String::default(),
+        value: Some(value),
+    }
+}
+
+/// Type wrapping the value type.
+#[derive(Clone)]
+enum Wrapper {
+    Sequence,
+    Map(DataType),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{builder, text};
+
+    #[test]
+    fn symbolic_dims() {
+        let value = Value::new("x")
+            .typed(DataType::Float)
+            .dim_param("batch")
+            .dim_denotation("DATA_BATCH")
+            .unknown_dim()
+            .dim(3)
+            .denotation("IMAGE")
+            .build();
+        let r#type = value.r#type.unwrap();
+        assert_eq!(r#type.denotation, "IMAGE");
+        let dims = match r#type.value {
+            Some(type_proto::Value::TensorType(tensor)) => tensor.shape.unwrap().dim,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            dims[0].value,
+            Some(dimension::Value::DimParam("batch".to_owned()))
+        );
+        assert_eq!(dims[0].denotation, "DATA_BATCH");
+        assert_eq!(dims[1].value, None);
+        assert_eq!(dims[2].value, Some(dimension::Value::DimValue(3)));
+    }
+
+    #[test]
+    fn sequences() {
+        let mut graph = builder::Graph::new("sequences").opset(15);
+        let x = graph.input("x").typed(DataType::Float).dim(3).node();
+        let y = graph.input("y").typed(DataType::Float).dim(3).node();
+        let items = graph.sequence(vec![&x, &y]).sequence_insert(&x);
+        let zero = builder::Tensor::from_slice(&[], &[0i64]).unwrap();
+        let first = items.sequence_at(graph.constant("zero", zero));
+        let stacked = items.concat_from_sequence(0, true);
+        let rows = stacked.split_to_sequence(0, false);
+        let model = graph
+            .outputs(Value::new(&items).typed(DataType::Float).dim(3).sequence())
+            .outputs(Value::new(&rows).typed(DataType::Float).dim(3).sequence())
+            .outputs(Value::new(&first).typed(DataType::Float).dim(3))
+            .model()
+            .build();
+        assert!(crate::checker::check_model(&model).is_ok());
+        let graph = model.graph.unwrap();
+        assert_eq!(
+            text::print_type(graph.output[0].r#type.as_ref().unwrap()),
+            "seq(float[3])"
+        );
+
+        let scores = Value::new("scores")
+            .typed(DataType::Float)
+            .map(DataType::Int64)
+            .sequence()
+            .build();
+        assert_eq!(
+            text::print_type(scores.r#type.as_ref().unwrap()),
+            "seq(map(int64, float[]))"
+        );
     }
 }
@@ -0,0 +1,457 @@
+//! Model validation.
+//!
+//! Checks structural validity of models: required fields, unique value
+//! names, topological order of nodes, imported operator domains, attribute
+//! types and tensor data sizes. Operators with a registered
+//! [`schema`](../schema/index.html) are checked against their signatures.
+
+use std::collections::{HashMap, HashSet};
+
+use onnx_pb::{
+    attribute_proto::AttributeType, tensor_proto::DataType, type_proto, AttributeProto, GraphProto,
+    ModelProto, NodeProto, TensorProto, TypeProto,
+};
+
+use crate::{opset, schema, tensor};
+
+/// Validation error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    /// Path to the invalid item, e.g. `graph.node[3]`.
+    pub path: String,
+    /// Error message.
+    pub message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Checks a model, returning all found errors.
+pub fn check_model(model: &ModelProto) -> Result<(), Vec<Error>> {
+    let mut checker = Checker::default();
+    checker.model(model);
+    checker.finish()
+}
+
+/// Checks a top-level graph, returning all found errors.
+///
+/// Operator domains are not checked.
+pub fn check_graph(graph: &GraphProto) -> Result<(), Vec<Error>> {
+    let mut checker = Checker::default();
+    checker.graph("graph", graph, &HashSet::new(), true);
+    checker.finish()
+}
+
+#[derive(Default)]
+struct Checker {
+    domains: Option<HashMap<String, i64>>,
+    errors: Vec<Error>,
+}
+
+impl Checker {
+    fn finish(self) -> Result<(), Vec<Error>> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn error<P: Into<String>, M: Into<String>>(&mut self, path: P, message: M) {
+        self.errors.push(Error {
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    fn model(&mut self, model: &ModelProto) {
+        if model.ir_version <= 0 {
+            self.error("model", "ir_version is not set");
+        } else if model.ir_version > opset::ir_version(opset::MAX_VERSION) {
+            self.error(
+                "model",
+                format!("unsupported ir_version {}", model.ir_version),
+            );
+        }
+        let mut domains = HashMap::new();
+        for opset in model.opset_import.iter() {
+            let domain = schema::normalize_domain(&opset.domain);
+            if domains.insert(domain.to_owned(), opset.version).is_some() {
+                self.error(
+                    format!("opset_import[{:?}]", opset.domain),
+                    "domain is imported more than once",
+                );
+            }
+            if opset.version <= 0 {
+                self.error(
+                    format!("opset_import[{:?}]", opset.domain),
+                    format!("invalid version {}", opset.version),
+                );
+            }
+        }
+        self.domains = Some(domains);
+        match model.graph.as_ref() {
+            Some(graph) => self.graph("graph", graph, &HashSet::new(), true),
+            None => self.error("model", "graph is not set"),
+        }
+    }
+
+    fn graph(&mut self, path: &str, graph: &GraphProto, outer: &HashSet<String>, top: bool) {
+        if top && graph.name.is_empty() {
+            self.error(path, "graph name is empty");
+        }
+        let mut defined = outer.clone();
+        let mut local = HashSet::new();
+
+        for (index, tensor) in graph.initializer.iter().enumerate() {
+            let tensor_path = format!("{}.initializer[{}]", path, index);
+            if tensor.name.is_empty() {
+                self.error(&tensor_path, "initializer name is empty");
+            } else if !local.insert(tensor.name.clone()) {
+                self.error(
+                    &tensor_path,
+                    format!("initializer {:?} is defined more than once", tensor.name),
+                );
+            }
+            self.tensor(&tensor_path, tensor);
+        }
+        let initializers = local.clone();
+        let mut inputs = HashSet::new();
+        for (index, input) in graph.input.iter().enumerate() {
+            let input_path = format!("{}.input[{}]", path, index);
+            if input.name.is_empty() {
+                self.error(&input_path, "input name is empty");
+            } else if !inputs.insert(input.name.as_str()) {
+                self.error(
+                    &input_path,
+                    format!("input {:?} is defined more than once", input.name),
+                );
+            } else if top && input.r#type.is_none() {
+                self.error(&input_path, format!("input {:?} has no type", input.name));
+            }
+            if !initializers.contains(&input.name) {
+                local.insert(input.name.clone());
+            }
+        }
+        defined.extend(local.iter().cloned());
+
+        let types: HashMap<&str, TypeProto> = graph
+            .initializer
+            .iter()
+            .map(|tensor| {
+                let typ = TypeProto {
+                    value: Some(type_proto::Value::TensorType(type_proto::Tensor {
+                        elem_type: tensor.data_type,
+                        shape: None,
+                    })),
+                    ..TypeProto::default()
+                };
+                (tensor.name.as_str(), typ)
+            })
+            .chain(
+                graph
+                    .input
+                    .iter()
+                    .chain(graph.value_info.iter())
+                    .chain(graph.output.iter())
+                    .filter_map(|value| Some((value.name.as_str(), value.r#type.clone()?))),
+            )
+            .collect();
+        let later: HashSet<&str> = graph
+            .node
+            .iter()
+            .flat_map(|node| node.output.iter().map(String::as_str))
+            .collect();
+        for (index, node) in graph.node.iter().enumerate() {
+            let node_path = if node.name.is_empty() {
+                format!("{}.node[{}]", path, index)
+            } else {
+                format!("{}.node[{}]", path, node.name)
+            };
+            self.node(&node_path, node, &defined, &later, &types);
+            for output in node.output.iter().filter(|output| !output.is_empty()) {
+                if !local.insert(output.clone()) {
+                    self.error(
+                        &node_path,
+                        format!("value {:?} is defined more than once", output),
+                    );
+                }
+                defined.insert(output.clone());
+            }
+        }
+
+        for (index, output) in graph.output.iter().enumerate() {
+            let output_path = format!("{}.output[{}]", path, index);
+            if output.name.is_empty() {
+                self.error(&output_path, "output name is empty");
+            } else if !defined.contains(&output.name) {
+                self.error(
+                    &output_path,
+                    format!("output {:?} is not defined", output.name),
+                );
+            } else if top && output.r#type.is_none() {
+                self.error(
+                    &output_path,
+                    format!("output {:?} has no type", output.name),
+                );
+            }
+        }
+    }
+
+    fn node(
+        &mut self,
+        path: &str,
+        node: &NodeProto,
+        defined: &HashSet<String>,
+        later: &HashSet<&str>,
+        types: &HashMap<&str, TypeProto>,
+    ) {
+        if node.op_type.is_empty() {
+            self.error(path, "op_type is empty");
+        }
+        let version = self
+            .domains
+            .as_ref()
+            .map(|domains| domains.get(schema::normalize_domain(&node.domain)).copied());
+        if version == Some(None) {
+            self.error(
+                path,
+                format!("operator domain {:?} is not imported", node.domain),
+            );
+        } else {
+            self.schema(path, node, version.flatten(), types);
+        }
+        for input in node.input.iter().filter(|input| !input.is_empty()) {
+            if defined.contains(input) {
+                continue;
+            }
+            let message = if later.contains(input.as_str()) {
+                format!(
+                    "input {:?} is used before it is defined, nodes are not topologically sorted",
+                    input
+                )
+            } else {
+                format!("input {:?} is not defined", input)
+            };
+            self.error(path, message);
+        }
+
+        let mut names = HashSet::new();
+        for attr in node.attribute.iter() {
+            let attr_path = format!("{}.attribute[{}]", path, attr.name);
+            if attr.name.is_empty() {
+                self.error(&attr_path, "attribute name is empty");
+            } else if !names.insert(attr.name.as_str()) {
+                self.error(&attr_path, "attribute is defined more than once");
+            }
+            self.attribute(&attr_path, attr, defined);
+        }
+    }
+
+    fn schema(
+        &mut self,
+        path: &str,
+        node: &NodeProto,
+        version: Option<i64>,
+        types: &HashMap<&str, TypeProto>,
+    ) {
+        let standard = schema::normalize_domain(&node.domain).is_empty();
+        if let (true, Some(version)) = (standard, version) {
+            match opset::op_version(&node.op_type, version) {
+                Err(err @ opset::Error::NotYetAvailable { .. })
+                | Err(err @ opset::Error::Removed { .. }) => {
+                    self.error(path, err.to_string());
+                    return;
+                }
+                _ => {}
+            }
+        }
+        match schema::lookup(&node.domain, &node.op_type, version) {
+            Some(schema) => {
+                let mut messages = schema.verify(node);
+                let value_types = |values: &[String]| -> Vec<Option<&TypeProto>> {
+                    values
+                        .iter()
+                        .map(|value| types.get(value.as_str()))
+                        .collect()
+                };
+                messages.extend(schema.verify_types(
+                    node,
+                    &value_types(&node.input),
+                    &value_types(&node.output),
+                ));
+                for message in messages {
+                    self.error(path, message);
+                }
+            }
+            None if !standard && schema::is_registered(&node.domain, &node.op_type) => self.error(
+                path,
+                format!(
+                    "{} is not available in version {} of domain {:?}",
+                    node.op_type,
+                    version.unwrap_or_default(),
+                    node.domain
+                ),
+            ),
+            None => {}
+        }
+    }
+
+    fn attribute(&mut self, path: &str, attr: &AttributeProto, defined: &HashSet<String>) {
+        if !attr.ref_attr_name.is_empty() {
+            self.error(path, "attribute references are only allowed in functions");
+            return;
+        }
+        let typ = match AttributeType::from_i32(attr.r#type) {
+            Some(AttributeType::Undefined) | None => {
+                self.error(path, format!("invalid attribute type {}", attr.r#type));
+                return;
+            }
+            Some(typ) => typ,
+        };
+        let present = [
+            (AttributeType::Float, attr.f != 0.0),
+            (AttributeType::Int, attr.i != 0),
+            (AttributeType::String, !attr.s.is_empty()),
+            (AttributeType::Tensor, attr.t.is_some()),
+            (AttributeType::Graph, attr.g.is_some()),
+            (AttributeType::Floats, !attr.floats.is_empty()),
+            (AttributeType::Ints, !attr.ints.is_empty()),
+            (AttributeType::Strings, !attr.strings.is_empty()),
+            (AttributeType::Tensors, !attr.tensors.is_empty()),
+            (AttributeType::Graphs, !attr.graphs.is_empty()),
+        ];
+        for (field, is_set) in present.iter() {
+            if *is_set && *field != typ {
+                self.error(
+                    path,
+                    format!("attribute of type {:?} has a {:?} value set", typ, field),
+                );
+            }
+        }
+        match typ {
+            AttributeType::Tensor => match attr.t.as_ref() {
+                Some(tensor) => self.tensor(path, tensor),
+                None => self.error(path, "tensor attribute has no value"),
+            },
+            AttributeType::Tensors => {
+                for (index, tensor) in attr.tensors.iter().enumerate() {
+                    self.tensor(&format!("{}[{}]", path, index), tensor);
+                }
+            }
+            AttributeType::Graph => match attr.g.as_ref() {
+                Some(graph) => self.graph(path, graph, defined, false),
+                None => self.error(path, "graph attribute has no value"),
+            },
+            AttributeType::Graphs => {
+                for (index, graph) in attr.graphs.iter().enumerate() {
+                    self.graph(&format!("{}[{}]", path, index), graph, defined, false);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn tensor(&mut self, path: &str, tensor: &TensorProto) {
+        let data_type = match DataType::from_i32(tensor.data_type) {
+            Some(DataType::Undefined) | None => {
+                self.error(
+                    path,
+                    format!("invalid tensor data type {}", tensor.data_type),
+                );
+                return;
+            }
+            Some(data_type) => data_type,
+        };
+        if tensor.dims.iter().any(|dim| *dim < 0) {
+            self.error(
+                path,
+                format!("negative tensor dimension in {:?}", tensor.dims),
+            );
+            return;
+        }
+        if tensor.data_location != 0 {
+            // data is stored externally
+            return;
+        }
+        let count = tensor.dims.iter().product::<i64>() as usize;
+        if !tensor.raw_data.is_empty() {
+            match tensor::element_size(data_type) {
+                Some(size) if tensor.raw_data.len() != count * size => self.error(
+                    path,
+                    format!(
+                        "raw data has {} bytes, expected {} for shape {:?}",
+                        tensor.raw_data.len(),
+                        count * size,
+                        tensor.dims
+                    ),
+                ),
+                Some(_) => {}
+                None => self.error(path, "string tensor can not be stored in raw data"),
+            }
+        } else {
+            let len = tensor::typed_data_len(tensor);
+            if len != count {
+                self.error(
+                    path,
+                    format!(
+                        "tensor has {} values, expected {} for shape {:?}",
+                        len, count, tensor.dims
+                    ),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::text::parse_model;
+
+    #[test]
+    fn valid_model() {
+        let model =
+            parse_model(&std::fs::read_to_string("tests/mean-reverse.txt").unwrap()).unwrap();
+        assert_eq!(check_model(&model), Ok(()));
+    }
+
+    #[test]
+    fn invalid_model() {
+        let mut model = parse_model(
+            r#"
+            <ir_version: 6, opset_import: ["" : 11]>
+            g (float[2] X) => (float[2] Y, float[2] W) <float[2] w = {1.0, 2.0}> {
+                Y = Add (X, T)
+                T = Neg (X)
+                T = com.example.Abs (X)
+            }
+            "#,
+        )
+        .unwrap();
+        model.graph.as_mut().unwrap().initializer[0].dims = vec![3];
+        model.ir_version = 9;
+        let errors: Vec<String> = check_model(&model)
+            .unwrap_err()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "model: unsupported ir_version 9",
+                "graph.initializer[0]: tensor has 2 values, expected 3 for shape [3]",
+                "graph.node[0]: input \"T\" is used before it is defined, nodes are not topologically sorted",
+                "graph.node[2]: operator domain \"com.example\" is not imported",
+                "graph.node[2]: value \"T\" is defined more than once",
+                "graph.output[1]: output \"W\" is not defined",
+            ]
+        );
+    }
+}
@@ -0,0 +1,613 @@
+//! Operator set version conversion.
+//!
+//! Rewrites nodes of the standard domain between operator set versions:
+//! attributes which became inputs are moved in either direction, renamed
+//! and replaced operators (`Upsample` and `Resize`, `Scatter` and
+//! `ScatterElements`) are swapped and obsolete attributes are dropped.
+//! Nodes which can not be expressed in the target version are reported.
+
+use std::collections::{HashMap, HashSet};
+
+use onnx_pb::{
+    attribute_proto::AttributeType, make_attribute, tensor_proto::DataType, GraphProto, ModelProto,
+    NodeProto, OperatorSetIdProto, TensorProto,
+};
+
+use crate::{opset, shape, tensor, visit};
+
+/// Conversion error.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// Operator set version is not known to this crate.
+    UnknownVersion(i64),
+    /// Nodes which could not be converted.
+    Unconvertible(Vec<Unconvertible>),
+}
+
+/// Node which could not be converted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unconvertible {
+    /// Node name, or its first output if the node is unnamed.
+    pub node: String,
+    /// Operator type.
+    pub op_type: String,
+    /// Reason of the failure.
+    pub reason: String,
+}
+
+impl std::fmt::Display for Unconvertible {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}): {}", self.node, self.op_type, self.reason)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownVersion(version) => {
+                write!(f, "{}", opset::Error::UnknownVersion(*version))
+            }
+            Error::Unconvertible(nodes) => {
+                write!(f, "{} nodes can not be converted", nodes.len())?;
+                for node in nodes.iter() {
+                    write!(f, "\n  {}", node)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Converts model to a standard operator set version.
+///
+/// Source version is taken from model `opset_import`. Operators unknown to
+/// this crate and operators of other domains are left unchanged.
+pub fn convert_version(model: &ModelProto, target: i64) -> Result<ModelProto, Error> {
+    if !(1..=opset::MAX_VERSION).contains(&target) {
+        return Err(Error::UnknownVersion(target));
+    }
+    let source = model
+        .opset_import
+        .iter()
+        .find(|opset| opset.domain.is_empty() || opset.domain == "ai.onnx")
+        .map(|opset| opset.version)
+        .unwrap_or(target);
+    if !(1..=opset::MAX_VERSION).contains(&source) {
+        return Err(Error::UnknownVersion(source));
+    }
+
+    let mut model = model.clone();
+    let mut converter = Converter {
+        source,
+        target,
+        failures: Vec::new(),
+        names: HashSet::new(),
+    };
+    if let Some(graph) = model.graph.as_mut() {
+        converter.graph(graph, &HashMap::new());
+    }
+    if !converter.failures.is_empty() {
+        return Err(Error::Unconvertible(converter.failures));
+    }
+
+    match model
+        .opset_import
+        .iter_mut()
+        .find(|opset| opset.domain.is_empty() || opset.domain == "ai.onnx")
+    {
+        Some(opset) => opset.version = target,
+        None => model.opset_import.push(OperatorSetIdProto {
+            version: target,
+            ..OperatorSetIdProto::default()
+        }),
+    }
+    model.ir_version = model.ir_version.max(opset::ir_version(target));
+    Ok(model)
+}
+
+struct Converter {
+    source: i64,
+    target: i64,
+    failures: Vec<Unconvertible>,
+    names: HashSet<String>,
+}
+
+impl Converter {
+    /// Returns true if conversion crosses a version in which an operator changed.
+    fn crosses(&self, version: i64) -> bool {
+        let (low, high) = if self.source < self.target {
+            (self.source, self.target)
+        } else {
+            (self.target, self.source)
+        };
+        low < version && version <= high
+    }
+
+    #[inline]
+    fn upgrade(&self) -> bool {
+        self.target > self.source
+    }
+
+    fn fail<S: Into<String>>(&mut self, node: &NodeProto, reason: S) {
+        self.failures.push(Unconvertible {
+            node: node_label(node),
+            op_type: node.op_type.clone(),
+            reason: reason.into(),
+        });
+    }
+
+    fn graph(&mut self, graph: &mut GraphProto, outer: &HashMap<String, TensorProto>) {
+        let inputs: HashSet<String> = graph.input.iter().map(|value| value.name.clone()).collect();
+        let mut constants = outer.clone();
+        for tensor in graph.initializer.iter() {
+            if !inputs.contains(&tensor.name) {
+                constants.insert(tensor.name.clone(), tensor.clone());
+            }
+        }
+        for node in graph.node.iter() {
+            if node.op_type == "Constant" && opset::is_default_domain(node) {
+                let value = node
+                    .attribute
+                    .iter()
+                    .find(|attr| attr.name == "value")
+                    .and_then(|attr| attr.t.clone());
+                if let (Some(value), Some(output)) = (value, node.output.first()) {
+                    constants.insert(output.clone(), value);
+                }
+            }
+        }
+
+        let ranks: HashMap<String, usize> =
+            shape::infer_shapes(graph, &shape::integer_constants(graph))
+                .into_iter()
+                .map(|(name, dims)| (name, dims.len()))
+                .collect();
+
+        let types = shape::infer_types(graph);
+        let outer_names = std::mem::replace(&mut self.names, visit::value_names(graph));
+        let mut initializers = Vec::new();
+        let mut consumed = HashSet::new();
+        for node in graph.node.iter_mut() {
+            for subgraph in visit::subgraphs_mut(node) {
+                self.graph(subgraph, &constants);
+            }
+            if opset::is_default_domain(node) {
+                let elem_type = node
+                    .input
+                    .first()
+                    .and_then(|input| types.get(input))
+                    .and_then(|elem_type| DataType::from_i32(*elem_type));
+                self.node(
+                    node,
+                    &constants,
+                    &ranks,
+                    elem_type,
+                    &mut initializers,
+                    &mut consumed,
+                );
+            }
+        }
+        graph.initializer.extend(initializers);
+        remove_unused(graph, &consumed);
+        self.names = outer_names;
+    }
+
+    fn node(
+        &mut self,
+        node: &mut NodeProto,
+        constants: &HashMap<String, TensorProto>,
+        ranks: &HashMap<String, usize>,
+        elem_type: Option<DataType>,
+        initializers: &mut Vec<TensorProto>,
+        consumed: &mut HashSet<String>,
+    ) {
+        let failures = self.failures.len();
+        self.attributes(node, ranks);
+        if self.upgrade() {
+            initializers.extend(opset::attributes_to_inputs(
+                node,
+                self.target,
+                elem_type,
+                &mut self.names,
+            ));
+            self.replace(node, initializers);
+        } else {
+            self.replace(node, initializers);
+            self.inputs_to_attributes(node, constants, consumed);
+        }
+        if self.failures.len() == failures {
+            if let Err(err) = opset::op_version(&node.op_type, self.target) {
+                self.fail(node, err.to_string());
+            }
+        }
+    }
+
+    /// Updates attributes which were removed or changed meaning.
+    ///
+    /// Ranks of values are used where the meaning of an attribute depends
+    /// on the rank of an input.
+    fn attributes(&mut self, node: &mut NodeProto, ranks: &HashMap<String, usize>) {
+        let op_type = node.op_type.clone();
+        let op_type = op_type.as_str();
+        if self.upgrade() {
+            if self.crosses(6) {
+                remove_attribute(node, "consumed_inputs");
+            }
+            if self.crosses(7) && (op_type == "BatchNormalization" || op_type == "Dropout") {
+                remove_attribute(node, "is_test");
+            }
+            if self.crosses(9) && op_type == "BatchNormalization" {
+                if int_attribute(node, "spatial").unwrap_or(1) != 1 {
+                    self.fail(node, "non-spatial mode was removed in opset 9");
+                }
+                remove_attribute(node, "spatial");
+            }
+            if self.crosses(4) && op_type == "Concat" && int_attribute(node, "axis").is_none() {
+                node.attribute.push(make_attribute("axis", 1));
+            }
+        } else {
+            let since = match op_type {
+                "ReduceSum" => 13,
+                op_type if op_type.starts_with("Reduce") => 18,
+                _ => 0,
+            };
+            if self.crosses(since) {
+                if int_attribute(node, "noop_with_empty_axes").unwrap_or(0) != 0 {
+                    self.fail(
+                        node,
+                        format!("noop_with_empty_axes was introduced in opset {}", since),
+                    );
+                }
+                remove_attribute(node, "noop_with_empty_axes");
+            }
+            if self.crosses(18) && op_type == "Split" {
+                remove_attribute(node, "num_outputs");
+            }
+        }
+        if self.crosses(13) && matches!(op_type, "Softmax" | "LogSoftmax" | "Hardmax") {
+            // axes were coerced to 2D before opset 13, both forms agree on the last axis
+            let default = if self.upgrade() { 1 } else { -1 };
+            let axis = int_attribute(node, "axis").unwrap_or(default);
+            let rank = node.input.first().and_then(|input| ranks.get(input));
+            let last = axis == -1
+                || rank.is_some_and(|rank| {
+                    let rank = *rank as i64;
+                    (if axis < 0 { axis + rank } else { axis }) == rank - 1
+                });
+            if !last {
+                self.fail(
+                    node,
+                    "semantics of axis changed in opset 13, only the last axis can be converted",
+                );
+            }
+            remove_attribute(node, "axis");
+            node.attribute.push(make_attribute("axis", -1));
+        }
+    }
+
+    /// Replaces operators which were renamed or superseded.
+    fn replace(&mut self, node: &mut NodeProto, initializers: &mut Vec<TensorProto>) {
+        match node.op_type.as_str() {
+            "Upsample" if self.upgrade() && self.crosses(10) => {
+                node.op_type = "Resize".to_owned();
+                if self.target >= 11 {
+                    let scales = node.input.get(1).cloned().unwrap_or_default();
+                    let roi = if self.target < 13 {
+                        let tensor = TensorProto {
+                            name: format!("{}_roi", node_label(node)),
+                            dims: vec![0],
+                            data_type: DataType::Float as i32,
+                            ..TensorProto::default()
+                        };
+                        let name = tensor.name.clone();
+                        initializers.push(tensor);
+                        name
+                    } else {
+                        String::new()
+                    };
+                    node.input.truncate(1);
+                    node.input.push(roi);
+                    node.input.push(scales);
+                    node.attribute.push(make_attribute(
+                        "coordinate_transformation_mode",
+                        "asymmetric",
+                    ));
+                    if string_attribute(node, "mode").unwrap_or("nearest") == "nearest" {
+                        node.attribute.push(make_attribute("nearest_mode", "floor"));
+                    }
+                }
+            }
+            "Resize" if !self.upgrade() && self.crosses(10) => {
+                if self.source >= 11 {
+                    let sizes = node.input.get(3).is_some_and(|sizes| !sizes.is_empty());
+                    let mode = string_attribute(node, "mode").unwrap_or("nearest");
+                    let transform = string_attribute(node, "coordinate_transformation_mode")
+                        .unwrap_or("half_pixel");
+                    let rounding =
+                        string_attribute(node, "nearest_mode").unwrap_or("round_prefer_floor");
+                    if sizes
+                        || mode == "cubic"
+                        || transform != "asymmetric"
+                        || (mode == "nearest" && rounding != "floor")
+                    {
+                        self.fail(
+                            node,
+                            "only asymmetric nearest (floor) and linear resizing by scales can be converted to Upsample",
+                        );
+                        return;
+                    }
+                    let scales = node.input.get(2).cloned().unwrap_or_default();
+                    node.input.truncate(1);
+                    node.input.push(scales);
+                }
+                node.attribute.retain(|attr| attr.name == "mode");
+                node.op_type = "Upsample".to_owned();
+            }
+            "Scatter" if self.upgrade() && self.crosses(11) => {
+                node.op_type = "ScatterElements".to_owned();
+            }
+            "ScatterElements" if !self.upgrade() && self.crosses(11) => {
+                let reduction = string_attribute(node, "reduction").unwrap_or("none");
+                if reduction != "none" {
+                    self.fail(
+                        node,
+                        format!("reduction {:?} was introduced in opset 16", reduction),
+                    );
+                    return;
+                }
+                remove_attribute(node, "reduction");
+                node.op_type = "Scatter".to_owned();
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves constant inputs back to attributes.
+    fn inputs_to_attributes(
+        &mut self,
+        node: &mut NodeProto,
+        constants: &HashMap<String, TensorProto>,
+        consumed: &mut HashSet<String>,
+    ) {
+        let moves = match opset::ATTRIBUTE_INPUTS
+            .iter()
+            .find(|(op_type, since, _)| *op_type == node.op_type && self.crosses(*since))
+        {
+            Some((_, _, moves)) => *moves,
+            None => return,
+        };
+        let first = moves
+            .iter()
+            .map(|(_, position)| *position)
+            .min()
+            .unwrap_or(1);
+        let mut extra: Vec<(usize, &str)> = node
+            .input
+            .iter()
+            .enumerate()
+            .skip(first)
+            .filter(|(position, name)| {
+                !name.is_empty() && moves.iter().all(|(_, moved)| moved != position)
+            })
+            .map(|(position, name)| (position, name.as_str()))
+            .collect();
+        // steps of `Slice` can be dropped if they are all ones
+        if node.op_type == "Slice" {
+            extra.retain(|(position, name)| {
+                *position != 4
+                    || constants
+                        .get(*name)
+                        .and_then(tensor::to_f64_vec)
+                        .map_or(true, |steps| steps.iter().any(|step| *step != 1.0))
+            });
+        }
+        if let Some((position, _)) = extra.first() {
+            self.fail(
+                node,
+                format!("input {} can not be converted to an attribute", position),
+            );
+            return;
+        }
+
+        let mut attributes = Vec::new();
+        for (name, position) in moves.iter() {
+            let input = match node.input.get(*position) {
+                Some(input) if !input.is_empty() => input,
+                _ => continue,
+            };
+            let values = match constants.get(input).and_then(tensor::to_f64_vec) {
+                Some(values) => values,
+                None => {
+                    self.fail(
+                        node,
+                        format!("input {:?} of {} is not a constant", input, name),
+                    );
+                    return;
+                }
+            };
+            let attr = match *name {
+                "min" | "max" | "value" | "ratio" if values.len() == 1 => {
+                    make_attribute(*name, values[0] as f32)
+                }
+                "k" if values.len() == 1 => make_attribute(*name, values[0] as i64),
+                "scales" => {
+                    make_attribute(*name, values.iter().map(|v| *v as f32).collect::<Vec<_>>())
+                }
+                "min" | "max" | "value" | "ratio" | "k" => {
+                    self.fail(node, format!("input {} is not a scalar", name));
+                    return;
+                }
+                _ => make_attribute(*name, values.iter().map(|v| *v as i64).collect::<Vec<_>>()),
+            };
+            consumed.insert(input.clone());
+            attributes.push(attr);
+        }
+        node.input.truncate(first);
+        node.attribute.extend(attributes);
+    }
+}
+
+/// Removes consumed constants which are no longer used.
+fn remove_unused(graph: &mut GraphProto, consumed: &HashSet<String>) {
+    if consumed.is_empty() {
+        return;
+    }
+    let used: HashSet<String> = graph
+        .node
+        .iter()
+        .flat_map(visit::node_references)
+        .chain(graph.output.iter().map(|value| value.name.clone()))
+        .collect();
+    let unused = |name: &String| consumed.contains(name) && !used.contains(name);
+    graph.initializer.retain(|tensor| !unused(&tensor.name));
+    graph.node.retain(|node| {
+        !(node.op_type == "Constant" && node.output.len() == 1 && unused(&node.output[0]))
+    });
+}
+
+fn node_label(node: &NodeProto) -> String {
+    if node.name.is_empty() {
+        node.output.first().cloned().unwrap_or_default()
+    } else {
+        node.name.clone()
+    }
+}
+
+fn remove_attribute(node: &mut NodeProto, name: &str) {
+    node.attribute.retain(|attr| attr.name != name);
+}
+
+fn int_attribute(node: &NodeProto, name: &str) -> Option<i64> {
+    node.attribute
+        .iter()
+        .find(|attr| attr.name == name && attr.r#type == AttributeType::Int as i32)
+        .map(|attr| attr.i)
+}
+
+fn string_attribute<'a>(node: &'a NodeProto, name: &str) -> Option<&'a str> {
+    node.attribute
+        .iter()
+        .find(|attr| attr.name == name && attr.r#type == AttributeType::String as i32)
+        .and_then(|attr| std::str::from_utf8(&attr.s).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::text::{parse_model, print_model};
+
+    fn assert_converts(source: &str, target: i64, expected: &str) {
+        let converted = convert_version(&parse_model(source).unwrap(), target).unwrap();
+        assert_eq!(
+            print_model(&converted),
+            print_model(&parse_model(expected).unwrap())
+        );
+    }
+
+    #[test]
+    fn move_attributes() {
+        let v11 = r#"
+            <ir_version: 6, opset_import: ["" : 11]>
+            g (float[2,1,3] X) => (float[2] Y) {
+                S = ReduceSum <axes = [2], keepdims = 0> (X)
+                Y = Squeeze <axes = [1]> (S)
+            }
+        "#;
+        let v13 = r#"
+            <ir_version: 7, opset_import: ["" : 13]>
+            g (float[2,1,3] X) => (float[2] Y) <int64[1] S_axes = {2}, int64[1] Y_axes = {1}> {
+                S = ReduceSum <keepdims = 0> (X, S_axes)
+                Y = Squeeze (S, Y_axes)
+            }
+        "#;
+        assert_converts(v11, 13, v13);
+        let converted = convert_version(&parse_model(v11).unwrap(), 13).unwrap();
+        assert_eq!(crate::checker::check_model(&converted), Ok(()));
+        assert_converts(
+            v13,
+            11,
+            r#"
+            <ir_version: 7, opset_import: ["" : 11]>
+            g (float[2,1,3] X) => (float[2] Y) {
+                S = ReduceSum <keepdims = 0, axes = [2]> (X)
+                Y = Squeeze <axes = [1]> (S)
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn upsample_to_resize() {
+        assert_converts(
+            r#"
+            <ir_version: 4, opset_import: ["" : 9]>
+            g (float[1,1,2,2] X) => (float[1,1,4,4] Y) <float[4] scales = {1.0, 1.0, 2.0, 2.0}> {
+                Y = Upsample <mode = "nearest"> (X, scales)
+            }
+            "#,
+            11,
+            r#"
+            <ir_version: 6, opset_import: ["" : 11]>
+            g (float[1,1,2,2] X) => (float[1,1,4,4] Y) <float[4] scales = {1.0, 1.0, 2.0, 2.0}, float[0] Y_roi = {}> {
+                Y = Resize <mode = "nearest", coordinate_transformation_mode = "asymmetric", nearest_mode = "floor"> (X, Y_roi, scales)
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn softmax_axis() {
+        let v11 = r#"
+            <ir_version: 6, opset_import: ["" : 11]>
+            g (float[2,3] X) => (float[2,3] Y, float[2,3] Z) {
+                Y = Softmax (X)
+                Z = LogSoftmax <axis = -1> (X)
+            }
+        "#;
+        let v13 = r#"
+            <ir_version: 7, opset_import: ["" : 13]>
+            g (float[2,3] X) => (float[2,3] Y, float[2,3] Z) {
+                Y = Softmax <axis = -1> (X)
+                Z = LogSoftmax <axis = -1> (X)
+            }
+        "#;
+        assert_converts(v11, 13, v13);
+        assert_converts(
+            &v13.replace("Softmax <axis = -1>", "Softmax <axis = 1>"),
+            11,
+            &v11.replace("Softmax (X)", "Softmax <axis = -1> (X)")
+                .replace("ir_version: 6", "ir_version: 7"),
+        );
+
+        let model = parse_model(&v11.replace("float[2,3] X", "float[2,3,4] X")).unwrap();
+        assert!(convert_version(&model, 13).is_err());
+        let model = parse_model(&v11.replace("float[2,3] X", "float X")).unwrap();
+        assert!(convert_version(&model, 13).is_err());
+    }
+
+    #[test]
+    fn report_unconvertible() {
+        let model = parse_model(
+            r#"
+            <ir_version: 8, opset_import: ["" : 17]>
+            g (float[2,3] X, int64[1] A) => (float[2,3] Y, float[2,3] Z, float[2] W) {
+                Y = LayerNormalization (X)
+                Z = Softmax <axis = 0> (X)
+                W = ReduceSum (X, A)
+            }
+            "#,
+        )
+        .unwrap();
+        let err = convert_version(&model, 11).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "3 nodes can not be converted\n  \
+             Y (LayerNormalization): LayerNormalization is not available in opset 11, it was introduced in opset 17\n  \
+             Z (Softmax): semantics of axis changed in opset 13, only the last axis can be converted\n  \
+             W (ReduceSum): input \"A\" of axes is not a constant"
+        );
+    }
+}
@@ -0,0 +1,751 @@
+//! Structural model diffing.
+//!
+//! Compares two models ignoring node order, optionally ignoring names of
+//! nodes and intermediate values, and comparing tensors with a numeric
+//! tolerance. Differences are reported as a list of changes which display
+//! one per line:
+//!
+//! ```text
+//! ~ opset_import[""]: 11 -> 13
+//! - graph.node[Sub_1]: Sub(X, T) -> (U)
+//! ~ graph.initializer[w]: value[3]: 0.5 -> 0.51
+//! ```
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use onnx_pb::{
+    attribute_proto::AttributeType, AttributeProto, GraphProto, ModelProto, NodeProto, TensorProto,
+    ValueInfoProto,
+};
+
+use crate::{tensor, text};
+
+/// Diff options.
+#[derive(Clone, Debug, Default)]
+pub struct DiffOptions {
+    ignore_names: bool,
+    ignore_doc_strings: bool,
+    tolerance: f64,
+    relative_tolerance: f64,
+}
+
+impl DiffOptions {
+    /// Creates default options: exact comparison of everything.
+    #[inline]
+    pub fn new() -> Self {
+        DiffOptions::default()
+    }
+
+    /// Ignores names of nodes and intermediate values.
+    ///
+    /// Nodes are matched by op type, attribute names and the structure of
+    /// their inputs, and graph outputs are matched by position.
+    #[inline]
+    pub fn ignore_names(mut self, ignore_names: bool) -> Self {
+        self.ignore_names = ignore_names;
+        self
+    }
+
+    /// Ignores doc strings.
+    #[inline]
+    pub fn ignore_doc_strings(mut self, ignore_doc_strings: bool) -> Self {
+        self.ignore_doc_strings = ignore_doc_strings;
+        self
+    }
+
+    /// Sets absolute tolerance of numeric values.
+    #[inline]
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets relative tolerance of numeric values.
+    #[inline]
+    pub fn relative_tolerance(mut self, relative_tolerance: f64) -> Self {
+        self.relative_tolerance = relative_tolerance;
+        self
+    }
+
+    fn close(&self, left: f64, right: f64) -> bool {
+        left == right
+            || (left.is_nan() && right.is_nan())
+            || (left - right).abs() <= self.tolerance + self.relative_tolerance * right.abs()
+    }
+}
+
+/// Kind of a change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Present only in the right model.
+    Added,
+    /// Present only in the left model.
+    Removed,
+    /// Present in both models with different contents.
+    Changed,
+}
+
+/// Single difference between models.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    /// Kind of a change.
+    pub kind: ChangeKind,
+    /// Path to the changed item, e.g. `graph.node[name].attribute[axes]`.
+    pub path: String,
+    /// Human readable description.
+    pub detail: String,
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sigil = match self.kind {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Changed => '~',
+        };
+        write!(f, "{} {}: {}", sigil, self.path, self.detail)
+    }
+}
+
+/// Differences between two models.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Diff {
+    /// List of changes from the left to the right model.
+    pub changes: Vec<Change>,
+}
+
+impl Diff {
+    /// Returns true if models are equivalent.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl std::fmt::Display for Diff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for change in self.changes.iter() {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares two models.
+pub fn diff_models(left: &ModelProto, right: &ModelProto, options: &DiffOptions) -> Diff {
+    let mut differ = Differ {
+        options,
+        changes: Vec::new(),
+    };
+    differ.model(left, right);
+    Diff {
+        changes: differ.changes,
+    }
+}
+
+/// Compares two graphs.
+pub fn diff_graphs(left: &GraphProto, right: &GraphProto, options: &DiffOptions) -> Diff {
+    let mut differ = Differ {
+        options,
+        changes: Vec::new(),
+    };
+    differ.graph("graph", left, right);
+    Diff {
+        changes: differ.changes,
+    }
+}
+
+/// Asserts that two models are equal, printing a readable diff otherwise.
+///
+/// Optionally takes [`DiffOptions`](diff/struct.DiffOptions.html) as a third argument.
+#[macro_export]
+macro_rules! assert_model_eq {
+    ( $left:expr, $right:expr ) => {
+        $crate::assert_model_eq!($left, $right, $crate::diff::DiffOptions::default())
+    };
+    ( $left:expr, $right:expr, $options:expr ) => {{
+        let diff = $crate::diff::diff_models(&$left, &$right, &$options);
+        if !diff.is_empty() {
+            panic!("models are not equal:\n{}", diff);
+        }
+    }};
+}
+
+struct Differ<'a> {
+    options: &'a DiffOptions,
+    changes: Vec<Change>,
+}
+
+impl<'a> Differ<'a> {
+    fn push<P: Into<String>, D: Into<String>>(&mut self, kind: ChangeKind, path: P, detail: D) {
+        self.changes.push(Change {
+            kind,
+            path: path.into(),
+            detail: detail.into(),
+        });
+    }
+
+    fn field<T: PartialEq + std::fmt::Debug>(&mut self, path: &str, left: T, right: T) {
+        if left != right {
+            self.push(
+                ChangeKind::Changed,
+                path,
+                format!("{:?} -> {:?}", left, right),
+            );
+        }
+    }
+
+    fn doc_string(&mut self, path: &str, left: &str, right: &str) {
+        if !self.options.ignore_doc_strings {
+            self.field(&format!("{}.doc_string", path), left, right);
+        }
+    }
+
+    fn model(&mut self, left: &ModelProto, right: &ModelProto) {
+        self.field("ir_version", left.ir_version, right.ir_version);
+        self.field("producer_name", &left.producer_name, &right.producer_name);
+        self.field(
+            "producer_version",
+            &left.producer_version,
+            &right.producer_version,
+        );
+        self.field("domain", &left.domain, &right.domain);
+        self.field("model_version", left.model_version, right.model_version);
+        self.doc_string("model", &left.doc_string, &right.doc_string);
+
+        let opsets = |model: &ModelProto| {
+            model
+                .opset_import
+                .iter()
+                .map(|opset| (opset.domain.clone(), opset.version))
+                .collect::<Vec<_>>()
+        };
+        self.keyed(
+            "opset_import",
+            &opsets(left),
+            &opsets(right),
+            |(domain, _)| format!("{:?}", domain),
+            |(_, version)| version.to_string(),
+            |differ, path, left, right| differ.field(path, left.1, right.1),
+        );
+        let props = |model: &ModelProto| {
+            model
+                .metadata_props
+                .iter()
+                .map(|prop| (prop.key.clone(), prop.value.clone()))
+                .collect::<Vec<_>>()
+        };
+        self.keyed(
+            "metadata_props",
+            &props(left),
+            &props(right),
+            |(key, _)| format!("{:?}", key),
+            |(_, value)| format!("{:?}", value),
+            |differ, path, left, right| differ.field(path, &left.1, &right.1),
+        );
+
+        match (left.graph.as_ref(), right.graph.as_ref()) {
+            (Some(left), Some(right)) => self.graph("graph", left, right),
+            (Some(_), None) => self.push(ChangeKind::Removed, "graph", "graph"),
+            (None, Some(_)) => self.push(ChangeKind::Added, "graph", "graph"),
+            (None, None) => {}
+        }
+    }
+
+    fn graph(&mut self, path: &str, left: &GraphProto, right: &GraphProto) {
+        self.field(&format!("{}.name", path), &left.name, &right.name);
+        self.doc_string(path, &left.doc_string, &right.doc_string);
+
+        self.keyed(
+            &format!("{}.input", path),
+            &left.input,
+            &right.input,
+            |value| value.name.clone(),
+            value_info_detail,
+            |differ, path, left, right| differ.value_info(path, left, right),
+        );
+        if self.options.ignore_names {
+            let outputs = |graph: &'_ GraphProto| {
+                graph
+                    .output
+                    .iter()
+                    .enumerate()
+                    .map(|(index, value)| (index, value.clone()))
+                    .collect::<Vec<_>>()
+            };
+            self.keyed(
+                &format!("{}.output", path),
+                &outputs(left),
+                &outputs(right),
+                |(index, _)| index.to_string(),
+                |(_, value)| value_info_detail(value),
+                |differ, path, left, right| {
+                    let (left, right) = (&left.1, &right.1);
+                    differ.field(&format!("{}.type", path), type_text(left), type_text(right));
+                    differ.doc_string(path, &left.doc_string, &right.doc_string);
+                },
+            );
+        } else {
+            self.keyed(
+                &format!("{}.output", path),
+                &left.output,
+                &right.output,
+                |value| value.name.clone(),
+                value_info_detail,
+                |differ, path, left, right| differ.value_info(path, left, right),
+            );
+            self.keyed(
+                &format!("{}.value_info", path),
+                &left.value_info,
+                &right.value_info,
+                |value| value.name.clone(),
+                value_info_detail,
+                |differ, path, left, right| differ.value_info(path, left, right),
+            );
+        }
+        self.keyed(
+            &format!("{}.initializer", path),
+            &left.initializer,
+            &right.initializer,
+            |tensor| tensor.name.clone(),
+            tensor_detail,
+            |differ, path, left, right| differ.tensor(path, left, right),
+        );
+        self.nodes(path, left, right);
+    }
+
+    fn nodes(&mut self, path: &str, left: &GraphProto, right: &GraphProto) {
+        let (left_keys, right_keys) = if self.options.ignore_names {
+            (structural_keys(left), structural_keys(right))
+        } else {
+            (name_keys(left), name_keys(right))
+        };
+        let mut unmatched: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, key) in right_keys.iter().enumerate() {
+            unmatched.entry(key.as_str()).or_default().push(index);
+        }
+        for indices in unmatched.values_mut() {
+            indices.reverse();
+        }
+        let mut matched = HashSet::new();
+        for (index, key) in left_keys.iter().enumerate() {
+            let node = &left.node[index];
+            let node_path = format!("{}.node[{}]", path, node_label(node));
+            match unmatched
+                .get_mut(key.as_str())
+                .and_then(|indices| indices.pop())
+            {
+                Some(right_index) => {
+                    matched.insert(right_index);
+                    self.node(&node_path, node, &right.node[right_index]);
+                }
+                None => self.push(ChangeKind::Removed, node_path, node_detail(node)),
+            }
+        }
+        for (index, node) in right.node.iter().enumerate() {
+            if !matched.contains(&index) {
+                let node_path = format!("{}.node[{}]", path, node_label(node));
+                self.push(ChangeKind::Added, node_path, node_detail(node));
+            }
+        }
+    }
+
+    fn node(&mut self, path: &str, left: &NodeProto, right: &NodeProto) {
+        self.field(&format!("{}.op_type", path), &left.op_type, &right.op_type);
+        self.field(&format!("{}.domain", path), &left.domain, &right.domain);
+        if !self.options.ignore_names {
+            self.field(&format!("{}.input", path), &left.input, &right.input);
+            self.field(&format!("{}.output", path), &left.output, &right.output);
+        }
+        self.doc_string(path, &left.doc_string, &right.doc_string);
+        self.keyed(
+            &format!("{}.attribute", path),
+            &left.attribute,
+            &right.attribute,
+            |attr| attr.name.clone(),
+            attribute_text,
+            |differ, path, left, right| differ.attribute(path, left, right),
+        );
+    }
+
+    fn attribute(&mut self, path: &str, left: &AttributeProto, right: &AttributeProto) {
+        if left.r#type != right.r#type || left.ref_attr_name != right.ref_attr_name {
+            let detail = format!("{} -> {}", attribute_text(left), attribute_text(right));
+            self.push(ChangeKind::Changed, path, detail);
+            return;
+        }
+        self.doc_string(path, &left.doc_string, &right.doc_string);
+        match AttributeType::from_i32(left.r#type) {
+            Some(AttributeType::Float) => self.floats(path, &[left.f], &[right.f]),
+            Some(AttributeType::Floats) => self.floats(path, &left.floats, &right.floats),
+            Some(AttributeType::Tensor) => self.tensor(
+                path,
+                &left.t.clone().unwrap_or_default(),
+                &right.t.clone().unwrap_or_default(),
+            ),
+            Some(AttributeType::Tensors) => {
+                if left.tensors.len() != right.tensors.len() {
+                    self.field(
+                        &format!("{}.len", path),
+                        left.tensors.len(),
+                        right.tensors.len(),
+                    );
+                }
+                for (index, (left, right)) in left.tensors.iter().zip(&right.tensors).enumerate() {
+                    self.tensor(&format!("{}[{}]", path, index), left, right);
+                }
+            }
+            Some(AttributeType::Graph) => self.graph(
+                path,
+                &left.g.clone().unwrap_or_default(),
+                &right.g.clone().unwrap_or_default(),
+            ),
+            Some(AttributeType::Graphs) => {
+                if left.graphs.len() != right.graphs.len() {
+                    self.field(
+                        &format!("{}.len", path),
+                        left.graphs.len(),
+                        right.graphs.len(),
+                    );
+                }
+                for (index, (left, right)) in left.graphs.iter().zip(&right.graphs).enumerate() {
+                    self.graph(&format!("{}[{}]", path, index), left, right);
+                }
+            }
+            _ => {
+                let (left, right) = (attribute_text(left), attribute_text(right));
+                if left != right {
+                    self.push(ChangeKind::Changed, path, format!("{} -> {}", left, right));
+                }
+            }
+        }
+    }
+
+    fn floats(&mut self, path: &str, left: &[f32], right: &[f32]) {
+        let left: Vec<f64> = left.iter().map(|v| *v as f64).collect();
+        let right: Vec<f64> = right.iter().map(|v| *v as f64).collect();
+        if let Some(detail) = self.values(&left, &right) {
+            self.push(ChangeKind::Changed, path, detail);
+        }
+    }
+
+    fn value_info(&mut self, path: &str, left: &ValueInfoProto, right: &ValueInfoProto) {
+        self.field(&format!("{}.type", path), type_text(left), type_text(right));
+        self.doc_string(path, &left.doc_string, &right.doc_string);
+    }
+
+    fn tensor(&mut self, path: &str, left: &TensorProto, right: &TensorProto) {
+        if left.data_type != right.data_type || left.dims != right.dims {
+            let detail = format!("{} -> {}", tensor_detail(left), tensor_detail(right));
+            self.push(ChangeKind::Changed, path, detail);
+            return;
+        }
+        self.doc_string(path, &left.doc_string, &right.doc_string);
+        match (tensor::to_f64_vec(left), tensor::to_f64_vec(right)) {
+            (Some(left), Some(right)) => {
+                if let Some(detail) = self.values(&left, &right) {
+                    self.push(ChangeKind::Changed, path, detail);
+                }
+            }
+            _ => {
+                let (left, right) = (text::tensor_values(left), text::tensor_values(right));
+                if left != right {
+                    self.push(
+                        ChangeKind::Changed,
+                        path,
+                        format!("{{{}}} -> {{{}}}", left.join(", "), right.join(", ")),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Compares numeric values, describing the first mismatch.
+    fn values(&self, left: &[f64], right: &[f64]) -> Option<String> {
+        if left.len() != right.len() {
+            return Some(format!("length {} -> {}", left.len(), right.len()));
+        }
+        let mismatches: Vec<usize> = (0..left.len())
+            .filter(|index| !self.options.close(left[*index], right[*index]))
+            .collect();
+        let first = *mismatches.first()?;
+        let mut detail = format!("value[{}]: {} -> {}", first, left[first], right[first]);
+        if mismatches.len() > 1 {
+            detail.push_str(&format!(" (and {} more)", mismatches.len() - 1));
+        }
+        Some(detail)
+    }
+
+    /// Compares items matched by key.
+    fn keyed<T, K, D, C>(
+        &mut self,
+        path: &str,
+        left: &[T],
+        right: &[T],
+        key: K,
+        detail: D,
+        compare: C,
+    ) where
+        K: Fn(&T) -> String,
+        D: Fn(&T) -> String,
+        C: Fn(&mut Self, &str, &T, &T),
+    {
+        let right_by_key: HashMap<String, &T> =
+            right.iter().map(|item| (key(item), item)).collect();
+        let left_keys: HashSet<String> = left.iter().map(&key).collect();
+        for item in left.iter() {
+            let item_key = key(item);
+            let item_path = format!("{}[{}]", path, item_key);
+            match right_by_key.get(&item_key) {
+                Some(other) => compare(self, &item_path, item, other),
+                None => self.push(ChangeKind::Removed, item_path, detail(item)),
+            }
+        }
+        for item in right.iter() {
+            let item_key = key(item);
+            if !left_keys.contains(&item_key) {
+                let item_path = format!("{}[{}]", path, item_key);
+                self.push(ChangeKind::Added, item_path, detail(item));
+            }
+        }
+    }
+}
+
+/// Returns node keys based on names.
+fn name_keys(graph: &GraphProto) -> Vec<String> {
+    graph
+        .node
+        .iter()
+        .map(|node| {
+            if node.name.is_empty() {
+                format!("->{}", node.output.join(","))
+            } else {
+                node.name.clone()
+            }
+        })
+        .collect()
+}
+
+/// Returns node keys based on graph structure, independent of node
+/// and intermediate value names.
+fn structural_keys(graph: &GraphProto) -> Vec<String> {
+    let producers: HashMap<&str, (usize, usize)> = graph
+        .node
+        .iter()
+        .enumerate()
+        .flat_map(|(index, node)| {
+            node.output
+                .iter()
+                .enumerate()
+                .map(move |(output, name)| (name.as_str(), (index, output)))
+        })
+        .collect();
+    let mut keys: Vec<Option<u64>> = vec![None; graph.node.len()];
+    let mut visiting = HashSet::new();
+    for index in 0..graph.node.len() {
+        node_key(graph, &producers, &mut keys, &mut visiting, index);
+    }
+    keys.into_iter()
+        .map(|key| format!("{:016x}", key.unwrap_or_default()))
+        .collect()
+}
+
+fn node_key(
+    graph: &GraphProto,
+    producers: &HashMap<&str, (usize, usize)>,
+    keys: &mut Vec<Option<u64>>,
+    visiting: &mut HashSet<usize>,
+    index: usize,
+) -> u64 {
+    if let Some(key) = keys[index] {
+        return key;
+    }
+    let node = &graph.node[index];
+    let mut hasher = DefaultHasher::new();
+    node.domain.hash(&mut hasher);
+    node.op_type.hash(&mut hasher);
+    let mut attributes: Vec<&str> = node.attribute.iter().map(|a| a.name.as_str()).collect();
+    attributes.sort_unstable();
+    attributes.hash(&mut hasher);
+    visiting.insert(index);
+    for input in node.input.iter() {
+        match producers.get(input.as_str()) {
+            Some((producer, output)) if !visiting.contains(producer) => {
+                node_key(graph, producers, keys, visiting, *producer).hash(&mut hasher);
+                output.hash(&mut hasher);
+            }
+            // graph input, initializer, outer scope value or a cycle
+            _ => input.hash(&mut hasher),
+        }
+    }
+    visiting.remove(&index);
+    let key = hasher.finish();
+    keys[index] = Some(key);
+    key
+}
+
+fn node_label(node: &NodeProto) -> String {
+    if node.name.is_empty() {
+        format!("->{}", node.output.join(","))
+    } else {
+        node.name.clone()
+    }
+}
+
+fn node_detail(node: &NodeProto) -> String {
+    let op = if node.domain.is_empty() {
+        node.op_type.clone()
+    } else {
+        format!("{}.{}", node.domain, node.op_type)
+    };
+    let attributes = if node.attribute.is_empty() {
+        String::new()
+    } else {
+        let attributes: Vec<String> = node
+            .attribute
+            .iter()
+            .map(|attr| format!("{} = {}", attr.name, attribute_text(attr)))
+            .collect();
+        format!(" <{}>", attributes.join(", "))
+    };
+    format!(
+        "{}{}({}) -> ({})",
+        op,
+        attributes,
+        node.input.join(", "),
+        node.output.join(", ")
+    )
+}
+
+fn attribute_text(attr: &AttributeProto) -> String {
+    if !attr.ref_attr_name.is_empty() {
+        return format!("@{}", attr.ref_attr_name);
+    }
+    let join = |items: Vec<String>| format!("[{}]", items.join(", "));
+    match AttributeType::from_i32(attr.r#type) {
+        Some(AttributeType::Float) => attr.f.to_string(),
+        Some(AttributeType::Int) => attr.i.to_string(),
+        Some(AttributeType::String) => format!("{:?}", String::from_utf8_lossy(&attr.s)),
+        Some(AttributeType::Tensor) => attr.t.as_ref().map(tensor_detail).unwrap_or_default(),
+        Some(AttributeType::Graph) => format!(
+            "graph {:?}",
+            attr.g.as_ref().map(|g| g.name.as_str()).unwrap_or_default()
+        ),
+        Some(AttributeType::Floats) => join(attr.floats.iter().map(|v| v.to_string()).collect()),
+        Some(AttributeType::Ints) => join(attr.ints.iter().map(|v| v.to_string()).collect()),
+        Some(AttributeType::Strings) => join(
+            attr.strings
+                .iter()
+                .map(|v| format!("{:?}", String::from_utf8_lossy(v)))
+                .collect(),
+        ),
+        Some(AttributeType::Tensors) => join(attr.tensors.iter().map(tensor_detail).collect()),
+        Some(AttributeType::Graphs) => join(
+            attr.graphs
+                .iter()
+                .map(|g| format!("graph {:?}", g.name))
+                .collect(),
+        ),
+        _ => "?".to_owned(),
+    }
+}
+
+fn tensor_detail(tensor: &TensorProto) -> String {
+    let dims: Vec<String> = tensor.dims.iter().map(|dim| dim.to_string()).collect();
+    format!(
+        "{}[{}]",
+        text::data_type_name(tensor.data_type).unwrap_or("undefined"),
+        dims.join(",")
+    )
+}
+
+fn type_text(value: &ValueInfoProto) -> String {
+    value
+        .r#type
+        .as_ref()
+        .map(text::print_type)
+        .unwrap_or_default()
+}
+
+fn value_info_detail(value: &ValueInfoProto) -> String {
+    let typ = type_text(value);
+    if typ.is_empty() {
+        value.name.clone()
+    } else {
+        format!("{} {}", typ, value.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::text::parse_model;
+
+    const LEFT: &str = r#"
+        <ir_version: 6, opset_import: ["" : 11]>
+        g (float[1,6] X) => (float[1,6] Y) <float[2] w = {1.0, 2.0}> {
+            [a] T = ReduceMean <axes = [1], keepdims = 1> (X)
+            [b] U = Sub (X, T)
+            [c] Y = Mul (U, w)
+        }
+    "#;
+
+    #[test]
+    fn identical() {
+        let model = parse_model(LEFT).unwrap();
+        assert!(diff_models(&model, &model, &DiffOptions::default()).is_empty());
+        assert_model_eq!(model, model);
+    }
+
+    #[test]
+    fn ignores_node_order_and_names() {
+        let left = parse_model(LEFT).unwrap();
+        let right = parse_model(
+            r#"
+            <ir_version: 6, opset_import: ["" : 11]>
+            g (float[1,6] X) => (float[1,6] Z) <float[2] w = {1.0, 2.00001}> {
+                [n2] V = Sub (X, M)
+                [n1] M = ReduceMean <axes = [1], keepdims = 1> (X)
+                [n3] Z = Mul (V, w)
+            }
+            "#,
+        )
+        .unwrap();
+        let options = DiffOptions::new().ignore_names(true).tolerance(1e-3);
+        assert_eq!(diff_models(&left, &right, &options), Diff::default());
+
+        let diff = diff_models(&left, &right, &DiffOptions::new().ignore_names(true));
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].path, "graph.initializer[w]");
+    }
+
+    #[test]
+    fn reports_changes() {
+        let left = parse_model(LEFT).unwrap();
+        let right = parse_model(
+            r#"
+            <ir_version: 6, opset_import: ["" : 13]>
+            g (float[1,6] X) => (float[1,6] Y) <float[2] w = {1.0, 2.0}> {
+                [a] T = ReduceMean <axes = [0], keepdims = 1> (X)
+                [b] U = Sub (X, T)
+                [d] Y = Add (U, w)
+            }
+            "#,
+        )
+        .unwrap();
+        let diff = diff_models(&left, &right, &DiffOptions::default());
+        let lines: Vec<String> = diff.changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "~ opset_import[\"\"]: 11 -> 13",
+                "~ graph.node[a].attribute[axes]: [1] -> [0]",
+                "- graph.node[c]: Mul(U, w) -> (Y)",
+                "+ graph.node[d]: Add(U, w) -> (Y)",
+            ]
+        );
+    }
+}
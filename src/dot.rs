@@ -0,0 +1,298 @@
+//! Graphviz DOT export.
+//!
+//! Operators are drawn as ellipses labelled with their op type and key
+//! attributes; graph inputs, outputs and initializers are drawn as boxes of
+//! distinct colors. Edges are labelled with value names and value types when
+//! they are known from graph inputs, outputs, initializers or `value_info`.
+//! Graph attributes of nodes such as `If` and `Loop` are drawn as clusters.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use onnx_pb::{
+    attribute_proto::AttributeType, AttributeProto, GraphProto, ModelProto, TensorProto,
+};
+
+use crate::text::{data_type_name, print_type, tensor_values};
+
+/// Maximum number of tensor values shown in labels.
+const MAX_TENSOR_VALUES: usize = 4;
+
+/// Renders model graph to Graphviz DOT.
+pub fn model_to_dot(model: &ModelProto) -> String {
+    graph_to_dot(&model.graph.clone().unwrap_or_default())
+}
+
+/// Renders graph to Graphviz DOT.
+pub fn graph_to_dot(graph: &GraphProto) -> String {
+    let mut dot = Dot::default();
+    let _ = writeln!(dot.out, "digraph {} {{", escape(&graph.name));
+    dot.out
+        .push_str("  node [fontname=\"Helvetica\", fontsize=10];\n");
+    dot.out
+        .push_str("  edge [fontname=\"Helvetica\", fontsize=8];\n");
+    dot.graph(graph, "g", &Scope::default(), 1);
+    dot.out.push_str("}\n");
+    dot.out
+}
+
+/// Values visible in a graph: producing DOT node and known type.
+#[derive(Default, Clone)]
+struct Scope {
+    producers: HashMap<String, String>,
+    types: HashMap<String, String>,
+}
+
+#[derive(Default)]
+struct Dot {
+    out: String,
+}
+
+impl Dot {
+    /// Writes graph contents and returns DOT node IDs of graph outputs.
+    fn graph(
+        &mut self,
+        graph: &GraphProto,
+        prefix: &str,
+        outer: &Scope,
+        depth: usize,
+    ) -> Vec<String> {
+        let indent = "  ".repeat(depth);
+        let mut scope = outer.clone();
+        for value in graph
+            .input
+            .iter()
+            .chain(graph.output.iter())
+            .chain(graph.value_info.iter())
+        {
+            if let Some(typ) = value.r#type.as_ref() {
+                scope.types.insert(value.name.clone(), print_type(typ));
+            }
+        }
+
+        for (index, tensor) in graph.initializer.iter().enumerate() {
+            let id = format!("{}_init{}", prefix, index);
+            let label = format!("{}\n{}", tensor.name, tensor_label(tensor));
+            self.vertex(&indent, &id, &label, "box", "lightgrey");
+            scope.producers.insert(tensor.name.clone(), id);
+            scope
+                .types
+                .entry(tensor.name.clone())
+                .or_insert_with(|| tensor_type_label(tensor));
+        }
+        for (index, input) in graph.input.iter().enumerate() {
+            if graph
+                .initializer
+                .iter()
+                .any(|tensor| tensor.name == input.name)
+            {
+                // input with an initializer default value
+                continue;
+            }
+            let id = format!("{}_in{}", prefix, index);
+            let label = match scope.types.get(&input.name) {
+                Some(typ) => format!("{}\n{}", input.name, typ),
+                None => input.name.clone(),
+            };
+            self.vertex(&indent, &id, &label, "box", "lightblue");
+            scope.producers.insert(input.name.clone(), id);
+        }
+
+        for (index, node) in graph.node.iter().enumerate() {
+            let id = format!("{}_n{}", prefix, index);
+            let mut label = node.op_type.clone();
+            if !node.domain.is_empty() {
+                label = format!("{}.{}", node.domain, label);
+            }
+            for attr in node.attribute.iter() {
+                if let Some(value) = attribute_label(attr) {
+                    let _ = write!(label, "\n{} = {}", attr.name, value);
+                }
+            }
+            self.vertex(&indent, &id, &label, "ellipse", "white");
+
+            for input in node.input.iter().filter(|input| !input.is_empty()) {
+                self.edge(&indent, &scope, input, &id);
+            }
+            for attr in node.attribute.iter() {
+                let graphs = match AttributeType::from_i32(attr.r#type) {
+                    Some(AttributeType::Graph) => attr.g.iter().collect::<Vec<_>>(),
+                    Some(AttributeType::Graphs) => attr.graphs.iter().collect(),
+                    _ => continue,
+                };
+                for (sub_index, subgraph) in graphs.into_iter().enumerate() {
+                    let sub_prefix = format!("{}_{}{}", id, attr.name, sub_index);
+                    let _ = writeln!(
+                        self.out,
+                        "{}subgraph {} {{",
+                        indent,
+                        escape(&format!("cluster_{}", sub_prefix))
+                    );
+                    let _ = writeln!(
+                        self.out,
+                        "{}  label={}; style=dashed;",
+                        indent,
+                        escape(&format!("{}.{}", node.op_type, attr.name))
+                    );
+                    let outputs = self.graph(subgraph, &sub_prefix, &scope, depth + 1);
+                    let _ = writeln!(self.out, "{}}}", indent);
+                    for output in outputs {
+                        let _ = writeln!(
+                            self.out,
+                            "{}{} -> {} [style=dashed];",
+                            indent,
+                            escape(&output),
+                            escape(&id)
+                        );
+                    }
+                }
+            }
+            for output in node.output.iter().filter(|output| !output.is_empty()) {
+                scope.producers.insert(output.clone(), id.clone());
+            }
+        }
+
+        let mut outputs = Vec::with_capacity(graph.output.len());
+        for (index, output) in graph.output.iter().enumerate() {
+            let id = format!("{}_out{}", prefix, index);
+            self.vertex(&indent, &id, &output.name, "box", "lightgreen");
+            self.edge(&indent, &scope, &output.name, &id);
+            outputs.push(id);
+        }
+        outputs
+    }
+
+    fn vertex(&mut self, indent: &str, id: &str, label: &str, shape: &str, color: &str) {
+        let _ = writeln!(
+            self.out,
+            "{}{} [label={}, shape={}, style=filled, fillcolor={}];",
+            indent,
+            escape(id),
+            escape(label),
+            shape,
+            color
+        );
+    }
+
+    fn edge(&mut self, indent: &str, scope: &Scope, value: &str, to: &str) {
+        let from = match scope.producers.get(value) {
+            Some(from) => from,
+            None => return,
+        };
+        let label = match scope.types.get(value) {
+            Some(typ) => format!("{}\n{}", value, typ),
+            None => value.to_owned(),
+        };
+        let _ = writeln!(
+            self.out,
+            "{}{} -> {} [label={}];",
+            indent,
+            escape(from),
+            escape(to),
+            escape(&label)
+        );
+    }
+}
+
+/// Formats attribute value for a node label.
+///
+/// Graph attributes are drawn as clusters and are not included.
+fn attribute_label(attr: &AttributeProto) -> Option<String> {
+    let join = |items: Vec<String>| format!("[{}]", items.join(", "));
+    Some(match AttributeType::from_i32(attr.r#type)? {
+        AttributeType::Float => attr.f.to_string(),
+        AttributeType::Int => attr.i.to_string(),
+        AttributeType::String => String::from_utf8_lossy(&attr.s).into_owned(),
+        AttributeType::Tensor => tensor_label(attr.t.as_ref()?),
+        AttributeType::Floats => join(attr.floats.iter().map(|v| v.to_string()).collect()),
+        AttributeType::Ints => join(attr.ints.iter().map(|v| v.to_string()).collect()),
+        AttributeType::Strings => join(
+            attr.strings
+                .iter()
+                .map(|v| String::from_utf8_lossy(v).into_owned())
+                .collect(),
+        ),
+        AttributeType::Tensors => join(attr.tensors.iter().map(tensor_label).collect()),
+        _ => return None,
+    })
+}
+
+/// Formats tensor type and, for small tensors, its values.
+fn tensor_label(tensor: &TensorProto) -> String {
+    let values = tensor_values(tensor);
+    if values.len() <= MAX_TENSOR_VALUES {
+        format!("{} {{{}}}", tensor_type_label(tensor), values.join(", "))
+    } else {
+        tensor_type_label(tensor)
+    }
+}
+
+fn tensor_type_label(tensor: &TensorProto) -> String {
+    let dims = tensor
+        .dims
+        .iter()
+        .map(|dim| dim.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{}[{}]",
+        data_type_name(tensor.data_type).unwrap_or("undefined"),
+        dims
+    )
+}
+
+/// Quotes and escapes a DOT identifier.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use onnx_pb::tensor_proto::DataType;
+
+    use crate::{builder, text};
+
+    #[test]
+    fn mean_reverse() {
+        let model =
+            text::parse_model(&std::fs::read_to_string("tests/mean-reverse.txt").unwrap()).unwrap();
+        let dot = model_to_dot(&model);
+        assert!(dot.starts_with("digraph \"reverse\" {"));
+        assert!(dot.contains("\"g_in0\" [label=\"X\\nfloat[1,6]\", shape=box"));
+        assert!(dot.contains("label=\"ReduceMean\\naxes = [1]\\nkeepdims = 1\""));
+        assert!(dot.contains("label=\"Constant\\nvalue = float[1] {2.0}\""));
+        assert!(dot.contains("\"g_in0\" -> \"g_n0\" [label=\"X\\nfloat[1,6]\"];"));
+    }
+
+    #[test]
+    fn subgraph_cluster() {
+        let mut body = builder::Graph::new("body");
+        let x = body.input("X").typed(DataType::Float).node();
+        let body = body.outputs(x.abs()).build();
+        let mut graph = builder::Graph::new("cond");
+        let c = graph.input("C").typed(DataType::Bool).node();
+        let out = graph
+            .node("if")
+            .op("If")
+            .input(c)
+            .attribute("then_branch", body.clone())
+            .attribute("else_branch", body)
+            .build();
+        let dot = builder::Graph::to_dot(&graph.outputs(out));
+        assert!(dot.contains("subgraph \"cluster_g_n0_then_branch0\" {"));
+        assert!(dot.contains("\"g_n0_then_branch0_out0\" -> \"g_n0\" [style=dashed];"));
+    }
+}
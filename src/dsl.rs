@@ -0,0 +1,226 @@
+//! Declarative graph construction.
+//!
+//! The [`onnx_graph!`](../macro.onnx_graph.html) macro builds a
+//! [`builder::Graph`](../builder/struct.Graph.html) from a signature and a
+//! list of assignments:
+//!
+//! ```
+//! use onnx_helpers::{onnx_graph, prelude::*};
+//!
+//! fn scaled(x: &Node, factor: f32) -> Node {
+//!     x * factor
+//! }
+//!
+//! let graph = onnx_graph! {
+//!     graph add(X: f32[N, 10], Y: f32[N, 10]) -> (Z: f32[N, 10], W) {
+//!         two = const 2.0f32;
+//!         S = X + Y;
+//!         Z = scaled(S, 0.5) * two;
+//!         (A, B) = @Split[axis = 1](Z);
+//!         W = @Concat[axis = 1](B, A);
+//!     }
+//! };
+//! let model = graph.model().build();
+//! assert_eq!(model.graph.unwrap().output[0].name, "Z");
+//! ```
+//!
+//! Inputs and typed outputs take an element type and dimensions, which are
+//! sizes, symbolic names or `_` for unknown sizes. Statements assign to one
+//! or more names:
+//!
+//! * `name = const value;` creates a constant from a tensor or scalar,
+//! * `(names) = @Op[attr = value, ...](inputs);` creates a node of an
+//!   operator with given attributes and outputs,
+//! * `name = expression;` evaluates Rust expression on earlier names, which
+//!   are bound to `&Node`, e.g. operators or calls to helper functions.
+//!
+//! Names are Rust bindings, so referring to a name which was not defined is
+//! a compile time error:
+//!
+//! ```compile_fail
+//! use onnx_helpers::onnx_graph;
+//!
+//! let graph = onnx_graph! {
+//!     graph add(X: f32[1]) -> (Z) {
+//!         Z = X + Y;
+//!     }
+//! };
+//! ```
+
+/// Builds a graph from a declarative description.
+///
+/// See [`dsl`](dsl/index.html) module for the syntax.
+#[macro_export]
+macro_rules! onnx_graph {
+    (
+        graph $name:ident ( $( $input:ident : $ty:ident [ $( $dim:tt ),* ] ),* $(,)? )
+            -> ( $( $output:ident $( : $oty:ident [ $( $odim:tt ),* ] )? ),* $(,)? )
+        { $( $body:tt )* }
+    ) => {{
+        let mut graph = $crate::builder::Graph::new(stringify!($name));
+        $(
+            #[allow(non_snake_case)]
+            let $input = &$crate::__onnx_value!(
+                graph.input(stringify!($input)).typed(<$ty as $crate::tensor::Element>::DATA_TYPE);
+                $( $dim ),*
+            )
+            .node();
+        )*
+        $crate::onnx_graph!(@body graph; $( $body )*);
+        $(
+            let graph = graph.outputs($crate::onnx_graph!(@output $output $( : $oty [ $( $odim ),* ] )?));
+        )*
+        graph
+    }};
+
+    (@body $graph:ident; ) => {};
+    (@body $graph:ident; $name:ident = const $value:expr ; $( $rest:tt )* ) => {
+        #[allow(non_snake_case)]
+        let $name = &$graph
+            .constant(concat!("Constant_", stringify!($name)), $value)
+            .with_output_name(stringify!($name));
+        $crate::onnx_graph!(@body $graph; $( $rest )*);
+    };
+    (@body $graph:ident; $name:ident = @ $( $rest:tt )* ) => {
+        $crate::onnx_graph!(@body $graph; ($name) = @ $( $rest )*);
+    };
+    (
+        @body $graph:ident;
+        ( $( $name:ident ),+ $(,)? ) = @ $op:ident
+            $( [ $( $attr:ident = $value:expr ),* $(,)? ] )?
+            ( $( $arg:expr ),* $(,)? ) ;
+        $( $rest:tt )*
+    ) => {
+        let node = $graph
+            .node(concat!(stringify!($op) $( , "_", stringify!($name) )+))
+            .op(stringify!($op))
+            $( .input($arg) )*
+            $( $( .attribute(stringify!($attr), $value) )* )?
+            $( .output(stringify!($name)) )+
+            .build();
+        $crate::onnx_graph!(@select node; 0; $( $name ),+);
+        $crate::onnx_graph!(@body $graph; $( $rest )*);
+    };
+    (@body $graph:ident; $name:ident = $value:expr ; $( $rest:tt )* ) => {
+        #[allow(non_snake_case)]
+        let $name = &::std::convert::AsRef::<$crate::nodes::Node>::as_ref(&$value)
+            .clone()
+            .with_output_name(stringify!($name));
+        $crate::onnx_graph!(@body $graph; $( $rest )*);
+    };
+
+    (@select $node:ident; $index:expr; $name:ident $( , $rest:ident )* ) => {
+        #[allow(non_snake_case)]
+        let $name = &$node.select($index);
+        $crate::onnx_graph!(@select $node; $index + 1; $( $rest ),*);
+    };
+    (@select $node:ident; $index:expr; ) => {};
+
+    (@output $output:ident) => {
+        $output
+    };
+    (@output $output:ident : $ty:ident [ $( $dim:tt ),* ]) => {
+        $crate::__onnx_value!(
+            $crate::builder::Value::new($output).typed(<$ty as $crate::tensor::Element>::DATA_TYPE);
+            $( $dim ),*
+        )
+        .build()
+    };
+}
+
+/// Inserts dimensions into a value builder.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __onnx_value {
+    ( $value:expr; ) => {
+        $value
+    };
+    ( $value:expr; _ $( , $rest:tt )* ) => {
+        $crate::__onnx_value!($value.unknown_dim(); $( $rest ),*)
+    };
+    ( $value:expr; $dim:literal $( , $rest:tt )* ) => {
+        $crate::__onnx_value!($value.dim($dim); $( $rest ),*)
+    };
+    ( $value:expr; $dim:ident $( , $rest:tt )* ) => {
+        $crate::__onnx_value!($value.dim_param(stringify!($dim)); $( $rest ),*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use onnx_pb::{tensor_proto::DataType, type_proto};
+
+    use crate::{checker, nodes::Node};
+
+    fn affine(x: &Node, scale: &Node, shift: &Node) -> Node {
+        x * scale + shift
+    }
+
+    #[test]
+    fn add() {
+        let graph = onnx_graph! {
+            graph add(X: f32[1, 10], Y: f32[1, 10]) -> (Z) {
+                Z = X + Y;
+            }
+        };
+        let graph = graph.build();
+        assert_eq!(graph.name, "add");
+        assert_eq!(graph.node.len(), 1);
+        assert_eq!(graph.node[0].input, vec!["X", "Y"]);
+        assert_eq!(graph.node[0].output, vec!["Z"]);
+        let names: Vec<&str> = graph.input.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(names, vec!["X", "Y"]);
+        assert_eq!(graph.output[0].name, "Z");
+    }
+
+    #[test]
+    fn statements() {
+        let weights = crate::builder::Tensor::from_slice(&[3], &[0.5f32; 3]).unwrap();
+        let model = onnx_graph! {
+            graph mlp(X: f32[batch, 3], B: f32[3]) -> (
+                H: f32[batch, 3],
+                Left: f32[batch, 1],
+                Right: f32[batch, 2],
+                X2: f32[batch, _],
+            ) {
+                W = const weights;
+                H = affine(X, W, B).relu();
+                (Left, Right) = @Split[axis = 1, split = vec![1i64, 2]](H);
+                X2 = X;
+            }
+        }
+        .model()
+        .build();
+        assert!(checker::check_model(&model).is_ok());
+        let graph = model.graph.unwrap();
+        let split = graph
+            .node
+            .iter()
+            .find(|node| node.op_type == "Split")
+            .unwrap();
+        assert_eq!(split.name, "Split_Left_Right");
+        assert_eq!(split.input, vec!["H"]);
+        assert_eq!(split.output, vec!["Left", "Right"]);
+        assert_eq!(split.attribute.len(), 2);
+        let identity = graph
+            .node
+            .iter()
+            .find(|node| node.op_type == "Identity")
+            .unwrap();
+        assert_eq!(identity.input, vec!["X"]);
+        let outputs: Vec<&str> = graph.output.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(outputs, vec!["H", "Left", "Right", "X2"]);
+        match graph.output[0].r#type.as_ref().unwrap().value.as_ref() {
+            Some(type_proto::Value::TensorType(tensor)) => {
+                assert_eq!(tensor.elem_type, DataType::Float as i32)
+            }
+            _ => panic!("expected tensor type"),
+        }
+        let constant = graph
+            .node
+            .iter()
+            .find(|node| node.op_type == "Constant")
+            .unwrap();
+        assert_eq!(constant.output, vec!["W"]);
+    }
+}
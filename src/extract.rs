@@ -0,0 +1,250 @@
+//! Subgraph extraction.
+
+use std::collections::{HashMap, HashSet};
+
+use onnx_pb::{
+    tensor_proto::DataType,
+    tensor_shape_proto::{dimension, Dimension},
+    type_proto, GraphProto, ModelProto, NodeProto, TensorShapeProto, TypeProto, ValueInfoProto,
+};
+
+use crate::{
+    shape::{self, Dim},
+    visit,
+};
+
+/// Extraction error.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// Value is not defined in the graph.
+    UnknownValue(String),
+    /// Outputs depend on a graph input which is not one of the given inputs.
+    MissingInput(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownValue(name) => write!(f, "value {:?} is not defined in the graph", name),
+            Error::MissingInput(name) => write!(
+                f,
+                "outputs depend on graph input {:?} which is not an extracted input",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Extracts model with a subgraph computing `outputs` from `inputs`.
+pub fn extract_model<S: AsRef<str>>(
+    model: &ModelProto,
+    inputs: &[S],
+    outputs: &[S],
+) -> Result<ModelProto, Error> {
+    let graph = model.graph.clone().unwrap_or_default();
+    Ok(ModelProto {
+        graph: Some(extract_graph(&graph, inputs, outputs)?),
+        ..model.clone()
+    })
+}
+
+/// Extracts subgraph computing `outputs` from `inputs`.
+///
+/// Includes all nodes on paths from inputs to outputs and initializers they
+/// use. Types of new inputs and outputs are taken from graph inputs, outputs,
+/// `value_info` and initializers, or inferred from the nodes producing them
+/// when not declared.
+pub fn extract_graph<S: AsRef<str>>(
+    graph: &GraphProto,
+    inputs: &[S],
+    outputs: &[S],
+) -> Result<GraphProto, Error> {
+    let producers: HashMap<&str, usize> = graph
+        .node
+        .iter()
+        .enumerate()
+        .flat_map(|(index, node)| node.output.iter().map(move |name| (name.as_str(), index)))
+        .collect();
+    let initializers: HashSet<&str> = graph
+        .initializer
+        .iter()
+        .map(|tensor| tensor.name.as_str())
+        .collect();
+    let graph_inputs: HashSet<&str> = graph
+        .input
+        .iter()
+        .map(|value| value.name.as_str())
+        .collect();
+    let boundary: HashSet<&str> = inputs.iter().map(AsRef::as_ref).collect();
+    for name in boundary.iter() {
+        if !producers.contains_key(name)
+            && !graph_inputs.contains(name)
+            && !initializers.contains(name)
+        {
+            return Err(Error::UnknownValue((*name).to_owned()));
+        }
+    }
+
+    let mut nodes = HashSet::new();
+    let mut used_initializers = HashSet::new();
+    let mut visited = HashSet::new();
+    let mut stack: Vec<String> = outputs
+        .iter()
+        .map(|name| name.as_ref().to_owned())
+        .collect();
+    while let Some(name) = stack.pop() {
+        if !visited.insert(name.clone()) || boundary.contains(name.as_str()) {
+            continue;
+        }
+        if let Some(index) = producers.get(name.as_str()) {
+            if nodes.insert(*index) {
+                stack.extend(visit::node_references(&graph.node[*index]));
+            }
+        } else if initializers.contains(name.as_str()) {
+            used_initializers.insert(name);
+        } else if graph_inputs.contains(name.as_str()) {
+            return Err(Error::MissingInput(name));
+        } else {
+            return Err(Error::UnknownValue(name));
+        }
+    }
+
+    let node: Vec<NodeProto> = graph
+        .node
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| nodes.contains(index))
+        .map(|(_, node)| node.clone())
+        .collect();
+    let defined: HashSet<&str> = node
+        .iter()
+        .flat_map(|node| node.output.iter().map(String::as_str))
+        .collect();
+    let value_info = graph
+        .value_info
+        .iter()
+        .filter(|value| defined.contains(value.name.as_str()))
+        .cloned()
+        .collect();
+    let types = value_types(graph);
+    let value = |name: &str| ValueInfoProto {
+        name: name.to_owned(),
+        r#type: types.get(name).cloned(),
+        ..ValueInfoProto::default()
+    };
+    Ok(GraphProto {
+        node,
+        name: graph.name.clone(),
+        initializer: graph
+            .initializer
+            .iter()
+            .filter(|tensor| used_initializers.contains(&tensor.name))
+            .cloned()
+            .collect(),
+        doc_string: graph.doc_string.clone(),
+        input: inputs.iter().map(|name| value(name.as_ref())).collect(),
+        output: outputs.iter().map(|name| value(name.as_ref())).collect(),
+        value_info,
+        ..GraphProto::default()
+    })
+}
+
+/// Returns known types of graph values, declared or inferred.
+fn value_types(graph: &GraphProto) -> HashMap<String, TypeProto> {
+    let shapes = shape::infer_shapes(graph, &shape::integer_constants(graph));
+    let mut types: HashMap<String, TypeProto> = shape::infer_types(graph)
+        .into_iter()
+        .filter_map(|(name, elem_type)| {
+            let mut typ: TypeProto = DataType::from_i32(elem_type)
+                .filter(|elem_type| *elem_type != DataType::Undefined)?
+                .into();
+            if let (Some(type_proto::Value::TensorType(tensor_type)), Some(dims)) =
+                (typ.value.as_mut(), shapes.get(&name))
+            {
+                tensor_type.shape = Some(TensorShapeProto {
+                    dim: dims
+                        .iter()
+                        .map(|dim| Dimension {
+                            value: match dim {
+                                Dim::Value(value) => Some(dimension::Value::DimValue(*value)),
+                                Dim::Param(param) => {
+                                    Some(dimension::Value::DimParam(param.clone()))
+                                }
+                                Dim::Unknown => None,
+                            },
+                            ..Dimension::default()
+                        })
+                        .collect(),
+                });
+            }
+            Some((name, typ))
+        })
+        .collect();
+    for value in graph
+        .input
+        .iter()
+        .chain(graph.output.iter())
+        .chain(graph.value_info.iter())
+    {
+        if let Some(typ) = value.r#type.as_ref() {
+            types.insert(value.name.clone(), typ.clone());
+        }
+    }
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::text::{parse_graph, print_graph};
+
+    const GRAPH: &str = r#"
+        g (float[1,6] X) => (float[1,6] Y) <float[1] two = {2.0}> {
+            T = ReduceMean <axes = [1], keepdims = 1> (X)
+            U = Sub (X, T)
+            V = Mul (U, two)
+            Y = Add (V, X)
+        }
+    "#;
+
+    #[test]
+    fn extract_middle() {
+        let mut graph = parse_graph(GRAPH).unwrap();
+        graph.value_info.push(ValueInfoProto {
+            name: "U".to_owned(),
+            r#type: graph.input[0].r#type.clone(),
+            ..ValueInfoProto::default()
+        });
+        let extracted = extract_graph(&graph, &["U"], &["V"]).unwrap();
+        assert_eq!(
+            print_graph(&extracted),
+            "g (float[1,6] U) => (float[1,6] V) <float[1] two = {2.0}> {\n  V = Mul (U, two)\n}\n"
+        );
+
+        // types of undeclared values are inferred
+        let graph = parse_graph(GRAPH).unwrap();
+        let extracted = extract_graph(&graph, &["X"], &["U"]).unwrap();
+        assert_eq!(
+            print_graph(&extracted),
+            "g (float[1,6] X) => (float[1,6] U) {\n  \
+             T = ReduceMean <axes = [1], keepdims = 1> (X)\n  \
+             U = Sub (X, T)\n}\n"
+        );
+    }
+
+    #[test]
+    fn extract_errors() {
+        let graph = parse_graph(GRAPH).unwrap();
+        assert_eq!(
+            extract_graph(&graph, &["T"], &["Y"]),
+            Err(Error::MissingInput("X".to_owned()))
+        );
+        assert_eq!(
+            extract_graph(&graph, &["X"], &["Z"]),
+            Err(Error::UnknownValue("Z".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,758 @@
+//! Operator fusion passes.
+//!
+//! Passes are sets of [`rewrite`](../rewrite/index.html) rules. Like
+//! [`optim`](../optim/index.html) passes they operate on a single graph in
+//! place, and they return the number of rewritten patterns. Constants which
+//! are no longer used are left for [`optim::prune`](../optim/fn.prune.html).
+//!
+//! Fusions which depend on shapes, such as broadcasting of a `Gemm` bias,
+//! use shapes declared in the graph and propagated from its inputs. Patterns
+//! over values of unknown shapes are left unchanged.
+
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use onnx_pb::{
+    make_attribute, tensor_proto::DataType, GraphProto, ModelProto, NodeProto, TensorProto,
+};
+
+use crate::{
+    opset,
+    rewrite::{self, Match, Pattern, Replacement, Rule},
+    shape::{self, Dim, Shape},
+    tensor,
+};
+
+/// Runs all passes until the graph no longer changes.
+///
+/// Operators are emitted in the form of operator set `opset`, which also
+/// decides if `LayerNormalization` can be used. Returns total number of
+/// rewritten patterns.
+pub fn fuse(graph: &mut GraphProto, opset: i64) -> usize {
+    let mut total = 0;
+    loop {
+        let changed = eliminate_identities(graph)
+            + collapse_transposes(graph)
+            + collapse_reshapes(graph)
+            + fold_batch_norms(graph)
+            + fuse_layer_norms(graph, opset)
+            + fuse_gemms(graph);
+        if changed == 0 {
+            return total;
+        }
+        total += changed;
+    }
+}
+
+/// Runs all passes on a model graph in the model operator set version.
+pub fn fuse_model(model: &mut ModelProto) -> usize {
+    let version = model
+        .opset_import
+        .iter()
+        .find(|opset| opset.domain.is_empty() || opset.domain == "ai.onnx")
+        .map(|opset| opset.version)
+        .unwrap_or(opset::DEFAULT_VERSION);
+    match model.graph.as_mut() {
+        Some(graph) => fuse(graph, version),
+        None => 0,
+    }
+}
+
+/// Removes `Identity` nodes and additions of zeros or multiplications by
+/// ones which do not broadcast their input.
+///
+/// Nodes producing graph outputs are kept.
+pub fn eliminate_identities(graph: &mut GraphProto) -> usize {
+    let info = Info::new(graph);
+    let mut rules = vec![Rule::new(
+        "identity",
+        Pattern::op("Identity", vec![Pattern::value("x")]),
+        |m| Some(Replacement::forward(m.value("x"))),
+    )];
+    for &(op_type, neutral) in [("Add", 0.0), ("Sub", 0.0), ("Mul", 1.0), ("Div", 1.0)].iter() {
+        let info = info.clone();
+        let pattern = Pattern::op(op_type, vec![Pattern::value("x"), Pattern::constant("c")]);
+        rules.push(Rule::new(op_type, pattern, move |m| {
+            let c = m.constant("c");
+            if tensor::to_f64_vec(c)?.iter().any(|value| *value != neutral) {
+                return None;
+            }
+            let preserved = match info.shapes.get(m.value("x")) {
+                Some(x) => shape::broadcast(x, &dims(c)) == *x,
+                None => c.dims.is_empty(),
+            };
+            if preserved {
+                Some(Replacement::forward(m.value("x")))
+            } else {
+                None
+            }
+        }));
+    }
+    rewrite::rewrite(graph, &rules)
+}
+
+/// Replaces `MatMul` of matrices followed by `Add` of a bias with `Gemm`.
+///
+/// The bias has to broadcast to the product without changing its shape.
+pub fn fuse_gemms(graph: &mut GraphProto) -> usize {
+    let info = Info::new(graph);
+    let pattern = Pattern::parse("Add(MatMul(a, b), c)").unwrap();
+    let rule = Rule::new("gemm", pattern, move |m| {
+        let a = info.shapes.get(m.value("a"))?;
+        let b = info.shapes.get(m.value("b"))?;
+        let c = info.shapes.get(m.value("c"))?;
+        if a.len() != 2 || b.len() != 2 || c.len() > 2 {
+            return None;
+        }
+        let product = vec![a[0].clone(), b[1].clone()];
+        if shape::broadcast(&product, c) != product {
+            return None;
+        }
+        let node = NodeProto {
+            name: m.root().name.clone(),
+            op_type: "Gemm".to_owned(),
+            input: vec![
+                m.value("a").to_owned(),
+                m.value("b").to_owned(),
+                m.value("c").to_owned(),
+            ],
+            output: vec![m.output().to_owned()],
+            ..NodeProto::default()
+        };
+        Some(node.into())
+    });
+    rule.apply(graph)
+}
+
+/// Folds `BatchNormalization` in inference mode following a `Conv` with
+/// constant weights into the weights and bias of the convolution.
+pub fn fold_batch_norms(graph: &mut GraphProto) -> usize {
+    let rules: Vec<Rule> = [false, true]
+        .iter()
+        .map(|&biased| {
+            let mut conv = vec![Pattern::value("x"), Pattern::constant("w")];
+            if biased {
+                conv.push(Pattern::constant("b"));
+            }
+            let pattern = Pattern::op(
+                "BatchNormalization",
+                vec![
+                    Pattern::op("Conv", conv).named("conv"),
+                    Pattern::constant("scale"),
+                    Pattern::constant("bias"),
+                    Pattern::constant("mean"),
+                    Pattern::constant("var"),
+                ],
+            );
+            Rule::new("batch norm", pattern, move |m| fold_batch_norm(m, biased))
+        })
+        .collect();
+    rewrite::rewrite(graph, &rules)
+}
+
+/// Folds a matched batch normalization into convolution weights.
+fn fold_batch_norm(m: &Match, biased: bool) -> Option<Replacement> {
+    let weight = m.constant("w");
+    let data_type = DataType::from_i32(weight.data_type)?;
+    if data_type != DataType::Float && data_type != DataType::Double {
+        return None;
+    }
+    let channels = *weight.dims.first()? as usize;
+    let mut weights = tensor::to_f64_vec(weight)?;
+    if channels == 0 || weights.len() % channels != 0 {
+        return None;
+    }
+    let param = |name: &str| tensor::to_f64_vec(m.constant(name)).filter(|v| v.len() == channels);
+    let (scale, bias, mean, var) = (
+        param("scale")?,
+        param("bias")?,
+        param("mean")?,
+        param("var")?,
+    );
+    let mut biases = if biased {
+        param("b")?
+    } else {
+        vec![0.0; channels]
+    };
+    let epsilon = m
+        .root()
+        .attribute
+        .iter()
+        .find(|attr| attr.name == "epsilon")
+        .map_or(1e-5, |attr| f64::from(attr.f));
+
+    let size = weights.len() / channels;
+    for channel in 0..channels {
+        let factor = scale[channel] / (var[channel] + epsilon).sqrt();
+        for value in weights[channel * size..(channel + 1) * size].iter_mut() {
+            *value *= factor;
+        }
+        biases[channel] = (biases[channel] - mean[channel]) * factor + bias[channel];
+    }
+    let mut weight = tensor::from_f64_vec(data_type, weight.dims.clone(), &weights)?;
+    weight.name = m.unique_name("weight");
+    let mut bias = tensor::from_f64_vec(data_type, vec![channels as i64], &biases)?;
+    bias.name = m.unique_name("bias");
+
+    let mut node = m.node("conv").clone();
+    node.input = vec![
+        m.value("x").to_owned(),
+        weight.name.clone(),
+        bias.name.clone(),
+    ];
+    node.output = vec![m.output().to_owned()];
+    Some(
+        Replacement::new(vec![node])
+            .initializer(weight)
+            .initializer(bias),
+    )
+}
+
+/// Merges consecutive `Transpose` nodes, removing them if the permutations
+/// cancel out.
+pub fn collapse_transposes(graph: &mut GraphProto) -> usize {
+    let info = Info::new(graph);
+    let pattern = Pattern::parse("Transpose(inner: Transpose(x))").unwrap();
+    let rule = Rule::new("transposes", pattern, move |m| {
+        let perm = |node: &NodeProto| {
+            node.attribute
+                .iter()
+                .find(|attr| attr.name == "perm")
+                .map(|attr| attr.ints.clone())
+        };
+        let (inner, outer) = (perm(m.node("inner"))?, perm(m.root())?);
+        if inner.len() != outer.len() {
+            return None;
+        }
+        let perm = outer
+            .iter()
+            .map(|axis| inner.get(*axis as usize).cloned())
+            .collect::<Option<Vec<i64>>>()?;
+        let identity = perm.iter().enumerate().all(|(i, axis)| i as i64 == *axis);
+        if identity && !info.outputs.contains(m.output()) {
+            return Some(Replacement::forward(m.value("x")));
+        }
+        let mut node = NodeProto {
+            name: m.root().name.clone(),
+            op_type: "Transpose".to_owned(),
+            input: vec![m.value("x").to_owned()],
+            output: vec![m.output().to_owned()],
+            attribute: vec![make_attribute("perm", perm)],
+            ..NodeProto::default()
+        };
+        if identity {
+            node.op_type = "Identity".to_owned();
+            node.attribute.clear();
+        }
+        Some(node.into())
+    });
+    rule.apply(graph)
+}
+
+/// Replaces `Reshape` of a `Reshape` by a single `Reshape` when the outer
+/// shape is a constant without dimensions copied from its input.
+pub fn collapse_reshapes(graph: &mut GraphProto) -> usize {
+    let pattern = Pattern::op(
+        "Reshape",
+        vec![
+            Pattern::op("Reshape", vec![Pattern::value("x"), Pattern::value("_")]),
+            Pattern::constant("shape"),
+        ],
+    );
+    let rule = Rule::new("reshapes", pattern, |m| {
+        let allow_zero = shape::int(m.root(), "allowzero") == Some(1);
+        if !allow_zero && tensor::to_f64_vec(m.constant("shape"))?.contains(&0.0) {
+            return None;
+        }
+        let mut node = m.root().clone();
+        node.input[0] = m.value("x").to_owned();
+        Some(node.into())
+    });
+    rule.apply(graph)
+}
+
+/// Replaces normalization over trailing axes composed of `ReduceMean`,
+/// `Sub`, a square, `Sqrt` and `Div`, optionally followed by scaling and
+/// shifting, with `LayerNormalization`.
+///
+/// Standard deviation alone, as in `(x - x.mean()).pow(2).mean().sqrt()`, is
+/// computed as the reciprocal of the `InvStdDev` output of
+/// `LayerNormalization` for float inputs. Does nothing if operator set
+/// `opset` does not have `LayerNormalization`.
+pub fn fuse_layer_norms(graph: &mut GraphProto, opset: i64) -> usize {
+    match opset::op_version("LayerNormalization", opset) {
+        Ok(Some(_)) => {}
+        _ => return 0,
+    }
+    let info = Info::new(graph);
+    let mean = |x: Pattern| {
+        let mut inputs = vec![x];
+        if opset >= 18 {
+            inputs.push(Pattern::value("_"));
+        }
+        Pattern::op("ReduceMean", inputs)
+    };
+    let centered = || {
+        Pattern::op(
+            "Sub",
+            vec![Pattern::value("x"), mean(Pattern::value("x")).named("mean")],
+        )
+        .named("d")
+    };
+    let squares = |d: &dyn Fn() -> Pattern| {
+        vec![
+            Pattern::op("Pow", vec![d(), Pattern::scalar(2.0)]),
+            Pattern::op(
+                "Pow",
+                vec![Pattern::op("Abs", vec![d()]), Pattern::scalar(2.0)],
+            ),
+            Pattern::op("Mul", vec![d(), Pattern::value("d")]),
+        ]
+    };
+    let deviation = |square: Pattern, epsilon: bool| {
+        let variance = mean(square).named("var");
+        let variance = if epsilon {
+            Pattern::op("Add", vec![variance, Pattern::constant("epsilon")])
+        } else {
+            variance
+        };
+        Pattern::op("Sqrt", vec![variance])
+    };
+
+    let mut rules = Vec::new();
+    for &affine in [Affine::ScaleBias, Affine::Scale, Affine::None].iter() {
+        for &epsilon in [true, false].iter() {
+            for square in squares(&|| Pattern::value("d")) {
+                let normalized = Pattern::op("Div", vec![centered(), deviation(square, epsilon)]);
+                let pattern = match affine {
+                    Affine::ScaleBias => Pattern::op(
+                        "Add",
+                        vec![
+                            Pattern::op("Mul", vec![normalized, Pattern::value("scale")]),
+                            Pattern::value("bias"),
+                        ],
+                    ),
+                    Affine::Scale => Pattern::op("Mul", vec![normalized, Pattern::value("scale")]),
+                    Affine::None => normalized,
+                };
+                let info = info.clone();
+                rules.push(Rule::new("layer norm", pattern, move |m| {
+                    layer_norm(m, &info, affine, epsilon)
+                }));
+            }
+        }
+    }
+    for &epsilon in [true, false].iter() {
+        for square in squares(&centered) {
+            let info = info.clone();
+            rules.push(Rule::new("std", deviation(square, epsilon), move |m| {
+                standard_deviation(m, &info, epsilon)
+            }));
+        }
+    }
+    rewrite::rewrite(graph, &rules)
+}
+
+/// Scaling and shifting applied after normalization.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Affine {
+    ScaleBias,
+    Scale,
+    None,
+}
+
+/// Normalized dimensions and attributes of a matched normalization.
+struct Normalization {
+    axis: i64,
+    dims: Shape,
+    epsilon: f32,
+    data_type: DataType,
+}
+
+impl Normalization {
+    fn new(m: &Match, info: &Info, epsilon: bool) -> Option<Self> {
+        let x = m.value("x");
+        let shape = info.shapes.get(x)?;
+        let reduced = |node: &NodeProto| {
+            if shape::int(node, "keepdims").unwrap_or(1) != 1 {
+                return None;
+            }
+            let mut axes: Vec<usize> = match shape::ints(node, "axes", 1, &info.constants) {
+                Some(axes) => axes
+                    .iter()
+                    .map(|axis| {
+                        let axis = if *axis < 0 {
+                            axis + shape.len() as i64
+                        } else {
+                            *axis
+                        };
+                        Some(axis as usize).filter(|axis| *axis < shape.len())
+                    })
+                    .collect::<Option<_>>()?,
+                None if node.input.len() > 1 => return None,
+                None => (0..shape.len()).collect(),
+            };
+            axes.sort_unstable();
+            Some(axes)
+        };
+        let axes = reduced(m.node("mean"))?;
+        let first = *axes.first()?;
+        if axes != reduced(m.node("var"))? || axes != (first..shape.len()).collect::<Vec<_>>() {
+            return None;
+        }
+        let (epsilon, epsilon_type) = if epsilon {
+            let constant = m.constant("epsilon");
+            match tensor::to_f64_vec(constant)?.as_slice() {
+                [value] => (*value as f32, Some(constant.data_type)),
+                _ => return None,
+            }
+        } else {
+            (0.0, None)
+        };
+        let data_type = info.types.get(x).cloned().or(epsilon_type)?;
+        let data_type = match DataType::from_i32(data_type)? {
+            data_type @ DataType::Float
+            | data_type @ DataType::Double
+            | data_type @ DataType::Float16
+            | data_type @ DataType::Bfloat16 => data_type,
+            _ => return None,
+        };
+        Some(Normalization {
+            axis: first as i64,
+            dims: shape[first..].to_vec(),
+            epsilon,
+            data_type,
+        })
+    }
+
+    /// Returns initializer with ones of the normalized shape.
+    fn ones(&self, name: String) -> Option<TensorProto> {
+        let dims = self
+            .dims
+            .iter()
+            .map(|dim| match dim {
+                Dim::Value(dim) => Some(*dim),
+                _ => None,
+            })
+            .collect::<Option<Vec<i64>>>()?;
+        let size = dims.iter().product::<i64>() as usize;
+        let mut tensor = tensor::from_f64_vec(self.data_type, dims, &vec![1.0; size])?;
+        tensor.name = name;
+        Some(tensor)
+    }
+
+    fn node(&self, m: &Match, inputs: Vec<String>, outputs: Vec<String>) -> NodeProto {
+        NodeProto {
+            name: m.unique_name("LayerNormalization"),
+            op_type: "LayerNormalization".to_owned(),
+            input: inputs,
+            output: outputs,
+            attribute: vec![
+                make_attribute("axis", self.axis),
+                make_attribute("epsilon", self.epsilon),
+            ],
+            ..NodeProto::default()
+        }
+    }
+}
+
+/// Replaces a matched normalization with `LayerNormalization`.
+fn layer_norm(m: &Match, info: &Info, affine: Affine, epsilon: bool) -> Option<Replacement> {
+    let norm = Normalization::new(m, info, epsilon)?;
+    let mut inputs = vec![m.value("x").to_owned()];
+    let mut initializers = Vec::new();
+    if affine == Affine::None {
+        let ones = norm.ones(m.unique_name("scale"))?;
+        inputs.push(ones.name.clone());
+        initializers.push(ones);
+    } else {
+        let names: &[&str] = match affine {
+            Affine::ScaleBias => &["scale", "bias"],
+            _ => &["scale"],
+        };
+        for name in names.iter() {
+            if *info.shapes.get(m.value(name))? != norm.dims {
+                return None;
+            }
+            inputs.push(m.value(name).to_owned());
+        }
+    }
+    let node = norm.node(m, inputs, vec![m.output().to_owned()]);
+    Some(
+        initializers
+            .into_iter()
+            .fold(Replacement::new(vec![node]), Replacement::initializer),
+    )
+}
+
+/// Replaces a matched standard deviation with `LayerNormalization` and
+/// `Reciprocal` of its inverse standard deviation.
+fn standard_deviation(m: &Match, info: &Info, epsilon: bool) -> Option<Replacement> {
+    let norm = Normalization::new(m, info, epsilon)?;
+    if norm.data_type != DataType::Float {
+        return None;
+    }
+    let ones = norm.ones(m.unique_name("scale"))?;
+    let inverse = m.unique_name("inv_std_dev");
+    let node = norm.node(
+        m,
+        vec![m.value("x").to_owned(), ones.name.clone()],
+        vec![m.unique_name("normalized"), String::new(), inverse.clone()],
+    );
+    let reciprocal = NodeProto {
+        name: m.root().name.clone(),
+        op_type: "Reciprocal".to_owned(),
+        input: vec![inverse],
+        output: vec![m.output().to_owned()],
+        ..NodeProto::default()
+    };
+    Some(Replacement::new(vec![node, reciprocal]).initializer(ones))
+}
+
+/// Graph properties used to check fusion conditions.
+struct Info {
+    shapes: HashMap<String, Shape>,
+    constants: HashMap<String, Vec<i64>>,
+    types: HashMap<String, i32>,
+    outputs: HashSet<String>,
+}
+
+impl Info {
+    fn new(graph: &GraphProto) -> Rc<Self> {
+        let constants = shape::integer_constants(graph);
+        let shapes = shape::infer_shapes(graph, &constants);
+        Rc::new(Info {
+            shapes,
+            constants,
+            types: shape::infer_types(graph),
+            outputs: graph
+                .output
+                .iter()
+                .map(|value| value.name.clone())
+                .collect(),
+        })
+    }
+}
+
+/// Returns dimensions of a tensor.
+fn dims(tensor: &TensorProto) -> Shape {
+    tensor.dims.iter().map(|dim| Dim::Value(*dim)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use onnx_pb::tensor_proto::DataType;
+
+    use crate::{
+        builder,
+        nn::{LayerNorm, Module},
+        optim,
+        text::{parse_graph, print_graph},
+    };
+
+    fn assert_graph_eq(graph: &GraphProto, expected: &str) {
+        assert_eq!(
+            print_graph(graph),
+            print_graph(&parse_graph(expected).unwrap())
+        );
+    }
+
+    #[test]
+    fn identities() {
+        let mut graph = parse_graph(
+            r#"
+            g (float[2,3] X) => (float[2,3] Y, float[2,3] Z, float[2,2,3] W)
+                <float zero = {0.0}, float[3] ones = {1.0, 1.0, 1.0}, float[2,1,1] zeros = {0.0, 0.0}> {
+                a = Identity (X)
+                b = Add (zero, a)
+                c = Mul (b, ones)
+                Y = Neg (c)
+                Z = Identity (c)
+                W = Add (c, zeros)
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(eliminate_identities(&mut graph), 3);
+        optim::prune(&mut graph);
+        assert_graph_eq(
+            &graph,
+            r#"
+            g (float[2,3] X) => (float[2,3] Y, float[2,3] Z, float[2,2,3] W)
+                <float[2,1,1] zeros = {0.0, 0.0}> {
+                Y = Neg (X)
+                Z = Identity (X)
+                W = Add (X, zeros)
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn gemm() {
+        let mut graph = parse_graph(
+            r#"
+            g (float[N,3] X, float[3,4] W, float[4] B, float[2,N,3] V) => (float[N,4] Y, float[2,N,4] Z) {
+                m = MatMul (X, W)
+                Y = Add (B, m)
+                n = MatMul (V, W)
+                Z = Add (n, B)
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(fuse_gemms(&mut graph), 1);
+        assert_graph_eq(
+            &graph,
+            r#"
+            g (float[N,3] X, float[3,4] W, float[4] B, float[2,N,3] V) => (float[N,4] Y, float[2,N,4] Z) {
+                Y = Gemm (X, W, B)
+                n = MatMul (V, W)
+                Z = Add (n, B)
+            }
+            "#,
+        );
+
+        let mut graph = parse_graph(
+            r#"
+            g (float[1,3] X, float[3,1] W, float[4] B) => (float[1,4] Y) {
+                m = MatMul (X, W)
+                Y = Add (m, B)
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(fuse_gemms(&mut graph), 0);
+    }
+
+    #[test]
+    fn batch_norm() {
+        let mut graph = parse_graph(
+            r#"
+            g (float[1,1,2,2] X) => (float[1,2,2,2] Y) <
+                float[2,1,1,1] w = {1.0, 2.0},
+                float[2] scale = {2.0, 3.0},
+                float[2] bias = {1.0, 1.0},
+                float[2] mean = {0.5, 1.0},
+                float[2] var = {3.0, 8.0}
+            > {
+                c = Conv (X, w)
+                Y = BatchNormalization <epsilon = 1.0> (c, scale, bias, mean, var)
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(fold_batch_norms(&mut graph), 1);
+        optim::prune(&mut graph);
+        assert_graph_eq(
+            &graph,
+            r#"
+            g (float[1,1,2,2] X) => (float[1,2,2,2] Y) <
+                float[2,1,1,1] "Y/weight" = {1.0, 2.0},
+                float[2] "Y/bias" = {0.5, 0.0}
+            > {
+                Y = Conv (X, "Y/weight", "Y/bias")
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn transposes_and_reshapes() {
+        let mut graph = parse_graph(
+            r#"
+            g (float[2,3,4] X) => (float[4,2,3] Y, float[2,3,4] Z, float[6,4] W) <int64[2] s = {6, 4}, int64[2] t = {0, -1}> {
+                a = Transpose <perm = [1, 2, 0]> (X)
+                Y = Transpose <perm = [1, 2, 0]> (a)
+                b = Transpose <perm = [2, 0, 1]> (X)
+                c = Transpose <perm = [1, 2, 0]> (b)
+                Z = Neg (c)
+                d = Reshape (X, s)
+                W = Reshape (d, s)
+                e = Reshape (X, s)
+                f = Reshape (e, t)
+                V = Neg (f)
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            collapse_transposes(&mut graph) + collapse_reshapes(&mut graph),
+            3
+        );
+        assert_graph_eq(
+            &graph,
+            r#"
+            g (float[2,3,4] X) => (float[4,2,3] Y, float[2,3,4] Z, float[6,4] W) <int64[2] s = {6, 4}, int64[2] t = {0, -1}> {
+                Y = Transpose <perm = [2, 0, 1]> (X)
+                Z = Neg (X)
+                W = Reshape (X, s)
+                e = Reshape (X, s)
+                f = Reshape (e, t)
+                V = Neg (f)
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn standard_deviation() {
+        let build = |opset| {
+            let mut graph = builder::Graph::new("stddev").opset(opset);
+            let x = graph.input("X").typed(DataType::Float).dim(1).dim(6).node();
+            let std = (&x - x.mean(1, true)).abs().pow(2.0).mean(1, true).sqrt();
+            graph
+                .outputs_typed(std.with_name("stddev"), DataType::Float)
+                .build()
+        };
+        let mut graph = build(11);
+        assert_eq!(fuse_layer_norms(&mut graph, 11), 0);
+
+        for &opset in [17, 18].iter() {
+            let mut graph = build(opset);
+            assert_eq!(fuse_layer_norms(&mut graph, opset), 1);
+            optim::prune(&mut graph);
+            let ops: Vec<&str> = graph
+                .node
+                .iter()
+                .map(|node| node.op_type.as_str())
+                .collect();
+            assert_eq!(ops, vec!["LayerNormalization", "Reciprocal"]);
+            assert_eq!(graph.node[0].input[0], "X");
+            assert_eq!(graph.node[1].output[0], graph.output[0].name);
+            let scale = &graph.initializer[0];
+            assert_eq!((scale.dims.clone(), scale.float_data.len()), (vec![6], 6));
+        }
+    }
+
+    #[test]
+    fn layer_norm() {
+        let weight = builder::Tensor::from_slice(&[4], &[0.5f32; 4])
+            .unwrap()
+            .build();
+        let bias = builder::Tensor::from_slice(&[4], &[0.25f32; 4])
+            .unwrap()
+            .build();
+        let mut g = builder::Graph::new("norm").opset(17);
+        let x = g
+            .input("X")
+            .typed(DataType::Float)
+            .dim_param("N")
+            .dim(4)
+            .node();
+        let y = LayerNorm::new("norm", weight, bias).forward(&mut g, &x);
+        let mut graph = g.outputs_typed(y, DataType::Float).build();
+        assert_eq!(fuse(&mut graph, 17), 1);
+        optim::prune(&mut graph);
+        assert_eq!(graph.node.len(), 1);
+        let node = &graph.node[0];
+        assert_eq!(node.op_type, "LayerNormalization");
+        assert_eq!(node.input, vec!["X", "norm.weight", "norm.bias"]);
+        assert_eq!(node.attribute[0].i, 1);
+        assert!((node.attribute[1].f - 1e-5).abs() < 1e-12);
+    }
+}
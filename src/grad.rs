@@ -0,0 +1,764 @@
+//! Reverse-mode automatic differentiation.
+//!
+//! [`gradients`] appends nodes computing gradients of a scalar loss with
+//! respect to chosen values, usually initializers holding trainable
+//! weights, turning an inference model into a training graph. Gradients are
+//! exposed as graph outputs named after the values with a `_grad` suffix.
+//!
+//! Differentiated values are expected to be of type float. Gradients of
+//! broadcasting operators are reduced to the shapes of their inputs, which
+//! have to be known from the graph or propagated from its inputs.
+
+use std::collections::{HashMap, HashSet};
+
+use onnx_pb::{
+    make_attribute, tensor_proto::DataType, type_proto, AttributeProto, GraphProto, ModelProto,
+    NodeProto, TensorProto, TypeProto, ValueInfoProto,
+};
+
+use crate::{
+    opset,
+    shape::{infer_shapes, int, integer_constants, ints, Dim, Shape},
+    visit,
+};
+
+/// Operators with outputs which do not depend smoothly on their inputs.
+const NON_DIFFERENTIABLE_OPS: &[&str] = &[
+    "And",
+    "ArgMax",
+    "ArgMin",
+    "Constant",
+    "ConstantOfShape",
+    "Equal",
+    "Greater",
+    "GreaterOrEqual",
+    "Less",
+    "LessOrEqual",
+    "Not",
+    "Or",
+    "Shape",
+    "Sign",
+    "Size",
+    "Xor",
+];
+
+/// Differentiation error.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// Value is not defined in the graph.
+    UnknownValue(String),
+    /// Loss has more than one element.
+    NonScalarLoss(String),
+    /// Shape of a value is required but not known.
+    UnknownShape(String),
+    /// Node which can not be differentiated.
+    Unsupported {
+        /// Node name, or its first output if the node is unnamed.
+        node: String,
+        /// Operator type.
+        op_type: String,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownValue(name) => write!(f, "value {:?} is not defined in the graph", name),
+            Error::NonScalarLoss(name) => write!(f, "loss {:?} is not a scalar", name),
+            Error::UnknownShape(name) => write!(f, "shape of {:?} is required but not known", name),
+            Error::Unsupported { node, op_type } => {
+                write!(f, "{} ({}): can not be differentiated", node, op_type)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Appends gradients of a scalar loss with respect to given values.
+///
+/// Values which do not contribute to the loss get zero gradients. Nodes are
+/// emitted in the form of the model operator set version.
+pub fn gradients(model: &ModelProto, loss: &str, params: &[&str]) -> Result<ModelProto, Error> {
+    let version = model
+        .opset_import
+        .iter()
+        .find(|opset| opset.domain.is_empty() || opset.domain == "ai.onnx")
+        .map(|opset| opset.version)
+        .unwrap_or(opset::DEFAULT_VERSION);
+    let mut model = model.clone();
+    let graph = model.graph.get_or_insert_with(GraphProto::default);
+    let types = value_types(graph);
+    for name in std::iter::once(&loss).chain(params.iter()) {
+        let produced = graph
+            .node
+            .iter()
+            .any(|node| node.output.iter().any(|output| output == name));
+        if !produced && !types.contains_key(*name) {
+            return Err(Error::UnknownValue((*name).to_owned()));
+        }
+    }
+    let constants = integer_constants(graph);
+    let shapes = infer_shapes(graph, &constants);
+    if let Some(shape) = shapes.get(loss) {
+        if shape.iter().any(|dim| *dim != Dim::Value(1)) {
+            return Err(Error::NonScalarLoss(loss.to_owned()));
+        }
+    }
+
+    let mut active: HashSet<&str> = params.iter().copied().collect();
+    for node in graph.node.iter() {
+        if !NON_DIFFERENTIABLE_OPS.contains(&node.op_type.as_str())
+            && node
+                .input
+                .iter()
+                .any(|input| active.contains(input.as_str()))
+        {
+            active.extend(node.output.iter().map(String::as_str));
+        }
+    }
+
+    let mut emitter = Emitter {
+        version,
+        shapes: &shapes,
+        constants: &constants,
+        nodes: Vec::new(),
+        initializers: Vec::new(),
+        scalars: Vec::new(),
+        names: visit::value_names(graph),
+    };
+    let mut grads: HashMap<String, Vec<String>> = HashMap::new();
+    let shape = emitter.op("Shape", &[loss], vec![]);
+    let seed = emitter.op(
+        "ConstantOfShape",
+        &[&shape],
+        vec![make_attribute("value", TensorProto::from(1.0f32))],
+    );
+    grads.insert(loss.to_owned(), vec![seed]);
+    for node in graph.node.iter().rev() {
+        if NON_DIFFERENTIABLE_OPS.contains(&node.op_type.as_str()) {
+            continue;
+        }
+        let wants: Vec<bool> = node
+            .input
+            .iter()
+            .map(|input| active.contains(input.as_str()))
+            .collect();
+        if !wants.contains(&true) {
+            continue;
+        }
+        let gy: Vec<Option<String>> = node
+            .output
+            .iter()
+            .map(|output| emitter.total(&mut grads, output))
+            .collect();
+        if gy.iter().all(Option::is_none) {
+            continue;
+        }
+        let gx = emitter.backward(node, &gy, &wants)?;
+        for ((input, grad), wanted) in node.input.iter().zip(gx).zip(wants) {
+            if let (Some(grad), true) = (grad, wanted) {
+                grads.entry(input.clone()).or_default().push(grad);
+            }
+        }
+    }
+
+    for param in params {
+        let output = format!("{}_grad", param);
+        match emitter.total(&mut grads, param) {
+            Some(grad) => emitter.node("Identity", &[&grad], vec![], &output),
+            None => {
+                let shape = emitter.op("Shape", &[param], vec![]);
+                let zeros = make_attribute("value", TensorProto::from(0.0f32));
+                emitter.node("ConstantOfShape", &[&shape], vec![zeros], &output);
+            }
+        }
+        graph.output.push(ValueInfoProto {
+            name: output,
+            r#type: types.get(*param).cloned(),
+            ..ValueInfoProto::default()
+        });
+    }
+    graph.node.extend(emitter.nodes);
+    graph.initializer.extend(emitter.initializers);
+    Ok(model)
+}
+
+/// Emits gradient nodes.
+struct Emitter<'a> {
+    version: i64,
+    shapes: &'a HashMap<String, Shape>,
+    constants: &'a HashMap<String, Vec<i64>>,
+    nodes: Vec<NodeProto>,
+    initializers: Vec<TensorProto>,
+    scalars: Vec<(u32, String)>,
+    names: HashSet<String>,
+}
+
+impl Emitter<'_> {
+    /// Emits a node and returns its output.
+    fn op(&mut self, op_type: &str, inputs: &[&str], attribute: Vec<AttributeProto>) -> String {
+        let output = format!("grad/{}_{}", op_type, self.nodes.len());
+        self.node(op_type, inputs, attribute, &output);
+        output
+    }
+
+    /// Emits a node with given output.
+    fn node(
+        &mut self,
+        op_type: &str,
+        inputs: &[&str],
+        attribute: Vec<AttributeProto>,
+        output: &str,
+    ) {
+        self.outputs(op_type, inputs, attribute, vec![output.to_owned()]);
+    }
+
+    /// Emits a node with given outputs.
+    fn outputs(
+        &mut self,
+        op_type: &str,
+        inputs: &[&str],
+        attribute: Vec<AttributeProto>,
+        output: Vec<String>,
+    ) {
+        let mut node = NodeProto {
+            name: output[0].clone(),
+            op_type: op_type.to_owned(),
+            input: inputs.iter().map(|input| (*input).to_owned()).collect(),
+            output,
+            attribute,
+            ..NodeProto::default()
+        };
+        // differentiated values are float, the default of data typed inputs
+        self.initializers.extend(opset::attributes_to_inputs(
+            &mut node,
+            self.version,
+            None,
+            &mut self.names,
+        ));
+        self.nodes.push(node);
+    }
+
+    /// Returns a float scalar initializer.
+    fn scalar(&mut self, value: f32) -> String {
+        if let Some((_, name)) = self
+            .scalars
+            .iter()
+            .find(|(bits, _)| *bits == value.to_bits())
+        {
+            return name.clone();
+        }
+        let name = format!("grad/scalar_{}", self.scalars.len());
+        self.initializers.push(TensorProto {
+            name: name.clone(),
+            data_type: DataType::Float as i32,
+            float_data: vec![value],
+            ..TensorProto::default()
+        });
+        self.scalars.push((value.to_bits(), name.clone()));
+        name
+    }
+
+    /// Returns float mask of a boolean value.
+    fn mask(&mut self, condition: &str) -> String {
+        let to = make_attribute("to", DataType::Float as i64);
+        self.op("Cast", &[condition], vec![to])
+    }
+
+    /// Returns accumulated gradient of a value.
+    fn total(&mut self, grads: &mut HashMap<String, Vec<String>>, value: &str) -> Option<String> {
+        let parts = grads.get_mut(value)?;
+        if parts.len() > 1 {
+            let inputs: Vec<&str> = parts.iter().map(String::as_str).collect();
+            let sum = self.op("Sum", &inputs, vec![]);
+            *parts = vec![sum];
+        }
+        parts.first().cloned()
+    }
+
+    /// Returns shape of a value.
+    fn shape(&self, value: &str) -> Result<&Shape, Error> {
+        self.shapes
+            .get(value)
+            .ok_or_else(|| Error::UnknownShape(value.to_owned()))
+    }
+
+    /// Sums gradient over dimensions broadcast from an input to an output,
+    /// leaving `core` trailing dimensions unchanged.
+    fn unbroadcast(
+        &mut self,
+        grad: &str,
+        input: &str,
+        output: &str,
+        core: usize,
+    ) -> Result<String, Error> {
+        let (from, to) = (self.shape(input)?, self.shape(output)?);
+        if from == to {
+            return Ok(grad.to_owned());
+        }
+        let extra = to.len().saturating_sub(from.len());
+        let batch = from.len().saturating_sub(core);
+        let axes: Vec<i64> = (0..extra)
+            .chain(
+                (0..batch)
+                    .filter(|axis| {
+                        from[*axis] == Dim::Value(1) && to[extra + axis] != Dim::Value(1)
+                    })
+                    .map(|axis| extra + axis),
+            )
+            .map(|axis| axis as i64)
+            .collect();
+        if axes.is_empty() {
+            return Ok(grad.to_owned());
+        }
+        let reduced = self.op(
+            "ReduceSum",
+            &[grad],
+            vec![
+                make_attribute("axes", axes),
+                make_attribute("keepdims", 1i64),
+            ],
+        );
+        if extra == 0 {
+            return Ok(reduced);
+        }
+        let shape = self.op("Shape", &[input], vec![]);
+        Ok(self.op("Reshape", &[&reduced, &shape], vec![]))
+    }
+
+    /// Returns gradients of node inputs from gradients of its outputs.
+    fn backward(
+        &mut self,
+        node: &NodeProto,
+        gy: &[Option<String>],
+        wants: &[bool],
+    ) -> Result<Vec<Option<String>>, Error> {
+        let unsupported = || Error::Unsupported {
+            node: if node.name.is_empty() {
+                node.output.first().cloned().unwrap_or_default()
+            } else {
+                node.name.clone()
+            },
+            op_type: node.op_type.clone(),
+        };
+        if !opset::is_default_domain(node) {
+            return Err(unsupported());
+        }
+        let gy = match gy.first() {
+            Some(Some(gy)) => gy.as_str(),
+            _ => return Ok(vec![None; node.input.len()]),
+        };
+        let x = node.input.first().map(String::as_str).unwrap_or_default();
+        let y = node.output[0].as_str();
+        let want = |index: usize| wants.get(index).copied().unwrap_or(false);
+        let mut gx = vec![None; node.input.len()];
+        match node.op_type.as_str() {
+            "Identity" => gx[0] = Some(gy.to_owned()),
+            "Neg" => gx[0] = Some(self.op("Neg", &[gy], vec![])),
+            "Abs" => {
+                let sign = self.op("Sign", &[x], vec![]);
+                gx[0] = Some(self.op("Mul", &[gy, &sign], vec![]));
+            }
+            "Sqrt" => {
+                let two = self.scalar(2.0);
+                let double = self.op("Mul", &[y, &two], vec![]);
+                gx[0] = Some(self.op("Div", &[gy, &double], vec![]));
+            }
+            "Relu" => {
+                let zero = self.scalar(0.0);
+                let positive = self.op("Greater", &[x, &zero], vec![]);
+                let mask = self.mask(&positive);
+                gx[0] = Some(self.op("Mul", &[gy, &mask], vec![]));
+            }
+            "Tanh" => {
+                let one = self.scalar(1.0);
+                let square = self.op("Mul", &[y, y], vec![]);
+                let slope = self.op("Sub", &[&one, &square], vec![]);
+                gx[0] = Some(self.op("Mul", &[gy, &slope], vec![]));
+            }
+            "Clip" => gx[0] = Some(self.clip(node, gy)),
+            "Add" | "Sub" | "Mul" | "Div" | "Pow" => {
+                let (a, b) = (x, node.input[1].as_str());
+                if want(0) {
+                    let grad = match node.op_type.as_str() {
+                        "Add" | "Sub" => gy.to_owned(),
+                        "Mul" => self.op("Mul", &[gy, b], vec![]),
+                        "Div" => self.op("Div", &[gy, b], vec![]),
+                        _ => {
+                            let one = self.scalar(1.0);
+                            let exponent = self.op("Sub", &[b, &one], vec![]);
+                            let power = self.op("Pow", &[a, &exponent], vec![]);
+                            let slope = self.op("Mul", &[b, &power], vec![]);
+                            self.op("Mul", &[gy, &slope], vec![])
+                        }
+                    };
+                    gx[0] = Some(self.unbroadcast(&grad, a, y, 0)?);
+                }
+                if want(1) {
+                    let grad = match node.op_type.as_str() {
+                        "Add" => gy.to_owned(),
+                        "Sub" => self.op("Neg", &[gy], vec![]),
+                        "Mul" => self.op("Mul", &[gy, a], vec![]),
+                        "Div" => {
+                            let product = self.op("Mul", &[gy, y], vec![]);
+                            let quotient = self.op("Div", &[&product, b], vec![]);
+                            self.op("Neg", &[&quotient], vec![])
+                        }
+                        _ => {
+                            let log = self.op("Log", &[a], vec![]);
+                            let slope = self.op("Mul", &[y, &log], vec![]);
+                            self.op("Mul", &[gy, &slope], vec![])
+                        }
+                    };
+                    gx[1] = Some(self.unbroadcast(&grad, b, y, 0)?);
+                }
+            }
+            "ReduceSum" | "ReduceMean" | "ReduceMax" | "ReduceMin" => {
+                let axes = match ints(node, "axes", 1, self.constants) {
+                    Some(axes) => axes,
+                    None if node.input.len() > 1 && !node.input[1].is_empty() => {
+                        return Err(unsupported())
+                    }
+                    None => Vec::new(),
+                };
+                if axes.is_empty() && int(node, "noop_with_empty_axes") == Some(1) {
+                    gx[0] = Some(gy.to_owned());
+                    return Ok(gx);
+                }
+                let keepdims = int(node, "keepdims").unwrap_or(1) != 0;
+                let shape = self.op("Shape", &[x], vec![]);
+                let expand = |emitter: &mut Self, value: &str| {
+                    let value = if keepdims || axes.is_empty() {
+                        value.to_owned()
+                    } else {
+                        let axes = make_attribute("axes", axes.clone());
+                        emitter.op("Unsqueeze", &[value], vec![axes])
+                    };
+                    emitter.op("Expand", &[&value, &shape], vec![])
+                };
+                let spread = expand(self, gy);
+                gx[0] = Some(match node.op_type.as_str() {
+                    "ReduceSum" => spread,
+                    "ReduceMean" => {
+                        let counts: Vec<String> = [x, y]
+                            .iter()
+                            .map(|value| {
+                                let size = self.op("Size", &[value], vec![]);
+                                self.mask(&size)
+                            })
+                            .collect();
+                        let count = self.op("Div", &[&counts[0], &counts[1]], vec![]);
+                        self.op("Div", &[&spread, &count], vec![])
+                    }
+                    _ => {
+                        let extreme = expand(self, y);
+                        let equal = self.op("Equal", &[x, &extreme], vec![]);
+                        let mask = self.mask(&equal);
+                        self.op("Mul", &[&spread, &mask], vec![])
+                    }
+                });
+            }
+            "Concat" => {
+                let mut axis = int(node, "axis").ok_or_else(unsupported)?;
+                let mut split = Vec::with_capacity(node.input.len());
+                for input in node.input.iter() {
+                    let shape = self.shape(input)?;
+                    if axis < 0 {
+                        axis += shape.len() as i64;
+                    }
+                    match shape.get(axis as usize) {
+                        Some(Dim::Value(size)) => split.push(*size),
+                        _ => return Err(Error::UnknownShape(input.clone())),
+                    }
+                }
+                let outputs: Vec<String> = (0..node.input.len())
+                    .map(|index| format!("grad/Split_{}_{}", self.nodes.len(), index))
+                    .collect();
+                let attributes = vec![make_attribute("axis", axis), make_attribute("split", split)];
+                self.outputs("Split", &[gy], attributes, outputs.clone());
+                gx = outputs.into_iter().map(Some).collect();
+            }
+            "MatMul" => {
+                let b = node.input[1].as_str();
+                let transpose = |emitter: &mut Self, value: &str| -> Result<String, Error> {
+                    let rank = emitter.shape(value)?.len();
+                    if rank < 2 {
+                        return Err(unsupported());
+                    }
+                    let mut perm: Vec<i64> = (0..rank as i64).collect();
+                    perm.swap(rank - 2, rank - 1);
+                    let perm = make_attribute("perm", perm);
+                    Ok(emitter.op("Transpose", &[value], vec![perm]))
+                };
+                if want(0) {
+                    let bt = transpose(self, b)?;
+                    let grad = self.op("MatMul", &[gy, &bt], vec![]);
+                    gx[0] = Some(self.unbroadcast(&grad, x, y, 2)?);
+                }
+                if want(1) {
+                    let at = transpose(self, x)?;
+                    let grad = self.op("MatMul", &[&at, gy], vec![]);
+                    gx[1] = Some(self.unbroadcast(&grad, b, y, 2)?);
+                }
+            }
+            "Transpose" => {
+                let perm = match ints(node, "perm", usize::MAX, self.constants) {
+                    Some(perm) => {
+                        let mut inverse = vec![0i64; perm.len()];
+                        for (axis, dim) in perm.iter().enumerate() {
+                            inverse[*dim as usize] = axis as i64;
+                        }
+                        vec![make_attribute("perm", inverse)]
+                    }
+                    None => vec![],
+                };
+                gx[0] = Some(self.op("Transpose", &[gy], perm));
+            }
+            "Reshape" | "Squeeze" | "Unsqueeze" | "Flatten" => {
+                let shape = self.op("Shape", &[x], vec![]);
+                gx[0] = Some(self.op("Reshape", &[gy, &shape], vec![]));
+            }
+            "Slice" => {
+                let dims = self.shape(x)?.clone();
+                let param = |name, position| ints(node, name, position, self.constants);
+                let (starts, ends) = match (param("starts", 1), param("ends", 2)) {
+                    (Some(starts), Some(ends)) => (starts, ends),
+                    _ => return Err(unsupported()),
+                };
+                let axes = param("axes", 3).unwrap_or_else(|| (0..starts.len() as i64).collect());
+                if param("steps", 4).is_some_and(|steps| steps.iter().any(|step| *step != 1)) {
+                    return Err(unsupported());
+                }
+                let rank = dims.len() as i64;
+                let mut pads = vec![0i64; dims.len() * 2];
+                for ((start, end), axis) in starts.iter().zip(ends.iter()).zip(axes.iter()) {
+                    let axis = if *axis < 0 { axis + rank } else { *axis } as usize;
+                    let dim = match dims.get(axis) {
+                        Some(Dim::Value(dim)) => *dim,
+                        _ => return Err(Error::UnknownShape(x.to_owned())),
+                    };
+                    let clamp = |index: i64| {
+                        let index = if index < 0 { index + dim } else { index };
+                        index.clamp(0, dim)
+                    };
+                    let (start, end) = (clamp(*start), clamp(*end).max(clamp(*start)));
+                    pads[axis] = start;
+                    pads[dims.len() + axis] = dim - end;
+                }
+                gx[0] = Some(self.op("Pad", &[gy], vec![make_attribute("pads", pads)]));
+            }
+            _ => return Err(unsupported()),
+        }
+        Ok(gx)
+    }
+
+    /// Returns gradient of clip passing values within bounds.
+    fn clip(&mut self, node: &NodeProto, gy: &str) -> String {
+        let x = node.input[0].as_str();
+        let bound = |emitter: &mut Self, name: &str, position: usize| match node
+            .input
+            .get(position)
+            .filter(|input| !input.is_empty())
+        {
+            Some(input) => Some(input.clone()),
+            None => node
+                .attribute
+                .iter()
+                .find(|attr| attr.name == name)
+                .map(|attr| emitter.scalar(attr.f)),
+        };
+        let mut masks = Vec::new();
+        for (name, position, op_type) in [("min", 1, "Less"), ("max", 2, "Greater")] {
+            if let Some(bound) = bound(self, name, position) {
+                let outside = self.op(op_type, &[x, &bound], vec![]);
+                masks.push(self.op("Not", &[&outside], vec![]));
+            }
+        }
+        let within = match masks.len() {
+            0 => return gy.to_owned(),
+            1 => masks.remove(0),
+            _ => self.op("And", &[&masks[0], &masks[1]], vec![]),
+        };
+        let mask = self.mask(&within);
+        self.op("Mul", &[gy, &mask], vec![])
+    }
+}
+
+/// Returns types of graph inputs, outputs, initializers and `value_info`.
+fn value_types(graph: &GraphProto) -> HashMap<String, TypeProto> {
+    let mut types: HashMap<String, TypeProto> = graph
+        .initializer
+        .iter()
+        .map(|tensor| {
+            let dims: Vec<i64> = tensor.dims.clone();
+            let typ = TypeProto {
+                value: Some(type_proto::Value::TensorType(type_proto::Tensor {
+                    elem_type: tensor.data_type,
+                    shape: Some(dims.into()),
+                })),
+                ..TypeProto::default()
+            };
+            (tensor.name.clone(), typ)
+        })
+        .collect();
+    for value in graph
+        .input
+        .iter()
+        .chain(graph.value_info.iter())
+        .chain(graph.output.iter())
+    {
+        if let Some(typ) = value.r#type.as_ref() {
+            types.insert(value.name.clone(), typ.clone());
+        }
+    }
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{checker, text};
+
+    fn parse(graph: &str) -> ModelProto {
+        text::parse_model(&format!(
+            "<ir_version: 6, opset_import: [\"\" : 11]> {}",
+            graph
+        ))
+        .unwrap()
+    }
+
+    fn ops(model: &ModelProto) -> Vec<String> {
+        model
+            .graph
+            .as_ref()
+            .unwrap()
+            .node
+            .iter()
+            .map(|node| format!("{}({})", node.op_type, node.input.join(", ")))
+            .collect()
+    }
+
+    #[test]
+    fn square() {
+        let model = parse("g (float[] X) => (float[] L) <float[] W = {3.0}> { L = Mul (W, W) }");
+        let model = gradients(&model, "L", &["W"]).unwrap();
+        assert_eq!(
+            ops(&model),
+            vec![
+                "Mul(W, W)",
+                "Shape(L)",
+                "ConstantOfShape(grad/Shape_0)",
+                "Mul(grad/ConstantOfShape_1, W)",
+                "Mul(grad/ConstantOfShape_1, W)",
+                "Sum(grad/Mul_2, grad/Mul_3)",
+                "Identity(grad/Sum_4)",
+            ]
+        );
+        let graph = model.graph.as_ref().unwrap();
+        assert_eq!(graph.output[1].name, "W_grad");
+        assert!(checker::check_model(&model).is_ok());
+    }
+
+    #[test]
+    fn linear_regression() {
+        let model = parse(
+            r#"
+            g (float[N,3] X, float[N,1] T) => (float[] L) <float[3,1] W = {0.1, 0.2, 0.3}, float[1] B = {0.0}, float[] U = {1.0}, float[] Lo = {0.0}, float[] Hi = {10.0}> {
+                P = MatMul (X, W)
+                Y = Add (P, B)
+                D = Sub (Y, T)
+                S = Mul (D, D)
+                C = Clip (S, Lo, Hi)
+                L = ReduceMean <keepdims = 0> (C)
+            }
+            "#,
+        );
+        let model = gradients(&model, "L", &["W", "B", "U"]).unwrap();
+        assert!(checker::check_model(&model).is_ok());
+        let graph = model.graph.as_ref().unwrap();
+        let bias = graph
+            .node
+            .iter()
+            .find(|node| node.output[0] == "B_grad")
+            .unwrap();
+        let reshape = graph
+            .node
+            .iter()
+            .find(|node| node.output[0] == bias.input[0])
+            .unwrap();
+        assert_eq!(reshape.op_type, "Reshape");
+        let zeros = graph
+            .node
+            .iter()
+            .find(|node| node.output[0] == "U_grad")
+            .unwrap();
+        assert_eq!(zeros.op_type, "ConstantOfShape");
+        assert_eq!(
+            text::print_type(graph.output[1].r#type.as_ref().unwrap()),
+            "float[3,1]"
+        );
+    }
+
+    #[test]
+    fn shape_ops() {
+        let model = text::parse_model(
+            r#"
+            <ir_version: 7, opset_import: ["" : 13]>
+            g (float[2,3] X) => (float[] L) <float[2,2] W = {1.0, 2.0, 3.0, 4.0}, int64[1] s = {1}, int64[1] e = {4}, int64[1] a = {1}> {
+                C = Concat <axis = -1> (X, W)
+                S = Slice (C, s, e, a)
+                R = Relu (S)
+                T = Tanh (R)
+                M = ReduceMax <keepdims = 0> (T)
+                L = Sqrt (M)
+            }
+            "#,
+        )
+        .unwrap();
+        let model = gradients(&model, "L", &["W"]).unwrap();
+        let graph = model.graph.as_ref().unwrap();
+        let node = |op_type: &str| {
+            graph
+                .node
+                .iter()
+                .find(|node| node.op_type == op_type && node.name.starts_with("grad/"))
+                .unwrap()
+        };
+        let pads = graph
+            .initializer
+            .iter()
+            .find(|tensor| tensor.name == node("Pad").input[1])
+            .unwrap();
+        assert_eq!(pads.int64_data, vec![0, 1, 0, 1]);
+        let split = graph
+            .initializer
+            .iter()
+            .find(|tensor| tensor.name == node("Split").input[1])
+            .unwrap();
+        assert_eq!(split.int64_data, vec![3, 2]);
+        assert_eq!(node("Split").attribute[0].i, 1);
+        assert_eq!(graph.output[1].name, "W_grad");
+    }
+
+    #[test]
+    fn errors() {
+        let model = parse("g (float[2] X) => (float[2] L) { L = Neg (X) }");
+        assert_eq!(
+            gradients(&model, "L", &["X"]).unwrap_err(),
+            Error::NonScalarLoss("L".to_owned())
+        );
+        assert_eq!(
+            gradients(&model, "L", &["W"]).unwrap_err(),
+            Error::UnknownValue("W".to_owned())
+        );
+        let model = parse(
+            "g (float[2] X) => (float[] L) { Y = Exp (X) \n L = ReduceSum <keepdims = 0> (Y) }",
+        );
+        assert_eq!(
+            gradients(&model, "L", &["X"]).unwrap_err().to_string(),
+            "Y (Exp): can not be differentiated"
+        );
+    }
+}
@@ -0,0 +1,967 @@
+//! JSON mapping of models.
+//!
+//! Follows the protocol buffers JSON mapping as produced by `MessageToJson`
+//! in the `onnx` Python package: field names are lowerCamelCase, 64-bit
+//! integers are strings, `bytes` fields (e.g. `rawData`) are base64 encoded,
+//! enums are written by name and fields with default values are omitted.
+//!
+//! Decoding also accepts original field names, numbers in place of enum names
+//! and 64-bit integer strings, and element type names (e.g. `"FLOAT"`) in
+//! place of `dataType`/`elemType` numbers.
+
+use serde_json::{Map, Value};
+
+use onnx_pb::{
+    attribute_proto::AttributeType,
+    tensor_proto::{DataLocation, DataType, Segment},
+    tensor_shape_proto::{dimension, Dimension},
+    type_proto, AttributeProto, GraphProto, ModelProto, NodeProto, OperatorSetIdProto,
+    SparseTensorProto, StringStringEntryProto, TensorAnnotation, TensorProto, TensorShapeProto,
+    TypeProto, ValueInfoProto,
+};
+
+/// JSON decoding error.
+#[derive(Debug)]
+pub enum Error {
+    /// Malformed JSON.
+    Json(serde_json::Error),
+
+    /// JSON not matching the model schema.
+    Invalid {
+        /// Path to the invalid field, e.g. `graph.node[1].attribute[0].i`.
+        path: String,
+        /// Error message.
+        message: String,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Json(err) => write!(f, "json error: {}", err),
+            Error::Invalid { path, message } => write!(f, "invalid field {}: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Json(err) => Some(err),
+            Error::Invalid { .. } => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+/// Converts model to a JSON value.
+pub fn model_to_json(model: &ModelProto) -> Value {
+    Encode::model(model)
+}
+
+/// Converts JSON value to a model.
+pub fn model_from_json(value: &Value) -> Result<ModelProto, Error> {
+    Decode::model(&Fields::new(value, String::new())?)
+}
+
+/// Encodes model as a JSON string.
+pub fn to_string(model: &ModelProto) -> String {
+    model_to_json(model).to_string()
+}
+
+/// Encodes model as a pretty-printed JSON string.
+pub fn to_string_pretty(model: &ModelProto) -> String {
+    // serializing a `Value` can't fail
+    serde_json::to_string_pretty(&model_to_json(model)).unwrap()
+}
+
+/// Decodes model from a JSON string.
+pub fn from_str(json: &str) -> Result<ModelProto, Error> {
+    model_from_json(&serde_json::from_str(json)?)
+}
+
+/// Serde helpers for models embedded in other structures.
+///
+/// ```ignore
+/// #[derive(Serialize, Deserialize)]
+/// struct Manifest {
+///     #[serde(with = "onnx_helpers::json::model")]
+///     model: ModelProto,
+/// }
+/// ```
+pub mod model {
+    use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+    use onnx_pb::ModelProto;
+
+    /// Serializes model using the JSON mapping.
+    pub fn serialize<S: Serializer>(model: &ModelProto, serializer: S) -> Result<S::Ok, S::Error> {
+        super::model_to_json(model).serialize(serializer)
+    }
+
+    /// Deserializes model using the JSON mapping.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ModelProto, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        super::model_from_json(&value).map_err(D::Error::custom)
+    }
+}
+
+/// JSON object builder omitting default values.
+#[derive(Default)]
+struct Object(Map<String, Value>);
+
+impl Object {
+    fn string(mut self, key: &str, value: &str) -> Self {
+        if !value.is_empty() {
+            self.0.insert(key.to_owned(), value.into());
+        }
+        self
+    }
+
+    fn bytes(mut self, key: &str, value: &[u8]) -> Self {
+        if !value.is_empty() {
+            self.0.insert(key.to_owned(), base64::encode(value).into());
+        }
+        self
+    }
+
+    fn int32(mut self, key: &str, value: i32) -> Self {
+        if value != 0 {
+            self.0.insert(key.to_owned(), value.into());
+        }
+        self
+    }
+
+    fn int64(mut self, key: &str, value: i64) -> Self {
+        if value != 0 {
+            self.0.insert(key.to_owned(), value.to_string().into());
+        }
+        self
+    }
+
+    fn float(mut self, key: &str, value: f32) -> Self {
+        if value != 0.0 || value.is_sign_negative() {
+            self.0.insert(key.to_owned(), float(value as f64));
+        }
+        self
+    }
+
+    fn enumeration(mut self, key: &str, value: i32, name: Option<&str>) -> Self {
+        if value != 0 {
+            let value = match name {
+                Some(name) => name.into(),
+                None => value.into(),
+            };
+            self.0.insert(key.to_owned(), value);
+        }
+        self
+    }
+
+    fn message(mut self, key: &str, value: Option<Value>) -> Self {
+        if let Some(value) = value {
+            self.0.insert(key.to_owned(), value);
+        }
+        self
+    }
+
+    fn repeated<T, F: Fn(&T) -> Value>(mut self, key: &str, values: &[T], f: F) -> Self {
+        if !values.is_empty() {
+            self.0.insert(
+                key.to_owned(),
+                values.iter().map(f).collect::<Vec<_>>().into(),
+            );
+        }
+        self
+    }
+
+    fn build(self) -> Value {
+        Value::Object(self.0)
+    }
+}
+
+fn float(value: f64) -> Value {
+    if value.is_nan() {
+        "NaN".into()
+    } else if value.is_infinite() {
+        if value > 0.0 { "Infinity" } else { "-Infinity" }.into()
+    } else {
+        value.into()
+    }
+}
+
+struct Encode;
+
+impl Encode {
+    fn model(model: &ModelProto) -> Value {
+        Object::default()
+            .int64("irVersion", model.ir_version)
+            .repeated("opsetImport", &model.opset_import, |opset| {
+                Object::default()
+                    .string("domain", &opset.domain)
+                    .int64("version", opset.version)
+                    .build()
+            })
+            .string("producerName", &model.producer_name)
+            .string("producerVersion", &model.producer_version)
+            .string("domain", &model.domain)
+            .int64("modelVersion", model.model_version)
+            .string("docString", &model.doc_string)
+            .message("graph", model.graph.as_ref().map(Encode::graph))
+            .repeated("metadataProps", &model.metadata_props, Encode::entry)
+            .build()
+    }
+
+    fn graph(graph: &GraphProto) -> Value {
+        Object::default()
+            .repeated("node", &graph.node, Encode::node)
+            .string("name", &graph.name)
+            .repeated("initializer", &graph.initializer, Encode::tensor)
+            .repeated(
+                "sparseInitializer",
+                &graph.sparse_initializer,
+                Encode::sparse,
+            )
+            .string("docString", &graph.doc_string)
+            .repeated("input", &graph.input, Encode::value_info)
+            .repeated("output", &graph.output, Encode::value_info)
+            .repeated("valueInfo", &graph.value_info, Encode::value_info)
+            .repeated(
+                "quantizationAnnotation",
+                &graph.quantization_annotation,
+                |annotation| {
+                    Object::default()
+                        .string("tensorName", &annotation.tensor_name)
+                        .repeated(
+                            "quantParameterTensorNames",
+                            &annotation.quant_parameter_tensor_names,
+                            Encode::entry,
+                        )
+                        .build()
+                },
+            )
+            .build()
+    }
+
+    fn node(node: &NodeProto) -> Value {
+        Object::default()
+            .repeated("input", &node.input, |s| s.as_str().into())
+            .repeated("output", &node.output, |s| s.as_str().into())
+            .string("name", &node.name)
+            .string("opType", &node.op_type)
+            .string("domain", &node.domain)
+            .repeated("attribute", &node.attribute, Encode::attribute)
+            .string("docString", &node.doc_string)
+            .build()
+    }
+
+    fn attribute(attr: &AttributeProto) -> Value {
+        let typ = AttributeType::from_i32(attr.r#type).map(attribute_type_name);
+        Object::default()
+            .string("name", &attr.name)
+            .string("refAttrName", &attr.ref_attr_name)
+            .string("docString", &attr.doc_string)
+            .enumeration("type", attr.r#type, typ)
+            .float("f", attr.f)
+            .int64("i", attr.i)
+            .bytes("s", &attr.s)
+            .message("t", attr.t.as_ref().map(Encode::tensor))
+            .message("g", attr.g.as_ref().map(Encode::graph))
+            .message(
+                "sparseTensor",
+                attr.sparse_tensor.as_ref().map(Encode::sparse),
+            )
+            .repeated("floats", &attr.floats, |v| float(*v as f64))
+            .repeated("ints", &attr.ints, |v| v.to_string().into())
+            .repeated("strings", &attr.strings, |v| base64::encode(v).into())
+            .repeated("tensors", &attr.tensors, Encode::tensor)
+            .repeated("graphs", &attr.graphs, Encode::graph)
+            .repeated("sparseTensors", &attr.sparse_tensors, Encode::sparse)
+            .build()
+    }
+
+    fn value_info(value: &ValueInfoProto) -> Value {
+        Object::default()
+            .string("name", &value.name)
+            .message("type", value.r#type.as_ref().map(Encode::type_proto))
+            .string("docString", &value.doc_string)
+            .build()
+    }
+
+    fn type_proto(typ: &TypeProto) -> Value {
+        let object = match typ.value.as_ref() {
+            Some(type_proto::Value::TensorType(tensor)) => Object::default().message(
+                "tensorType",
+                Some(
+                    Object::default()
+                        .int32("elemType", tensor.elem_type)
+                        .message("shape", tensor.shape.as_ref().map(Encode::shape))
+                        .build(),
+                ),
+            ),
+            Some(type_proto::Value::SequenceType(seq)) => Object::default().message(
+                "sequenceType",
+                Some(
+                    Object::default()
+                        .message(
+                            "elemType",
+                            seq.elem_type.as_ref().map(|t| Encode::type_proto(t)),
+                        )
+                        .build(),
+                ),
+            ),
+            Some(type_proto::Value::MapType(map)) => Object::default().message(
+                "mapType",
+                Some(
+                    Object::default()
+                        .int32("keyType", map.key_type)
+                        .message(
+                            "valueType",
+                            map.value_type.as_ref().map(|t| Encode::type_proto(t)),
+                        )
+                        .build(),
+                ),
+            ),
+            None => Object::default(),
+        };
+        object.string("denotation", &typ.denotation).build()
+    }
+
+    fn shape(shape: &TensorShapeProto) -> Value {
+        Object::default()
+            .repeated("dim", &shape.dim, |dim| {
+                let object = match dim.value.as_ref() {
+                    // oneof members are written even if they have default values
+                    Some(dimension::Value::DimValue(value)) => {
+                        Object::default().message("dimValue", Some(value.to_string().into()))
+                    }
+                    Some(dimension::Value::DimParam(param)) => {
+                        Object::default().message("dimParam", Some(param.as_str().into()))
+                    }
+                    None => Object::default(),
+                };
+                object.string("denotation", &dim.denotation).build()
+            })
+            .build()
+    }
+
+    fn tensor(tensor: &TensorProto) -> Value {
+        let location =
+            DataLocation::from_i32(tensor.data_location).map(|location| match location {
+                DataLocation::Default => "DEFAULT",
+                DataLocation::External => "EXTERNAL",
+            });
+        Object::default()
+            .repeated("dims", &tensor.dims, |v| v.to_string().into())
+            .int32("dataType", tensor.data_type)
+            .message(
+                "segment",
+                tensor.segment.as_ref().map(|segment| {
+                    Object::default()
+                        .int64("begin", segment.begin)
+                        .int64("end", segment.end)
+                        .build()
+                }),
+            )
+            .repeated("floatData", &tensor.float_data, |v| float(*v as f64))
+            .repeated("int32Data", &tensor.int32_data, |v| (*v).into())
+            .repeated("stringData", &tensor.string_data, |v| {
+                base64::encode(v).into()
+            })
+            .repeated("int64Data", &tensor.int64_data, |v| v.to_string().into())
+            .string("name", &tensor.name)
+            .string("docString", &tensor.doc_string)
+            .bytes("rawData", &tensor.raw_data)
+            .repeated("externalData", &tensor.external_data, Encode::entry)
+            .enumeration("dataLocation", tensor.data_location, location)
+            .repeated("doubleData", &tensor.double_data, |v| float(*v))
+            .repeated("uint64Data", &tensor.uint64_data, |v| v.to_string().into())
+            .build()
+    }
+
+    fn sparse(sparse: &SparseTensorProto) -> Value {
+        Object::default()
+            .message("values", sparse.values.as_ref().map(Encode::tensor))
+            .message("indices", sparse.indices.as_ref().map(Encode::tensor))
+            .repeated("dims", &sparse.dims, |v| v.to_string().into())
+            .build()
+    }
+
+    fn entry(entry: &StringStringEntryProto) -> Value {
+        Object::default()
+            .string("key", &entry.key)
+            .string("value", &entry.value)
+            .build()
+    }
+}
+
+/// JSON object reader tracking the path for errors.
+struct Fields<'a> {
+    map: &'a Map<String, Value>,
+    path: String,
+}
+
+impl<'a> Fields<'a> {
+    fn new(value: &'a Value, path: String) -> Result<Self, Error> {
+        match value {
+            Value::Object(map) => Ok(Fields { map, path }),
+            _ => Err(invalid(&path, "expected an object")),
+        }
+    }
+
+    /// Checks that the object contains only known fields.
+    fn known(&self, fields: &[&str]) -> Result<(), Error> {
+        for key in self.map.keys() {
+            if !fields
+                .iter()
+                .any(|field| *field == key || camel(field) == *key)
+            {
+                return Err(invalid(&self.path, &format!("unknown field `{}`", key)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns field by its original name or lowerCamelCase name.
+    fn get(&self, name: &str) -> Option<(&'a Value, String)> {
+        let path = if self.path.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}.{}", self.path, name)
+        };
+        self.map
+            .get(&camel(name))
+            .or_else(|| self.map.get(name))
+            .filter(|value| !value.is_null())
+            .map(|value| (value, path))
+    }
+
+    fn string(&self, name: &str) -> Result<String, Error> {
+        match self.get(name) {
+            Some((value, path)) => string(value, &path),
+            None => Ok(String::new()),
+        }
+    }
+
+    fn bytes(&self, name: &str) -> Result<Vec<u8>, Error> {
+        match self.get(name) {
+            Some((value, path)) => bytes(value, &path),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn int64(&self, name: &str) -> Result<i64, Error> {
+        match self.get(name) {
+            Some((value, path)) => int64(value, &path),
+            None => Ok(0),
+        }
+    }
+
+    fn float(&self, name: &str) -> Result<f32, Error> {
+        match self.get(name) {
+            Some((value, path)) => Ok(float64(value, &path)? as f32),
+            None => Ok(0.0),
+        }
+    }
+
+    fn data_type(&self, name: &str) -> Result<i32, Error> {
+        match self.get(name) {
+            Some((Value::String(typ), path)) => data_type_by_name(typ)
+                .map(|typ| typ as i32)
+                .ok_or_else(|| invalid(&path, &format!("unknown data type `{}`", typ))),
+            Some((value, path)) => int32(value, &path),
+            None => Ok(0),
+        }
+    }
+
+    fn enumeration<F>(&self, name: &str, by_name: F) -> Result<i32, Error>
+    where
+        F: Fn(&str) -> Option<i32>,
+    {
+        match self.get(name) {
+            Some((Value::String(variant), path)) => by_name(variant)
+                .ok_or_else(|| invalid(&path, &format!("unknown enum value `{}`", variant))),
+            Some((value, path)) => int32(value, &path),
+            None => Ok(0),
+        }
+    }
+
+    fn message<T, F>(&self, name: &str, f: F) -> Result<Option<T>, Error>
+    where
+        F: Fn(&Fields) -> Result<T, Error>,
+    {
+        match self.get(name) {
+            Some((value, path)) => Ok(Some(f(&Fields::new(value, path)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn repeated<T, F>(&self, name: &str, f: F) -> Result<Vec<T>, Error>
+    where
+        F: Fn(&Value, &str) -> Result<T, Error>,
+    {
+        match self.get(name) {
+            Some((Value::Array(values), path)) => values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| f(value, &format!("{}[{}]", path, index)))
+                .collect(),
+            Some((_, path)) => Err(invalid(&path, "expected an array")),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn messages<T, F>(&self, name: &str, f: F) -> Result<Vec<T>, Error>
+    where
+        F: Fn(&Fields) -> Result<T, Error>,
+    {
+        self.repeated(name, |value, path| f(&Fields::new(value, path.to_owned())?))
+    }
+}
+
+struct Decode;
+
+impl Decode {
+    fn model(fields: &Fields) -> Result<ModelProto, Error> {
+        fields.known(&[
+            "ir_version",
+            "opset_import",
+            "producer_name",
+            "producer_version",
+            "domain",
+            "model_version",
+            "doc_string",
+            "graph",
+            "metadata_props",
+        ])?;
+        Ok(ModelProto {
+            ir_version: fields.int64("ir_version")?,
+            opset_import: fields.messages("opset_import", |fields| {
+                fields.known(&["domain", "version"])?;
+                Ok(OperatorSetIdProto {
+                    domain: fields.string("domain")?,
+                    version: fields.int64("version")?,
+                })
+            })?,
+            producer_name: fields.string("producer_name")?,
+            producer_version: fields.string("producer_version")?,
+            domain: fields.string("domain")?,
+            model_version: fields.int64("model_version")?,
+            doc_string: fields.string("doc_string")?,
+            graph: fields.message("graph", Decode::graph)?,
+            metadata_props: fields.messages("metadata_props", Decode::entry)?,
+        })
+    }
+
+    fn graph(fields: &Fields) -> Result<GraphProto, Error> {
+        fields.known(&[
+            "node",
+            "name",
+            "initializer",
+            "sparse_initializer",
+            "doc_string",
+            "input",
+            "output",
+            "value_info",
+            "quantization_annotation",
+        ])?;
+        Ok(GraphProto {
+            node: fields.messages("node", Decode::node)?,
+            name: fields.string("name")?,
+            initializer: fields.messages("initializer", Decode::tensor)?,
+            sparse_initializer: fields.messages("sparse_initializer", Decode::sparse)?,
+            doc_string: fields.string("doc_string")?,
+            input: fields.messages("input", Decode::value_info)?,
+            output: fields.messages("output", Decode::value_info)?,
+            value_info: fields.messages("value_info", Decode::value_info)?,
+            quantization_annotation: fields.messages("quantization_annotation", |fields| {
+                fields.known(&["tensor_name", "quant_parameter_tensor_names"])?;
+                Ok(TensorAnnotation {
+                    tensor_name: fields.string("tensor_name")?,
+                    quant_parameter_tensor_names: fields
+                        .messages("quant_parameter_tensor_names", Decode::entry)?,
+                })
+            })?,
+        })
+    }
+
+    fn node(fields: &Fields) -> Result<NodeProto, Error> {
+        fields.known(&[
+            "input",
+            "output",
+            "name",
+            "op_type",
+            "domain",
+            "attribute",
+            "doc_string",
+        ])?;
+        Ok(NodeProto {
+            input: fields.repeated("input", string)?,
+            output: fields.repeated("output", string)?,
+            name: fields.string("name")?,
+            op_type: fields.string("op_type")?,
+            domain: fields.string("domain")?,
+            attribute: fields.messages("attribute", Decode::attribute)?,
+            doc_string: fields.string("doc_string")?,
+        })
+    }
+
+    fn attribute(fields: &Fields) -> Result<AttributeProto, Error> {
+        fields.known(&[
+            "name",
+            "ref_attr_name",
+            "doc_string",
+            "type",
+            "f",
+            "i",
+            "s",
+            "t",
+            "g",
+            "sparse_tensor",
+            "floats",
+            "ints",
+            "strings",
+            "tensors",
+            "graphs",
+            "sparse_tensors",
+        ])?;
+        Ok(AttributeProto {
+            name: fields.string("name")?,
+            ref_attr_name: fields.string("ref_attr_name")?,
+            doc_string: fields.string("doc_string")?,
+            r#type: fields.enumeration("type", |name| {
+                ATTRIBUTE_TYPES
+                    .iter()
+                    .find(|typ| attribute_type_name(**typ) == name)
+                    .map(|typ| *typ as i32)
+            })?,
+            f: fields.float("f")?,
+            i: fields.int64("i")?,
+            s: fields.bytes("s")?,
+            t: fields.message("t", Decode::tensor)?,
+            g: fields.message("g", Decode::graph)?,
+            sparse_tensor: fields.message("sparse_tensor", Decode::sparse)?,
+            floats: fields.repeated("floats", |v, p| Ok(float64(v, p)? as f32))?,
+            ints: fields.repeated("ints", int64)?,
+            strings: fields.repeated("strings", bytes)?,
+            tensors: fields.messages("tensors", Decode::tensor)?,
+            graphs: fields.messages("graphs", Decode::graph)?,
+            sparse_tensors: fields.messages("sparse_tensors", Decode::sparse)?,
+        })
+    }
+
+    fn value_info(fields: &Fields) -> Result<ValueInfoProto, Error> {
+        fields.known(&["name", "type", "doc_string"])?;
+        Ok(ValueInfoProto {
+            name: fields.string("name")?,
+            r#type: fields.message("type", Decode::type_proto)?,
+            doc_string: fields.string("doc_string")?,
+        })
+    }
+
+    fn type_proto(fields: &Fields) -> Result<TypeProto, Error> {
+        fields.known(&["tensor_type", "sequence_type", "map_type", "denotation"])?;
+        let tensor = fields.message("tensor_type", |fields| {
+            fields.known(&["elem_type", "shape"])?;
+            Ok(type_proto::Value::TensorType(type_proto::Tensor {
+                elem_type: fields.data_type("elem_type")?,
+                shape: fields.message("shape", Decode::shape)?,
+            }))
+        })?;
+        let sequence = fields.message("sequence_type", |fields| {
+            fields.known(&["elem_type"])?;
+            Ok(type_proto::Value::SequenceType(Box::new(
+                type_proto::Sequence {
+                    elem_type: fields
+                        .message("elem_type", Decode::type_proto)?
+                        .map(Box::new),
+                },
+            )))
+        })?;
+        let map = fields.message("map_type", |fields| {
+            fields.known(&["key_type", "value_type"])?;
+            Ok(type_proto::Value::MapType(Box::new(type_proto::Map {
+                key_type: fields.data_type("key_type")?,
+                value_type: fields
+                    .message("value_type", Decode::type_proto)?
+                    .map(Box::new),
+            })))
+        })?;
+        Ok(TypeProto {
+            value: tensor.or(sequence).or(map),
+            denotation: fields.string("denotation")?,
+        })
+    }
+
+    fn shape(fields: &Fields) -> Result<TensorShapeProto, Error> {
+        fields.known(&["dim"])?;
+        Ok(TensorShapeProto {
+            dim: fields.messages("dim", |fields| {
+                fields.known(&["dim_value", "dim_param", "denotation"])?;
+                let value = match fields.get("dim_value") {
+                    Some((value, path)) => Some(dimension::Value::DimValue(int64(value, &path)?)),
+                    None => match fields.get("dim_param") {
+                        Some((value, path)) => {
+                            Some(dimension::Value::DimParam(string(value, &path)?))
+                        }
+                        None => None,
+                    },
+                };
+                Ok(Dimension {
+                    value,
+                    denotation: fields.string("denotation")?,
+                })
+            })?,
+        })
+    }
+
+    fn tensor(fields: &Fields) -> Result<TensorProto, Error> {
+        fields.known(&[
+            "dims",
+            "data_type",
+            "segment",
+            "float_data",
+            "int32_data",
+            "string_data",
+            "int64_data",
+            "name",
+            "doc_string",
+            "raw_data",
+            "external_data",
+            "data_location",
+            "double_data",
+            "uint64_data",
+        ])?;
+        Ok(TensorProto {
+            dims: fields.repeated("dims", int64)?,
+            data_type: fields.data_type("data_type")?,
+            segment: fields.message("segment", |fields| {
+                fields.known(&["begin", "end"])?;
+                Ok(Segment {
+                    begin: fields.int64("begin")?,
+                    end: fields.int64("end")?,
+                })
+            })?,
+            float_data: fields.repeated("float_data", |v, p| Ok(float64(v, p)? as f32))?,
+            int32_data: fields.repeated("int32_data", int32)?,
+            string_data: fields.repeated("string_data", bytes)?,
+            int64_data: fields.repeated("int64_data", int64)?,
+            name: fields.string("name")?,
+            doc_string: fields.string("doc_string")?,
+            raw_data: fields.bytes("raw_data")?,
+            external_data: fields.messages("external_data", Decode::entry)?,
+            data_location: fields.enumeration("data_location", |name| match name {
+                "DEFAULT" => Some(DataLocation::Default as i32),
+                "EXTERNAL" => Some(DataLocation::External as i32),
+                _ => None,
+            })?,
+            double_data: fields.repeated("double_data", float64)?,
+            uint64_data: fields.repeated("uint64_data", |value, path| {
+                let number = match value {
+                    Value::String(s) => s.parse().ok(),
+                    value => value.as_u64(),
+                };
+                number.ok_or_else(|| invalid(path, "expected an unsigned integer"))
+            })?,
+        })
+    }
+
+    fn sparse(fields: &Fields) -> Result<SparseTensorProto, Error> {
+        fields.known(&["values", "indices", "dims"])?;
+        Ok(SparseTensorProto {
+            values: fields.message("values", Decode::tensor)?,
+            indices: fields.message("indices", Decode::tensor)?,
+            dims: fields.repeated("dims", int64)?,
+        })
+    }
+
+    fn entry(fields: &Fields) -> Result<StringStringEntryProto, Error> {
+        fields.known(&["key", "value"])?;
+        Ok(StringStringEntryProto {
+            key: fields.string("key")?,
+            value: fields.string("value")?,
+        })
+    }
+}
+
+const ATTRIBUTE_TYPES: &[AttributeType] = &[
+    AttributeType::Undefined,
+    AttributeType::Float,
+    AttributeType::Int,
+    AttributeType::String,
+    AttributeType::Tensor,
+    AttributeType::Graph,
+    AttributeType::SparseTensor,
+    AttributeType::Floats,
+    AttributeType::Ints,
+    AttributeType::Strings,
+    AttributeType::Tensors,
+    AttributeType::Graphs,
+    AttributeType::SparseTensors,
+];
+
+fn attribute_type_name(typ: AttributeType) -> &'static str {
+    match typ {
+        AttributeType::Undefined => "UNDEFINED",
+        AttributeType::Float => "FLOAT",
+        AttributeType::Int => "INT",
+        AttributeType::String => "STRING",
+        AttributeType::Tensor => "TENSOR",
+        AttributeType::Graph => "GRAPH",
+        AttributeType::SparseTensor => "SPARSE_TENSOR",
+        AttributeType::Floats => "FLOATS",
+        AttributeType::Ints => "INTS",
+        AttributeType::Strings => "STRINGS",
+        AttributeType::Tensors => "TENSORS",
+        AttributeType::Graphs => "GRAPHS",
+        AttributeType::SparseTensors => "SPARSE_TENSORS",
+    }
+}
+
+fn data_type_by_name(name: &str) -> Option<DataType> {
+    crate::text::data_type_by_name(&name.to_ascii_lowercase())
+}
+
+/// Converts snake_case field name to lowerCamelCase.
+fn camel(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn invalid(path: &str, message: &str) -> Error {
+    Error::Invalid {
+        path: path.to_owned(),
+        message: message.to_owned(),
+    }
+}
+
+fn string(value: &Value, path: &str) -> Result<String, Error> {
+    value
+        .as_str()
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| invalid(path, "expected a string"))
+}
+
+fn bytes(value: &Value, path: &str) -> Result<Vec<u8>, Error> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| invalid(path, "expected a base64 string"))?;
+    base64::decode(text)
+        .or_else(|_| base64::decode_config(text, base64::URL_SAFE))
+        .map_err(|err| invalid(path, &err.to_string()))
+}
+
+fn int32(value: &Value, path: &str) -> Result<i32, Error> {
+    let number = int64(value, path)?;
+    if number < i32::MIN as i64 || number > i32::MAX as i64 {
+        return Err(invalid(path, "integer out of range"));
+    }
+    Ok(number as i32)
+}
+
+fn int64(value: &Value, path: &str) -> Result<i64, Error> {
+    let number = match value {
+        Value::String(s) => s.parse().ok(),
+        value => value.as_i64(),
+    };
+    number.ok_or_else(|| invalid(path, "expected an integer"))
+}
+
+fn float64(value: &Value, path: &str) -> Result<f64, Error> {
+    match value {
+        Value::String(s) => match s.as_str() {
+            "NaN" => Ok(f64::NAN),
+            "Infinity" => Ok(f64::INFINITY),
+            "-Infinity" => Ok(f64::NEG_INFINITY),
+            s => s.parse().map_err(|_| invalid(path, "expected a number")),
+        },
+        value => value
+            .as_f64()
+            .ok_or_else(|| invalid(path, "expected a number")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::proto::open_model;
+
+    #[test]
+    fn roundtrip() {
+        let mut model = open_model("tests/mean-reverse.onnx").unwrap();
+        model.metadata_props.push(("key", "value").into());
+        let graph = model.graph.as_mut().unwrap();
+        graph.initializer.push(TensorProto {
+            name: "w".to_owned(),
+            dims: vec![2],
+            data_type: DataType::Float as i32,
+            raw_data: vec![0, 0, 128, 63, 0, 0, 0, 64],
+            ..TensorProto::default()
+        });
+        let json = to_string(&model);
+        assert_eq!(from_str(&json).unwrap(), model);
+        assert_eq!(from_str(&to_string_pretty(&model)).unwrap(), model);
+    }
+
+    #[test]
+    fn python_conventions() {
+        let model = open_model("tests/mean-reverse.onnx").unwrap();
+        let json = model_to_json(&model);
+        assert_eq!(json["irVersion"], "6");
+        assert_eq!(json["opsetImport"][0]["version"], "11");
+        let node = &json["graph"]["node"][0];
+        assert_eq!(node["opType"], "ReduceMean");
+        assert_eq!(node["attribute"][0]["type"], "INTS");
+        assert_eq!(node["attribute"][0]["ints"][0], "1");
+        let dim = &json["graph"]["input"][0]["type"]["tensorType"]["shape"]["dim"][0];
+        assert_eq!(dim["dimValue"], "1");
+    }
+
+    #[test]
+    fn decode_python_output() {
+        let model = from_str(
+            r#"{
+                "ir_version": 6,
+                "opsetImport": [{"version": "11"}],
+                "graph": {
+                    "initializer": [{
+                        "dims": ["2"],
+                        "dataType": "FLOAT",
+                        "rawData": "AACAPwAAAEA=",
+                        "name": "w"
+                    }],
+                    "node": [{"input": ["w"], "output": ["y"], "opType": "Abs"}],
+                    "output": [{"name": "y"}]
+                }
+            }"#,
+        )
+        .unwrap();
+        let graph = model.graph.unwrap();
+        assert_eq!(graph.initializer[0].data_type, DataType::Float as i32);
+        assert_eq!(
+            graph.initializer[0].raw_data,
+            vec![0, 0, 128, 63, 0, 0, 0, 64]
+        );
+
+        match from_str(r#"{"graph": {"nodes": []}}"#) {
+            Err(Error::Invalid { path, .. }) => assert_eq!(path, "graph"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
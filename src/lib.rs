@@ -1,19 +1,47 @@
 //! ONNX model construction helpers.
 
 pub mod builder;
+pub mod checker;
+pub mod convert;
+pub mod diff;
+pub mod dot;
+pub mod dsl;
+pub mod extract;
+pub mod fuse;
+pub mod grad;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod nn;
 pub mod nodes;
+pub mod opset;
+pub mod optim;
+pub mod plan;
+pub mod proto;
+pub mod quantize;
+pub mod rewrite;
+pub mod schema;
+mod shape;
+pub mod stats;
+pub mod tensor;
+pub mod text;
+pub mod visit;
+pub mod weights;
 
 pub mod prelude {
     pub use crate::builder;
     pub use crate::nodes::ops::*;
     pub use crate::nodes::*;
+    pub use crate::onnx_graph;
+    pub use crate::proto::{open_model, save_model};
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use onnx_pb::{open_model, tensor_proto::DataType};
+    use onnx_pb::tensor_proto::DataType;
+
+    use crate::proto::open_model;
 
     #[test]
     fn compare_with_prev_output() {
@@ -23,6 +51,18 @@ mod tests {
         let two = graph.constant("two", 2.0f32);
         let graph = graph.outputs_typed(-(&x - x.mean(1, true)) * two + x, DataType::Float);
         let model = graph.model().build();
-        assert_eq!(model, prev_output);
+        assert_model_eq!(model, prev_output);
+    }
+
+    #[test]
+    fn compare_with_text_output() {
+        let prev_output = std::fs::read_to_string("tests/mean-reverse.txt").unwrap();
+        let mut graph = builder::Graph::new("reverse");
+        let x = graph.input("X").typed(DataType::Float).dim(1).dim(6).node();
+        let two = graph.constant("two", 2.0f32);
+        let graph = graph.outputs_typed(-(&x - x.mean(1, true)) * two + x, DataType::Float);
+        let model = graph.model().build();
+        assert_eq!(text::print_model(&model), prev_output);
+        assert_eq!(text::parse_model(&prev_output).unwrap(), model);
     }
 }
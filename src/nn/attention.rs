@@ -0,0 +1,150 @@
+//! Multi-head attention layer.
+
+use onnx_pb::TensorProto;
+
+use crate::{
+    builder::{Graph, Tensor},
+    nn::{op, Linear, Module},
+    nodes::Node,
+};
+
+/// Multi-head self attention over inputs of shape `[batch, seq, embed]`.
+///
+/// Projections are linear layers named `{name}.query`, `{name}.key`,
+/// `{name}.value` and `{name}.out`, all of shape `[embed, embed]`, which
+/// have to be set before the layer is applied. The embedding size has to be
+/// divisible by the number of heads.
+#[derive(Clone)]
+pub struct MultiHeadAttention {
+    name: String,
+    num_heads: i64,
+    query: Option<Linear>,
+    key: Option<Linear>,
+    value: Option<Linear>,
+    out: Option<Linear>,
+}
+
+impl MultiHeadAttention {
+    /// Creates new layer without projections.
+    ///
+    /// # Panics
+    ///
+    /// Panics if number of heads is not positive.
+    pub fn new<S: Into<String>>(name: S, num_heads: i64) -> Self {
+        let name = name.into();
+        assert!(
+            num_heads > 0,
+            "{}: number of heads {} is not positive",
+            name,
+            num_heads
+        );
+        MultiHeadAttention {
+            name,
+            num_heads,
+            query: None,
+            key: None,
+            value: None,
+            out: None,
+        }
+    }
+
+    /// Sets query projection.
+    #[inline]
+    pub fn query<W: Into<TensorProto>, B: Into<TensorProto>>(mut self, weight: W, bias: B) -> Self {
+        self.query = Some(self.projection("query", weight, bias));
+        self
+    }
+
+    /// Sets key projection.
+    #[inline]
+    pub fn key<W: Into<TensorProto>, B: Into<TensorProto>>(mut self, weight: W, bias: B) -> Self {
+        self.key = Some(self.projection("key", weight, bias));
+        self
+    }
+
+    /// Sets value projection.
+    #[inline]
+    pub fn value<W: Into<TensorProto>, B: Into<TensorProto>>(mut self, weight: W, bias: B) -> Self {
+        self.value = Some(self.projection("value", weight, bias));
+        self
+    }
+
+    /// Sets output projection.
+    #[inline]
+    pub fn out<W: Into<TensorProto>, B: Into<TensorProto>>(mut self, weight: W, bias: B) -> Self {
+        self.out = Some(self.projection("out", weight, bias));
+        self
+    }
+
+    fn projection<W: Into<TensorProto>, B: Into<TensorProto>>(
+        &self,
+        name: &str,
+        weight: W,
+        bias: B,
+    ) -> Linear {
+        Linear::new(format!("{}.{}", self.name, name), weight).bias(bias)
+    }
+
+    /// Splits projected input into heads of shape `[batch, heads, seq, head]`,
+    /// or `[batch, heads, head, seq]` if transposed for keys.
+    fn heads(&self, g: &mut Graph, x: &Node, shape: &Node, transposed: bool) -> Node {
+        let x: Node = op(g, &self.name, "Reshape").input(x).input(shape).build();
+        let perm = if transposed {
+            vec![0i64, 2, 3, 1]
+        } else {
+            vec![0i64, 2, 1, 3]
+        };
+        op(g, &self.name, "Transpose")
+            .input(x)
+            .attribute("perm", perm)
+            .build()
+    }
+}
+
+impl Module for MultiHeadAttention {
+    fn forward(&self, g: &mut Graph, x: &Node) -> Node {
+        let (query, key, value, out) = match (&self.query, &self.key, &self.value, &self.out) {
+            (Some(query), Some(key), Some(value), Some(out)) => (query, key, value, out),
+            _ => panic!("{}: attention projections are not set", self.name),
+        };
+        let embed = query.out_features();
+        if embed % self.num_heads != 0 {
+            panic!(
+                "{}: embedding size {} is not divisible by {} heads",
+                self.name, embed, self.num_heads
+            );
+        }
+        let head = embed / self.num_heads;
+        let split = shape(g, &self.name, &[0, 0, self.num_heads, head]);
+        let q = query.forward(g, x);
+        let q = self.heads(g, &q, &split, false);
+        let k = key.forward(g, x);
+        let k = self.heads(g, &k, &split, true);
+        let v = value.forward(g, x);
+        let v = self.heads(g, &v, &split, false);
+        let scores: Node = op(g, &self.name, "MatMul").input(q).input(k).build();
+        let scores = scores * (1.0 / (head as f32).sqrt());
+        let probs: Node = op(g, &self.name, "Softmax")
+            .input(scores)
+            .attribute("axis", -1)
+            .build();
+        let context: Node = op(g, &self.name, "MatMul").input(probs).input(v).build();
+        let context: Node = op(g, &self.name, "Transpose")
+            .input(context)
+            .attribute("perm", vec![0i64, 2, 1, 3])
+            .build();
+        let merge = shape(g, &self.name, &[0, 0, embed]);
+        let context: Node = op(g, &self.name, "Reshape")
+            .input(context)
+            .input(merge)
+            .build();
+        out.forward(g, &context)
+    }
+}
+
+/// Creates constant shape for reshapes, where zeros copy input dimensions.
+fn shape(g: &mut Graph, scope: &str, dims: &[i64]) -> Node {
+    let name = g.unique_name(format!("{}/shape", scope));
+    let tensor = Tensor::from_slice(&[dims.len()], dims).unwrap();
+    g.constant(name, tensor)
+}
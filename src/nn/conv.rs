@@ -0,0 +1,89 @@
+//! Convolution layer.
+
+use onnx_pb::TensorProto;
+
+use crate::{
+    builder::Graph,
+    nn::{op, Module},
+    nodes::Node,
+};
+
+/// Two dimensional convolution layer.
+///
+/// Weight is of shape `[out_channels, in_channels / groups, height, width]`.
+#[derive(Clone)]
+pub struct Conv2d {
+    name: String,
+    weight: TensorProto,
+    bias: Option<TensorProto>,
+    stride: i64,
+    padding: i64,
+    dilation: i64,
+    groups: i64,
+}
+
+impl Conv2d {
+    /// Creates new layer with unit stride and no padding.
+    pub fn new<S: Into<String>, W: Into<TensorProto>>(name: S, weight: W) -> Self {
+        Conv2d {
+            name: name.into(),
+            weight: weight.into(),
+            bias: None,
+            stride: 1,
+            padding: 0,
+            dilation: 1,
+            groups: 1,
+        }
+    }
+
+    /// Sets bias of shape `[out_channels]`.
+    #[inline]
+    pub fn bias<B: Into<TensorProto>>(mut self, bias: B) -> Self {
+        self.bias = Some(bias.into());
+        self
+    }
+
+    /// Sets stride along both spatial axes.
+    #[inline]
+    pub fn stride(mut self, stride: i64) -> Self {
+        self.stride = stride;
+        self
+    }
+
+    /// Sets padding at both ends of both spatial axes.
+    #[inline]
+    pub fn padding(mut self, padding: i64) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets dilation along both spatial axes.
+    #[inline]
+    pub fn dilation(mut self, dilation: i64) -> Self {
+        self.dilation = dilation;
+        self
+    }
+
+    /// Sets number of groups of channels.
+    #[inline]
+    pub fn groups(mut self, groups: i64) -> Self {
+        self.groups = groups;
+        self
+    }
+}
+
+impl Module for Conv2d {
+    fn forward(&self, g: &mut Graph, x: &Node) -> Node {
+        let weight = g.parameter(format!("{}.weight", self.name), self.weight.clone());
+        let mut conv = op(g, &self.name, "Conv").input(x).input(weight);
+        if let Some(bias) = self.bias.as_ref() {
+            conv = conv.input(g.parameter(format!("{}.bias", self.name), bias.clone()));
+        }
+        conv.attribute("kernel_shape", self.weight.dims[2..].to_vec())
+            .attribute("strides", vec![self.stride; 2])
+            .attribute("pads", vec![self.padding; 4])
+            .attribute("dilations", vec![self.dilation; 2])
+            .attribute("group", self.groups)
+            .build()
+    }
+}
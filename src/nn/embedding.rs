@@ -0,0 +1,39 @@
+//! Embedding layer.
+
+use onnx_pb::TensorProto;
+
+use crate::{
+    builder::Graph,
+    nn::{op, Module},
+    nodes::Node,
+};
+
+/// Embedding layer looking up rows of weight by integer indices.
+///
+/// Weight is of shape `[num_embeddings, embedding_dim]`.
+#[derive(Clone)]
+pub struct Embedding {
+    name: String,
+    weight: TensorProto,
+}
+
+impl Embedding {
+    /// Creates new layer.
+    pub fn new<S: Into<String>, W: Into<TensorProto>>(name: S, weight: W) -> Self {
+        Embedding {
+            name: name.into(),
+            weight: weight.into(),
+        }
+    }
+}
+
+impl Module for Embedding {
+    fn forward(&self, g: &mut Graph, x: &Node) -> Node {
+        let weight = g.parameter(format!("{}.weight", self.name), self.weight.clone());
+        op(g, &self.name, "Gather")
+            .input(weight)
+            .input(x)
+            .attribute("axis", 0)
+            .build()
+    }
+}
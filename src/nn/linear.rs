@@ -0,0 +1,65 @@
+//! Linear layer.
+
+use onnx_pb::TensorProto;
+
+use crate::{
+    builder::Graph,
+    nn::{op, Module},
+    nodes::Node,
+    tensor,
+};
+
+/// Linear layer computing `x * weight^T + bias`.
+///
+/// Weight is of shape `[out_features, in_features]`. It is transposed when
+/// the layer is created and inserted as the `{name}.weight` initializer of
+/// shape `[in_features, out_features]`, so weights loaded into it later have
+/// to be transposed too.
+#[derive(Clone)]
+pub struct Linear {
+    name: String,
+    weight: TensorProto,
+    bias: Option<TensorProto>,
+}
+
+impl Linear {
+    /// Creates new layer without bias.
+    ///
+    /// # Panics
+    ///
+    /// Panics if weight is not a matrix.
+    pub fn new<S: Into<String>, W: Into<TensorProto>>(name: S, weight: W) -> Self {
+        let name = name.into();
+        let weight = tensor::transpose(&weight.into())
+            .unwrap_or_else(|| panic!("{}: weight is not a matrix", name));
+        Linear {
+            name,
+            weight,
+            bias: None,
+        }
+    }
+
+    /// Sets bias of shape `[out_features]`.
+    #[inline]
+    pub fn bias<B: Into<TensorProto>>(mut self, bias: B) -> Self {
+        self.bias = Some(bias.into());
+        self
+    }
+
+    /// Returns number of output features.
+    #[inline]
+    pub fn out_features(&self) -> i64 {
+        self.weight.dims[1]
+    }
+}
+
+impl Module for Linear {
+    fn forward(&self, g: &mut Graph, x: &Node) -> Node {
+        let weight = g.parameter(format!("{}.weight", self.name), self.weight.clone());
+        let y: Node = op(g, &self.name, "MatMul").input(x).input(weight).build();
+        match self.bias.as_ref() {
+            Some(bias) => y + g.parameter(format!("{}.bias", self.name), bias.clone()),
+            None => y,
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Multi-layer perceptron.
+
+use onnx_pb::TensorProto;
+
+use crate::{
+    builder::Graph,
+    nn::{Linear, Module},
+    nodes::Node,
+};
+
+/// Stack of linear layers with relu activations in between.
+#[derive(Clone)]
+pub struct Mlp {
+    name: String,
+    layers: Vec<Linear>,
+}
+
+impl Mlp {
+    /// Creates new perceptron without layers.
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Mlp {
+            name: name.into(),
+            layers: Vec::new(),
+        }
+    }
+
+    /// Appends linear layer named `{name}.layer{index}`.
+    pub fn layer<W: Into<TensorProto>, B: Into<TensorProto>>(mut self, weight: W, bias: B) -> Self {
+        let name = format!("{}.layer{}", self.name, self.layers.len());
+        self.layers.push(Linear::new(name, weight).bias(bias));
+        self
+    }
+}
+
+impl Module for Mlp {
+    fn forward(&self, g: &mut Graph, x: &Node) -> Node {
+        let mut y = x.clone();
+        for (index, layer) in self.layers.iter().enumerate() {
+            if index > 0 {
+                y = y.relu();
+            }
+            y = layer.forward(g, &y);
+        }
+        y
+    }
+}
@@ -0,0 +1,176 @@
+//! Neural network layers.
+//!
+//! Layers own their weights and insert them into a graph as initializers
+//! named after the layer, e.g. `encoder.layer0.weight`, when applied to an
+//! input with [`Module::forward`](trait.Module.html#tymethod.forward).
+
+mod attention;
+mod conv;
+mod embedding;
+mod linear;
+mod mlp;
+mod norm;
+
+pub use self::attention::*;
+pub use self::conv::*;
+pub use self::embedding::*;
+pub use self::linear::*;
+pub use self::mlp::*;
+pub use self::norm::*;
+
+use crate::{
+    builder::{self, Graph},
+    nodes::Node,
+};
+
+/// Neural network block applied to nodes of a graph.
+pub trait Module {
+    /// Inserts the block into a graph, returning its output.
+    fn forward(&self, g: &mut Graph, x: &Node) -> Node;
+}
+
+/// Creates node builder of an operator named within the scope of a layer.
+fn op(g: &mut Graph, scope: &str, op_type: &str) -> builder::Node {
+    let name = g.unique_name(format!("{}/{}", scope, op_type));
+    g.node(name).op(op_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use onnx_pb::{tensor_proto::DataType, TensorProto};
+
+    use crate::{builder::Tensor, checker};
+
+    fn weight(dims: &[usize]) -> TensorProto {
+        let len = dims.iter().product();
+        let values: Vec<f32> = (0..len).map(|index| index as f32 / len as f32).collect();
+        Tensor::from_slice(dims, &values).unwrap().build()
+    }
+
+    #[test]
+    fn encoder() {
+        let mut g = Graph::new("encoder");
+        let ids = g
+            .input("ids")
+            .typed(DataType::Int64)
+            .dim_param("batch")
+            .dim_param("seq")
+            .node();
+        let embedding = Embedding::new("encoder.embed", weight(&[10, 8]));
+        let norm = LayerNorm::new("encoder.norm", weight(&[8]), weight(&[8]));
+        let attention = MultiHeadAttention::new("encoder.attention", 2)
+            .query(weight(&[8, 8]), weight(&[8]))
+            .key(weight(&[8, 8]), weight(&[8]))
+            .value(weight(&[8, 8]), weight(&[8]))
+            .out(weight(&[8, 8]), weight(&[8]));
+        let mlp = Mlp::new("encoder.mlp")
+            .layer(weight(&[16, 8]), weight(&[16]))
+            .layer(weight(&[8, 16]), weight(&[8]));
+        let h = embedding.forward(&mut g, &ids);
+        let h = norm.forward(&mut g, &h);
+        let h = &h + attention.forward(&mut g, &h);
+        let y = mlp.forward(&mut g, &h);
+        let model = g.outputs_typed(y, DataType::Float).model().build();
+        assert!(checker::check_model(&model).is_ok());
+        let graph = model.graph.unwrap();
+        let mut names: Vec<&str> = graph.initializer.iter().map(|t| t.name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            vec![
+                "encoder.attention.key.bias",
+                "encoder.attention.key.weight",
+                "encoder.attention.out.bias",
+                "encoder.attention.out.weight",
+                "encoder.attention.query.bias",
+                "encoder.attention.query.weight",
+                "encoder.attention.value.bias",
+                "encoder.attention.value.weight",
+                "encoder.embed.weight",
+                "encoder.mlp.layer0.bias",
+                "encoder.mlp.layer0.weight",
+                "encoder.mlp.layer1.bias",
+                "encoder.mlp.layer1.weight",
+                "encoder.norm.bias",
+                "encoder.norm.weight",
+            ]
+        );
+        let count = |op_type: &str| graph.node.iter().filter(|n| n.op_type == op_type).count();
+        assert_eq!(count("Gather"), 1);
+        assert_eq!(count("Softmax"), 1);
+        assert_eq!(count("MatMul"), 8);
+        assert_eq!(count("Reshape"), 4);
+        assert_eq!(count("Relu"), 1);
+        // only attention heads are transposed, linear weights are stored transposed
+        assert_eq!(count("Transpose"), 4);
+    }
+
+    #[test]
+    fn shared_weights() {
+        let mut g = Graph::new("convnet");
+        let x = g
+            .input("X")
+            .typed(DataType::Float)
+            .dim(1)
+            .dim(3)
+            .dim(8)
+            .dim(8)
+            .node();
+        let conv = Conv2d::new("features.conv", weight(&[4, 3, 3, 3]))
+            .bias(weight(&[4]))
+            .padding(1);
+        let norm = BatchNorm::new(
+            "features.norm",
+            weight(&[4]),
+            weight(&[4]),
+            weight(&[4]),
+            weight(&[4]),
+        );
+        let head = Linear::new("head", weight(&[2, 8]));
+        let features = conv.forward(&mut g, &x);
+        let y = norm.forward(&mut g, &features).relu();
+        let left = head.forward(&mut g, &y);
+        let right = head.forward(&mut g, &(&y * 2.0));
+        let model = g
+            .outputs_typed(left, DataType::Float)
+            .outputs_typed(right, DataType::Float)
+            .model()
+            .build();
+        assert!(checker::check_model(&model).is_ok());
+        let graph = model.graph.unwrap();
+        assert_eq!(graph.initializer.len(), 7);
+        let conv = graph.node.iter().find(|n| n.op_type == "Conv").unwrap();
+        assert_eq!(conv.name, "features.conv/Conv");
+        assert_eq!(conv.input.len(), 3);
+        let mut matmuls: Vec<&str> = graph
+            .node
+            .iter()
+            .filter(|n| n.op_type == "MatMul")
+            .map(|n| n.name.as_str())
+            .collect();
+        matmuls.sort_unstable();
+        assert_eq!(matmuls, vec!["head/MatMul", "head/MatMul_1"]);
+        let head = graph
+            .initializer
+            .iter()
+            .find(|t| t.name == "head.weight")
+            .unwrap();
+        assert_eq!(head.dims, vec![8, 2]);
+        assert!(graph.node.iter().all(|n| n.op_type != "Transpose"));
+    }
+
+    #[test]
+    #[should_panic(expected = "attention: embedding size 8 is not divisible by 3 heads")]
+    fn attention_heads() {
+        let mut g = Graph::new("attention");
+        let x = g.input("X").typed(DataType::Float).node();
+        MultiHeadAttention::new("attention", 3)
+            .query(weight(&[8, 8]), weight(&[8]))
+            .key(weight(&[8, 8]), weight(&[8]))
+            .value(weight(&[8, 8]), weight(&[8]))
+            .out(weight(&[8, 8]), weight(&[8]))
+            .forward(&mut g, &x);
+    }
+}
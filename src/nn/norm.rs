@@ -0,0 +1,115 @@
+//! Normalization layers.
+
+use onnx_pb::TensorProto;
+
+use crate::{
+    builder::Graph,
+    nn::{op, Module},
+    nodes::Node,
+};
+
+/// Batch normalization layer using running statistics.
+///
+/// All parameters are of shape `[channels]`.
+#[derive(Clone)]
+pub struct BatchNorm {
+    name: String,
+    weight: TensorProto,
+    bias: TensorProto,
+    running_mean: TensorProto,
+    running_var: TensorProto,
+    epsilon: f32,
+}
+
+impl BatchNorm {
+    /// Creates new layer from scale, bias and running statistics.
+    pub fn new<S, W, B, M, V>(name: S, weight: W, bias: B, running_mean: M, running_var: V) -> Self
+    where
+        S: Into<String>,
+        W: Into<TensorProto>,
+        B: Into<TensorProto>,
+        M: Into<TensorProto>,
+        V: Into<TensorProto>,
+    {
+        BatchNorm {
+            name: name.into(),
+            weight: weight.into(),
+            bias: bias.into(),
+            running_mean: running_mean.into(),
+            running_var: running_var.into(),
+            epsilon: 1e-5,
+        }
+    }
+
+    /// Sets epsilon added to the variance, `1e-5` by default.
+    #[inline]
+    pub fn epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+}
+
+impl Module for BatchNorm {
+    fn forward(&self, g: &mut Graph, x: &Node) -> Node {
+        let weight = g.parameter(format!("{}.weight", self.name), self.weight.clone());
+        let bias = g.parameter(format!("{}.bias", self.name), self.bias.clone());
+        let mean = g.parameter(
+            format!("{}.running_mean", self.name),
+            self.running_mean.clone(),
+        );
+        let var = g.parameter(
+            format!("{}.running_var", self.name),
+            self.running_var.clone(),
+        );
+        op(g, &self.name, "BatchNormalization")
+            .inputs(vec![x, &weight, &bias, &mean, &var])
+            .attribute("epsilon", self.epsilon)
+            .build()
+    }
+}
+
+/// Layer normalization over the last axis.
+///
+/// Normalization is composed of reductions and arithmetic, so it is
+/// available before `LayerNormalization` of opset 17.
+#[derive(Clone)]
+pub struct LayerNorm {
+    name: String,
+    weight: TensorProto,
+    bias: TensorProto,
+    epsilon: f32,
+}
+
+impl LayerNorm {
+    /// Creates new layer from scale and bias of shape `[features]`.
+    pub fn new<S, W, B>(name: S, weight: W, bias: B) -> Self
+    where
+        S: Into<String>,
+        W: Into<TensorProto>,
+        B: Into<TensorProto>,
+    {
+        LayerNorm {
+            name: name.into(),
+            weight: weight.into(),
+            bias: bias.into(),
+            epsilon: 1e-5,
+        }
+    }
+
+    /// Sets epsilon added to the variance, `1e-5` by default.
+    #[inline]
+    pub fn epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+}
+
+impl Module for LayerNorm {
+    fn forward(&self, g: &mut Graph, x: &Node) -> Node {
+        let weight = g.parameter(format!("{}.weight", self.name), self.weight.clone());
+        let bias = g.parameter(format!("{}.bias", self.name), self.bias.clone());
+        let centered = x - x.mean(-1, true);
+        let variance = (&centered * &centered).mean(-1, true);
+        centered / (variance + self.epsilon).sqrt() * weight + bias
+    }
+}
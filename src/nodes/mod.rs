@@ -5,9 +5,12 @@ pub mod ops;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use onnx_pb::{Axes, NodeProto};
+use onnx_pb::{make_attribute, tensor_proto::DataType, Attribute, Axes, NodeProto};
 
-use crate::builder::Bag;
+use crate::{
+    builder::{self, Bag},
+    tensor,
+};
 
 /// Node wrapper.
 #[derive(Clone)]
@@ -42,7 +45,7 @@ impl Node {
                 .enumerate()
                 .for_each(|(index, output)| {
                     let name = format!("{}{}", name, index);
-                    maybe_bag_rename(&mut bag, &output, &name);
+                    maybe_bag_rename(&mut bag, output, &name);
                     *output = name;
                 });
             maybe_bag_rename(&mut bag, &inner.name, &name);
@@ -51,6 +54,35 @@ impl Node {
         self
     }
 
+    /// Sets node doc_string.
+    pub fn doc<S: Into<String>>(self, doc_string: S) -> Self {
+        self.inner.borrow_mut().doc_string = doc_string.into();
+        self
+    }
+
+    /// Sets node domain.
+    pub fn domain<S: Into<String>>(self, domain: S) -> Self {
+        self.inner.borrow_mut().domain = domain.into();
+        self
+    }
+
+    /// Sets node attribute, replacing an attribute of the same name.
+    pub fn attr<S: Into<String>, A: Into<Attribute>>(self, name: S, value: A) -> Self {
+        let attribute = make_attribute(name, value);
+        {
+            let mut inner = self.inner.borrow_mut();
+            match inner
+                .attribute
+                .iter_mut()
+                .find(|attr| attr.name == attribute.name)
+            {
+                Some(attr) => *attr = attribute,
+                None => inner.attribute.push(attribute),
+            }
+        }
+        self
+    }
+
     /// Creates new absolute operation.
     pub fn abs(&self) -> Node {
         let mut node: Node = ops::Abs::new(self.select_output()).into();
@@ -66,7 +98,8 @@ impl Node {
     }
 
     /// Creates new power operation.
-    pub fn pow<T: Into<String>>(&self, power: T) -> Node {
+    pub fn pow<T: Operand>(&self, power: T) -> Node {
+        let power = power.operand(self);
         let mut node: Node = ops::Pow::new(self.select_output(), power).into();
         maybe_bag_node(self.bag.clone(), &mut node);
         node
@@ -101,40 +134,78 @@ impl Node {
     }
 
     /// Creates new equal comparison operation.
-    pub fn equal<Rhs: Into<String>>(&self, right: Rhs) -> Node {
+    pub fn equal<Rhs: Operand>(&self, right: Rhs) -> Node {
+        let right = right.operand(self);
         let mut node: Node = ops::Equal::new(self.select_output(), right).into();
         maybe_bag_node(self.bag.clone(), &mut node);
         node
     }
 
     /// Creates new greater comparison operation.
-    pub fn greater<Rhs: Into<String>>(&self, right: Rhs) -> Node {
+    pub fn greater<Rhs: Operand>(&self, right: Rhs) -> Node {
+        let right = right.operand(self);
         let mut node: Node = ops::Greater::new(self.select_output(), right).into();
         maybe_bag_node(self.bag.clone(), &mut node);
         node
     }
 
     /// Creates new less comparison operation.
-    pub fn less<Rhs: Into<String>>(&self, right: Rhs) -> Node {
+    pub fn less<Rhs: Operand>(&self, right: Rhs) -> Node {
+        let right = right.operand(self);
         let mut node: Node = ops::Less::new(self.select_output(), right).into();
         maybe_bag_node(self.bag.clone(), &mut node);
         node
     }
 
     /// Creates new logical and operation.
-    pub fn and<Rhs: Into<String>>(&self, right: Rhs) -> Node {
+    pub fn and<Rhs: Operand>(&self, right: Rhs) -> Node {
+        let right = right.operand(self);
         let mut node: Node = ops::And::new(self.select_output(), right).into();
         maybe_bag_node(self.bag.clone(), &mut node);
         node
     }
 
     /// Creates new logical or operation.
-    pub fn or<Rhs: Into<String>>(&self, right: Rhs) -> Node {
+    pub fn or<Rhs: Operand>(&self, right: Rhs) -> Node {
+        let right = right.operand(self);
         let mut node: Node = ops::Or::new(self.select_output(), right).into();
         maybe_bag_node(self.bag.clone(), &mut node);
         node
     }
 
+    /// Creates new greater or equal comparison operation.
+    ///
+    /// Requires opset 12 or later.
+    pub fn greater_or_equal<Rhs: Operand>(&self, right: Rhs) -> Node {
+        let right = right.operand(self);
+        let mut node: Node = ops::GreaterOrEqual::new(self.select_output(), right).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Creates new less or equal comparison operation.
+    ///
+    /// Requires opset 12 or later.
+    pub fn less_or_equal<Rhs: Operand>(&self, right: Rhs) -> Node {
+        let right = right.operand(self);
+        let mut node: Node = ops::LessOrEqual::new(self.select_output(), right).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Creates new not equal comparison operation, negating equality.
+    pub fn not_equal<Rhs: Operand>(&self, right: Rhs) -> Node {
+        !self.equal(right)
+    }
+
+    /// Creates new logical xor operation.
+    pub fn xor<Rhs: Operand>(&self, right: Rhs) -> Node {
+        let right = right.operand(self);
+        let mut node: Node = ops::Xor::new(self.select_output(), right).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
     /// Creates new relu activation operation.
     pub fn relu(&self) -> Node {
         let mut node: Node = ops::Relu::new(self.select_output()).into();
@@ -156,6 +227,167 @@ impl Node {
         node
     }
 
+    /// Creates new squeeze operation.
+    pub fn squeeze<A: Into<Axes>>(&self, axes: A) -> Node {
+        let mut node: Node = ops::Squeeze::new(self.select_output(), axes).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Creates new unsqueeze operation.
+    pub fn unsqueeze<A: Into<Axes>>(&self, axes: A) -> Node {
+        let mut node: Node = ops::Unsqueeze::new(self.select_output(), axes).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Creates new clip operation.
+    pub fn clip(&self, min: f32, max: f32) -> Node {
+        let mut node: Node = ops::Clip::new(self.select_output(), min, max).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Creates new slice operation.
+    pub fn slice<A: Into<Axes>>(&self, starts: Vec<i64>, ends: Vec<i64>, axes: A) -> Node {
+        let mut node: Node = ops::Slice::new(self.select_output(), starts, ends, axes).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Creates new sequence at operation.
+    pub fn sequence_at<P: Into<String>>(&self, position: P) -> Node {
+        let mut node: Node = ops::SequenceAt::new(self.select_output(), position).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Creates new sequence insert operation, appending the tensor.
+    pub fn sequence_insert<T: Into<String>>(&self, tensor: T) -> Node {
+        let mut node: Node = ops::SequenceInsert::new(self.select_output(), tensor).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Creates new concat from sequence operation.
+    pub fn concat_from_sequence(&self, axis: i64, new_axis: bool) -> Node {
+        let mut node: Node =
+            ops::ConcatFromSequence::new(self.select_output(), axis, new_axis).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Creates new split to sequence operation, splitting into chunks of one.
+    pub fn split_to_sequence(&self, axis: i64, keepdims: bool) -> Node {
+        let mut node: Node = ops::SplitToSequence::new(self.select_output(), axis, keepdims).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Creates new quantize linear operation.
+    pub fn quantize<S: Into<String>, Z: Into<String>>(&self, scale: S, zero_point: Z) -> Node {
+        let mut node: Node =
+            ops::QuantizeLinear::new(self.select_output(), scale, zero_point).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Creates new dequantize linear operation.
+    pub fn dequantize<S: Into<String>, Z: Into<String>>(&self, scale: S, zero_point: Z) -> Node {
+        let mut node: Node =
+            ops::DequantizeLinear::new(self.select_output(), scale, zero_point).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Creates new dynamic quantize linear operation.
+    ///
+    /// Scale and zero point are available with [`output`](#method.output).
+    pub fn dynamic_quantize(&self) -> Node {
+        let mut node: Node = ops::DynamicQuantizeLinear::new(self.select_output()).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Returns name of node output at index.
+    ///
+    /// Panics if the node has no such output.
+    pub fn output(&self, index: usize) -> String {
+        self.inner.borrow().output[index].clone()
+    }
+
+    /// Returns node standing for output of given index, e.g. of `Split`.
+    ///
+    /// Panics if the node has no such output.
+    pub fn select(&self, index: usize) -> Node {
+        let mut node = Node::from_proto(NodeProto {
+            name: self.output(index),
+            ..NodeProto::default()
+        });
+        node.bag = self.bag.clone();
+        node
+    }
+
+    /// Names the first output of the node, e.g. after a graph value.
+    ///
+    /// Graph inputs and outputs which were already named or consumed are
+    /// forwarded through an identity node instead.
+    pub fn with_output_name<N: Into<String>>(self, name: N) -> Node {
+        let name = name.into();
+        let fresh = {
+            let inner = self.inner.borrow();
+            !inner.op_type.is_empty()
+                && inner.output.first() == Some(&format!("{}O", inner.name))
+                && !self
+                    .bag
+                    .as_ref()
+                    .is_some_and(|bag| bag.consumes(&inner.output[0]))
+        };
+        if fresh {
+            self.inner.borrow_mut().output[0] = name;
+            return self;
+        }
+        let mut node = builder::Node::new("Identity")
+            .name(format!("Identity_{}", name))
+            .input(self.select_output())
+            .output(name)
+            .build();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+
+    /// Returns constant holding a scalar in the element type of this node.
+    ///
+    /// Constants are added to the bag of the node once per value.
+    ///
+    /// Panics if the element type can not hold numbers, e.g. strings.
+    fn scalar(&self, value: f64, natural: DataType) -> String {
+        let data_type = self.elem_type().unwrap_or(natural);
+        let tensor = tensor::from_f64_vec(data_type, vec![], &[value]).unwrap_or_else(|| {
+            panic!(
+                "scalar {} can not be a constant of type {:?}",
+                value, data_type
+            )
+        });
+        let name = format!(
+            "scalar_{}_{}",
+            format!("{:?}", data_type).to_lowercase(),
+            value
+        );
+        let mut node: Node = ops::Constant::new(name.clone(), tensor).into();
+        if !self.bag.as_ref().is_some_and(|bag| bag.contains(&name)) {
+            maybe_bag_node(self.bag.clone(), &mut node);
+        }
+        node.select_output()
+    }
+
+    /// Returns element type of the node output when the bag knows it.
+    fn elem_type(&self) -> Option<DataType> {
+        self.bag
+            .as_ref()
+            .and_then(|bag| bag.elem_type(&self.select_output()))
+    }
+
     #[inline]
     fn select_output(&self) -> String {
         let node = self.inner.borrow();
@@ -170,23 +402,25 @@ impl Node {
 #[macro_export]
 macro_rules! impl_nodes_op {
     ( $t:ident, $k:ident, $f:ident ) => {
-        impl<Rhs: AsRef<Node>> std::ops::$k<Rhs> for $t {
+        impl<Rhs: Operand> std::ops::$k<Rhs> for $t {
             type Output = Node;
 
             #[inline(always)]
             fn $f(self, rhs: Rhs) -> Self::Output {
-                let mut node: Node = ops::$k::new(self.select_output(), rhs.as_ref()).into();
+                let rhs = rhs.operand(&self);
+                let mut node: Node = ops::$k::new(self.select_output(), rhs).into();
                 maybe_bag_node(self.bag.clone(), &mut node);
                 node
             }
         }
 
-        impl<Rhs: AsRef<Node>> std::ops::$k<Rhs> for &$t {
+        impl<Rhs: Operand> std::ops::$k<Rhs> for &$t {
             type Output = Node;
 
             #[inline(always)]
             fn $f(self, rhs: Rhs) -> Self::Output {
-                let mut node: Node = ops::$k::new(self.select_output(), rhs.as_ref()).into();
+                let rhs = rhs.operand(self);
+                let mut node: Node = ops::$k::new(self.select_output(), rhs).into();
                 maybe_bag_node(self.bag.clone(), &mut node);
                 node
             }
@@ -221,12 +455,241 @@ macro_rules! impl_node_op {
     };
 }
 
+/// Implements operators with a scalar on the left hand side.
+macro_rules! impl_scalar_op {
+    ( $s:ty, $k:ident, $f:ident ) => {
+        impl std::ops::$k<Node> for $s {
+            type Output = Node;
+
+            #[inline(always)]
+            fn $f(self, rhs: Node) -> Self::Output {
+                self.$f(&rhs)
+            }
+        }
+
+        impl std::ops::$k<&Node> for $s {
+            type Output = Node;
+
+            #[inline(always)]
+            fn $f(self, rhs: &Node) -> Self::Output {
+                let lhs = self.operand(rhs);
+                let mut node: Node = ops::$k::new(lhs, rhs.select_output()).into();
+                maybe_bag_node(rhs.bag.clone(), &mut node);
+                node
+            }
+        }
+    };
+    ( $($s:ty),* ) => {
+        $(
+            impl_scalar_op!($s, Add, add);
+            impl_scalar_op!($s, Sub, sub);
+            impl_scalar_op!($s, Mul, mul);
+            impl_scalar_op!($s, Div, div);
+        )*
+    };
+}
+
+/// Implements bitwise operators as logical operations on booleans.
+///
+/// Integer operands map to the bitwise operators which require opset 18,
+/// graphs of older operator sets fail in
+/// [`Graph::try_build`](../builder/struct.Graph.html#method.try_build).
+///
+/// # Panics
+///
+/// Panics if element type of neither operand is known or is not boolean or
+/// integer, use [`Node::and`](struct.Node.html#method.and) and similar
+/// methods to build logical operations on untyped values.
+macro_rules! impl_bitwise_op {
+    ( $k:ident, $f:ident, $logical:ident, $bitwise:ident ) => {
+        impl<Rhs: Operand> std::ops::$k<Rhs> for Node {
+            type Output = Node;
+
+            #[inline(always)]
+            fn $f(self, rhs: Rhs) -> Self::Output {
+                (&self).$f(rhs)
+            }
+        }
+
+        impl<Rhs: Operand> std::ops::$k<Rhs> for &Node {
+            type Output = Node;
+
+            fn $f(self, rhs: Rhs) -> Self::Output {
+                let rhs = rhs.operand(self);
+                let elem_type = self.elem_type().or_else(|| {
+                    self.bag.as_ref().and_then(|bag| bag.elem_type(&rhs))
+                });
+                let mut node: Node = match elem_type {
+                    Some(DataType::Bool) => ops::$logical::new(self.select_output(), rhs).into(),
+                    Some(data_type) if tensor::is_integer(data_type) => {
+                        ops::$bitwise::new(self.select_output(), rhs).into()
+                    }
+                    Some(data_type) => panic!(
+                        "{} of {:?} values of \"{}\" is not supported",
+                        stringify!($logical),
+                        data_type,
+                        self.select_output()
+                    ),
+                    None => panic!(
+                        "element type of \"{}\" is unknown, {} requires boolean or integer values",
+                        self.select_output(),
+                        stringify!($logical)
+                    ),
+                };
+                maybe_bag_node(self.bag.clone(), &mut node);
+                node
+            }
+        }
+    };
+}
+
+/// Implements shift operators on unsigned integers.
+macro_rules! impl_shift_op {
+    ( $k:ident, $f:ident, $left:expr ) => {
+        impl<Rhs: Operand> std::ops::$k<Rhs> for Node {
+            type Output = Node;
+
+            #[inline(always)]
+            fn $f(self, rhs: Rhs) -> Self::Output {
+                (&self).$f(rhs)
+            }
+        }
+
+        impl<Rhs: Operand> std::ops::$k<Rhs> for &Node {
+            type Output = Node;
+
+            #[inline(always)]
+            fn $f(self, rhs: Rhs) -> Self::Output {
+                let rhs = rhs.operand(self);
+                let mut node: Node = ops::BitShift::new(self.select_output(), rhs, $left).into();
+                maybe_bag_node(self.bag.clone(), &mut node);
+                node
+            }
+        }
+    };
+}
+
+/// Implements compound assignment through the binary operator.
+macro_rules! impl_assign_op {
+    ( $k:ident, $f:ident, $op:ident, $g:ident ) => {
+        impl<Rhs: Operand> std::ops::$k<Rhs> for Node {
+            #[inline(always)]
+            fn $f(&mut self, rhs: Rhs) {
+                *self = std::ops::$op::$g(&*self, rhs);
+            }
+        }
+    };
+}
+
+impl<Rhs: Operand> std::ops::Rem<Rhs> for Node {
+    type Output = Node;
+
+    #[inline(always)]
+    fn rem(self, rhs: Rhs) -> Self::Output {
+        &self % rhs
+    }
+}
+
+/// Remainder takes the sign of the dividend like Rust `%`, so `fmod` is set
+/// for integer as well as floating point and untyped values.
+impl<Rhs: Operand> std::ops::Rem<Rhs> for &Node {
+    type Output = Node;
+
+    fn rem(self, rhs: Rhs) -> Self::Output {
+        let rhs = rhs.operand(self);
+        let mut node: Node = ops::Mod::new(self.select_output(), rhs, true).into();
+        maybe_bag_node(self.bag.clone(), &mut node);
+        node
+    }
+}
+
 impl_nodes_op!(Node, Add, add);
 impl_nodes_op!(Node, Sub, sub);
 impl_nodes_op!(Node, Mul, mul);
 impl_nodes_op!(Node, Div, div);
 impl_node_op!(Node, Neg, neg);
 impl_node_op!(Node, Not, not);
+impl_scalar_op!(f32, f64, i32, i64);
+impl_bitwise_op!(BitAnd, bitand, And, BitwiseAnd);
+impl_bitwise_op!(BitOr, bitor, Or, BitwiseOr);
+impl_bitwise_op!(BitXor, bitxor, Xor, BitwiseXor);
+impl_shift_op!(Shl, shl, true);
+impl_shift_op!(Shr, shr, false);
+impl_assign_op!(AddAssign, add_assign, Add, add);
+impl_assign_op!(SubAssign, sub_assign, Sub, sub);
+impl_assign_op!(MulAssign, mul_assign, Mul, mul);
+impl_assign_op!(DivAssign, div_assign, Div, div);
+
+/// Operand of node operations: a node, a value name or a scalar.
+///
+/// Scalars become constants of the element type of the other operand when
+/// it is known, or of their own type otherwise. Scalars used with values of
+/// types which can not hold numbers, e.g. strings, panic.
+///
+/// Operators `&`, `|` and `^` build logical operations on booleans and
+/// bitwise operations, available from opset 18, on integers. They panic
+/// when element type of neither operand is known.
+pub trait Operand {
+    /// Returns name of the operand value used together with a node.
+    fn operand(self, node: &Node) -> String;
+}
+
+impl Operand for Node {
+    #[inline]
+    fn operand(self, _: &Node) -> String {
+        self.select_output()
+    }
+}
+
+impl Operand for &Node {
+    #[inline]
+    fn operand(self, _: &Node) -> String {
+        self.select_output()
+    }
+}
+
+impl Operand for String {
+    #[inline]
+    fn operand(self, _: &Node) -> String {
+        self
+    }
+}
+
+impl Operand for &String {
+    #[inline]
+    fn operand(self, _: &Node) -> String {
+        self.clone()
+    }
+}
+
+impl Operand for &str {
+    #[inline]
+    fn operand(self, _: &Node) -> String {
+        self.to_owned()
+    }
+}
+
+impl Operand for bool {
+    #[inline]
+    fn operand(self, node: &Node) -> String {
+        node.scalar(self as i64 as f64, DataType::Bool)
+    }
+}
+
+macro_rules! impl_scalar_operand {
+    ( $( $s:ty => $d:ident ),* ) => {
+        $(
+            impl Operand for $s {
+                #[inline]
+                fn operand(self, node: &Node) -> String {
+                    node.scalar(self as f64, DataType::$d)
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar_operand!(f32 => Float, f64 => Double, i32 => Int32, i64 => Int64);
 
 impl From<NodeProto> for Node {
     fn from(inner: NodeProto) -> Self {
@@ -234,9 +697,9 @@ impl From<NodeProto> for Node {
     }
 }
 
-impl Into<NodeProto> for Node {
-    fn into(self) -> NodeProto {
-        self.inner.borrow().clone()
+impl From<Node> for NodeProto {
+    fn from(node: Node) -> NodeProto {
+        node.inner.borrow().clone()
     }
 }
 
@@ -255,7 +718,7 @@ impl From<&Node> for String {
 impl AsRef<Node> for Node {
     #[inline(always)]
     fn as_ref(&self) -> &Node {
-        &self
+        self
     }
 }
 
@@ -273,3 +736,267 @@ pub(crate) fn maybe_bag_rename(bag: &mut Option<Bag>, name: &str, new_name: &str
         bag.rename(name, new_name);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{builder, checker, opset};
+
+    #[test]
+    fn scalar_operands() {
+        let mut graph = builder::Graph::new("scalars");
+        let x = graph.input("X").typed(DataType::Float).dim(2).node();
+        let n = graph.input("N").typed(DataType::Int64).dim(2).node();
+        let y = (&x * 2.0f32 + 1.0).pow(2.0) - (1.0 - &x);
+        let mask = x.greater(0.5).and(true);
+        let count = &n * 3;
+        let model = graph
+            .outputs_typed(y, DataType::Float)
+            .outputs_typed(mask, DataType::Bool)
+            .outputs_typed(count, DataType::Int64)
+            .model()
+            .build();
+        assert!(checker::check_model(&model).is_ok());
+        let mut constants: Vec<(String, i32)> = model
+            .graph
+            .unwrap()
+            .node
+            .iter()
+            .filter(|node| node.op_type == "Constant")
+            .map(|node| {
+                let value = node.attribute[0].t.as_ref().unwrap();
+                (node.name.clone(), value.data_type)
+            })
+            .collect();
+        constants.sort();
+        assert_eq!(
+            constants,
+            vec![
+                ("scalar_bool_1".to_owned(), DataType::Bool as i32),
+                ("scalar_float_0.5".to_owned(), DataType::Float as i32),
+                ("scalar_float_1".to_owned(), DataType::Float as i32),
+                ("scalar_float_2".to_owned(), DataType::Float as i32),
+                ("scalar_int64_3".to_owned(), DataType::Int64 as i32),
+            ]
+        );
+    }
+
+    #[test]
+    fn scalar_types() {
+        let mut graph = builder::Graph::new("scalar_types").opset(13);
+        let x = graph.input("X").typed(DataType::Float).dim(2).node();
+        let s = graph.input("S").typed(DataType::Float).node();
+        let z = graph.input("Z").typed(DataType::Uint8).node();
+        let a = graph.input("A").typed(DataType::Uint8).dim(2).dim(2).node();
+        let h = graph.input("H").typed(DataType::Float16).dim(2).node();
+        let b = graph.input("B").typed(DataType::Bfloat16).dim(2).node();
+        let q = graph
+            .node("q")
+            .op("QuantizeLinear")
+            .inputs(vec![&x, &s, &z])
+            .build();
+        let d = graph
+            .node("d")
+            .op("DequantizeLinear")
+            .inputs(vec![&q, &s, &z])
+            .build();
+        let m = graph
+            .node("m")
+            .op("MatMulInteger")
+            .inputs(vec![&a, &a])
+            .build();
+        let model = graph
+            .outputs_typed(&q + 1, DataType::Uint8)
+            .outputs_typed(&d * 2.0f32, DataType::Float)
+            .outputs_typed(&m * 3, DataType::Int32)
+            .outputs_typed(&h + 0.1, DataType::Float16)
+            .outputs_typed(&b * 1.5, DataType::Bfloat16)
+            .model()
+            .build();
+        assert!(checker::check_model(&model).is_ok());
+        let mut constants: Vec<(String, i32, Vec<i32>)> = model
+            .graph
+            .unwrap()
+            .node
+            .iter()
+            .filter(|node| node.op_type == "Constant")
+            .map(|node| {
+                let value = node.attribute[0].t.as_ref().unwrap();
+                (node.name.clone(), value.data_type, value.int32_data.clone())
+            })
+            .collect();
+        constants.sort();
+        assert_eq!(
+            constants,
+            vec![
+                (
+                    "scalar_bfloat16_1.5".to_owned(),
+                    DataType::Bfloat16 as i32,
+                    vec![0x3fc0]
+                ),
+                (
+                    "scalar_float16_0.1".to_owned(),
+                    DataType::Float16 as i32,
+                    vec![0x2e66]
+                ),
+                ("scalar_float_2".to_owned(), DataType::Float as i32, vec![]),
+                ("scalar_int32_3".to_owned(), DataType::Int32 as i32, vec![3]),
+                ("scalar_uint8_1".to_owned(), DataType::Uint8 as i32, vec![1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn scalar_parameter_types() {
+        let mut graph = builder::Graph::new("scalar_parameter_types");
+        let w = graph.parameter(
+            "w",
+            crate::tensor::from_slice(&[2], &[1.0f32, 2.0], crate::tensor::Encoding::Raw).unwrap(),
+        );
+        let model = graph
+            .outputs_typed(&w * 2.0, DataType::Float)
+            .model()
+            .build();
+        assert!(checker::check_model(&model).is_ok());
+        let graph = model.graph.unwrap();
+        let constant = graph
+            .node
+            .iter()
+            .find(|node| node.op_type == "Constant")
+            .unwrap();
+        assert_eq!(constant.name, "scalar_float_2");
+        assert_eq!(
+            constant.attribute[0].t.as_ref().unwrap().data_type,
+            DataType::Float as i32
+        );
+    }
+
+    #[test]
+    fn bitwise_operators_opset() {
+        let mut graph = builder::Graph::new("bitwise_opset");
+        let n = graph.input("N").typed(DataType::Int64).dim(2).node();
+        let graph = graph.outputs_typed(&n & 12, DataType::Int64);
+        assert_eq!(
+            graph.try_build(),
+            Err(opset::Error::NotYetAvailable {
+                op_type: "BitwiseAnd".to_owned(),
+                opset: opset::DEFAULT_VERSION,
+                since: 18,
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "element type of \"X\" is unknown")]
+    fn bitwise_operators_untyped() {
+        let mut graph = builder::Graph::new("bitwise_untyped");
+        let x = graph.input("X").node();
+        let y = graph.input("Y").node();
+        let _ = x & y;
+    }
+
+    #[test]
+    #[should_panic(expected = "And of Float values of \"X\" is not supported")]
+    fn bitwise_operators_float() {
+        let mut graph = builder::Graph::new("bitwise_float");
+        let x = graph.input("X").typed(DataType::Float).node();
+        let _ = x & 1.0;
+    }
+
+    #[test]
+    fn bitwise_operators() {
+        let mut graph = builder::Graph::new("bitwise").opset(18);
+        let x = graph.input("X").typed(DataType::Float).dim(2).node();
+        let n = graph.input("N").typed(DataType::Int64).dim(2).node();
+        let u = graph.input("U").typed(DataType::Uint8).dim(2).node();
+        let mut y = x.clone();
+        y += 1.0;
+        y *= &x;
+        y -= 0.5;
+        y /= 2.0;
+        let y = y % 3.0;
+        let mask = (x.greater_or_equal(0.0) & x.less_or_equal(1.0)) | x.not_equal(0.5);
+        let mask = mask ^ x.less(0.25).xor(true);
+        let bits = (&n & 12) | (&n % 5) ^ 1;
+        let shifted = (&u << &u) >> &u;
+        let model = graph
+            .outputs_typed(y, DataType::Float)
+            .outputs_typed(mask, DataType::Bool)
+            .outputs_typed(bits, DataType::Int64)
+            .outputs_typed(shifted, DataType::Uint8)
+            .model()
+            .build();
+        assert!(checker::check_model(&model).is_ok());
+        let graph = model.graph.unwrap();
+        let mut ops: Vec<&str> = graph
+            .node
+            .iter()
+            .map(|node| node.op_type.as_str())
+            .filter(|op| *op != "Constant")
+            .collect();
+        ops.sort_unstable();
+        assert_eq!(
+            ops,
+            vec![
+                "Add",
+                "And",
+                "BitShift",
+                "BitShift",
+                "BitwiseAnd",
+                "BitwiseOr",
+                "BitwiseXor",
+                "Div",
+                "Equal",
+                "GreaterOrEqual",
+                "Less",
+                "LessOrEqual",
+                "Mod",
+                "Mod",
+                "Mul",
+                "Not",
+                "Or",
+                "Sub",
+                "Xor",
+                "Xor",
+            ]
+        );
+        let mut fmod: Vec<(bool, Option<i64>)> = graph
+            .node
+            .iter()
+            .filter(|node| node.op_type == "Mod")
+            .map(|node| {
+                let fmod = node.attribute.iter().find(|attr| attr.name == "fmod");
+                (node.input[0] == "N", fmod.map(|attr| attr.i))
+            })
+            .collect();
+        fmod.sort();
+        assert_eq!(fmod, vec![(false, Some(1)), (true, Some(1))]);
+
+        let mut graph = builder::Graph::new("untyped");
+        let x = graph.input("X").node();
+        let graph = graph.outputs(x % 2.0).build();
+        assert_eq!(graph.node[1].op_type, "Mod");
+        assert_eq!(graph.node[1].attribute[0].name, "fmod");
+        assert_eq!(graph.node[1].attribute[0].i, 1);
+    }
+
+    #[test]
+    fn node_metadata() {
+        let mut graph = builder::Graph::new("metadata");
+        let x = graph.input("X").typed(DataType::Float).dim(2).node();
+        let y = x
+            .abs()
+            .doc("absolute value")
+            .domain("com.example")
+            .attr("alpha", 0.5f32)
+            .attr("alpha", 1.5f32);
+        let proto: NodeProto = y.into();
+        assert_eq!(proto.doc_string, "absolute value");
+        assert_eq!(proto.domain, "com.example");
+        assert_eq!(proto.attribute.len(), 1);
+        assert_eq!(proto.attribute[0].f, 1.5);
+        let graph = graph.build();
+        assert_eq!(graph.node[0].domain, "com.example");
+    }
+}
@@ -0,0 +1,24 @@
+//! BitShift operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// BitShift node.
+pub struct BitShift {
+    inner: Node,
+}
+
+impl BitShift {
+    /// Creates new bit shift operation, shifting left or right.
+    #[inline(always)]
+    pub fn new<Lhs: Into<String>, Rhs: Into<String>>(lhs: Lhs, rhs: Rhs, left: bool) -> Self {
+        BitShift {
+            inner: builder::Node::new("BitShift")
+                .input(lhs)
+                .input(rhs)
+                .attribute("direction", if left { "LEFT" } else { "RIGHT" })
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(BitShift);
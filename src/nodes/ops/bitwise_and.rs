@@ -0,0 +1,23 @@
+//! BitwiseAnd operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// BitwiseAnd node.
+pub struct BitwiseAnd {
+    inner: Node,
+}
+
+impl BitwiseAnd {
+    /// Creates new bitwise and operation.
+    #[inline(always)]
+    pub fn new<Lhs: Into<String>, Rhs: Into<String>>(lhs: Lhs, rhs: Rhs) -> Self {
+        BitwiseAnd {
+            inner: builder::Node::new("BitwiseAnd")
+                .input(lhs)
+                .input(rhs)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(BitwiseAnd);
@@ -0,0 +1,23 @@
+//! BitwiseOr operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// BitwiseOr node.
+pub struct BitwiseOr {
+    inner: Node,
+}
+
+impl BitwiseOr {
+    /// Creates new bitwise or operation.
+    #[inline(always)]
+    pub fn new<Lhs: Into<String>, Rhs: Into<String>>(lhs: Lhs, rhs: Rhs) -> Self {
+        BitwiseOr {
+            inner: builder::Node::new("BitwiseOr")
+                .input(lhs)
+                .input(rhs)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(BitwiseOr);
@@ -0,0 +1,23 @@
+//! BitwiseXor operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// BitwiseXor node.
+pub struct BitwiseXor {
+    inner: Node,
+}
+
+impl BitwiseXor {
+    /// Creates new bitwise xor operation.
+    #[inline(always)]
+    pub fn new<Lhs: Into<String>, Rhs: Into<String>>(lhs: Lhs, rhs: Rhs) -> Self {
+        BitwiseXor {
+            inner: builder::Node::new("BitwiseXor")
+                .input(lhs)
+                .input(rhs)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(BitwiseXor);
@@ -0,0 +1,24 @@
+//! Clip operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// Clip node.
+pub struct Clip {
+    inner: Node,
+}
+
+impl Clip {
+    /// Creates new clip operation.
+    #[inline(always)]
+    pub fn new<S: Into<String>>(input: S, min: f32, max: f32) -> Self {
+        Clip {
+            inner: builder::Node::new("Clip")
+                .input(input)
+                .attribute("min", min)
+                .attribute("max", max)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(Clip);
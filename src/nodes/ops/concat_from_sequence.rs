@@ -0,0 +1,24 @@
+//! ConcatFromSequence operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// ConcatFromSequence node.
+pub struct ConcatFromSequence {
+    inner: Node,
+}
+
+impl ConcatFromSequence {
+    /// Creates new concat from sequence operation.
+    #[inline(always)]
+    pub fn new<S: Into<String>>(input_sequence: S, axis: i64, new_axis: bool) -> Self {
+        ConcatFromSequence {
+            inner: builder::Node::new("ConcatFromSequence")
+                .input(input_sequence)
+                .attribute("axis", axis)
+                .attribute("new_axis", new_axis)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(ConcatFromSequence);
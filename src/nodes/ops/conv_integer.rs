@@ -0,0 +1,31 @@
+//! ConvInteger operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// ConvInteger node.
+pub struct ConvInteger {
+    inner: Node,
+}
+
+impl ConvInteger {
+    /// Creates new integer convolution operation.
+    #[inline(always)]
+    pub fn new<X, W, Zx, Zw>(x: X, w: W, x_zero_point: Zx, w_zero_point: Zw) -> Self
+    where
+        X: Into<String>,
+        W: Into<String>,
+        Zx: Into<String>,
+        Zw: Into<String>,
+    {
+        ConvInteger {
+            inner: builder::Node::new("ConvInteger")
+                .input(x)
+                .input(w)
+                .input(x_zero_point)
+                .input(w_zero_point)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(ConvInteger);
@@ -0,0 +1,28 @@
+//! DequantizeLinear operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// DequantizeLinear node.
+pub struct DequantizeLinear {
+    inner: Node,
+}
+
+impl DequantizeLinear {
+    /// Creates new dequantize linear operation.
+    #[inline(always)]
+    pub fn new<S: Into<String>, Sc: Into<String>, Z: Into<String>>(
+        input: S,
+        scale: Sc,
+        zero_point: Z,
+    ) -> Self {
+        DequantizeLinear {
+            inner: builder::Node::new("DequantizeLinear")
+                .input(input)
+                .input(scale)
+                .input(zero_point)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(DequantizeLinear);
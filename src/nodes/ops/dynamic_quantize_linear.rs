@@ -0,0 +1,31 @@
+//! DynamicQuantizeLinear operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// DynamicQuantizeLinear node.
+pub struct DynamicQuantizeLinear {
+    inner: Node,
+}
+
+impl DynamicQuantizeLinear {
+    /// Creates new dynamic quantize linear operation.
+    ///
+    /// Outputs are the quantized input, its scale and zero point, named
+    /// after the input with `_quantized`, `_scale` and `_zero_point` suffixes.
+    #[inline(always)]
+    pub fn new<S: Into<String>>(input: S) -> Self {
+        let input = input.into();
+        DynamicQuantizeLinear {
+            inner: builder::Node::new("DynamicQuantizeLinear")
+                .outputs(vec![
+                    format!("{}_quantized", input),
+                    format!("{}_scale", input),
+                    format!("{}_zero_point", input),
+                ])
+                .input(input)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(DynamicQuantizeLinear);
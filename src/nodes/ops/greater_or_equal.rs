@@ -0,0 +1,23 @@
+//! GreaterOrEqual operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// GreaterOrEqual node.
+pub struct GreaterOrEqual {
+    inner: Node,
+}
+
+impl GreaterOrEqual {
+    /// Creates new greater or equal comparison operation.
+    #[inline(always)]
+    pub fn new<Lhs: Into<String>, Rhs: Into<String>>(lhs: Lhs, rhs: Rhs) -> Self {
+        GreaterOrEqual {
+            inner: builder::Node::new("GreaterOrEqual")
+                .input(lhs)
+                .input(rhs)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(GreaterOrEqual);
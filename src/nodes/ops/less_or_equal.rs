@@ -0,0 +1,23 @@
+//! LessOrEqual operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// LessOrEqual node.
+pub struct LessOrEqual {
+    inner: Node,
+}
+
+impl LessOrEqual {
+    /// Creates new less or equal comparison operation.
+    #[inline(always)]
+    pub fn new<Lhs: Into<String>, Rhs: Into<String>>(lhs: Lhs, rhs: Rhs) -> Self {
+        LessOrEqual {
+            inner: builder::Node::new("LessOrEqual")
+                .input(lhs)
+                .input(rhs)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(LessOrEqual);
@@ -0,0 +1,31 @@
+//! MatMulInteger operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// MatMulInteger node.
+pub struct MatMulInteger {
+    inner: Node,
+}
+
+impl MatMulInteger {
+    /// Creates new integer matrix multiplication operation.
+    #[inline(always)]
+    pub fn new<A, B, Za, Zb>(a: A, b: B, a_zero_point: Za, b_zero_point: Zb) -> Self
+    where
+        A: Into<String>,
+        B: Into<String>,
+        Za: Into<String>,
+        Zb: Into<String>,
+    {
+        MatMulInteger {
+            inner: builder::Node::new("MatMulInteger")
+                .input(a)
+                .input(b)
+                .input(a_zero_point)
+                .input(b_zero_point)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(MatMulInteger);
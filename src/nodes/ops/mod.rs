@@ -1,79 +1,133 @@
 mod abs;
 mod add;
 mod and;
+mod bit_shift;
+mod bitwise_and;
+mod bitwise_or;
+mod bitwise_xor;
+mod clip;
 mod concat;
+mod concat_from_sequence;
 mod constant;
+mod conv_integer;
+mod dequantize_linear;
 mod div;
+mod dynamic_quantize_linear;
 mod equal;
 mod greater;
+mod greater_or_equal;
 mod less;
+mod less_or_equal;
+mod matmul_integer;
+mod modulo;
 mod mul;
 mod neg;
 mod not;
 mod or;
 mod pow;
+mod qlinear_conv;
+mod qlinear_matmul;
+mod quantize_linear;
 mod reduce_max;
 mod reduce_mean;
 mod reduce_min;
 mod reduce_sum;
 mod relu;
+mod sequence_at;
+mod sequence_construct;
+mod sequence_insert;
 mod size;
+mod slice;
+mod split_to_sequence;
 mod sqrt;
+mod squeeze;
 mod sub;
 mod tanh;
+mod unsqueeze;
+mod xor;
 
 pub use self::abs::*;
 pub use self::add::*;
 pub use self::and::*;
+pub use self::bit_shift::*;
+pub use self::bitwise_and::*;
+pub use self::bitwise_or::*;
+pub use self::bitwise_xor::*;
+pub use self::clip::*;
 pub use self::concat::*;
+pub use self::concat_from_sequence::*;
 pub use self::constant::*;
+pub use self::conv_integer::*;
+pub use self::dequantize_linear::*;
 pub use self::div::*;
+pub use self::dynamic_quantize_linear::*;
 pub use self::equal::*;
 pub use self::greater::*;
+pub use self::greater_or_equal::*;
 pub use self::less::*;
+pub use self::less_or_equal::*;
+pub use self::matmul_integer::*;
+pub use self::modulo::*;
 pub use self::mul::*;
 pub use self::neg::*;
 pub use self::not::*;
 pub use self::or::*;
 pub use self::pow::*;
+pub use self::qlinear_conv::*;
+pub use self::qlinear_matmul::*;
+pub use self::quantize_linear::*;
 pub use self::reduce_max::*;
 pub use self::reduce_mean::*;
 pub use self::reduce_min::*;
 pub use self::reduce_sum::*;
 pub use self::relu::*;
+pub use self::sequence_at::*;
+pub use self::sequence_construct::*;
+pub use self::sequence_insert::*;
 pub use self::size::*;
+pub use self::slice::*;
+pub use self::split_to_sequence::*;
 pub use self::sqrt::*;
+pub use self::squeeze::*;
 pub use self::sub::*;
 pub use self::tanh::*;
+pub use self::unsqueeze::*;
+pub use self::xor::*;
 
 #[macro_export]
 macro_rules! node_to_inner {
     (  $t: ty ) => {
-        impl Into<crate::nodes::Node> for $t {
+        impl From<$t> for $crate::nodes::Node {
             #[inline(always)]
-            fn into(self) -> crate::nodes::Node {
-                self.inner
+            fn from(op: $t) -> $crate::nodes::Node {
+                op.inner
             }
         }
 
-        impl Into<onnx_pb::NodeProto> for $t {
+        impl From<$t> for onnx_pb::NodeProto {
             #[inline(always)]
-            fn into(self) -> onnx_pb::NodeProto {
-                self.inner.into()
+            fn from(op: $t) -> onnx_pb::NodeProto {
+                op.inner.into()
             }
         }
 
-        impl Into<String> for &$t {
+        impl From<&$t> for String {
             #[inline(always)]
-            fn into(self) -> String {
-                (&self.inner).into()
+            fn from(op: &$t) -> String {
+                (&op.inner).into()
             }
         }
 
-        impl AsRef<crate::nodes::Node> for $t {
+        impl $crate::nodes::Operand for &$t {
             #[inline(always)]
+            fn operand(self, node: &$crate::nodes::Node) -> String {
+                $crate::nodes::Operand::operand(&self.inner, node)
+            }
+        }
+
+        impl AsRef<$crate::nodes::Node> for $t {
             #[inline(always)]
-            fn as_ref(&self) -> &crate::nodes::Node {
+            fn as_ref(&self) -> &$crate::nodes::Node {
                 &self.inner
             }
         }
@@ -0,0 +1,27 @@
+//! Mod operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// Mod node.
+pub struct Mod {
+    inner: Node,
+}
+
+impl Mod {
+    /// Creates new modulo operation.
+    ///
+    /// Integer remainder takes the sign of the divisor unless `fmod` is set,
+    /// which is required for floating point inputs.
+    #[inline(always)]
+    pub fn new<Lhs: Into<String>, Rhs: Into<String>>(lhs: Lhs, rhs: Rhs, fmod: bool) -> Self {
+        Mod {
+            inner: builder::Node::new("Mod")
+                .input(lhs)
+                .input(rhs)
+                .attribute("fmod", fmod)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(Mod);
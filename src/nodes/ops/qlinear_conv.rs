@@ -0,0 +1,27 @@
+//! QLinearConv operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// QLinearConv node.
+pub struct QLinearConv {
+    inner: Node,
+}
+
+impl QLinearConv {
+    /// Creates new quantized convolution operation.
+    ///
+    /// Inputs are `x`, `x_scale`, `x_zero_point`, `w`, `w_scale`,
+    /// `w_zero_point`, `y_scale`, `y_zero_point` and optional bias `B`.
+    #[inline(always)]
+    pub fn new<I>(inputs: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        QLinearConv {
+            inner: builder::Node::new("QLinearConv").inputs(inputs).build(),
+        }
+    }
+}
+
+node_to_inner!(QLinearConv);
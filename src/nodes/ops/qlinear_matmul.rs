@@ -0,0 +1,27 @@
+//! QLinearMatMul operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// QLinearMatMul node.
+pub struct QLinearMatMul {
+    inner: Node,
+}
+
+impl QLinearMatMul {
+    /// Creates new quantized matrix multiplication operation.
+    ///
+    /// Inputs are `a`, `a_scale`, `a_zero_point`, `b`, `b_scale`,
+    /// `b_zero_point`, `y_scale` and `y_zero_point`.
+    #[inline(always)]
+    pub fn new<I>(inputs: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        QLinearMatMul {
+            inner: builder::Node::new("QLinearMatMul").inputs(inputs).build(),
+        }
+    }
+}
+
+node_to_inner!(QLinearMatMul);
@@ -0,0 +1,28 @@
+//! QuantizeLinear operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// QuantizeLinear node.
+pub struct QuantizeLinear {
+    inner: Node,
+}
+
+impl QuantizeLinear {
+    /// Creates new quantize linear operation.
+    #[inline(always)]
+    pub fn new<S: Into<String>, Sc: Into<String>, Z: Into<String>>(
+        input: S,
+        scale: Sc,
+        zero_point: Z,
+    ) -> Self {
+        QuantizeLinear {
+            inner: builder::Node::new("QuantizeLinear")
+                .input(input)
+                .input(scale)
+                .input(zero_point)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(QuantizeLinear);
@@ -0,0 +1,23 @@
+//! SequenceAt operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// SequenceAt node.
+pub struct SequenceAt {
+    inner: Node,
+}
+
+impl SequenceAt {
+    /// Creates new sequence at operation.
+    #[inline(always)]
+    pub fn new<S: Into<String>, P: Into<String>>(input_sequence: S, position: P) -> Self {
+        SequenceAt {
+            inner: builder::Node::new("SequenceAt")
+                .input(input_sequence)
+                .input(position)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(SequenceAt);
@@ -0,0 +1,26 @@
+//! SequenceConstruct operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// SequenceConstruct node.
+pub struct SequenceConstruct {
+    inner: Node,
+}
+
+impl SequenceConstruct {
+    /// Creates new sequence construct operation.
+    #[inline(always)]
+    pub fn new<I>(inputs: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        SequenceConstruct {
+            inner: builder::Node::new("SequenceConstruct")
+                .inputs(inputs)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(SequenceConstruct);
@@ -0,0 +1,23 @@
+//! SequenceInsert operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// SequenceInsert node.
+pub struct SequenceInsert {
+    inner: Node,
+}
+
+impl SequenceInsert {
+    /// Creates new sequence insert operation.
+    #[inline(always)]
+    pub fn new<S: Into<String>, T: Into<String>>(input_sequence: S, tensor: T) -> Self {
+        SequenceInsert {
+            inner: builder::Node::new("SequenceInsert")
+                .input(input_sequence)
+                .input(tensor)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(SequenceInsert);
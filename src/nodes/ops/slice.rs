@@ -0,0 +1,32 @@
+//! Slice operation.
+
+use onnx_pb::Axes;
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// Slice node.
+pub struct Slice {
+    inner: Node,
+}
+
+impl Slice {
+    /// Creates new slice operation.
+    #[inline(always)]
+    pub fn new<S: Into<String>, A: Into<Axes>>(
+        input: S,
+        starts: Vec<i64>,
+        ends: Vec<i64>,
+        axes: A,
+    ) -> Self {
+        Slice {
+            inner: builder::Node::new("Slice")
+                .input(input)
+                .attribute("starts", starts)
+                .attribute("ends", ends)
+                .attribute("axes", axes.into())
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(Slice);
@@ -0,0 +1,24 @@
+//! SplitToSequence operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// SplitToSequence node.
+pub struct SplitToSequence {
+    inner: Node,
+}
+
+impl SplitToSequence {
+    /// Creates new split to sequence operation.
+    #[inline(always)]
+    pub fn new<S: Into<String>>(input: S, axis: i64, keepdims: bool) -> Self {
+        SplitToSequence {
+            inner: builder::Node::new("SplitToSequence")
+                .input(input)
+                .attribute("axis", axis)
+                .attribute("keepdims", keepdims)
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(SplitToSequence);
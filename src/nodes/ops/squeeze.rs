@@ -0,0 +1,25 @@
+//! Squeeze operation.
+
+use onnx_pb::Axes;
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// Squeeze node.
+pub struct Squeeze {
+    inner: Node,
+}
+
+impl Squeeze {
+    /// Creates new squeeze operation.
+    #[inline(always)]
+    pub fn new<S: Into<String>, A: Into<Axes>>(input: S, axes: A) -> Self {
+        Squeeze {
+            inner: builder::Node::new("Squeeze")
+                .input(input)
+                .attribute("axes", axes.into())
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(Squeeze);
@@ -0,0 +1,25 @@
+//! Unsqueeze operation.
+
+use onnx_pb::Axes;
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// Unsqueeze node.
+pub struct Unsqueeze {
+    inner: Node,
+}
+
+impl Unsqueeze {
+    /// Creates new unsqueeze operation.
+    #[inline(always)]
+    pub fn new<S: Into<String>, A: Into<Axes>>(input: S, axes: A) -> Self {
+        Unsqueeze {
+            inner: builder::Node::new("Unsqueeze")
+                .input(input)
+                .attribute("axes", axes.into())
+                .build(),
+        }
+    }
+}
+
+node_to_inner!(Unsqueeze);
@@ -0,0 +1,20 @@
+//! Xor operation.
+
+use crate::{builder, node_to_inner, nodes::Node};
+
+/// Xor node.
+pub struct Xor {
+    inner: Node,
+}
+
+impl Xor {
+    /// Creates new logical xor operation.
+    #[inline(always)]
+    pub fn new<Lhs: Into<String>, Rhs: Into<String>>(lhs: Lhs, rhs: Rhs) -> Self {
+        Xor {
+            inner: builder::Node::new("Xor").input(lhs).input(rhs).build(),
+        }
+    }
+}
+
+node_to_inner!(Xor);
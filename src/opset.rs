@@ -0,0 +1,496 @@
+//! Standard operator set versions.
+//!
+//! Records versions in which operators of the default `ai.onnx` domain were
+//! introduced or changed, and attributes which were turned into inputs.
+
+use std::collections::HashSet;
+
+use onnx_pb::{
+    attribute_proto::AttributeType, tensor_proto::DataType, AttributeProto, NodeProto, TensorProto,
+};
+
+use crate::tensor;
+
+/// Default operator set version of built models.
+pub const DEFAULT_VERSION: i64 = 11;
+
+/// Latest operator set version known to this crate.
+pub const MAX_VERSION: i64 = 18;
+
+/// Operator set error.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// Operator set version is not known to this crate.
+    UnknownVersion(i64),
+    /// Operator was introduced in a later operator set.
+    NotYetAvailable {
+        op_type: String,
+        opset: i64,
+        since: i64,
+    },
+    /// Operator was removed in an earlier operator set.
+    Removed {
+        op_type: String,
+        opset: i64,
+        removed: i64,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnknownVersion(opset) => write!(
+                f,
+                "opset {} is not supported, versions 1 to {} are known",
+                opset, MAX_VERSION
+            ),
+            Error::NotYetAvailable {
+                op_type,
+                opset,
+                since,
+            } => write!(
+                f,
+                "{} is not available in opset {}, it was introduced in opset {}",
+                op_type, opset, since
+            ),
+            Error::Removed {
+                op_type,
+                opset,
+                removed,
+            } => write!(
+                f,
+                "{} is not available in opset {}, it was removed in opset {}",
+                op_type, opset, removed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Versions in which standard operators were introduced or changed.
+const OPS: &[(&str, &[i64])] = &[
+    ("Abs", &[1, 6, 13]),
+    ("Acos", &[7]),
+    ("Acosh", &[9]),
+    ("Add", &[1, 6, 7, 13, 14]),
+    ("And", &[1, 7]),
+    ("ArgMax", &[1, 11, 12, 13]),
+    ("ArgMin", &[1, 11, 12, 13]),
+    ("Asin", &[7]),
+    ("Asinh", &[9]),
+    ("Atan", &[7]),
+    ("Atanh", &[9]),
+    ("AveragePool", &[1, 7, 10, 11]),
+    ("BatchNormalization", &[1, 6, 7, 9, 14, 15]),
+    ("Bernoulli", &[15]),
+    ("BitShift", &[11]),
+    ("BitwiseAnd", &[18]),
+    ("BitwiseNot", &[18]),
+    ("BitwiseOr", &[18]),
+    ("BitwiseXor", &[18]),
+    ("BlackmanWindow", &[17]),
+    ("Cast", &[1, 6, 9, 13]),
+    ("CastLike", &[15]),
+    ("Ceil", &[1, 6, 13]),
+    ("Celu", &[12]),
+    ("CenterCropPad", &[18]),
+    ("Clip", &[1, 6, 11, 12, 13]),
+    ("Col2Im", &[18]),
+    ("Compress", &[9, 11]),
+    ("Concat", &[1, 4, 11, 13]),
+    ("ConcatFromSequence", &[11]),
+    ("Constant", &[1, 9, 11, 12, 13]),
+    ("ConstantOfShape", &[9]),
+    ("Conv", &[1, 11]),
+    ("ConvInteger", &[10]),
+    ("ConvTranspose", &[1, 11]),
+    ("Cos", &[7]),
+    ("Cosh", &[9]),
+    ("CumSum", &[11, 14]),
+    ("DFT", &[17]),
+    ("DepthToSpace", &[1, 11, 13]),
+    ("DequantizeLinear", &[10, 13]),
+    ("Det", &[11]),
+    ("Div", &[1, 6, 7, 13, 14]),
+    ("Dropout", &[1, 6, 7, 10, 12, 13]),
+    ("DynamicQuantizeLinear", &[11]),
+    ("Einsum", &[12]),
+    ("Elu", &[1, 6]),
+    ("Equal", &[1, 7, 11, 13]),
+    ("Erf", &[9, 13]),
+    ("Exp", &[1, 6, 13]),
+    ("Expand", &[8, 13]),
+    ("EyeLike", &[9]),
+    ("Flatten", &[1, 9, 11, 13]),
+    ("Floor", &[1, 6, 13]),
+    ("GRU", &[1, 3, 7, 14]),
+    ("Gather", &[1, 11, 13]),
+    ("GatherElements", &[11, 13]),
+    ("GatherND", &[11, 12, 13]),
+    ("Gemm", &[1, 6, 7, 9, 11, 13]),
+    ("GlobalAveragePool", &[1]),
+    ("GlobalLpPool", &[1, 2]),
+    ("GlobalMaxPool", &[1]),
+    ("Greater", &[1, 7, 9, 13]),
+    ("GreaterOrEqual", &[12, 16]),
+    ("GridSample", &[16]),
+    ("GroupNormalization", &[18]),
+    ("HammingWindow", &[17]),
+    ("HannWindow", &[17]),
+    ("HardSigmoid", &[1, 6]),
+    ("HardSwish", &[14]),
+    ("Hardmax", &[1, 11, 13]),
+    ("Identity", &[1, 13, 14, 16]),
+    ("If", &[1, 11, 13, 16]),
+    ("InstanceNormalization", &[1, 6]),
+    ("IsInf", &[10]),
+    ("IsNaN", &[9, 13]),
+    ("LRN", &[1, 13]),
+    ("LSTM", &[1, 7, 14]),
+    ("LayerNormalization", &[17]),
+    ("LeakyRelu", &[1, 6, 16]),
+    ("Less", &[1, 7, 9, 13]),
+    ("LessOrEqual", &[12, 16]),
+    ("Log", &[1, 6, 13]),
+    ("LogSoftmax", &[1, 11, 13]),
+    ("Loop", &[1, 11, 13, 16]),
+    ("LpNormalization", &[1]),
+    ("LpPool", &[1, 2, 11, 18]),
+    ("MatMul", &[1, 9, 13]),
+    ("MatMulInteger", &[10]),
+    ("Max", &[1, 6, 8, 12, 13]),
+    ("MaxPool", &[1, 8, 10, 11, 12]),
+    ("MaxRoiPool", &[1]),
+    ("MaxUnpool", &[9, 11]),
+    ("Mean", &[1, 6, 8, 13]),
+    ("MeanVarianceNormalization", &[9, 13]),
+    ("MelWeightMatrix", &[17]),
+    ("Min", &[1, 6, 8, 12, 13]),
+    ("Mish", &[18]),
+    ("Mod", &[10, 13]),
+    ("Mul", &[1, 6, 7, 13, 14]),
+    ("Multinomial", &[7]),
+    ("Neg", &[1, 6, 13]),
+    ("NegativeLogLikelihoodLoss", &[12, 13]),
+    ("NonMaxSuppression", &[10, 11]),
+    ("NonZero", &[9, 13]),
+    ("Not", &[1]),
+    ("OneHot", &[9, 11]),
+    ("Optional", &[15]),
+    ("OptionalGetElement", &[15, 18]),
+    ("OptionalHasElement", &[15, 18]),
+    ("Or", &[1, 7]),
+    ("PRelu", &[1, 6, 7, 9, 16]),
+    ("Pad", &[1, 2, 11, 13, 18]),
+    ("Pow", &[1, 7, 12, 13, 15]),
+    ("QLinearConv", &[10]),
+    ("QLinearMatMul", &[10]),
+    ("QuantizeLinear", &[10, 13]),
+    ("RNN", &[1, 7, 14]),
+    ("RandomNormal", &[1]),
+    ("RandomNormalLike", &[1]),
+    ("RandomUniform", &[1]),
+    ("RandomUniformLike", &[1]),
+    ("Range", &[11]),
+    ("Reciprocal", &[1, 6, 13]),
+    ("ReduceL1", &[1, 11, 13, 18]),
+    ("ReduceL2", &[1, 11, 13, 18]),
+    ("ReduceLogSum", &[1, 11, 13, 18]),
+    ("ReduceLogSumExp", &[1, 11, 13, 18]),
+    ("ReduceMax", &[1, 11, 12, 13, 18]),
+    ("ReduceMean", &[1, 11, 13, 18]),
+    ("ReduceMin", &[1, 11, 12, 13, 18]),
+    ("ReduceProd", &[1, 11, 13, 18]),
+    ("ReduceSum", &[1, 11, 13]),
+    ("ReduceSumSquare", &[1, 11, 13, 18]),
+    ("Relu", &[1, 6, 13, 14]),
+    ("Reshape", &[1, 5, 13, 14]),
+    ("Resize", &[10, 11, 13, 18]),
+    ("ReverseSequence", &[10]),
+    ("RoiAlign", &[10, 16]),
+    ("Round", &[11]),
+    ("STFT", &[17]),
+    ("Scan", &[8, 9, 11, 16]),
+    ("Scatter", &[9, 11]),
+    ("ScatterElements", &[11, 13, 16, 18]),
+    ("ScatterND", &[11, 13, 16, 18]),
+    ("Selu", &[1, 6]),
+    ("SequenceAt", &[11]),
+    ("SequenceConstruct", &[11]),
+    ("SequenceEmpty", &[11]),
+    ("SequenceErase", &[11]),
+    ("SequenceInsert", &[11]),
+    ("SequenceLength", &[11]),
+    ("SequenceMap", &[17]),
+    ("Shape", &[1, 13, 15]),
+    ("Shrink", &[9]),
+    ("Sigmoid", &[1, 6, 13]),
+    ("Sign", &[9, 13]),
+    ("Sin", &[7]),
+    ("Sinh", &[9]),
+    ("Size", &[1, 13]),
+    ("Slice", &[1, 10, 11, 13]),
+    ("Softmax", &[1, 11, 13]),
+    ("SoftmaxCrossEntropyLoss", &[12, 13]),
+    ("Softplus", &[1]),
+    ("Softsign", &[1]),
+    ("SpaceToDepth", &[1, 13]),
+    ("Split", &[1, 2, 11, 13, 18]),
+    ("SplitToSequence", &[11]),
+    ("Sqrt", &[1, 6, 13]),
+    ("Squeeze", &[1, 11, 13]),
+    ("StringNormalizer", &[10]),
+    ("Sub", &[1, 6, 7, 13, 14]),
+    ("Sum", &[1, 6, 8, 13]),
+    ("Tan", &[7]),
+    ("Tanh", &[1, 6, 13]),
+    ("TfIdfVectorizer", &[9]),
+    ("ThresholdedRelu", &[10]),
+    ("Tile", &[1, 6, 13]),
+    ("TopK", &[1, 10, 11]),
+    ("Transpose", &[1, 13]),
+    ("Trilu", &[14]),
+    ("Unique", &[11]),
+    ("Unsqueeze", &[1, 11, 13]),
+    ("Upsample", &[7, 9]),
+    ("Where", &[9, 16]),
+    ("Xor", &[1, 7]),
+];
+
+/// Operators removed from the standard operator set and version of removal.
+const REMOVED: &[(&str, i64)] = &[("Scatter", 11), ("Upsample", 10)];
+
+/// Attribute names with positions of inputs replacing them.
+type AttributeInputs = &'static [(&'static str, usize)];
+
+/// Attributes which were turned into inputs: operator, version of the
+/// change and moved attributes.
+pub(crate) const ATTRIBUTE_INPUTS: &[(&str, i64, AttributeInputs)] = &[
+    ("Clip", 11, &[("min", 1), ("max", 2)]),
+    ("Dropout", 12, &[("ratio", 1)]),
+    ("Pad", 11, &[("pads", 1), ("value", 2)]),
+    ("ReduceL1", 18, &[("axes", 1)]),
+    ("ReduceL2", 18, &[("axes", 1)]),
+    ("ReduceLogSum", 18, &[("axes", 1)]),
+    ("ReduceLogSumExp", 18, &[("axes", 1)]),
+    ("ReduceMax", 18, &[("axes", 1)]),
+    ("ReduceMean", 18, &[("axes", 1)]),
+    ("ReduceMin", 18, &[("axes", 1)]),
+    ("ReduceProd", 18, &[("axes", 1)]),
+    ("ReduceSum", 13, &[("axes", 1)]),
+    ("ReduceSumSquare", 18, &[("axes", 1)]),
+    ("Slice", 10, &[("starts", 1), ("ends", 2), ("axes", 3)]),
+    ("Split", 13, &[("split", 1)]),
+    ("Squeeze", 13, &[("axes", 1)]),
+    ("TopK", 10, &[("k", 1)]),
+    ("Unsqueeze", 13, &[("axes", 1)]),
+    ("Upsample", 9, &[("scales", 1)]),
+];
+
+/// Returns versions in which a standard operator was introduced or changed.
+pub fn op_versions(op_type: &str) -> Option<&'static [i64]> {
+    OPS.iter()
+        .find(|(name, _)| *name == op_type)
+        .map(|(_, versions)| *versions)
+}
+
+/// Returns version of a standard operator used in an operator set.
+///
+/// Returns `Ok(None)` for operators unknown to this crate.
+pub fn op_version(op_type: &str, opset: i64) -> Result<Option<i64>, Error> {
+    if !(1..=MAX_VERSION).contains(&opset) {
+        return Err(Error::UnknownVersion(opset));
+    }
+    let versions = match op_versions(op_type) {
+        Some(versions) => versions,
+        None => return Ok(None),
+    };
+    if let Some((_, removed)) = REMOVED.iter().find(|(name, _)| *name == op_type) {
+        if opset >= *removed {
+            return Err(Error::Removed {
+                op_type: op_type.to_owned(),
+                opset,
+                removed: *removed,
+            });
+        }
+    }
+    match versions.iter().rev().find(|version| **version <= opset) {
+        Some(version) => Ok(Some(*version)),
+        None => Err(Error::NotYetAvailable {
+            op_type: op_type.to_owned(),
+            opset,
+            since: versions[0],
+        }),
+    }
+}
+
+/// Returns IR version required by an operator set version.
+pub(crate) fn ir_version(opset: i64) -> i64 {
+    match opset {
+        1..=8 => 3,
+        9 => 4,
+        10 => 5,
+        11 => 6,
+        12..=14 => 7,
+        _ => 8,
+    }
+}
+
+/// Returns true if a node belongs to the standard operator set.
+#[inline]
+pub(crate) fn is_default_domain(node: &NodeProto) -> bool {
+    node.domain.is_empty() || node.domain == "ai.onnx"
+}
+
+/// Rewrites attributes of a standard node which are inputs in an operator
+/// set, returning tensors which have to be added as initializers.
+///
+/// Nodes built by operator helpers carry all constant parameters as
+/// attributes and are brought into the form of the target operator set here.
+/// Scalar parameters of the data type, e.g. `min` of `Clip`, are converted
+/// to `elem_type` of the data input, float if unknown. Names of the tensors
+/// are made unique against `names`, which receives them.
+pub(crate) fn attributes_to_inputs(
+    node: &mut NodeProto,
+    opset: i64,
+    elem_type: Option<DataType>,
+    names: &mut HashSet<String>,
+) -> Vec<TensorProto> {
+    let moves = match ATTRIBUTE_INPUTS
+        .iter()
+        .find(|(op_type, since, _)| *op_type == node.op_type && opset >= *since)
+    {
+        Some((_, _, moves)) => *moves,
+        None => return Vec::new(),
+    };
+    let prefix = if node.name.is_empty() {
+        node.output.first().cloned().unwrap_or_default()
+    } else {
+        node.name.clone()
+    };
+    let mut initializers = Vec::new();
+    for (name, position) in moves.iter() {
+        let index = match node.attribute.iter().position(|attr| attr.name == *name) {
+            Some(index) => index,
+            None => continue,
+        };
+        let attr = node.attribute.remove(index);
+        let mut tensor = match attribute_tensor(&node.op_type, &attr, elem_type) {
+            Some(tensor) => tensor,
+            None => {
+                node.attribute.insert(index, attr);
+                continue;
+            }
+        };
+        tensor.name = format!("{}_{}", prefix, name);
+        if names.contains(&tensor.name) {
+            tensor.name = (1..)
+                .map(|index| format!("{}_{}_{}", prefix, name, index))
+                .find(|name| !names.contains(name))
+                .unwrap();
+        }
+        names.insert(tensor.name.clone());
+        if node.input.len() <= *position {
+            node.input.resize(*position + 1, String::new());
+        }
+        node.input[*position] = tensor.name.clone();
+        initializers.push(tensor);
+    }
+    initializers
+}
+
+/// Scalar attributes which become inputs of the data type.
+const DATA_TYPED: &[(&str, &str)] = &[("Clip", "min"), ("Clip", "max"), ("Pad", "value")];
+
+/// Converts a constant parameter attribute to its input tensor.
+fn attribute_tensor(
+    op_type: &str,
+    attr: &AttributeProto,
+    elem_type: Option<DataType>,
+) -> Option<TensorProto> {
+    Some(match AttributeType::from_i32(attr.r#type)? {
+        AttributeType::Ints => {
+            let mut tensor: TensorProto = attr.ints.clone().into();
+            tensor.dims = vec![attr.ints.len() as i64];
+            tensor
+        }
+        AttributeType::Floats => {
+            let mut tensor: TensorProto = attr.floats.clone().into();
+            tensor.dims = vec![attr.floats.len() as i64];
+            tensor
+        }
+        // `k` of `TopK` is a single element 1-D tensor
+        AttributeType::Int if op_type == "TopK" => attr.i.into(),
+        AttributeType::Int => TensorProto {
+            data_type: DataType::Int64 as i32,
+            int64_data: vec![attr.i],
+            ..TensorProto::default()
+        },
+        // `min` and `max` of `Clip` and `value` of `Pad` are of the data type
+        AttributeType::Float if DATA_TYPED.contains(&(op_type, attr.name.as_str())) => {
+            let elem_type = elem_type.unwrap_or(DataType::Float);
+            tensor::from_f64_vec(elem_type, Vec::new(), &[f64::from(attr.f)])?
+        }
+        AttributeType::Float => TensorProto {
+            data_type: DataType::Float as i32,
+            float_data: vec![attr.f],
+            ..TensorProto::default()
+        },
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions() {
+        assert_eq!(op_version("ReduceSum", 11), Ok(Some(11)));
+        assert_eq!(op_version("ReduceSum", 17), Ok(Some(13)));
+        assert_eq!(op_version("Custom", 11), Ok(None));
+        assert_eq!(
+            op_version("Range", 9).unwrap_err().to_string(),
+            "Range is not available in opset 9, it was introduced in opset 11"
+        );
+        assert_eq!(
+            op_version("Upsample", 10).unwrap_err().to_string(),
+            "Upsample is not available in opset 10, it was removed in opset 10"
+        );
+        assert_eq!(op_version("Abs", 99), Err(Error::UnknownVersion(99)));
+        assert_eq!(op_version("Upsample", 9), Ok(Some(9)));
+        assert_eq!(op_versions("Upsample"), Some(&[7, 9][..]));
+    }
+
+    #[test]
+    fn attribute_inputs() {
+        let attribute = |name: &str, f: f32| AttributeProto {
+            name: name.to_owned(),
+            r#type: AttributeType::Float as i32,
+            f,
+            ..AttributeProto::default()
+        };
+        let mut node = NodeProto {
+            name: "c".to_owned(),
+            op_type: "Clip".to_owned(),
+            input: vec!["x".to_owned()],
+            output: vec!["y".to_owned()],
+            attribute: vec![attribute("min", 0.0), attribute("max", 6.0)],
+            ..NodeProto::default()
+        };
+        let mut names: HashSet<String> = vec!["x", "y", "c_min"]
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+        let tensors = attributes_to_inputs(&mut node, 11, Some(DataType::Int32), &mut names);
+        assert!(node.attribute.is_empty());
+        assert_eq!(node.input, vec!["x", "c_min_1", "c_max"]);
+        assert_eq!(tensors.len(), 2);
+        assert_eq!(tensors[0].data_type, DataType::Int32 as i32);
+        assert_eq!(tensors[0].int32_data, vec![0]);
+        assert_eq!(tensors[1].int32_data, vec![6]);
+        assert!(tensors[1].dims.is_empty());
+        assert!(names.contains("c_min_1") && names.contains("c_max"));
+    }
+}
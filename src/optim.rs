@@ -0,0 +1,634 @@
+//! Graph optimization passes.
+//!
+//! Passes operate on a single graph in place and return the number of
+//! nodes they removed or replaced. Values used by subgraphs from an outer
+//! scope are taken into account, but subgraphs themselves are not optimized.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+use onnx_pb::{
+    attribute_proto::AttributeType, tensor_proto::DataType, GraphProto, NodeProto, TensorProto,
+};
+use prost::Message;
+
+use crate::{tensor, visit};
+
+/// Operators with results which can not be deduplicated.
+const NONDETERMINISTIC_OPS: &[&str] = &[
+    "Multinomial",
+    "RandomNormal",
+    "RandomNormalLike",
+    "RandomUniform",
+    "RandomUniformLike",
+];
+
+/// Runs all passes until the graph no longer changes.
+///
+/// Returns total number of removed or replaced nodes.
+pub fn optimize(graph: &mut GraphProto) -> usize {
+    let mut total = 0;
+    loop {
+        let changed = fold_constants(graph) + eliminate_common_subexpressions(graph) + prune(graph);
+        if changed == 0 {
+            return total;
+        }
+        total += changed;
+    }
+}
+
+/// Replaces nodes computed from constants with initializers.
+///
+/// `Constant` nodes are turned into initializers and elementwise arithmetic,
+/// `Cast`, `Identity`, `Shape`, `Reshape`, `Squeeze` and `Unsqueeze` of
+/// numeric constants are evaluated. Initializers which are also graph inputs
+/// can be overridden and are not considered constant.
+pub fn fold_constants(graph: &mut GraphProto) -> usize {
+    let inputs: HashSet<&str> = graph
+        .input
+        .iter()
+        .map(|value| value.name.as_str())
+        .collect();
+    let mut constants: HashMap<String, TensorProto> = graph
+        .initializer
+        .iter()
+        .filter(|tensor| !inputs.contains(tensor.name.as_str()))
+        .map(|tensor| (tensor.name.clone(), tensor.clone()))
+        .collect();
+    let before = graph.node.len();
+    let mut folded = Vec::new();
+    let mut nodes = Vec::with_capacity(before);
+    for node in graph.node.drain(..) {
+        match fold_node(&node, &constants) {
+            Some(mut tensor) => {
+                tensor.name = node.output[0].clone();
+                constants.insert(tensor.name.clone(), tensor.clone());
+                folded.push(tensor);
+            }
+            None => nodes.push(node),
+        }
+    }
+    let count = before - nodes.len();
+    graph.node = nodes;
+    graph.initializer.extend(folded);
+    count
+}
+
+/// Merges nodes with identical operators, attributes and inputs.
+///
+/// Nodes producing graph outputs are kept.
+pub fn eliminate_common_subexpressions(graph: &mut GraphProto) -> usize {
+    let outputs: HashSet<String> = graph
+        .output
+        .iter()
+        .map(|value| value.name.clone())
+        .collect();
+    let mut seen: HashMap<Vec<u8>, Vec<String>> = HashMap::new();
+    let mut replaced: Vec<(String, String)> = Vec::new();
+    let mut nodes = Vec::with_capacity(graph.node.len());
+    for mut node in graph.node.drain(..) {
+        for (from, to) in replaced.iter() {
+            visit::rename_node_inputs(&mut node, from, to);
+        }
+        if node.output.is_empty()
+            || NONDETERMINISTIC_OPS.contains(&node.op_type.as_str())
+            || node.output.iter().any(|output| outputs.contains(output))
+        {
+            nodes.push(node);
+            continue;
+        }
+        let key = node_key(&node);
+        match seen.get(&key) {
+            Some(previous) => {
+                for (from, to) in node.output.iter().zip(previous) {
+                    if !from.is_empty() {
+                        replaced.push((from.clone(), to.clone()));
+                    }
+                }
+            }
+            None => {
+                seen.insert(key, node.output.clone());
+                nodes.push(node);
+            }
+        }
+    }
+    graph.node = nodes;
+    let removed: HashSet<&str> = replaced.iter().map(|(from, _)| from.as_str()).collect();
+    graph
+        .value_info
+        .retain(|value| !removed.contains(value.name.as_str()));
+    replaced.len()
+}
+
+/// Removes nodes and initializers which do not contribute to graph outputs.
+///
+/// Graph inputs and initializers declared as graph inputs are kept.
+pub fn prune(graph: &mut GraphProto) -> usize {
+    let mut live: HashSet<String> = graph
+        .output
+        .iter()
+        .map(|value| value.name.clone())
+        .collect();
+    let mut keep = vec![false; graph.node.len()];
+    for (index, node) in graph.node.iter().enumerate().rev() {
+        if node.output.iter().any(|output| live.contains(output)) {
+            keep[index] = true;
+            live.extend(visit::node_references(node));
+        }
+    }
+    let before = graph.node.len();
+    let mut keep = keep.into_iter();
+    graph.node.retain(|_| keep.next().unwrap_or(true));
+
+    let inputs: HashSet<String> = graph.input.iter().map(|value| value.name.clone()).collect();
+    graph
+        .initializer
+        .retain(|tensor| live.contains(&tensor.name) || inputs.contains(&tensor.name));
+    let defined: HashSet<&str> = graph
+        .node
+        .iter()
+        .flat_map(|node| node.output.iter().map(String::as_str))
+        .collect();
+    graph
+        .value_info
+        .retain(|value| defined.contains(value.name.as_str()));
+    before - graph.node.len()
+}
+
+/// Returns key identifying node computation.
+fn node_key(node: &NodeProto) -> Vec<u8> {
+    let mut attributes = node.attribute.clone();
+    attributes.sort_by(|a, b| a.name.cmp(&b.name));
+    let proto = NodeProto {
+        input: node.input.clone(),
+        op_type: node.op_type.clone(),
+        domain: node.domain.clone(),
+        attribute: attributes,
+        ..NodeProto::default()
+    };
+    let mut key = Vec::with_capacity(proto.encoded_len());
+    proto.encode(&mut key).unwrap();
+    key
+}
+
+/// Numeric constant tensor.
+struct Constant {
+    data_type: DataType,
+    dims: Vec<i64>,
+    values: Vec<f64>,
+}
+
+impl Constant {
+    fn from_proto(tensor: &TensorProto) -> Option<Self> {
+        Some(Constant {
+            data_type: DataType::from_i32(tensor.data_type)?,
+            dims: tensor.dims.clone(),
+            values: tensor::to_f64_vec(tensor)?,
+        })
+    }
+
+    fn into_proto(self) -> Option<TensorProto> {
+        tensor::from_f64_vec(self.data_type, self.dims, &self.values)
+    }
+}
+
+/// Returns value of a `Constant` node.
+pub(crate) fn constant_value(node: &NodeProto) -> Option<TensorProto> {
+    let attr = node.attribute.first()?;
+    let tensor = match (attr.name.as_str(), AttributeType::from_i32(attr.r#type)?) {
+        ("value", AttributeType::Tensor) => attr.t.clone()?,
+        ("value_float", AttributeType::Float) => TensorProto {
+            data_type: DataType::Float as i32,
+            float_data: vec![attr.f],
+            ..TensorProto::default()
+        },
+        ("value_floats", AttributeType::Floats) => attr.floats.clone().into(),
+        ("value_int", AttributeType::Int) => TensorProto {
+            data_type: DataType::Int64 as i32,
+            int64_data: vec![attr.i],
+            ..TensorProto::default()
+        },
+        ("value_ints", AttributeType::Ints) => attr.ints.clone().into(),
+        _ => return None,
+    };
+    Some(tensor)
+}
+
+/// Evaluates node if all of its inputs are constant.
+fn fold_node(node: &NodeProto, constants: &HashMap<String, TensorProto>) -> Option<TensorProto> {
+    if (!node.domain.is_empty() && node.domain != "ai.onnx")
+        || node.output.len() != 1
+        || node.output[0].is_empty()
+    {
+        return None;
+    }
+    let mut inputs = Vec::with_capacity(node.input.len());
+    for input in node.input.iter() {
+        if input.is_empty() {
+            inputs.push(None);
+        } else {
+            inputs.push(Some(constants.get(input)?));
+        }
+    }
+    let input = |index: usize| inputs.get(index).cloned().flatten();
+    let attribute = |name: &str| node.attribute.iter().find(|attr| attr.name == name);
+
+    let output = match node.op_type.as_str() {
+        "Constant" => constant_value(node)?,
+        "Identity" => input(0)?.clone(),
+        "Shape" => {
+            let dims = &input(0)?.dims;
+            let rank = dims.len() as i64;
+            let bound = |name: &str, default: i64| {
+                let value = attribute(name).map_or(default, |attr| attr.i);
+                (if value < 0 { value + rank } else { value }).clamp(0, rank) as usize
+            };
+            let (start, end) = (bound("start", 0), bound("end", rank));
+            dims[start..end.max(start)].to_vec().into()
+        }
+        "Cast" => {
+            let data_type = DataType::from_i32(attribute("to")?.i as i32)?;
+            let tensor = input(0)?;
+            match tensor::to_i64_vec(tensor) {
+                Some(values) if tensor::is_integer(data_type) => {
+                    tensor::from_i64_vec(data_type, tensor.dims.clone(), &values)?
+                }
+                _ => {
+                    let mut constant = Constant::from_proto(tensor)?;
+                    constant.data_type = data_type;
+                    constant.into_proto()?
+                }
+            }
+        }
+        "Reshape" => {
+            let mut tensor = input(0)?.clone();
+            let shape = tensor::to_f64_vec(input(1)?)?;
+            tensor.dims = reshape(&tensor.dims, &shape)?;
+            tensor
+        }
+        "Squeeze" | "Unsqueeze" => {
+            let mut tensor = input(0)?.clone();
+            let axes: Vec<i64> = match (attribute("axes"), input(1)) {
+                (Some(attr), _) => attr.ints.clone(),
+                (None, Some(axes)) => tensor::to_f64_vec(axes)?
+                    .iter()
+                    .map(|v| *v as i64)
+                    .collect(),
+                (None, None) if node.op_type == "Squeeze" => (0..tensor.dims.len() as i64)
+                    .filter(|axis| tensor.dims[*axis as usize] == 1)
+                    .collect(),
+                (None, None) => return None,
+            };
+            tensor.dims = if node.op_type == "Squeeze" {
+                squeeze(&tensor.dims, &axes)?
+            } else {
+                unsqueeze(&tensor.dims, &axes)?
+            };
+            tensor
+        }
+        op => {
+            if let Some(unary) = unary_op(op) {
+                let mut constant = Constant::from_proto(input(0)?)?;
+                if tensor::is_integer(constant.data_type) {
+                    return None;
+                }
+                constant.values.iter_mut().for_each(|v| *v = unary(*v));
+                constant.into_proto()?
+            } else if let Some(binary) = binary_op(op) {
+                let (left, right) = (input(0)?, input(1)?);
+                if left.data_type != right.data_type || inputs.len() != 2 {
+                    return None;
+                }
+                let data_type = DataType::from_i32(left.data_type)?;
+                let dims = broadcast(&left.dims, &right.dims)?;
+                let count = dims.iter().product::<i64>() as usize;
+                if tensor::is_integer(data_type) {
+                    if data_type == DataType::Bool {
+                        return None;
+                    }
+                    let (a, b) = (tensor::to_i64_vec(left)?, tensor::to_i64_vec(right)?);
+                    let mut values = Vec::with_capacity(count);
+                    for index in 0..count {
+                        values.push(integer_binary_op(
+                            op,
+                            data_type,
+                            a[broadcast_index(index, &dims, &left.dims)],
+                            b[broadcast_index(index, &dims, &right.dims)],
+                        )?);
+                    }
+                    tensor::from_i64_vec(data_type, dims, &values)?
+                } else {
+                    let (a, b) = (tensor::to_f64_vec(left)?, tensor::to_f64_vec(right)?);
+                    let values: Vec<f64> = (0..count)
+                        .map(|index| {
+                            binary(
+                                a[broadcast_index(index, &dims, &left.dims)],
+                                b[broadcast_index(index, &dims, &right.dims)],
+                            )
+                        })
+                        .collect();
+                    tensor::from_f64_vec(data_type, dims, &values)?
+                }
+            } else {
+                return None;
+            }
+        }
+    };
+    Some(output)
+}
+
+fn unary_op(op: &str) -> Option<fn(f64) -> f64> {
+    Some(match op {
+        "Neg" => |v: f64| -v,
+        "Abs" => f64::abs,
+        "Sqrt" => f64::sqrt,
+        "Exp" => f64::exp,
+        "Log" => f64::ln,
+        "Reciprocal" => f64::recip,
+        "Floor" => f64::floor,
+        "Ceil" => f64::ceil,
+        "Relu" => |v: f64| v.max(0.0),
+        "Sigmoid" => |v: f64| 1.0 / (1.0 + (-v).exp()),
+        "Tanh" => f64::tanh,
+        _ => return None,
+    })
+}
+
+fn binary_op(op: &str) -> Option<fn(f64, f64) -> f64> {
+    Some(match op {
+        "Add" => |a, b| a + b,
+        "Sub" => |a, b| a - b,
+        "Mul" => |a, b| a * b,
+        "Div" => |a, b| a / b,
+        "Pow" => f64::powf,
+        "Max" => f64::max,
+        "Min" => f64::min,
+        _ => return None,
+    })
+}
+
+/// Evaluates binary operator on integers of a type, wrapping around on
+/// overflow.
+fn integer_binary_op(op: &str, data_type: DataType, a: i64, b: i64) -> Option<i64> {
+    let value = if data_type == DataType::Uint64 {
+        let (a, b) = (a as u64, b as u64);
+        (match op {
+            "Add" => a.wrapping_add(b),
+            "Sub" => a.wrapping_sub(b),
+            "Mul" => a.wrapping_mul(b),
+            "Div" => a.checked_div(b)?,
+            "Pow" => a.wrapping_pow(u32::try_from(b).ok()?),
+            "Max" => a.max(b),
+            "Min" => a.min(b),
+            _ => return None,
+        }) as i64
+    } else {
+        match op {
+            "Add" => a.wrapping_add(b),
+            "Sub" => a.wrapping_sub(b),
+            "Mul" => a.wrapping_mul(b),
+            "Div" if b == 0 => return None,
+            "Div" => a.wrapping_div(b),
+            "Pow" => a.wrapping_pow(u32::try_from(b).ok()?),
+            "Max" => a.max(b),
+            "Min" => a.min(b),
+            _ => return None,
+        }
+    };
+    Some(tensor::wrap_integer(data_type, value))
+}
+
+/// Returns shape of multidirectional broadcast.
+fn broadcast(left: &[i64], right: &[i64]) -> Option<Vec<i64>> {
+    let rank = left.len().max(right.len());
+    let dim = |dims: &[i64], axis: usize| {
+        let offset = rank - dims.len();
+        if axis < offset {
+            1
+        } else {
+            dims[axis - offset]
+        }
+    };
+    (0..rank)
+        .map(|axis| match (dim(left, axis), dim(right, axis)) {
+            (a, b) if a == b => Some(a),
+            (1, b) => Some(b),
+            (a, 1) => Some(a),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Maps flat index of a broadcast result to flat index of an operand.
+fn broadcast_index(mut index: usize, dims: &[i64], operand: &[i64]) -> usize {
+    let offset = dims.len() - operand.len();
+    let mut result = 0;
+    let mut stride = 1;
+    for axis in (0..dims.len()).rev() {
+        let position = index % dims[axis] as usize;
+        index /= dims[axis] as usize;
+        if axis >= offset {
+            let size = operand[axis - offset] as usize;
+            if size != 1 {
+                result += position * stride;
+            }
+            stride *= size;
+        }
+    }
+    result
+}
+
+fn reshape(dims: &[i64], shape: &[f64]) -> Option<Vec<i64>> {
+    let count: i64 = dims.iter().product();
+    let mut result: Vec<i64> = shape
+        .iter()
+        .enumerate()
+        .map(|(axis, dim)| match *dim as i64 {
+            0 => dims.get(axis).cloned().unwrap_or(0),
+            dim => dim,
+        })
+        .collect();
+    if let Some(position) = result.iter().position(|dim| *dim == -1) {
+        let known: i64 = result.iter().filter(|dim| **dim != -1).product();
+        if known == 0 || count % known != 0 {
+            return None;
+        }
+        result[position] = count / known;
+    }
+    if result.iter().any(|dim| *dim < 0) || result.iter().product::<i64>() != count {
+        return None;
+    }
+    Some(result)
+}
+
+fn squeeze(dims: &[i64], axes: &[i64]) -> Option<Vec<i64>> {
+    let rank = dims.len() as i64;
+    let axes: Vec<i64> = axes
+        .iter()
+        .map(|axis| if *axis < 0 { axis + rank } else { *axis })
+        .collect();
+    if axes
+        .iter()
+        .any(|axis| *axis < 0 || *axis >= rank || dims[*axis as usize] != 1)
+    {
+        return None;
+    }
+    Some(
+        dims.iter()
+            .enumerate()
+            .filter(|(axis, _)| !axes.contains(&(*axis as i64)))
+            .map(|(_, dim)| *dim)
+            .collect(),
+    )
+}
+
+fn unsqueeze(dims: &[i64], axes: &[i64]) -> Option<Vec<i64>> {
+    let rank = (dims.len() + axes.len()) as i64;
+    let mut axes: Vec<i64> = axes
+        .iter()
+        .map(|axis| if *axis < 0 { axis + rank } else { *axis })
+        .collect();
+    axes.sort_unstable();
+    if axes.iter().any(|axis| *axis < 0 || *axis >= rank) {
+        return None;
+    }
+    let mut result = dims.to_vec();
+    for axis in axes {
+        result.insert(axis as usize, 1);
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::text::{parse_graph, print_graph};
+
+    #[test]
+    fn fold_shape_computation() {
+        let mut graph = parse_graph(
+            r#"
+            g (float[2,3] X) => (float[3,2] Y) <float[2,1] a = {1.0, 2.0}, float[3] b = {1.0, 2.0, 3.0}> {
+                c = Add (a, b)
+                s = Shape (c)
+                t = Constant <value = int64[1] {-1}> ()
+                u = Unsqueeze <axes = [0]> (s)
+                r = Squeeze <axes = [0]> (u)
+                q = Reshape (r, t)
+                p = Cast <to = 1> (q)
+                Y = Reshape (X, r)
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(fold_constants(&mut graph), 7);
+        assert_eq!(graph.node.len(), 1);
+        let c = graph.initializer.iter().find(|t| t.name == "c").unwrap();
+        assert_eq!(c.dims, vec![2, 3]);
+        assert_eq!(c.float_data, vec![2.0, 3.0, 4.0, 3.0, 4.0, 5.0]);
+        let r = graph.initializer.iter().find(|t| t.name == "r").unwrap();
+        assert_eq!(r.int64_data, vec![2, 3]);
+        let p = graph.initializer.iter().find(|t| t.name == "p").unwrap();
+        assert_eq!(
+            (p.dims.clone(), p.float_data.clone()),
+            (vec![2], vec![2.0, 3.0])
+        );
+
+        assert_eq!(prune(&mut graph), 0);
+        assert_eq!(
+            graph
+                .initializer
+                .iter()
+                .map(|t| t.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["r"]
+        );
+    }
+
+    #[test]
+    fn fold_integers() {
+        let mut graph = parse_graph(
+            r#"
+            g () => (int64[2] s, int64[1] l, int64[1] e, int64[2] b, int32[2] w, int32[2] d, uint8[2] u, int32[1] n) <float[2,3,4] x = {0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0}, int64[2] big = {9007199254740993, -9007199254740993}, int32[2] i = {2147483647, -7}, int32[1] two = {2}, uint8[2] c = {250, 3}, uint8[1] ten = {10}, int64[1] huge = {4294967297}> {
+                s = Shape <start = 1> (x)
+                l = Shape <start = -1> (x)
+                e = Shape <start = 2, end = 1> (x)
+                b = Add (big, big)
+                w = Add (i, i)
+                d = Div (i, two)
+                u = Add (c, ten)
+                n = Cast <to = 6> (huge)
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(fold_constants(&mut graph), 8);
+        let value = |name: &str| graph.initializer.iter().find(|t| t.name == name).unwrap();
+        assert_eq!(value("s").int64_data, vec![3, 4]);
+        assert_eq!(value("l").int64_data, vec![4]);
+        assert_eq!(value("e").dims, vec![0]);
+        assert_eq!(
+            value("b").int64_data,
+            vec![18014398509481986, -18014398509481986]
+        );
+        assert_eq!(value("w").int32_data, vec![-2, -14]);
+        assert_eq!(value("d").int32_data, vec![1073741823, -3]);
+        assert_eq!(value("u").int32_data, vec![4, 13]);
+        assert_eq!(value("n").int32_data, vec![1]);
+    }
+
+    #[test]
+    fn common_subexpressions() {
+        let mut graph = parse_graph(
+            r#"
+            g (float[2] X) => (float[2] Y, float[2] Z) {
+                a = Neg (X)
+                b = Neg (X)
+                c = Mul (a, X)
+                d = Mul (b, X)
+                Y = Add (c, d)
+                Z = Neg (X)
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(eliminate_common_subexpressions(&mut graph), 2);
+        assert_eq!(
+            print_graph(&graph),
+            print_graph(
+                &parse_graph(
+                    r#"
+                    g (float[2] X) => (float[2] Y, float[2] Z) {
+                        a = Neg (X)
+                        c = Mul (a, X)
+                        Y = Add (c, c)
+                        Z = Neg (X)
+                    }
+                    "#
+                )
+                .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn prune_unused() {
+        let mut graph = parse_graph(
+            r#"
+            g (float[2] X, float[2] W) => (float[2] Y) <float[2] W = {1.0, 2.0}, float[2] u = {0.0, 0.0}> {
+                a = Neg (u)
+                Y = If <
+                    then_branch = then () => (float[2] T) { T = Abs (X) },
+                    else_branch = else () => (float[2] T) { T = Neg (X) }
+                > (X)
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(prune(&mut graph), 1);
+        assert_eq!(graph.node.len(), 1);
+        assert_eq!(graph.initializer.len(), 1);
+        assert_eq!(graph.initializer[0].name, "W");
+    }
+}
@@ -0,0 +1,405 @@
+//! Execution plans with memory planning.
+//!
+//! [`plan`](fn.plan.html) orders graph nodes to keep little memory live at
+//! once, computes the interval each value is live in and assigns values
+//! offsets in a single arena, so values which are never live at the same
+//! time share memory. Sizes come from static shapes like in
+//! [`stats`](../stats/index.html), initializers are not part of the arena.
+//!
+//! A value is live from the schedule position of the node computing it, or
+//! position 0 for graph inputs, to the position of its last use. Graph
+//! outputs stay live after the last node, at position `schedule.len()`.
+//! Inputs and outputs of a node are live at its position at once, so a node
+//! never writes its outputs over its inputs.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use onnx_pb::GraphProto;
+
+use crate::{
+    stats::{self, Analysis},
+    visit,
+};
+
+/// Alignment of arena offsets in bytes.
+pub const ALIGNMENT: u64 = 64;
+
+/// Execution plan error.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// Graph has a cycle which the node is part of or depends on.
+    Cycle {
+        /// Name of the first node in graph order which can not be scheduled,
+        /// or its first output if the node is unnamed.
+        node: String,
+    },
+    /// Given schedule is not a topological order of the graph.
+    Schedule(stats::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Cycle { node } => write!(f, "node {:?} depends on its own output", node),
+            Error::Schedule(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<stats::Error> for Error {
+    fn from(err: stats::Error) -> Self {
+        Error::Schedule(err)
+    }
+}
+
+/// Memory plan of a value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Value {
+    /// Value name.
+    pub name: String,
+    /// Size in bytes, `None` if unknown.
+    pub size: Option<u64>,
+    /// Schedule position the value is computed at.
+    pub start: usize,
+    /// Schedule position of the last use of the value.
+    pub end: usize,
+    /// Offset in the arena, `None` if size is unknown.
+    pub offset: Option<u64>,
+}
+
+impl Value {
+    /// Checks if both values are live at some position.
+    pub fn overlaps(&self, other: &Value) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+/// Execution plan of a graph.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Plan {
+    /// Indices of graph nodes in execution order.
+    pub schedule: Vec<usize>,
+    /// Graph inputs followed by node outputs in execution order.
+    pub values: Vec<Value>,
+    /// Largest total size of values live at once in bytes.
+    pub peak_memory: u64,
+    /// Size of the arena holding all values of known size in bytes.
+    pub arena_size: u64,
+}
+
+impl Plan {
+    /// Returns plan of a value.
+    pub fn value(&self, name: &str) -> Option<&Value> {
+        self.values.iter().find(|value| value.name == name)
+    }
+}
+
+/// Plans graph execution in an order with low peak memory.
+///
+/// Nodes are scheduled greedily, preferring nodes which free the most
+/// memory, and the graph order is kept if it needs less memory.
+pub fn plan(graph: &GraphProto) -> Result<Plan, Error> {
+    let analysis = Analysis::new(graph);
+    let mut schedule = greedy_schedule(&analysis, graph)?;
+    let order: Vec<usize> = (0..graph.node.len()).collect();
+    if stats::check_schedule(graph, &order).is_ok()
+        && analysis.peak_memory(&order) <= analysis.peak_memory(&schedule)
+    {
+        schedule = order;
+    }
+    Ok(build(&analysis, graph, schedule))
+}
+
+/// Plans graph execution in the given order.
+pub fn plan_schedule(graph: &GraphProto, schedule: Vec<usize>) -> Result<Plan, Error> {
+    stats::check_schedule(graph, &schedule)?;
+    Ok(build(&Analysis::new(graph), graph, schedule))
+}
+
+/// Schedules nodes by the memory they free, the graph order breaking ties.
+fn greedy_schedule(analysis: &Analysis, graph: &GraphProto) -> Result<Vec<usize>, Error> {
+    let producers: HashMap<&str, usize> = graph
+        .node
+        .iter()
+        .enumerate()
+        .flat_map(|(index, node)| {
+            node.output
+                .iter()
+                .filter(|output| !output.is_empty())
+                .map(move |output| (output.as_str(), index))
+        })
+        .collect();
+    let references: Vec<HashSet<String>> = graph
+        .node
+        .iter()
+        .map(|node| visit::node_references(node).into_iter().collect())
+        .collect();
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); graph.node.len()];
+    let mut pending = vec![0; graph.node.len()];
+    let mut uses: HashMap<&str, usize> = HashMap::new();
+    for (index, names) in references.iter().enumerate() {
+        let mut dependencies = HashSet::new();
+        for name in names.iter() {
+            *uses.entry(name.as_str()).or_default() += 1;
+            if let Some(producer) = producers.get(name.as_str()) {
+                dependencies.insert(*producer);
+            }
+        }
+        pending[index] = dependencies.len();
+        for producer in dependencies {
+            dependents[producer].push(index);
+        }
+    }
+    let outputs: HashSet<&str> = graph
+        .output
+        .iter()
+        .map(|value| value.name.as_str())
+        .collect();
+    let live: HashSet<&str> = analysis
+        .inputs()
+        .into_iter()
+        .chain(producers.keys().copied())
+        .collect();
+
+    let mut ready: BTreeSet<usize> = (0..graph.node.len())
+        .filter(|index| pending[*index] == 0)
+        .collect();
+    let mut schedule = Vec::with_capacity(graph.node.len());
+    while let Some(index) = ready.iter().copied().min_by_key(|index| {
+        (
+            gain(analysis, graph, *index, &references, &uses, &outputs, &live),
+            *index,
+        )
+    }) {
+        ready.remove(&index);
+        schedule.push(index);
+        for name in references[index].iter() {
+            if let Some(count) = uses.get_mut(name.as_str()) {
+                *count -= 1;
+            }
+        }
+        for dependent in dependents[index].iter() {
+            pending[*dependent] -= 1;
+            if pending[*dependent] == 0 {
+                ready.insert(*dependent);
+            }
+        }
+    }
+    match (0..graph.node.len()).find(|index| pending[*index] > 0) {
+        Some(index) => Err(Error::Cycle {
+            node: stats::node_name(&graph.node[index]),
+        }),
+        None => Ok(schedule),
+    }
+}
+
+/// Returns memory allocated by a node less memory freed after it runs.
+fn gain(
+    analysis: &Analysis,
+    graph: &GraphProto,
+    index: usize,
+    references: &[HashSet<String>],
+    uses: &HashMap<&str, usize>,
+    outputs: &HashSet<&str>,
+    live: &HashSet<&str>,
+) -> i128 {
+    let allocated: u64 = graph.node[index]
+        .output
+        .iter()
+        .filter_map(|output| analysis.bytes(output))
+        .sum();
+    let freed: u64 = references[index]
+        .iter()
+        .map(|name| name.as_str())
+        .filter(|name| uses.get(name) == Some(&1) && live.contains(name) && !outputs.contains(name))
+        .filter_map(|name| analysis.bytes(name))
+        .sum();
+    allocated as i128 - freed as i128
+}
+
+/// Computes liveness of values and their arena offsets.
+fn build(analysis: &Analysis, graph: &GraphProto, schedule: Vec<usize>) -> Plan {
+    let last = analysis.last_uses(&schedule);
+    let value = |name: &str, start: usize| Value {
+        name: name.to_owned(),
+        size: analysis.bytes(name),
+        start,
+        end: last.get(name).copied().unwrap_or(start).max(start),
+        offset: None,
+    };
+    let mut values: Vec<Value> = analysis
+        .inputs()
+        .into_iter()
+        .map(|name| value(name, 0))
+        .collect();
+    for (position, index) in schedule.iter().enumerate() {
+        for output in graph.node[*index].output.iter() {
+            if !output.is_empty() {
+                values.push(value(output, position));
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..values.len())
+        .filter(|index| values[*index].size.is_some())
+        .collect();
+    order.sort_by_key(|index| (std::cmp::Reverse(values[*index].size), values[*index].start));
+    let mut placed: Vec<usize> = Vec::new();
+    let mut arena_size = 0;
+    for index in order {
+        let size = values[index].size.unwrap_or(0);
+        let mut taken: Vec<(u64, u64)> = placed
+            .iter()
+            .filter(|other| values[**other].overlaps(&values[index]))
+            .filter_map(|other| {
+                let other = &values[*other];
+                Some((other.offset?, other.offset? + other.size?))
+            })
+            .collect();
+        taken.sort();
+        let mut offset = 0;
+        for (start, end) in taken {
+            if offset + size <= start {
+                break;
+            }
+            offset = offset.max(align(end));
+        }
+        values[index].offset = Some(offset);
+        arena_size = arena_size.max(offset + size);
+        placed.push(index);
+    }
+
+    Plan {
+        peak_memory: analysis.peak_memory(&schedule),
+        schedule,
+        values,
+        arena_size,
+    }
+}
+
+fn align(offset: u64) -> u64 {
+    (offset + ALIGNMENT - 1) / ALIGNMENT * ALIGNMENT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::text::parse_graph;
+
+    #[test]
+    fn chain() {
+        let graph = parse_graph(
+            r#"
+            g (float[256] X) => (float[256] Y) {
+                a = Neg (X)
+                b = Exp (a)
+                c = Sqrt (b)
+                Y = Relu (c)
+            }
+            "#,
+        )
+        .unwrap();
+        let plan = plan(&graph).unwrap();
+        assert_eq!(plan.schedule, vec![0, 1, 2, 3]);
+        let intervals: Vec<(&str, usize, usize)> = plan
+            .values
+            .iter()
+            .map(|value| (value.name.as_str(), value.start, value.end))
+            .collect();
+        assert_eq!(
+            intervals,
+            vec![
+                ("X", 0, 0),
+                ("a", 0, 1),
+                ("b", 1, 2),
+                ("c", 2, 3),
+                ("Y", 3, 4)
+            ]
+        );
+        assert_eq!(plan.peak_memory, 2 * 1024);
+        assert_eq!(plan.arena_size, 2 * 1024);
+        assert_eq!(
+            plan.value("X").unwrap().offset,
+            plan.value("b").unwrap().offset
+        );
+        assert_ne!(
+            plan.value("X").unwrap().offset,
+            plan.value("a").unwrap().offset
+        );
+    }
+
+    #[test]
+    fn schedule() {
+        let graph = parse_graph(
+            r#"
+            g (float[256] X) => (float[] Y) {
+                a = Neg (X)
+                b = Exp (X)
+                c = ReduceSum <keepdims = 0> (a)
+                d = ReduceSum <keepdims = 0> (b)
+                Y = Add (c, d)
+            }
+            "#,
+        )
+        .unwrap();
+        let plan = plan(&graph).unwrap();
+        assert_eq!(plan.schedule, vec![0, 2, 1, 3, 4]);
+        assert_eq!(plan.peak_memory, 2 * 1024 + 4);
+        assert!(plan.arena_size >= plan.peak_memory);
+        for value in plan.values.iter() {
+            let (offset, size) = (value.offset.unwrap(), value.size.unwrap());
+            assert_eq!(offset % ALIGNMENT, 0);
+            for other in plan.values.iter().filter(|other| other.name != value.name) {
+                if value.overlaps(other) {
+                    let start = other.offset.unwrap();
+                    assert!(offset + size <= start || start + other.size.unwrap() <= offset);
+                }
+            }
+        }
+
+        let order = plan_schedule(&graph, vec![0, 1, 2, 3, 4]).unwrap();
+        assert_eq!(order.peak_memory, 3 * 1024);
+        assert_eq!(order.value("c").unwrap().start, 2);
+        assert_eq!(order.value("Y").unwrap().end, 5);
+        assert_eq!(
+            plan_schedule(&graph, vec![0, 1, 2]),
+            Err(Error::Schedule(stats::Error::Incomplete))
+        );
+    }
+
+    #[test]
+    fn unknown_sizes_and_cycles() {
+        let graph = parse_graph(
+            r#"
+            g (float[N] X) => (float[N] Y) {
+                a = Neg (X)
+                Y = Relu (a)
+            }
+            "#,
+        )
+        .unwrap();
+        let plan = plan(&graph).unwrap();
+        assert!(plan.values.iter().all(|value| value.offset.is_none()));
+        assert_eq!((plan.peak_memory, plan.arena_size), (0, 0));
+
+        let graph = parse_graph(
+            r#"
+            g (float[2] X) => (float[2] Y) {
+                a = Add (X, b)
+                b = Neg (a)
+                Y = Relu (b)
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            super::plan(&graph),
+            Err(Error::Cycle {
+                node: "a".to_owned()
+            })
+        );
+    }
+}
@@ -1,17 +1,147 @@
 //! Model encoding and decoding utilities.
 
-use bytes::Buf;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use bytes::{Buf, Bytes};
 use prost::Message;
 
 use onnx_pb::ModelProto;
 
+/// Encoding or decoding error.
+#[derive(Debug)]
+pub enum Error {
+    /// IO error.
+    Io(io::Error),
+
+    /// Decode error.
+    Decode(prost::DecodeError),
+
+    /// Encode error.
+    Encode(prost::EncodeError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "io error: {}", err),
+            Error::Decode(err) => write!(f, "decode error: {}", err),
+            Error::Encode(err) => write!(f, "encode error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Decode(err) => Some(err),
+            Error::Encode(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<prost::DecodeError> for Error {
+    fn from(err: prost::DecodeError) -> Self {
+        Error::Decode(err)
+    }
+}
+
+impl From<prost::EncodeError> for Error {
+    fn from(err: prost::EncodeError) -> Self {
+        Error::Encode(err)
+    }
+}
+
+/// Encodes a protocol buffers message.
+pub fn encode<T: Message>(message: &T) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::with_capacity(message.encoded_len());
+    message.encode(&mut buf)?;
+    Ok(buf)
+}
+
 /// Decodes a protocol buffers message.
-pub fn decode<T, B>(buf: B) -> Result<T, prost::DecodeError>
+pub fn decode<T, B>(buf: B) -> Result<T, Error>
 where
     B: Buf,
-    T: Message + Sized + Default,
+    T: Message + Default,
 {
-    let message = T::default();
-    message.decode(buf)?;
-    message
+    Ok(T::decode(buf)?)
+}
+
+/// Encodes model to a vector of bytes.
+#[inline]
+pub fn encode_model(model: &ModelProto) -> Result<Vec<u8>, Error> {
+    encode(model)
+}
+
+/// Encodes model to bytes.
+#[inline]
+pub fn encode_model_bytes(model: &ModelProto) -> Result<Bytes, Error> {
+    encode(model).map(Bytes::from)
+}
+
+/// Decodes model from a buffer.
+///
+/// Accepts anything implementing [`Buf`], e.g. `&[u8]` or [`Bytes`].
+#[inline]
+pub fn decode_model<B: Buf>(buf: B) -> Result<ModelProto, Error> {
+    decode(buf)
+}
+
+/// Writes encoded model to a writer.
+pub fn write_model<W: Write>(mut writer: W, model: &ModelProto) -> Result<(), Error> {
+    writer.write_all(&encode_model(model)?)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads model from a reader until EOF.
+pub fn read_model<R: Read>(mut reader: R) -> Result<ModelProto, Error> {
+    let mut body = Vec::new();
+    reader.read_to_end(&mut body)?;
+    decode_model(body.as_slice())
+}
+
+/// Saves model to a file.
+pub fn save_model<P: AsRef<Path>>(path: P, model: &ModelProto) -> Result<(), Error> {
+    write_model(File::create(path)?, model)
+}
+
+/// Opens model from a file.
+pub fn open_model<P: AsRef<Path>>(path: P) -> Result<ModelProto, Error> {
+    decode_model(std::fs::read(path)?.as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let model = open_model("tests/mean-reverse.onnx").unwrap();
+        let body = encode_model(&model).unwrap();
+        assert_eq!(decode_model(body.as_slice()).unwrap(), model);
+        let bytes = encode_model_bytes(&model).unwrap();
+        assert_eq!(decode_model(bytes).unwrap(), model);
+
+        let mut out = Vec::new();
+        write_model(&mut out, &model).unwrap();
+        assert_eq!(read_model(out.as_slice()).unwrap(), model);
+    }
+
+    #[test]
+    fn decode_error() {
+        match decode_model(&b"\xff\xff\xff"[..]) {
+            Err(Error::Decode(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,513 @@
+//! Post-training quantization.
+//!
+//! Inserts `QuantizeLinear` and `DequantizeLinear` pairs around inputs and
+//! outputs of `MatMul`, `Gemm` and `Conv` nodes, using scales and zero
+//! points calibrated by the caller. Constant weights are quantized ahead of
+//! time and only dequantized in the graph.
+
+use std::collections::{HashMap, HashSet};
+
+use onnx_pb::{
+    make_attribute, tensor_proto::DataType, GraphProto, ModelProto, NodeProto, TensorProto,
+};
+
+use crate::{opset, tensor, visit};
+
+/// Operators with quantized inputs and outputs.
+const QUANTIZED_OPS: &[&str] = &["Conv", "Gemm", "MatMul"];
+
+/// Quantization error.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// Operator set version of the model does not support quantization.
+    UnsupportedVersion {
+        /// Model operator set version.
+        version: i64,
+        /// Required operator set version.
+        required: i64,
+    },
+    /// Calibration parameters which can not be used for a value.
+    InvalidParams {
+        /// Value name.
+        value: String,
+        /// Reason of the failure.
+        reason: String,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnsupportedVersion { version, required } => write!(
+                f,
+                "quantization requires operator set version {}, model uses {}",
+                required, version
+            ),
+            Error::InvalidParams { value, reason } => write!(
+                f,
+                "invalid quantization parameters of {:?}: {}",
+                value, reason
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Calibrated quantization parameters of a value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuantParams {
+    scales: Vec<f32>,
+    zero_points: Vec<i32>,
+    axis: Option<i64>,
+    data_type: DataType,
+}
+
+impl QuantParams {
+    /// Creates parameters shared by all elements of a tensor.
+    pub fn per_tensor(scale: f32, zero_point: i32) -> Self {
+        QuantParams {
+            scales: vec![scale],
+            zero_points: vec![zero_point],
+            axis: None,
+            data_type: DataType::Uint8,
+        }
+    }
+
+    /// Creates parameters for each slice of a tensor along an axis.
+    ///
+    /// Requires operator set version 13.
+    pub fn per_channel(axis: i64, scales: Vec<f32>, zero_points: Vec<i32>) -> Self {
+        QuantParams {
+            scales,
+            zero_points,
+            axis: Some(axis),
+            data_type: DataType::Uint8,
+        }
+    }
+
+    /// Sets quantized element type, `Uint8` by default.
+    pub fn typed<T: Into<DataType>>(mut self, data_type: T) -> Self {
+        self.data_type = data_type.into();
+        self
+    }
+
+    /// Returns range of quantized values.
+    fn range(&self) -> (i32, i32) {
+        match self.data_type {
+            DataType::Int8 => (i8::MIN as i32, i8::MAX as i32),
+            _ => (u8::MIN as i32, u8::MAX as i32),
+        }
+    }
+
+    /// Returns reason why parameters are not valid.
+    fn validate(&self) -> Option<String> {
+        if self.data_type != DataType::Int8 && self.data_type != DataType::Uint8 {
+            return Some(format!(
+                "quantized type must be Int8 or Uint8, found {:?}",
+                self.data_type
+            ));
+        }
+        if self.scales.is_empty() || self.scales.len() != self.zero_points.len() {
+            return Some(format!(
+                "expected matching scales and zero points, got {} and {}",
+                self.scales.len(),
+                self.zero_points.len()
+            ));
+        }
+        if self.axis.is_none() && self.scales.len() != 1 {
+            return Some("per-tensor parameters must have a single scale".to_owned());
+        }
+        if let Some(scale) = self
+            .scales
+            .iter()
+            .find(|scale| !scale.is_finite() || **scale <= 0.0)
+        {
+            return Some(format!("scale {} is not positive", scale));
+        }
+        let (min, max) = self.range();
+        if let Some(zero_point) = self
+            .zero_points
+            .iter()
+            .find(|zero_point| !(min..=max).contains(*zero_point))
+        {
+            return Some(format!(
+                "zero point {} is out of range of {:?}",
+                zero_point, self.data_type
+            ));
+        }
+        None
+    }
+
+    /// Quantizes values of a tensor with given dimensions.
+    fn quantize(&self, dims: &[i64], values: &[f64]) -> Result<Vec<f64>, String> {
+        let (min, max) = self.range();
+        let (channels, stride) = match self.axis {
+            Some(axis) => {
+                let rank = dims.len() as i64;
+                if !(-rank..rank).contains(&axis) {
+                    return Err(format!("axis {} is out of range of rank {}", axis, rank));
+                }
+                let axis = if axis < 0 { axis + rank } else { axis } as usize;
+                if dims[axis] as usize != self.scales.len() {
+                    return Err(format!(
+                        "expected {} scales along axis {}, got {}",
+                        dims[axis],
+                        axis,
+                        self.scales.len()
+                    ));
+                }
+                let stride: i64 = dims[axis + 1..].iter().product();
+                (dims[axis] as usize, stride as usize)
+            }
+            None => (1, 1),
+        };
+        Ok(values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let channel = index / stride % channels;
+                let scale = self.scales[channel] as f64;
+                let quantized = round_half_even(value / scale) + self.zero_points[channel] as f64;
+                quantized.clamp(min as f64, max as f64)
+            })
+            .collect())
+    }
+}
+
+/// Quantizes calibrated values used or produced by `MatMul`, `Gemm` and
+/// `Conv` nodes of the main graph.
+///
+/// Calibration maps value names to their parameters; values without
+/// parameters are left in floating point. Nodes consuming a quantized value
+/// read its dequantized form, graph outputs keep float values. Float
+/// weights which are no longer used are removed.
+///
+/// Returns number of quantized values. The model is left unchanged on error.
+pub fn quantize(
+    model: &mut ModelProto,
+    calibration: &HashMap<String, QuantParams>,
+) -> Result<usize, Error> {
+    let version = model
+        .opset_import
+        .iter()
+        .find(|opset| opset.domain.is_empty() || opset.domain == "ai.onnx")
+        .map(|opset| opset.version)
+        .unwrap_or(opset::DEFAULT_VERSION);
+    let graph = match model.graph.as_mut() {
+        Some(graph) => graph,
+        None => return Ok(0),
+    };
+
+    let mut values: Vec<&str> = Vec::new();
+    for node in graph.node.iter() {
+        if !opset::is_default_domain(node) || !QUANTIZED_OPS.contains(&node.op_type.as_str()) {
+            continue;
+        }
+        let used = node.input.iter().take(2).chain(node.output.iter().take(1));
+        for value in used {
+            if calibration.contains_key(value) && !values.contains(&value.as_str()) {
+                values.push(value);
+            }
+        }
+    }
+    let inputs: HashSet<&str> = graph
+        .input
+        .iter()
+        .map(|value| value.name.as_str())
+        .collect();
+    let mut quantized = Vec::with_capacity(values.len());
+    for value in values {
+        let params = &calibration[value];
+        let required = if params.axis.is_some() { 13 } else { 10 };
+        if version < required {
+            return Err(Error::UnsupportedVersion { version, required });
+        }
+        let invalid = |reason| Error::InvalidParams {
+            value: value.to_owned(),
+            reason,
+        };
+        if let Some(reason) = params.validate() {
+            return Err(invalid(reason));
+        }
+        let weight = match graph.initializer.iter().find(|tensor| tensor.name == value) {
+            Some(tensor) if !inputs.contains(value) => {
+                if tensor.data_type != DataType::Float as i32 {
+                    return Err(invalid("weight must be of type float".to_owned()));
+                }
+                let values = tensor::to_f64_vec(tensor)
+                    .ok_or_else(|| invalid("weight data can not be read".to_owned()))?;
+                let values = params.quantize(&tensor.dims, &values).map_err(invalid)?;
+                tensor::from_f64_vec(params.data_type, tensor.dims.clone(), &values)
+            }
+            _ => None,
+        };
+        quantized.push((value.to_owned(), params, weight));
+    }
+
+    let mut front = Vec::new();
+    let mut after: HashMap<String, Vec<NodeProto>> = HashMap::new();
+    for (value, params, weight) in quantized.iter() {
+        let quantized_name = format!("{}_quantized", value);
+        let dequantized_name = format!("{}_dequantized", value);
+        for node in graph.node.iter_mut() {
+            visit::rename_node_inputs(node, value, &dequantized_name);
+        }
+        let (scale, zero_point) = parameter_tensors(value, params);
+        let mut nodes = Vec::new();
+        match weight {
+            Some(weight) => {
+                let mut weight = weight.clone();
+                weight.name = quantized_name.clone();
+                graph.initializer.push(weight);
+            }
+            None => nodes.push(qdq_node(
+                "QuantizeLinear",
+                value,
+                &quantized_name,
+                &scale.name,
+                &zero_point.name,
+                params.axis,
+            )),
+        }
+        nodes.push(qdq_node(
+            "DequantizeLinear",
+            &quantized_name,
+            &dequantized_name,
+            &scale.name,
+            &zero_point.name,
+            params.axis,
+        ));
+        graph.initializer.push(scale);
+        graph.initializer.push(zero_point);
+        let produced = graph
+            .node
+            .iter()
+            .any(|node| node.output.iter().any(|output| output == value));
+        if produced {
+            after.insert(value.clone(), nodes);
+        } else {
+            front.extend(nodes);
+        }
+    }
+    let mut nodes = front;
+    for node in graph.node.drain(..) {
+        let inserted: Vec<NodeProto> = node
+            .output
+            .iter()
+            .filter_map(|output| after.remove(output))
+            .flatten()
+            .collect();
+        nodes.push(node);
+        nodes.extend(inserted);
+    }
+    graph.node = nodes;
+    remove_unused_weights(graph, &quantized);
+    Ok(quantized.len())
+}
+
+/// Creates scale and zero point initializers of a value.
+fn parameter_tensors(value: &str, params: &QuantParams) -> (TensorProto, TensorProto) {
+    let dims = match params.axis {
+        Some(_) => vec![params.scales.len() as i64],
+        None => Vec::new(),
+    };
+    let scales: Vec<f64> = params.scales.iter().map(|scale| *scale as f64).collect();
+    let zero_points: Vec<f64> = params.zero_points.iter().map(|zero| *zero as f64).collect();
+    let mut scale = tensor::from_f64_vec(DataType::Float, dims.clone(), &scales).unwrap();
+    scale.name = format!("{}_scale", value);
+    let mut zero_point = tensor::from_f64_vec(params.data_type, dims, &zero_points).unwrap();
+    zero_point.name = format!("{}_zero_point", value);
+    (scale, zero_point)
+}
+
+/// Creates a quantize or dequantize node.
+fn qdq_node(
+    op_type: &str,
+    input: &str,
+    output: &str,
+    scale: &str,
+    zero_point: &str,
+    axis: Option<i64>,
+) -> NodeProto {
+    NodeProto {
+        name: format!("{}_{}", output, op_type),
+        op_type: op_type.to_owned(),
+        input: vec![input.to_owned(), scale.to_owned(), zero_point.to_owned()],
+        output: vec![output.to_owned()],
+        attribute: axis
+            .into_iter()
+            .map(|axis| make_attribute("axis", axis))
+            .collect(),
+        ..NodeProto::default()
+    }
+}
+
+/// Removes float weights which were replaced by their quantized form.
+fn remove_unused_weights(
+    graph: &mut GraphProto,
+    quantized: &[(String, &QuantParams, Option<TensorProto>)],
+) {
+    let used: HashSet<String> = graph
+        .node
+        .iter()
+        .flat_map(visit::node_references)
+        .chain(graph.output.iter().map(|value| value.name.clone()))
+        .collect();
+    graph.initializer.retain(|tensor| {
+        used.contains(&tensor.name)
+            || !quantized
+                .iter()
+                .any(|(value, _, weight)| weight.is_some() && *value == tensor.name)
+    });
+}
+
+/// Rounds to the nearest integer with ties to even, as `QuantizeLinear` does.
+fn round_half_even(value: f64) -> f64 {
+    if (value - value.trunc()).abs() == 0.5 {
+        2.0 * (value / 2.0).round()
+    } else {
+        value.round()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{checker, text};
+
+    fn parse(opset: i64) -> ModelProto {
+        text::parse_model(&format!(
+            r#"
+            <ir_version: 6, opset_import: ["" : {}]>
+            g (float[1,2] X) => (float[1,2] Z) <float[2,2] W = {{0.5, -1.0, 0.25, 2.0}}, float[2] B = {{1.0, 1.0}}> {{
+                Y = MatMul (X, W)
+                Z = Add (Y, B)
+            }}
+            "#,
+            opset
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn insert_qdq() {
+        let mut model = parse(13);
+        let calibration: HashMap<String, QuantParams> = vec![
+            ("X".to_owned(), QuantParams::per_tensor(0.02, 128)),
+            (
+                "W".to_owned(),
+                QuantParams::per_channel(1, vec![0.01, 0.02], vec![0, 0]).typed(DataType::Int8),
+            ),
+            ("Y".to_owned(), QuantParams::per_tensor(0.05, 100)),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(quantize(&mut model, &calibration).unwrap(), 3);
+        assert!(checker::check_model(&model).is_ok());
+        let graph = model.graph.as_ref().unwrap();
+        let nodes: Vec<String> = graph
+            .node
+            .iter()
+            .map(|node| format!("{}({})", node.op_type, node.input.join(", ")))
+            .collect();
+        assert_eq!(
+            nodes,
+            vec![
+                "QuantizeLinear(X, X_scale, X_zero_point)",
+                "DequantizeLinear(X_quantized, X_scale, X_zero_point)",
+                "DequantizeLinear(W_quantized, W_scale, W_zero_point)",
+                "MatMul(X_dequantized, W_dequantized)",
+                "QuantizeLinear(Y, Y_scale, Y_zero_point)",
+                "DequantizeLinear(Y_quantized, Y_scale, Y_zero_point)",
+                "Add(Y_dequantized, B)",
+            ]
+        );
+        assert!(graph.initializer.iter().all(|tensor| tensor.name != "W"));
+        let weight = graph
+            .initializer
+            .iter()
+            .find(|tensor| tensor.name == "W_quantized")
+            .unwrap();
+        assert_eq!(weight.data_type, DataType::Int8 as i32);
+        assert_eq!(weight.int32_data, vec![50, -50, 25, 100]);
+    }
+
+    #[test]
+    fn rounding() {
+        let rounded: Vec<f64> = [-3.5, -2.5, -1.4, -0.5, 0.5, 1.5, 2.5, 2.6]
+            .iter()
+            .map(|value| round_half_even(*value))
+            .collect();
+        assert_eq!(rounded, vec![-4.0, -2.0, -1.0, -0.0, 0.0, 2.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn invalid_params() {
+        let calibration: HashMap<String, QuantParams> = vec![(
+            "W".to_owned(),
+            QuantParams::per_channel(0, vec![0.1, 0.1, 0.1], vec![0, 0, 0]),
+        )]
+        .into_iter()
+        .collect();
+        let mut model = parse(11);
+        assert_eq!(
+            quantize(&mut model, &calibration).unwrap_err().to_string(),
+            "quantization requires operator set version 13, model uses 11"
+        );
+        let mut model = parse(13);
+        assert_eq!(
+            quantize(&mut model, &calibration).unwrap_err().to_string(),
+            "invalid quantization parameters of \"W\": expected 2 scales along axis 0, got 3"
+        );
+        assert_eq!(model, parse(13));
+    }
+
+    #[test]
+    fn builders() {
+        use crate::{builder, nodes::ops};
+
+        let mut graph = builder::Graph::new("integer");
+        let x = graph.input("X").typed(DataType::Float).dim(1).dim(2).node();
+        let w = graph.input("W").typed(DataType::Uint8).dim(2).dim(2).node();
+        let zero = graph.constant("zero", builder::Tensor::from_slice(&[], &[0u8]).unwrap());
+        let q = x.dynamic_quantize();
+        assert_eq!(q.output(2), "X_zero_point");
+        let product = ops::MatMulInteger::new(&q, &w, q.output(2), &zero);
+        let y = String::from(&product);
+        let cast = graph
+            .node("Y")
+            .op("Cast")
+            .input(y)
+            .attribute("to", 1)
+            .build();
+        let scale = graph
+            .node("scale")
+            .op("Mul")
+            .input(q.output(1))
+            .input(q.output(1))
+            .build();
+        let graph = graph
+            .nodes(product)
+            .outputs_typed(&cast * &scale, DataType::Float)
+            .build();
+        let mut ops: Vec<&str> = graph
+            .node
+            .iter()
+            .map(|node| node.op_type.as_str())
+            .collect();
+        ops.sort_unstable();
+        assert_eq!(
+            ops,
+            vec![
+                "Cast",
+                "Constant",
+                "DynamicQuantizeLinear",
+                "MatMulInteger",
+                "Mul",
+                "Mul"
+            ]
+        );
+    }
+}
@@ -0,0 +1,1079 @@
+//! Subgraph pattern rewriting.
+//!
+//! A [`Rule`](struct.Rule.html) pairs a [`Pattern`](struct.Pattern.html),
+//! a tree of operators over named values, with a function building
+//! replacement nodes for each match:
+//!
+//! ```
+//! use onnx_helpers::{builder, rewrite::{self, Pattern, Replacement, Rule}, text};
+//!
+//! let mut graph = text::parse_graph(
+//!     r#"
+//!     g (float[2,3] X, float[3,4] W, float[4] B) => (float[2,4] Y) {
+//!         m = MatMul (X, W)
+//!         Y = Add (B, m)
+//!     }
+//!     "#,
+//! )
+//! .unwrap();
+//! let gemm = Rule::new("gemm", "Add(MatMul(a, b), c)".parse().unwrap(), |m| {
+//!     let node = builder::Node::new("Gemm")
+//!         .name(m.unique_name("Gemm"))
+//!         .inputs(vec![m.value("a"), m.value("b"), m.value("c")])
+//!         .outputs(vec![m.output()]);
+//!     Some(Replacement::new(vec![node.into()]))
+//! });
+//! assert_eq!(rewrite::rewrite(&mut graph, &[gemm]), 1);
+//! assert_eq!(graph.node.len(), 1);
+//! assert_eq!(graph.node[0].input, vec!["X", "W", "B"]);
+//! ```
+//!
+//! Matching starts from each node in graph order. Nodes inside a match other
+//! than its root must have all of their outputs used only by the match, so
+//! removing them does not affect the rest of the graph. Matches never share
+//! nodes. Inputs of commutative operators such as `Add` and `Mul` are
+//! matched in either order.
+//!
+//! Patterns are written as `Op(inputs...)` for operators, lowercase names
+//! for values, `_` for values which are not bound and numbers for constants
+//! with all elements equal to the number. Operators can be bound to names
+//! with `name: Op(...)`, which also binds their output. A name used more
+//! than once must match the same value each time, e.g. `Sub(x, ReduceMean(x))`
+//! or `Div(d: Sub(x, ReduceMean(x)), Sqrt(ReduceMean(Mul(d, d))))`, where
+//! the `Sub` node is used twice inside the match.
+
+use std::collections::{HashMap, HashSet};
+
+use onnx_pb::{make_attribute, Attribute, AttributeProto, GraphProto, NodeProto, TensorProto};
+
+use crate::{optim, tensor, text::ParseError, visit};
+
+/// Operators with two inputs which can be swapped.
+const COMMUTATIVE_OPS: &[&str] = &[
+    "Add",
+    "And",
+    "BitwiseAnd",
+    "BitwiseOr",
+    "BitwiseXor",
+    "Equal",
+    "Max",
+    "Min",
+    "Mul",
+    "Or",
+    "Sum",
+    "Xor",
+];
+
+/// Subgraph pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    kind: Kind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    Value(String),
+    Constant(String),
+    Scalar(f64),
+    Op {
+        op_type: String,
+        domain: String,
+        inputs: Vec<Pattern>,
+        attributes: Vec<AttributeProto>,
+        name: Option<String>,
+    },
+}
+
+impl Pattern {
+    /// Matches any value and binds it to a name.
+    ///
+    /// Values named `_` are not bound.
+    pub fn value<S: Into<String>>(name: S) -> Self {
+        Pattern {
+            kind: Kind::Value(name.into()),
+        }
+    }
+
+    /// Matches a constant and binds it to a name.
+    ///
+    /// Constants are initializers which are not graph inputs and outputs of
+    /// `Constant` nodes.
+    pub fn constant<S: Into<String>>(name: S) -> Self {
+        Pattern {
+            kind: Kind::Constant(name.into()),
+        }
+    }
+
+    /// Matches a constant with all elements equal to a number.
+    pub fn scalar(value: f64) -> Self {
+        Pattern {
+            kind: Kind::Scalar(value),
+        }
+    }
+
+    /// Matches an output of an operator with given inputs.
+    pub fn op<S: Into<String>>(op_type: S, inputs: Vec<Pattern>) -> Self {
+        Pattern {
+            kind: Kind::Op {
+                op_type: op_type.into(),
+                domain: String::new(),
+                inputs,
+                attributes: Vec::new(),
+                name: None,
+            },
+        }
+    }
+
+    /// Parses pattern from text, e.g. `Add(MatMul(a, b), c)`.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(text);
+        let pattern = parser.pattern()?;
+        match parser.next() {
+            Token::Eof => Ok(pattern),
+            token => Err(parser.error(format!("unexpected {}", token))),
+        }
+    }
+
+    /// Binds matched operator node to a name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if pattern is not an operator pattern.
+    pub fn named<S: Into<String>>(mut self, node_name: S) -> Self {
+        match self.kind {
+            Kind::Op { ref mut name, .. } => *name = Some(node_name.into()),
+            _ => panic!("only operator patterns can be named"),
+        }
+        self
+    }
+
+    /// Sets operator domain, default domain is matched otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if pattern is not an operator pattern.
+    pub fn domain<S: Into<String>>(mut self, op_domain: S) -> Self {
+        match self.kind {
+            Kind::Op { ref mut domain, .. } => *domain = op_domain.into(),
+            _ => panic!("only operator patterns have a domain"),
+        }
+        self
+    }
+
+    /// Requires operator to have an attribute with given value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if pattern is not an operator pattern.
+    pub fn attr<S: Into<String>, A: Into<Attribute>>(mut self, name: S, value: A) -> Self {
+        match self.kind {
+            Kind::Op {
+                ref mut attributes, ..
+            } => attributes.push(make_attribute(name, value)),
+            _ => panic!("only operator patterns have attributes"),
+        }
+        self
+    }
+}
+
+impl std::str::FromStr for Pattern {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Pattern::parse(text)
+    }
+}
+
+/// Matched subgraph.
+#[derive(Debug, Clone)]
+pub struct Match {
+    output: String,
+    nodes: Vec<NodeProto>,
+    indices: Vec<usize>,
+    values: HashMap<String, String>,
+    names: HashMap<String, usize>,
+    constants: HashMap<String, TensorProto>,
+}
+
+impl Match {
+    /// Returns node matched by the root of the pattern.
+    pub fn root(&self) -> &NodeProto {
+        &self.nodes[0]
+    }
+
+    /// Returns matched nodes starting from the root.
+    pub fn nodes(&self) -> &[NodeProto] {
+        &self.nodes
+    }
+
+    /// Returns name of the matched root value.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Returns name of a value bound in the pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if name is not bound by the pattern.
+    pub fn value(&self, name: &str) -> &str {
+        match self.values.get(name) {
+            Some(value) => value,
+            None => panic!("value `{}` is not bound by the pattern", name),
+        }
+    }
+
+    /// Returns operator node bound in the pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if name is not bound by the pattern.
+    pub fn node(&self, name: &str) -> &NodeProto {
+        match self.names.get(name) {
+            Some(index) => &self.nodes[*index],
+            None => panic!("node `{}` is not bound by the pattern", name),
+        }
+    }
+
+    /// Returns value of a constant bound in the pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if name is not bound by a constant pattern.
+    pub fn constant(&self, name: &str) -> &TensorProto {
+        match self.constants.get(name) {
+            Some(tensor) => tensor,
+            None => panic!("constant `{}` is not bound by the pattern", name),
+        }
+    }
+
+    /// Returns a name for new nodes and values derived from the root value.
+    pub fn unique_name(&self, suffix: &str) -> String {
+        format!("{}/{}", self.output(), suffix)
+    }
+}
+
+/// Nodes replacing a match.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Replacement {
+    nodes: Vec<NodeProto>,
+    initializers: Vec<TensorProto>,
+    forward: Option<String>,
+}
+
+impl Replacement {
+    /// Creates a replacement from nodes producing outputs of the root node.
+    pub fn new(nodes: Vec<NodeProto>) -> Self {
+        Replacement {
+            nodes,
+            ..Replacement::default()
+        }
+    }
+
+    /// Creates a replacement using an existing value instead of the single
+    /// output of the root node.
+    pub fn forward<S: Into<String>>(value: S) -> Self {
+        Replacement {
+            forward: Some(value.into()),
+            ..Replacement::default()
+        }
+    }
+
+    /// Adds an initializer used by replacement nodes.
+    pub fn initializer(mut self, tensor: TensorProto) -> Self {
+        self.initializers.push(tensor);
+        self
+    }
+}
+
+impl From<NodeProto> for Replacement {
+    fn from(node: NodeProto) -> Self {
+        Replacement::new(vec![node])
+    }
+}
+
+impl From<Vec<NodeProto>> for Replacement {
+    fn from(nodes: Vec<NodeProto>) -> Self {
+        Replacement::new(nodes)
+    }
+}
+
+/// Replacement function, returns `None` to keep a match unchanged.
+type Replace = dyn Fn(&Match) -> Option<Replacement>;
+
+/// Rewrite rule.
+pub struct Rule {
+    name: String,
+    pattern: Pattern,
+    replace: Box<Replace>,
+}
+
+impl Rule {
+    /// Creates a new rule.
+    pub fn new<S, F>(name: S, pattern: Pattern, replace: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&Match) -> Option<Replacement> + 'static,
+    {
+        Rule {
+            name: name.into(),
+            pattern,
+            replace: Box::new(replace),
+        }
+    }
+
+    /// Returns rule name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns rule pattern.
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    /// Applies the rule to a graph.
+    ///
+    /// Returns number of replaced matches.
+    pub fn apply(&self, graph: &mut GraphProto) -> usize {
+        rewrite(graph, std::slice::from_ref(self))
+    }
+}
+
+impl std::fmt::Debug for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Rule")
+            .field("name", &self.name)
+            .field("pattern", &self.pattern)
+            .finish()
+    }
+}
+
+/// Returns non-overlapping matches of a pattern in graph order.
+pub fn find(graph: &GraphProto, pattern: &Pattern) -> Vec<Match> {
+    let mut matcher = Matcher::new(graph);
+    let mut matches = Vec::new();
+    for index in 0..graph.node.len() {
+        if let Some(found) = matcher.find_at(index, pattern) {
+            matcher.taken.extend(found.indices.iter().cloned());
+            matches.push(found);
+        }
+    }
+    matches
+}
+
+/// Applies rules one after another, each once over the whole graph.
+///
+/// Returns number of replaced matches. Matches for which the rule returns
+/// no replacement, or a replacement not producing all outputs of the root
+/// node, are left unchanged. Graph outputs are never forwarded.
+pub fn rewrite(graph: &mut GraphProto, rules: &[Rule]) -> usize {
+    rules.iter().map(|rule| rewrite_rule(graph, rule)).sum()
+}
+
+fn rewrite_rule(graph: &mut GraphProto, rule: &Rule) -> usize {
+    let mut replacements: HashMap<usize, Replacement> = HashMap::new();
+    let mut removed: HashSet<usize> = HashSet::new();
+    {
+        let mut matcher = Matcher::new(graph);
+        for index in 0..graph.node.len() {
+            let found = match matcher.find_at(index, &rule.pattern) {
+                Some(found) => found,
+                None => continue,
+            };
+            let replacement = match (rule.replace)(&found) {
+                Some(replacement) => replacement,
+                None => continue,
+            };
+            if !matcher.replaces_outputs(found.root(), &replacement) {
+                continue;
+            }
+            matcher.taken.extend(found.indices.iter().cloned());
+            removed.extend(found.indices[1..].iter().cloned());
+            replacements.insert(index, replacement);
+        }
+    }
+    if replacements.is_empty() {
+        return 0;
+    }
+    let count = replacements.len();
+    let mut forwarded = Vec::new();
+    let mut dropped = HashSet::new();
+    let mut nodes = Vec::with_capacity(graph.node.len());
+    for (index, node) in graph.node.drain(..).enumerate() {
+        if removed.contains(&index) {
+            dropped.extend(node.output);
+            continue;
+        }
+        let replacement = match replacements.remove(&index) {
+            Some(replacement) => replacement,
+            None => {
+                nodes.push(node);
+                continue;
+            }
+        };
+        graph.initializer.extend(replacement.initializers);
+        match replacement.forward {
+            Some(value) => {
+                dropped.insert(node.output[0].clone());
+                forwarded.push((node.output[0].clone(), value));
+            }
+            None => nodes.extend(replacement.nodes),
+        }
+    }
+    graph.node = nodes;
+    graph
+        .value_info
+        .retain(|value| !dropped.contains(&value.name));
+    // Later matches may forward to values forwarded by earlier ones.
+    for (from, to) in forwarded.iter().rev() {
+        visit::rename_value(graph, from, to);
+    }
+    count
+}
+
+/// Bindings of a partial match.
+#[derive(Clone, Default)]
+struct State {
+    indices: Vec<usize>,
+    values: HashMap<String, String>,
+    names: HashMap<String, usize>,
+    constants: HashMap<String, TensorProto>,
+}
+
+impl State {
+    fn bind(&mut self, name: &str, value: &str) -> bool {
+        if name == "_" {
+            return true;
+        }
+        match self.values.get(name) {
+            Some(bound) => bound == value,
+            None => {
+                self.values.insert(name.to_owned(), value.to_owned());
+                true
+            }
+        }
+    }
+}
+
+struct Matcher<'a> {
+    graph: &'a GraphProto,
+    producers: HashMap<&'a str, usize>,
+    consumers: HashMap<String, Vec<usize>>,
+    outputs: HashSet<&'a str>,
+    constants: HashMap<&'a str, TensorProto>,
+    taken: HashSet<usize>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(graph: &'a GraphProto) -> Self {
+        let inputs: HashSet<&str> = graph
+            .input
+            .iter()
+            .map(|value| value.name.as_str())
+            .collect();
+        let mut constants: HashMap<&str, TensorProto> = graph
+            .initializer
+            .iter()
+            .filter(|tensor| !inputs.contains(tensor.name.as_str()))
+            .map(|tensor| (tensor.name.as_str(), tensor.clone()))
+            .collect();
+        let mut producers = HashMap::new();
+        let mut consumers = HashMap::new();
+        for (index, node) in graph.node.iter().enumerate() {
+            let references: HashSet<String> = visit::node_references(node).into_iter().collect();
+            for name in references {
+                consumers.entry(name).or_insert_with(Vec::new).push(index);
+            }
+            for output in node.output.iter().filter(|output| !output.is_empty()) {
+                producers.insert(output.as_str(), index);
+            }
+            if node.op_type == "Constant" && node.domain.is_empty() && node.output.len() == 1 {
+                if let Some(tensor) = optim::constant_value(node) {
+                    constants.insert(node.output[0].as_str(), tensor);
+                }
+            }
+        }
+        let outputs = graph
+            .output
+            .iter()
+            .map(|value| value.name.as_str())
+            .collect();
+        Matcher {
+            graph,
+            producers,
+            consumers,
+            outputs,
+            constants,
+            taken: HashSet::new(),
+        }
+    }
+
+    /// Matches pattern against outputs of a node.
+    fn find_at(&self, index: usize, pattern: &Pattern) -> Option<Match> {
+        if self.taken.contains(&index) {
+            return None;
+        }
+        let node = &self.graph.node[index];
+        node.output
+            .iter()
+            .filter(|output| !output.is_empty())
+            .find_map(|output| {
+                let state = self.root(pattern, output, index)?;
+                if !state.indices[1..]
+                    .iter()
+                    .all(|index| self.is_internal(*index, &state))
+                {
+                    return None;
+                }
+                Some(Match {
+                    output: output.clone(),
+                    nodes: state
+                        .indices
+                        .iter()
+                        .map(|index| self.graph.node[*index].clone())
+                        .collect(),
+                    indices: state.indices,
+                    values: state.values,
+                    names: state.names,
+                    constants: state.constants,
+                })
+            })
+    }
+
+    fn root(&self, pattern: &Pattern, output: &str, index: usize) -> Option<State> {
+        match pattern.kind {
+            Kind::Op { .. } if self.producers.get(output) == Some(&index) => {
+                self.value(pattern, output, State::default(), true)
+            }
+            _ => None,
+        }
+    }
+
+    fn value(&self, pattern: &Pattern, value: &str, mut state: State, root: bool) -> Option<State> {
+        match &pattern.kind {
+            Kind::Value(name) => {
+                if state.bind(name, value) {
+                    Some(state)
+                } else {
+                    None
+                }
+            }
+            Kind::Constant(name) => {
+                let tensor = self.constants.get(value)?;
+                if !state.bind(name, value) {
+                    return None;
+                }
+                state.constants.insert(name.clone(), tensor.clone());
+                Some(state)
+            }
+            Kind::Scalar(expected) => {
+                let values = tensor::to_f64_vec(self.constants.get(value)?)?;
+                if !values.is_empty() && values.iter().all(|value| value == expected) {
+                    Some(state)
+                } else {
+                    None
+                }
+            }
+            Kind::Op {
+                op_type,
+                domain,
+                inputs,
+                attributes,
+                name,
+            } => {
+                let index = *self.producers.get(value)?;
+                let node = &self.graph.node[index];
+                if node.op_type != *op_type
+                    || normalize_domain(&node.domain) != normalize_domain(domain)
+                    || self.taken.contains(&index)
+                    || !attributes.iter().all(|attr| has_attribute(node, attr))
+                    || (!root
+                        && node
+                            .output
+                            .iter()
+                            .any(|output| self.outputs.contains(output.as_str())))
+                {
+                    return None;
+                }
+                if let Some(name) = name {
+                    if !state.bind(name, value) {
+                        return None;
+                    }
+                    match state.names.get(name) {
+                        Some(bound) if state.indices[*bound] != index => return None,
+                        Some(_) => {}
+                        None => {
+                            let position = match state.indices.iter().position(|i| *i == index) {
+                                Some(position) => position,
+                                None => state.indices.len(),
+                            };
+                            state.names.insert(name.clone(), position);
+                        }
+                    }
+                }
+                if !state.indices.contains(&index) {
+                    state.indices.push(index);
+                }
+                let mut node_inputs: &[String] = &node.input;
+                while let Some((last, rest)) = node_inputs.split_last() {
+                    if !last.is_empty() {
+                        break;
+                    }
+                    node_inputs = rest;
+                }
+                if node_inputs.len() != inputs.len() {
+                    return None;
+                }
+                if inputs.len() == 2 && COMMUTATIVE_OPS.contains(&op_type.as_str()) {
+                    let swapped = [node_inputs[1].clone(), node_inputs[0].clone()];
+                    if let Some(state) = self.inputs(inputs, node_inputs, state.clone()) {
+                        return Some(state);
+                    }
+                    return self.inputs(inputs, &swapped, state);
+                }
+                self.inputs(inputs, node_inputs, state)
+            }
+        }
+    }
+
+    fn inputs(&self, patterns: &[Pattern], values: &[String], mut state: State) -> Option<State> {
+        for (pattern, value) in patterns.iter().zip(values) {
+            if value.is_empty() {
+                return None;
+            }
+            state = self.value(pattern, value, state, false)?;
+        }
+        Some(state)
+    }
+
+    /// Checks if replacement defines all outputs of the replaced node.
+    ///
+    /// Graph outputs can not be forwarded to other values.
+    fn replaces_outputs(&self, root: &NodeProto, replacement: &Replacement) -> bool {
+        if let Some(value) = &replacement.forward {
+            return root.output.len() == 1
+                && root.output[0] != *value
+                && !self.outputs.contains(root.output[0].as_str());
+        }
+        let defined: HashSet<&str> = replacement
+            .nodes
+            .iter()
+            .flat_map(|node| node.output.iter().map(String::as_str))
+            .collect();
+        root.output
+            .iter()
+            .all(|output| output.is_empty() || defined.contains(output.as_str()))
+    }
+
+    /// Checks if outputs of a node are used only by matched nodes.
+    fn is_internal(&self, index: usize, state: &State) -> bool {
+        self.graph.node[index].output.iter().all(|output| {
+            self.consumers.get(output).map_or(true, |consumers| {
+                consumers.iter().all(|c| state.indices.contains(c))
+            })
+        })
+    }
+}
+
+fn normalize_domain(domain: &str) -> &str {
+    if domain == "ai.onnx" {
+        ""
+    } else {
+        domain
+    }
+}
+
+fn has_attribute(node: &NodeProto, expected: &AttributeProto) -> bool {
+    node.attribute.iter().any(|attr| {
+        let mut attr = attr.clone();
+        attr.doc_string.clear();
+        attr.ref_attr_name.clear();
+        attr == *expected
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Punct(char),
+    Eof,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "`{}`", ident),
+            Token::Number(number) => write!(f, "`{}`", number),
+            Token::Punct(c) => write!(f, "`{}`", c),
+            Token::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+/// Pattern text parser.
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    start: usize,
+}
+
+impl Parser {
+    fn new(text: &str) -> Self {
+        Parser {
+            chars: text.chars().collect(),
+            position: 0,
+            start: 0,
+        }
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        ParseError {
+            line: 1,
+            column: self.start + 1,
+            message,
+        }
+    }
+
+    fn peek(&mut self) -> Token {
+        let position = self.position;
+        let token = self.next();
+        self.position = position;
+        token
+    }
+
+    fn next(&mut self) -> Token {
+        while self.position < self.chars.len() && self.chars[self.position].is_whitespace() {
+            self.position += 1;
+        }
+        self.start = self.position;
+        let c = match self.chars.get(self.position) {
+            Some(c) => *c,
+            None => return Token::Eof,
+        };
+        let take = |this: &mut Self, f: fn(char) -> bool| {
+            while this.position < this.chars.len() && f(this.chars[this.position]) {
+                this.position += 1;
+            }
+            this.chars[this.start..this.position]
+                .iter()
+                .collect::<String>()
+        };
+        if c.is_alphabetic() || c == '_' {
+            Token::Ident(take(self, |c| c.is_alphanumeric() || c == '_' || c == '.'))
+        } else if c.is_ascii_digit() || c == '-' || c == '+' {
+            self.position += 1;
+            let number = take(self, |c| {
+                c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || c == '-' || c == '+'
+            });
+            match number.parse() {
+                Ok(number) => Token::Number(number),
+                Err(_) => Token::Ident(number),
+            }
+        } else {
+            self.position += 1;
+            Token::Punct(c)
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        match self.next() {
+            Token::Punct(c) if c == expected => Ok(()),
+            token => Err(self.error(format!("expected `{}`, found {}", expected, token))),
+        }
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, ParseError> {
+        match self.next() {
+            Token::Number(number) => Ok(Pattern::scalar(number)),
+            Token::Ident(ident) => match self.peek() {
+                Token::Punct('(') => self.op(ident),
+                Token::Punct(':') => {
+                    self.next();
+                    match self.next() {
+                        Token::Ident(op_type) => Ok(self.op(op_type)?.named(ident)),
+                        token => Err(self.error(format!("expected operator, found {}", token))),
+                    }
+                }
+                _ => Ok(Pattern::value(ident)),
+            },
+            token => Err(self.error(format!("expected pattern, found {}", token))),
+        }
+    }
+
+    fn op(&mut self, op_type: String) -> Result<Pattern, ParseError> {
+        self.expect('(')?;
+        let mut inputs = Vec::new();
+        if self.peek() == Token::Punct(')') {
+            self.next();
+            return Ok(Pattern::op(op_type, inputs));
+        }
+        loop {
+            inputs.push(self.pattern()?);
+            match self.next() {
+                Token::Punct(',') => {}
+                Token::Punct(')') => return Ok(Pattern::op(op_type, inputs)),
+                token => return Err(self.error(format!("expected `,` or `)`, found {}", token))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        builder,
+        text::{parse_graph, print_graph},
+    };
+
+    fn gemm() -> Rule {
+        Rule::new("gemm", "Add(MatMul(a, b), c)".parse().unwrap(), |m| {
+            let node = NodeProto {
+                op_type: "Gemm".to_owned(),
+                input: vec![
+                    m.value("a").to_owned(),
+                    m.value("b").to_owned(),
+                    m.value("c").to_owned(),
+                ],
+                output: vec![m.output().to_owned()],
+                ..NodeProto::default()
+            };
+            Some(node.into())
+        })
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Pattern::parse("Sqrt(ReduceMean(Pow(Sub(x, m: ReduceMean(x)), 2)))").unwrap(),
+            Pattern::op(
+                "Sqrt",
+                vec![Pattern::op(
+                    "ReduceMean",
+                    vec![Pattern::op(
+                        "Pow",
+                        vec![
+                            Pattern::op(
+                                "Sub",
+                                vec![
+                                    Pattern::value("x"),
+                                    Pattern::op("ReduceMean", vec![Pattern::value("x")]).named("m"),
+                                ]
+                            ),
+                            Pattern::scalar(2.0),
+                        ]
+                    )]
+                )]
+            )
+        );
+        assert_eq!(
+            Pattern::parse("Shape()").unwrap(),
+            Pattern::op("Shape", vec![])
+        );
+        let error = Pattern::parse("Add(x, y").unwrap_err();
+        assert_eq!(error.column, 9);
+        assert!(Pattern::parse("Add(x) y").is_err());
+        assert!(Pattern::parse("m: x").is_err());
+    }
+
+    #[test]
+    fn fuse_gemm() {
+        let mut graph = parse_graph(
+            r#"
+            g (float[2,3] X, float[3,4] W, float[4] B, float[4,4] V) => (float[2,4] Y, float[2,4] Z) {
+                m = MatMul (X, W)
+                Y = Add (B, m)
+                n = MatMul (Y, V)
+                Z = Add (n, B)
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(gemm().apply(&mut graph), 2);
+        assert_eq!(
+            print_graph(&graph),
+            print_graph(
+                &parse_graph(
+                    r#"
+                    g (float[2,3] X, float[3,4] W, float[4] B, float[4,4] V) => (float[2,4] Y, float[2,4] Z) {
+                        Y = Gemm (X, W, B)
+                        Z = Gemm (Y, V, B)
+                    }
+                    "#
+                )
+                .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn single_consumer() {
+        let source = r#"
+            g (float[2,3] X, float[3,4] W, float[4] B) => (float[2,4] Y, float[2,4] Z) {
+                m = MatMul (X, W)
+                Y = Add (m, B)
+                Z = Relu (m)
+            }
+            "#;
+        let mut graph = parse_graph(source).unwrap();
+        assert_eq!(rewrite(&mut graph, &[gemm()]), 0);
+        assert_eq!(graph, parse_graph(source).unwrap());
+    }
+
+    #[test]
+    fn standard_deviation() {
+        let mut graph = parse_graph(
+            r#"
+            g (float[2,3] X, float[2,3] Y) => (float[2,1] S, float[2,1] T) <float two = {2.0}> {
+                a = ReduceMean <axes = [1]> (X)
+                b = Sub (X, a)
+                c = Pow (b, two)
+                d = ReduceMean <axes = [1]> (c)
+                S = Sqrt (d)
+                e = ReduceMean <axes = [1]> (Y)
+                f = Sub (X, e)
+                g = Pow (f, two)
+                h = ReduceMean <axes = [1]> (g)
+                T = Sqrt (h)
+            }
+            "#,
+        )
+        .unwrap();
+        let pattern = Pattern::parse("Sqrt(ReduceMean(Pow(Sub(x, m: ReduceMean(x)), 2)))").unwrap();
+        let found = find(&graph, &pattern);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].value("x"), "X");
+        assert_eq!(found[0].output(), "S");
+        assert_eq!(found[0].node("m").output, vec!["a"]);
+        assert_eq!(found[0].nodes().len(), 5);
+
+        let rule = Rule::new("std", pattern, |m| {
+            let node = builder::Node::new("StdDev")
+                .domain("custom")
+                .input(m.value("x"))
+                .output(m.output())
+                .attribute("axes", vec![1i64]);
+            Some(Replacement::new(vec![node.into()]))
+        });
+        assert_eq!(rule.apply(&mut graph), 1);
+        assert_eq!(graph.node.len(), 6);
+        assert_eq!(graph.node[0].op_type, "StdDev");
+        assert_eq!(graph.node[0].output, vec!["S"]);
+    }
+
+    #[test]
+    fn shared_subexpression() {
+        let source = r#"
+            g (float[2,3] X) => (float[2,3] Y) {
+                a = ReduceMean <axes = [-1]> (X)
+                d = Sub (X, a)
+                e = Mul (d, d)
+                v = ReduceMean <axes = [-1]> (e)
+                s = Sqrt (v)
+                Y = Div (d, s)
+            }
+            "#;
+        let pattern =
+            Pattern::parse("Div(d: Sub(x, ReduceMean(x)), Sqrt(ReduceMean(Mul(d, d))))").unwrap();
+        let graph = parse_graph(source).unwrap();
+        let found = find(&graph, &pattern);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].nodes().len(), 6);
+        assert_eq!(found[0].value("d"), "d");
+
+        let graph =
+            parse_graph(&source.replace("(float[2,3] Y)", "(float[2,3] Y, float[2,3] d)")).unwrap();
+        assert!(find(&graph, &pattern).is_empty());
+        let graph = parse_graph(&source.replace("e = Mul (d, d)", "e = Mul (d, X)")).unwrap();
+        assert!(find(&graph, &pattern).is_empty());
+    }
+
+    #[test]
+    fn attributes_and_constants() {
+        let mut graph = parse_graph(
+            r#"
+            g (float[3,2] W, float[2,3] X) => (float[2,2] Y, float[2,2] Z) <float[2] s = {0.5, 0.5}> {
+                t = Transpose <perm = [1, 0]> (W)
+                m = MatMul (X, t)
+                Y = Mul (m, s)
+                u = Transpose <perm = [0, 1]> (W)
+                n = MatMul (X, u)
+                Z = Mul (n, s)
+            }
+            "#,
+        )
+        .unwrap();
+        let pattern = Pattern::op(
+            "Mul",
+            vec![
+                Pattern::op(
+                    "MatMul",
+                    vec![
+                        Pattern::value("a"),
+                        Pattern::op("Transpose", vec![Pattern::value("b")])
+                            .attr("perm", vec![1i64, 0]),
+                    ],
+                ),
+                Pattern::constant("alpha"),
+            ],
+        );
+        let rule = Rule::new("scaled gemm", pattern, |m| {
+            let alpha = tensor::to_f64_vec(m.constant("alpha"))?;
+            if alpha.iter().any(|value| *value != alpha[0]) {
+                return None;
+            }
+            let node = builder::Node::new("Gemm")
+                .inputs(vec![m.value("a"), m.value("b")])
+                .output(m.output())
+                .attribute("transB", 1i64)
+                .attribute("alpha", alpha[0] as f32);
+            Some(Replacement::new(vec![node.into()]))
+        });
+        assert_eq!(rule.apply(&mut graph), 1);
+        let ops: Vec<&str> = graph
+            .node
+            .iter()
+            .map(|node| node.op_type.as_str())
+            .collect();
+        assert_eq!(ops, vec!["Gemm", "Transpose", "MatMul", "Mul"]);
+        assert_eq!(graph.node[0].input, vec!["X", "W"]);
+    }
+
+    #[test]
+    fn forward() {
+        let mut graph = parse_graph(
+            r#"
+            g (float[2] X) => (float[2] Y, float[2] Z) {
+                a = Identity (X)
+                b = Identity (a)
+                Y = Neg (b)
+                c = Neg (a)
+                Z = Identity (c)
+            }
+            "#,
+        )
+        .unwrap();
+        let rule = Rule::new("identity", "Identity(x)".parse().unwrap(), |m| {
+            Some(Replacement::forward(m.value("x")))
+        });
+        assert_eq!(rule.apply(&mut graph), 2);
+        assert_eq!(
+            print_graph(&graph),
+            print_graph(
+                &parse_graph(
+                    r#"
+                    g (float[2] X) => (float[2] Y, float[2] Z) {
+                        Y = Neg (X)
+                        c = Neg (X)
+                        Z = Identity (c)
+                    }
+                    "#
+                )
+                .unwrap()
+            )
+        );
+    }
+}
@@ -0,0 +1,833 @@
+//! Operator schemas.
+//!
+//! Schemas describe operator signatures: input and output arity, attribute
+//! types and an optional type inference function. Schemas of custom
+//! operators are registered with [`register`](fn.register.html) and used
+//! by the [`checker`](../checker/index.html), by type inference and by
+//! [`builder::Model`](../builder/struct.Model.html) to import their domains.
+//!
+//! ```
+//! use onnx_helpers::schema::{self, OpSchema};
+//! use onnx_pb::attribute_proto::AttributeType;
+//!
+//! schema::register(
+//!     OpSchema::new("com.example", "Swish")
+//!         .since_version(1)
+//!         .inputs(1, 1)
+//!         .outputs(1, 1)
+//!         .attribute("beta", AttributeType::Float)
+//!         .inference(|_, inputs| Ok(vec![inputs[0].cloned().ok_or("unknown input type")?])),
+//! );
+//! assert!(schema::lookup("com.example", "Swish", Some(1)).is_some());
+//! ```
+
+mod standard;
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use onnx_pb::{
+    attribute_proto::AttributeType, make_attribute, tensor_proto::DataType, type_proto, Attribute,
+    AttributeProto, GraphProto, ModelProto, NodeProto, TypeProto, ValueInfoProto,
+};
+
+use crate::{optim, text, visit};
+
+/// Type inference function.
+///
+/// Receives the node and types of its inputs, `None` if a type is unknown
+/// or the input is omitted, and returns types of node outputs.
+pub type InferenceFn =
+    Arc<dyn Fn(&NodeProto, &[Option<&TypeProto>]) -> Result<Vec<TypeProto>, String> + Send + Sync>;
+
+/// Attribute of an operator schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttributeSchema {
+    /// Attribute name.
+    pub name: String,
+    /// Attribute type.
+    pub r#type: AttributeType,
+    /// Whether the attribute is required.
+    pub required: bool,
+    /// Value used when the attribute is not set.
+    pub default: Option<AttributeProto>,
+}
+
+/// Arity of a formal parameter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParameterOption {
+    /// Exactly one value.
+    Single,
+    /// One value, may be omitted or empty.
+    Optional,
+    /// Trailing values, at least the given number.
+    Variadic(usize),
+}
+
+/// Formal input or output of an operator schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormalParameter {
+    /// Parameter name.
+    pub name: String,
+    /// Type parameter, e.g. `T`, or a tensor element type, e.g. `int64`.
+    pub type_str: String,
+    /// Parameter arity.
+    pub option: ParameterOption,
+}
+
+/// Operator schema.
+#[derive(Clone)]
+pub struct OpSchema {
+    domain: String,
+    name: String,
+    since_version: i64,
+    min_inputs: usize,
+    max_inputs: usize,
+    min_outputs: usize,
+    max_outputs: usize,
+    formal_inputs: Vec<FormalParameter>,
+    formal_outputs: Vec<FormalParameter>,
+    attributes: Vec<AttributeSchema>,
+    type_constraints: Vec<(String, Vec<DataType>)>,
+    inference: Option<InferenceFn>,
+}
+
+impl OpSchema {
+    /// Creates a new schema of an operator taking and producing one value.
+    pub fn new<D: Into<String>, S: Into<String>>(domain: D, name: S) -> Self {
+        OpSchema {
+            domain: normalize_domain(&domain.into()).to_owned(),
+            name: name.into(),
+            since_version: 1,
+            min_inputs: 1,
+            max_inputs: 1,
+            min_outputs: 1,
+            max_outputs: 1,
+            formal_inputs: Vec::new(),
+            formal_outputs: Vec::new(),
+            attributes: Vec::new(),
+            type_constraints: Vec::new(),
+            inference: None,
+        }
+    }
+
+    /// Sets version of the domain which introduced this schema.
+    #[inline]
+    pub fn since_version(mut self, version: i64) -> Self {
+        self.since_version = version;
+        self
+    }
+
+    /// Sets input arity, use `usize::MAX` for variadic inputs.
+    #[inline]
+    pub fn inputs(mut self, min: usize, max: usize) -> Self {
+        self.min_inputs = min;
+        self.max_inputs = max;
+        self
+    }
+
+    /// Sets output arity, use `usize::MAX` for variadic outputs.
+    #[inline]
+    pub fn outputs(mut self, min: usize, max: usize) -> Self {
+        self.min_outputs = min;
+        self.max_outputs = max;
+        self
+    }
+
+    /// Inserts formal input, input arity follows formal inputs.
+    #[inline]
+    pub fn input<N: Into<String>, T: Into<String>>(
+        mut self,
+        name: N,
+        type_str: T,
+        option: ParameterOption,
+    ) -> Self {
+        self.formal_inputs.push(FormalParameter {
+            name: name.into(),
+            type_str: type_str.into(),
+            option,
+        });
+        let (min, max) = formal_arity(&self.formal_inputs);
+        self.inputs(min, max)
+    }
+
+    /// Inserts formal output, output arity follows formal outputs.
+    #[inline]
+    pub fn output<N: Into<String>, T: Into<String>>(
+        mut self,
+        name: N,
+        type_str: T,
+        option: ParameterOption,
+    ) -> Self {
+        self.formal_outputs.push(FormalParameter {
+            name: name.into(),
+            type_str: type_str.into(),
+            option,
+        });
+        let (min, max) = formal_arity(&self.formal_outputs);
+        self.outputs(min, max)
+    }
+
+    /// Inserts optional attribute.
+    #[inline]
+    pub fn attribute<S: Into<String>>(mut self, name: S, typ: AttributeType) -> Self {
+        self.attributes.push(AttributeSchema {
+            name: name.into(),
+            r#type: typ,
+            required: false,
+            default: None,
+        });
+        self
+    }
+
+    /// Inserts optional attribute with a default value.
+    #[inline]
+    pub fn default_attribute<S: Into<String>, A: Into<Attribute>>(
+        mut self,
+        name: S,
+        value: A,
+    ) -> Self {
+        let name = name.into();
+        let default = make_attribute(name.clone(), value.into());
+        self.attributes.push(AttributeSchema {
+            name,
+            r#type: AttributeType::from_i32(default.r#type).unwrap_or(AttributeType::Undefined),
+            required: false,
+            default: Some(default),
+        });
+        self
+    }
+
+    /// Inserts required attribute.
+    #[inline]
+    pub fn required_attribute<S: Into<String>>(mut self, name: S, typ: AttributeType) -> Self {
+        self.attributes.push(AttributeSchema {
+            name: name.into(),
+            r#type: typ,
+            required: true,
+            default: None,
+        });
+        self
+    }
+
+    /// Constrains tensor element types bound to a type parameter.
+    ///
+    /// Type parameters without constraints are not checked, values bound to
+    /// them may be of different types.
+    #[inline]
+    pub fn type_constraint<S: Into<String>>(mut self, param: S, types: &[DataType]) -> Self {
+        self.type_constraints.push((param.into(), types.to_vec()));
+        self
+    }
+
+    /// Sets type inference function.
+    #[inline]
+    pub fn inference<F>(mut self, inference: F) -> Self
+    where
+        F: Fn(&NodeProto, &[Option<&TypeProto>]) -> Result<Vec<TypeProto>, String>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.inference = Some(Arc::new(inference));
+        self
+    }
+
+    /// Returns operator domain, empty for the standard domain.
+    #[inline]
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Returns operator name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns version of the domain which introduced this schema.
+    #[inline]
+    pub fn version(&self) -> i64 {
+        self.since_version
+    }
+
+    /// Returns formal inputs.
+    #[inline]
+    pub fn formal_inputs(&self) -> &[FormalParameter] {
+        &self.formal_inputs
+    }
+
+    /// Returns formal outputs.
+    #[inline]
+    pub fn formal_outputs(&self) -> &[FormalParameter] {
+        &self.formal_outputs
+    }
+
+    /// Returns schema attributes.
+    #[inline]
+    pub fn attributes(&self) -> &[AttributeSchema] {
+        &self.attributes
+    }
+
+    /// Returns allowed element types of a type parameter.
+    #[inline]
+    pub fn type_constraint_of(&self, param: &str) -> Option<&[DataType]> {
+        self.type_constraints
+            .iter()
+            .find(|(name, _)| name == param)
+            .map(|(_, types)| types.as_slice())
+    }
+
+    /// Verifies node against the schema, returning error messages.
+    pub fn verify(&self, node: &NodeProto) -> Vec<String> {
+        let mut errors = Vec::new();
+        let inputs = trimmed_len(&node.input);
+        if inputs < self.min_inputs || inputs > self.max_inputs {
+            errors.push(format!(
+                "{} expects {} inputs, got {}",
+                self.name,
+                arity(self.min_inputs, self.max_inputs),
+                inputs
+            ));
+        }
+        let outputs = trimmed_len(&node.output);
+        if outputs < self.min_outputs || outputs > self.max_outputs {
+            errors.push(format!(
+                "{} expects {} outputs, got {}",
+                self.name,
+                arity(self.min_outputs, self.max_outputs),
+                outputs
+            ));
+        }
+        for (params, values, kind) in [
+            (&self.formal_inputs, &node.input, "input"),
+            (&self.formal_outputs, &node.output, "output"),
+        ] {
+            for (param, value) in params.iter().zip(values.iter()) {
+                if param.option != ParameterOption::Optional && value.is_empty() {
+                    errors.push(format!(
+                        "{} {:?} of {} is not optional",
+                        kind, param.name, self.name
+                    ));
+                }
+            }
+        }
+        for attr in node.attribute.iter() {
+            match self
+                .attributes
+                .iter()
+                .find(|schema| schema.name == attr.name)
+            {
+                Some(schema) if schema.r#type as i32 != attr.r#type => errors.push(format!(
+                    "attribute {:?} of {} must be of type {:?}",
+                    attr.name, self.name, schema.r#type
+                )),
+                Some(_) => {}
+                None => errors.push(format!("{} has no attribute {:?}", self.name, attr.name)),
+            }
+        }
+        for schema in self.attributes.iter().filter(|schema| schema.required) {
+            if node.attribute.iter().all(|attr| attr.name != schema.name) {
+                errors.push(format!(
+                    "{} requires attribute {:?}",
+                    self.name, schema.name
+                ));
+            }
+        }
+        errors
+    }
+
+    /// Verifies element types of node inputs and outputs against type
+    /// constraints, returning error messages. Unknown types are skipped.
+    pub fn verify_types(
+        &self,
+        node: &NodeProto,
+        inputs: &[Option<&TypeProto>],
+        outputs: &[Option<&TypeProto>],
+    ) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut bound: HashMap<&str, (i32, &str)> = HashMap::new();
+        let values = node
+            .input
+            .iter()
+            .zip(inputs.iter())
+            .zip(formal_iter(&self.formal_inputs))
+            .chain(
+                node.output
+                    .iter()
+                    .zip(outputs.iter())
+                    .zip(formal_iter(&self.formal_outputs)),
+            );
+        for ((name, typ), param) in values {
+            let elem_type = match typ.and_then(|typ| typ.value.as_ref()) {
+                Some(type_proto::Value::TensorType(tensor)) => tensor.elem_type,
+                _ => continue,
+            };
+            let type_name = text::data_type_name(elem_type).unwrap_or("undefined");
+            let allowed: Option<Vec<DataType>> = match text::data_type_by_name(&param.type_str) {
+                Some(data_type) => Some(vec![data_type]),
+                None => self.type_constraint_of(&param.type_str).map(<[_]>::to_vec),
+            };
+            let allowed = match allowed {
+                Some(allowed) => allowed,
+                None => continue,
+            };
+            if allowed
+                .iter()
+                .all(|data_type| *data_type as i32 != elem_type)
+            {
+                errors.push(format!(
+                    "{:?} of type {} is not allowed for {} of {}",
+                    name, type_name, param.type_str, self.name
+                ));
+                continue;
+            }
+            match bound.get(param.type_str.as_str()) {
+                Some((bound_type, bound_name)) if *bound_type != elem_type => {
+                    errors.push(format!(
+                        "{:?} of type {} does not match {:?} of type {} bound to {} of {}",
+                        name,
+                        type_name,
+                        bound_name,
+                        text::data_type_name(*bound_type).unwrap_or("undefined"),
+                        param.type_str,
+                        self.name
+                    ));
+                }
+                Some(_) => {}
+                None => {
+                    bound.insert(param.type_str.as_str(), (elem_type, name.as_str()));
+                }
+            }
+        }
+        errors
+    }
+
+    /// Returns element type of a node output.
+    ///
+    /// The type comes from the formal output when it names an element type
+    /// or a type constraint with a single type, from type attributes such as
+    /// `to` of `Cast`, or from inputs bound to the same type parameter, whose
+    /// element types are given by `input_type`.
+    pub(crate) fn output_elem_type<F>(
+        &self,
+        node: &NodeProto,
+        index: usize,
+        input_type: F,
+    ) -> Option<DataType>
+    where
+        F: Fn(&str) -> Option<DataType>,
+    {
+        let param = formal_iter(&self.formal_outputs).nth(index)?;
+        if let Some(data_type) = text::data_type_by_name(&param.type_str) {
+            return Some(data_type);
+        }
+        if node.op_type == "Constant" {
+            return DataType::from_i32(optim::constant_value(node)?.data_type);
+        }
+        let attribute = ["to", "dtype", "output_datatype"]
+            .iter()
+            .find_map(|name| node.attribute.iter().find(|attr| attr.name == *name));
+        if let Some(attr) = attribute {
+            return DataType::from_i32(attr.i as i32);
+        }
+        if let Some(tensor) = node
+            .attribute
+            .iter()
+            .find(|attr| attr.name == "value")
+            .and_then(|attr| attr.t.as_ref())
+        {
+            return DataType::from_i32(tensor.data_type);
+        }
+        if let Some([data_type]) = self.type_constraint_of(&param.type_str) {
+            return Some(*data_type);
+        }
+        node.input
+            .iter()
+            .zip(formal_iter(&self.formal_inputs))
+            .filter(|(input, formal)| !input.is_empty() && formal.type_str == param.type_str)
+            .find_map(|(input, _)| input_type(input))
+    }
+
+    /// Infers output types of a node, if schema has an inference function.
+    pub fn infer(
+        &self,
+        node: &NodeProto,
+        inputs: &[Option<&TypeProto>],
+    ) -> Option<Result<Vec<TypeProto>, String>> {
+        self.inference
+            .as_ref()
+            .map(|inference| inference(node, inputs))
+    }
+}
+
+impl std::fmt::Debug for OpSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpSchema")
+            .field("domain", &self.domain)
+            .field("name", &self.name)
+            .field("since_version", &self.since_version)
+            .field("inputs", &(self.min_inputs, self.max_inputs))
+            .field("outputs", &(self.min_outputs, self.max_outputs))
+            .field("formal_inputs", &self.formal_inputs)
+            .field("formal_outputs", &self.formal_outputs)
+            .field("attributes", &self.attributes)
+            .field("type_constraints", &self.type_constraints)
+            .field("inference", &self.inference.is_some())
+            .finish()
+    }
+}
+
+type Schemas = HashMap<(String, String), Vec<Arc<OpSchema>>>;
+
+fn registry() -> &'static RwLock<Schemas> {
+    static REGISTRY: OnceLock<RwLock<Schemas>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut schemas = Schemas::new();
+        for schema in standard::schemas() {
+            schemas
+                .entry((schema.domain.clone(), schema.name.clone()))
+                .or_default()
+                .push(Arc::new(schema));
+        }
+        RwLock::new(schemas)
+    })
+}
+
+/// Registers operator schema, replacing a schema of the same version.
+pub fn register(schema: OpSchema) {
+    let mut schemas = registry().write().unwrap();
+    let versions = schemas
+        .entry((schema.domain.clone(), schema.name.clone()))
+        .or_default();
+    versions.retain(|registered| registered.since_version != schema.since_version);
+    versions.push(Arc::new(schema));
+    versions.sort_by_key(|schema| schema.since_version);
+}
+
+/// Returns schema of an operator in a domain version, latest if `None`.
+pub fn lookup(domain: &str, op_type: &str, version: Option<i64>) -> Option<Arc<OpSchema>> {
+    let schemas = registry().read().unwrap();
+    let versions = schemas.get(&(normalize_domain(domain).to_owned(), op_type.to_owned()))?;
+    versions
+        .iter()
+        .rev()
+        .find(|schema| version.map_or(true, |version| schema.since_version <= version))
+        .cloned()
+}
+
+/// Returns true if any version of an operator is registered.
+pub fn is_registered(domain: &str, op_type: &str) -> bool {
+    registry()
+        .read()
+        .unwrap()
+        .contains_key(&(normalize_domain(domain).to_owned(), op_type.to_owned()))
+}
+
+/// Returns latest registered version of a domain.
+pub fn domain_version(domain: &str) -> Option<i64> {
+    let domain = normalize_domain(domain);
+    registry()
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|((registered, _), _)| registered == domain)
+        .flat_map(|(_, versions)| versions.iter().map(|schema| schema.since_version))
+        .max()
+}
+
+/// Schema error of a node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    /// Node name, or its first output if the node is unnamed.
+    pub node: String,
+    /// Error message.
+    pub message: String,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.node, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Infers types of node outputs in the main graph using registered schemas.
+///
+/// Inferred types are added to `value_info`, or to graph outputs which have
+/// no type. Returns number of inferred values.
+pub fn infer_types(model: &mut ModelProto) -> Result<usize, Error> {
+    let versions: HashMap<&str, i64> = model
+        .opset_import
+        .iter()
+        .map(|opset| (normalize_domain(&opset.domain), opset.version))
+        .collect();
+    let graph = match model.graph.as_mut() {
+        Some(graph) => graph,
+        None => return Ok(0),
+    };
+    let mut types: HashMap<String, TypeProto> = typed_values(graph)
+        .filter_map(|value| Some((value.name.clone(), value.r#type.clone()?)))
+        .collect();
+
+    let mut inferred = Vec::new();
+    for node in graph.node.iter() {
+        let version = versions.get(normalize_domain(&node.domain)).copied();
+        let schema = match lookup(&node.domain, &node.op_type, version) {
+            Some(schema) => schema,
+            None => continue,
+        };
+        let inputs: Vec<Option<&TypeProto>> =
+            node.input.iter().map(|input| types.get(input)).collect();
+        let outputs = match schema.infer(node, &inputs) {
+            Some(Ok(outputs)) => outputs,
+            Some(Err(message)) => {
+                return Err(Error {
+                    node: if node.name.is_empty() {
+                        node.output.first().cloned().unwrap_or_default()
+                    } else {
+                        node.name.clone()
+                    },
+                    message,
+                })
+            }
+            None => continue,
+        };
+        for (name, typ) in node.output.iter().zip(outputs) {
+            if !name.is_empty() && !types.contains_key(name) {
+                types.insert(name.clone(), typ.clone());
+                inferred.push((name.clone(), typ));
+            }
+        }
+    }
+
+    let count = inferred.len();
+    for (name, typ) in inferred {
+        match graph.output.iter_mut().find(|output| output.name == name) {
+            Some(output) => output.r#type = Some(typ),
+            None => graph.value_info.push(ValueInfoProto {
+                name,
+                r#type: Some(typ),
+                ..ValueInfoProto::default()
+            }),
+        }
+    }
+    Ok(count)
+}
+
+/// Returns values of a graph which may carry types.
+fn typed_values(graph: &GraphProto) -> impl Iterator<Item = &ValueInfoProto> {
+    graph
+        .input
+        .iter()
+        .chain(graph.value_info.iter())
+        .chain(graph.output.iter())
+        .filter(|value| value.r#type.is_some())
+}
+
+/// Returns non-standard domains used by nodes of a graph and its subgraphs.
+pub(crate) fn used_domains(graph: &GraphProto, domains: &mut Vec<String>) {
+    for node in graph.node.iter() {
+        let domain = normalize_domain(&node.domain);
+        if !domain.is_empty() && !domains.iter().any(|used| used == domain) {
+            domains.push(domain.to_owned());
+        }
+        for subgraph in visit::subgraphs(node) {
+            used_domains(subgraph, domains);
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn normalize_domain(domain: &str) -> &str {
+    if domain == "ai.onnx" {
+        ""
+    } else {
+        domain
+    }
+}
+
+/// Returns input or output arity of formal parameters.
+fn formal_arity(params: &[FormalParameter]) -> (usize, usize) {
+    let mut min = 0;
+    let mut max = params.len();
+    for (index, param) in params.iter().enumerate() {
+        match param.option {
+            ParameterOption::Single => min = index + 1,
+            ParameterOption::Optional => {}
+            ParameterOption::Variadic(count) => {
+                min = min.max(index + count);
+                max = usize::MAX;
+            }
+        }
+    }
+    (min, max)
+}
+
+/// Iterates formal parameters, repeating the trailing variadic one.
+fn formal_iter(params: &[FormalParameter]) -> impl Iterator<Item = &FormalParameter> {
+    let variadic = params
+        .last()
+        .filter(|param| matches!(param.option, ParameterOption::Variadic(_)));
+    params.iter().chain(variadic.into_iter().cycle())
+}
+
+fn trimmed_len(values: &[String]) -> usize {
+    values.len()
+        - values
+            .iter()
+            .rev()
+            .take_while(|value| value.is_empty())
+            .count()
+}
+
+fn arity(min: usize, max: usize) -> String {
+    if min == max {
+        min.to_string()
+    } else if max == usize::MAX {
+        format!("at least {}", min)
+    } else {
+        format!("{} to {}", min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{builder, checker, text::parse_model};
+
+    fn register_swish() {
+        register(
+            OpSchema::new("test.schema", "Swish")
+                .since_version(2)
+                .inputs(1, 1)
+                .outputs(1, 1)
+                .required_attribute("beta", AttributeType::Float)
+                .inference(|_, inputs| Ok(vec![inputs[0].cloned().ok_or("unknown input")?])),
+        );
+    }
+
+    #[test]
+    fn register_and_check() {
+        register_swish();
+        assert_eq!(domain_version("test.schema"), Some(2));
+        assert!(lookup("test.schema", "Swish", Some(1)).is_none());
+
+        let mut model = parse_model(
+            r#"
+            <ir_version: 6, opset_import: ["" : 11, "test.schema" : 2]>
+            g (float[2] X) => (Y, Z) {
+                Y = test.schema.Swish <beta = 1.0> (X)
+                Z = test.schema.Swish <beta = 1> (X, X)
+            }
+            "#,
+        )
+        .unwrap();
+        let errors: Vec<String> = checker::check_model(&model)
+            .unwrap_err()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "graph.node[1]: Swish expects 1 inputs, got 2",
+                "graph.node[1]: attribute \"beta\" of Swish must be of type Float",
+                "graph.output[0]: output \"Y\" has no type",
+                "graph.output[1]: output \"Z\" has no type",
+            ]
+        );
+
+        model.graph.as_mut().unwrap().node.pop();
+        model.graph.as_mut().unwrap().output.pop();
+        assert_eq!(infer_types(&mut model), Ok(1));
+        assert_eq!(checker::check_model(&model), Ok(()));
+    }
+
+    #[test]
+    fn import_used_domains() {
+        register_swish();
+        let mut graph = builder::Graph::new("g");
+        let swish = graph
+            .node("swish")
+            .op("Swish")
+            .domain("test.schema")
+            .input("X")
+            .attribute("beta", 1.0)
+            .build();
+        let model = graph.outputs(swish).model().build();
+        let imports: Vec<(&str, i64)> = model
+            .opset_import
+            .iter()
+            .map(|opset| (opset.domain.as_str(), opset.version))
+            .collect();
+        assert_eq!(imports, vec![("", 11), ("test.schema", 2)]);
+    }
+
+    #[test]
+    fn standard_schemas() {
+        let schema = lookup("", "ReduceSum", Some(17)).unwrap();
+        assert_eq!(schema.version(), 13);
+        assert_eq!(schema.formal_inputs()[1].option, ParameterOption::Optional);
+        let keepdims = schema.attributes().iter().find(|a| a.name == "keepdims");
+        assert_eq!(keepdims.unwrap().default.as_ref().unwrap().i, 1);
+
+        let mut graph = builder::Graph::new("g");
+        let err = graph
+            .node("concat")
+            .op("Concat")
+            .inputs(vec!["A", "B"])
+            .try_build()
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "concat: Concat requires attribute \"axis\""
+        );
+        let err = graph
+            .node("pow")
+            .op("Pow")
+            .input("A")
+            .try_build()
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "pow: Pow expects 2 inputs, got 1");
+
+        let mut graph = builder::Graph::new("g").opset(13);
+        assert!(graph
+            .node("sum")
+            .op("ReduceSum")
+            .input("A")
+            .attribute("axes", vec![1i64])
+            .try_build()
+            .is_ok());
+    }
+
+    #[test]
+    fn check_types() {
+        let model = parse_model(
+            r#"
+            <ir_version: 7, opset_import: ["" : 12]>
+            g (float[2] X, int64[2] N) => (float[2] Y, float[2] Z) {
+                Y = Add (X, N)
+                Z = Range (X, X, X)
+                M = Max (N, N, N)
+            }
+            "#,
+        )
+        .unwrap();
+        let errors: Vec<String> = checker::check_model(&model)
+            .unwrap_err()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "graph.node[0]: \"N\" of type int64 does not match \"X\" of type float bound to T of Add",
+            ]
+        );
+    }
+}
@@ -0,0 +1,455 @@
+//! Schemas of the standard operator set.
+//!
+//! Each row holds operator name, version from which the signature applies,
+//! signature, attributes and type constraints. Versions before the first
+//! row of an operator are not checked.
+//!
+//! Signature formal parameters are `name: T` with a type parameter or an
+//! element type, suffixed by `?` if optional, `...` if variadic and `*` if
+//! variadic and possibly empty. Attributes are `name: type`, suffixed by `!`
+//! if required or followed by `= value`. Type constraints list element
+//! types or groups: `all`, `numeric`, `signed`, `int`, `float`, `index`.
+
+use onnx_pb::{attribute_proto::AttributeType, tensor_proto::DataType, Attribute};
+
+use super::{OpSchema, ParameterOption};
+use crate::text;
+
+type Row = (&'static str, i64, &'static str, &'static str, &'static str);
+
+const CONV: &str = "auto_pad: string = \"NOTSET\"; dilations: ints; group: int = 1; \
+                    kernel_shape: ints; pads: ints; strides: ints";
+const CONV_TRANSPOSE: &str = "auto_pad: string = \"NOTSET\"; dilations: ints; group: int = 1; \
+                              kernel_shape: ints; output_padding: ints; output_shape: ints; \
+                              pads: ints; strides: ints";
+const REDUCE: &str = "axes: ints; keepdims: int = 1";
+const REDUCE_INPUT: &str = "keepdims: int = 1; noop_with_empty_axes: int = 0";
+const RNN: &str = "activation_alpha: floats; activation_beta: floats; activations: strings; \
+                   clip: float; direction: string = \"forward\"; hidden_size: int; \
+                   layout: int = 0";
+const RANDOM: &str = "dtype: int = 1; mean: float = 0.0; scale: float = 1.0; seed: float";
+const UNIFORM: &str = "dtype: int = 1; high: float = 1.0; low: float = 0.0; seed: float";
+const WINDOW: &str = "output_datatype: int = 1; periodic: int = 1";
+
+const SCHEMAS: &[Row] = &[
+    ("Abs", 6, "X: T -> Y: T", "", "T: numeric"),
+    ("Acos", 7, "input: T -> output: T", "", "T: float"),
+    ("Acosh", 9, "input: T -> output: T", "", "T: float"),
+    ("Add", 7, "A: T, B: T -> C: T", "", "T: numeric"),
+    ("And", 7, "A: T, B: T -> C: T1", "", "T: bool; T1: bool"),
+    ("ArgMax", 1, "data: T -> reduced: int64", "axis: int = 0; keepdims: int = 1", "T: numeric"),
+    ("ArgMax", 12, "data: T -> reduced: int64", "axis: int = 0; keepdims: int = 1; select_last_index: int = 0", "T: numeric"),
+    ("ArgMin", 1, "data: T -> reduced: int64", "axis: int = 0; keepdims: int = 1", "T: numeric"),
+    ("ArgMin", 12, "data: T -> reduced: int64", "axis: int = 0; keepdims: int = 1; select_last_index: int = 0", "T: numeric"),
+    ("Asin", 7, "input: T -> output: T", "", "T: float"),
+    ("Asinh", 9, "input: T -> output: T", "", "T: float"),
+    ("Atan", 7, "input: T -> output: T", "", "T: float"),
+    ("Atanh", 9, "input: T -> output: T", "", "T: float"),
+    ("AveragePool", 7, "X: T -> Y: T", "auto_pad: string = \"NOTSET\"; ceil_mode: int = 0; count_include_pad: int = 0; kernel_shape: ints!; pads: ints; strides: ints", "T: float"),
+    ("BatchNormalization", 9, "X: T, scale: T1, B: T1, input_mean: T2, input_var: T2 -> Y: T, running_mean?: T2, running_var?: T2, saved_mean?: T2, saved_var?: T2", "epsilon: float = 1e-5; momentum: float = 0.9; training_mode: int = 0", "T: float; T1: float; T2: float"),
+    ("Bernoulli", 15, "input: T1 -> output: T2", "dtype: int; seed: float", "T1: float; T2: numeric | bool"),
+    ("BitShift", 11, "X: T, Y: T -> Z: T", "direction: string!", "T: uint8 | uint16 | uint32 | uint64"),
+    ("BitwiseAnd", 18, "A: T, B: T -> C: T", "", "T: int"),
+    ("BitwiseNot", 18, "X: T -> Y: T", "", "T: int"),
+    ("BitwiseOr", 18, "A: T, B: T -> C: T", "", "T: int"),
+    ("BitwiseXor", 18, "A: T, B: T -> C: T", "", "T: int"),
+    ("BlackmanWindow", 17, "size: T1 -> output: T2", WINDOW, "T1: index; T2: numeric"),
+    ("Cast", 6, "input: T1 -> output: T2", "to: int!", "T1: all; T2: all"),
+    ("CastLike", 15, "input: T1, target_type: T2 -> output: T2", "", "T1: all; T2: all"),
+    ("Ceil", 6, "X: T -> Y: T", "", "T: float"),
+    ("Celu", 12, "X: T -> Y: T", "alpha: float = 1.0", "T: float"),
+    ("CenterCropPad", 18, "input_data: T, shape: Tind -> output_data: T", "axes: ints", "T: all; Tind: index"),
+    ("Clip", 6, "input: T -> output: T", "max: float; min: float", "T: float"),
+    ("Clip", 11, "input: T, min?: T, max?: T -> output: T", "", "T: float"),
+    ("Clip", 12, "input: T, min?: T, max?: T -> output: T", "", "T: numeric"),
+    ("Col2Im", 18, "input: T, image_shape: int64, block_shape: int64 -> output: T", "dilations: ints; pads: ints; strides: ints", "T: all"),
+    ("Compress", 9, "input: T, condition: T1 -> output: T", "axis: int", "T: all; T1: bool"),
+    ("Concat", 4, "inputs...: T -> concat_result: T", "axis: int!", "T: all"),
+    ("ConcatFromSequence", 11, "input_sequence: S -> concat_result: T", "axis: int!; new_axis: int = 0", ""),
+    ("Constant", 1, " -> output: T", "sparse_value: sparse_tensor; value: tensor; value_float: float; value_floats: floats; value_int: int; value_ints: ints; value_string: string; value_strings: strings", "T: all"),
+    ("ConstantOfShape", 9, "input: int64 -> output: T2", "value: tensor", "T2: numeric | bool"),
+    ("Conv", 1, "X: T, W: T, B?: T -> Y: T", CONV, "T: float"),
+    ("ConvInteger", 10, "x: T1, w: T2, x_zero_point?: T1, w_zero_point?: T2 -> y: int32", CONV, "T1: int8 | uint8; T2: int8 | uint8"),
+    ("ConvTranspose", 1, "X: T, W: T, B?: T -> Y: T", CONV_TRANSPOSE, "T: float"),
+    ("Cos", 7, "input: T -> output: T", "", "T: float"),
+    ("Cosh", 9, "input: T -> output: T", "", "T: float"),
+    ("CumSum", 11, "x: T, axis: T2 -> y: T", "exclusive: int = 0; reverse: int = 0", "T: numeric; T2: index"),
+    ("DFT", 17, "input: T1, dft_length?: T2 -> output: T1", "axis: int = 1; inverse: int = 0; onesided: int = 0", "T1: float; T2: index"),
+    ("DepthToSpace", 1, "input: T -> output: T", "blocksize: int!; mode: string = \"DCR\"", "T: all"),
+    ("DequantizeLinear", 10, "x: T, x_scale: float, x_zero_point?: T -> y: float", "axis: int = 1", "T: int8 | uint8 | int32"),
+    ("Det", 11, "X: T -> Y: T", "", "T: float"),
+    ("Div", 7, "A: T, B: T -> C: T", "", "T: numeric"),
+    ("Dropout", 7, "data: T -> output: T, mask?: T1", "ratio: float = 0.5", "T: float; T1: float | bool"),
+    ("Dropout", 12, "data: T, ratio?: T1, training_mode?: T2 -> output: T, mask?: T2", "seed: int", "T: float; T1: float; T2: bool"),
+    ("DynamicQuantizeLinear", 11, "x: float -> y: uint8, y_scale: float, y_zero_point: uint8", "", ""),
+    ("Einsum", 12, "Inputs...: T -> Output: T", "equation: string!", "T: numeric"),
+    ("Elu", 6, "X: T -> Y: T", "alpha: float = 1.0", "T: float"),
+    ("Equal", 7, "A: T, B: T -> C: T1", "", "T: all; T1: bool"),
+    ("Erf", 9, "input: T -> output: T", "", "T: numeric"),
+    ("Exp", 6, "input: T -> output: T", "", "T: float"),
+    ("Expand", 8, "input: T, shape: int64 -> output: T", "", "T: all"),
+    ("EyeLike", 9, "input: T1 -> output: T2", "dtype: int; k: int = 0", "T1: numeric | bool; T2: numeric | bool"),
+    ("Flatten", 1, "input: T -> output: T", "axis: int = 1", "T: all"),
+    ("Floor", 6, "X: T -> Y: T", "", "T: float"),
+    ("GRU", 7, "X: T, W: T, R: T, B?: T, sequence_lens?: T1, initial_h?: T -> Y?: T, Y_h?: T", "activation_alpha: floats; activation_beta: floats; activations: strings; clip: float; direction: string = \"forward\"; hidden_size: int; layout: int = 0; linear_before_reset: int = 0", "T: float; T1: int32"),
+    ("Gather", 1, "data: T, indices: Tind -> output: T", "axis: int = 0", "T: all; Tind: index"),
+    ("GatherElements", 11, "data: T, indices: Tind -> output: T", "axis: int = 0", "T: all; Tind: index"),
+    ("GatherND", 11, "data: T, indices: int64 -> output: T", "batch_dims: int = 0", "T: all"),
+    ("Gemm", 7, "A: T, B: T, C: T -> Y: T", "alpha: float = 1.0; beta: float = 1.0; transA: int = 0; transB: int = 0", "T: float"),
+    ("Gemm", 9, "A: T, B: T, C: T -> Y: T", "alpha: float = 1.0; beta: float = 1.0; transA: int = 0; transB: int = 0", "T: numeric"),
+    ("Gemm", 11, "A: T, B: T, C?: T -> Y: T", "alpha: float = 1.0; beta: float = 1.0; transA: int = 0; transB: int = 0", "T: numeric"),
+    ("GlobalAveragePool", 1, "X: T -> Y: T", "", "T: float"),
+    ("GlobalLpPool", 2, "X: T -> Y: T", "p: int = 2", "T: float"),
+    ("GlobalMaxPool", 1, "X: T -> Y: T", "", "T: float"),
+    ("Greater", 7, "A: T, B: T -> C: T1", "", "T: numeric; T1: bool"),
+    ("GreaterOrEqual", 12, "A: T, B: T -> C: T1", "", "T: numeric; T1: bool"),
+    ("GridSample", 16, "X: T1, grid: T2 -> Y: T1", "align_corners: int = 0; mode: string = \"bilinear\"; padding_mode: string = \"zeros\"", "T1: all; T2: float"),
+    ("GroupNormalization", 18, "X: T, scale: T, bias: T -> Y: T", "epsilon: float = 1e-5; num_groups: int!", "T: float"),
+    ("HammingWindow", 17, "size: T1 -> output: T2", WINDOW, "T1: index; T2: numeric"),
+    ("HannWindow", 17, "size: T1 -> output: T2", WINDOW, "T1: index; T2: numeric"),
+    ("HardSigmoid", 6, "X: T -> Y: T", "alpha: float = 0.2; beta: float = 0.5", "T: float"),
+    ("HardSwish", 14, "X: T -> Y: T", "", "T: float"),
+    ("Hardmax", 1, "input: T -> output: T", "axis: int = 1", "T: float"),
+    ("Hardmax", 13, "input: T -> output: T", "axis: int = -1", "T: float"),
+    ("Identity", 1, "input: V -> output: V", "", ""),
+    ("If", 1, "cond: B -> outputs...: V", "else_branch: graph!; then_branch: graph!", "B: bool"),
+    ("InstanceNormalization", 6, "input: T, scale: T, B: T -> output: T", "epsilon: float = 1e-5", "T: float"),
+    ("IsInf", 10, "X: T1 -> Y: T2", "detect_negative: int = 1; detect_positive: int = 1", "T1: float; T2: bool"),
+    ("IsNaN", 9, "X: T1 -> Y: T2", "", "T1: float; T2: bool"),
+    ("LRN", 1, "X: T -> Y: T", "alpha: float = 0.0001; beta: float = 0.75; bias: float = 1.0; size: int!", "T: float"),
+    ("LSTM", 7, "X: T, W: T, R: T, B?: T, sequence_lens?: T1, initial_h?: T, initial_c?: T, P?: T -> Y?: T, Y_h?: T, Y_c?: T", "activation_alpha: floats; activation_beta: floats; activations: strings; clip: float; direction: string = \"forward\"; hidden_size: int; input_forget: int = 0; layout: int = 0", "T: float; T1: int32"),
+    ("LayerNormalization", 17, "X: T, Scale: T, B?: T -> Y: T, Mean?: U, InvStdDev?: U", "axis: int = -1; epsilon: float = 1e-5; stash_type: int = 1", "T: float; U: float"),
+    ("LeakyRelu", 6, "X: T -> Y: T", "alpha: float = 0.01", "T: float"),
+    ("Less", 7, "A: T, B: T -> C: T1", "", "T: numeric; T1: bool"),
+    ("LessOrEqual", 12, "A: T, B: T -> C: T1", "", "T: numeric; T1: bool"),
+    ("Log", 6, "input: T -> output: T", "", "T: float"),
+    ("LogSoftmax", 1, "input: T -> output: T", "axis: int = 1", "T: float"),
+    ("LogSoftmax", 13, "input: T -> output: T", "axis: int = -1", "T: float"),
+    ("Loop", 1, "M?: I, cond?: B, v_initial*: V -> v_final_and_scan_outputs...: V", "body: graph!", "I: int64; B: bool"),
+    ("LpNormalization", 1, "input: T -> output: T", "axis: int = -1; p: int = 2", "T: float"),
+    ("LpPool", 2, "X: T -> Y: T", "auto_pad: string = \"NOTSET\"; ceil_mode: int = 0; dilations: ints; kernel_shape: ints!; p: int = 2; pads: ints; strides: ints", "T: float"),
+    ("MatMul", 1, "A: T, B: T -> Y: T", "", "T: numeric"),
+    ("MatMulInteger", 10, "A: T1, B: T2, a_zero_point?: T1, b_zero_point?: T2 -> Y: int32", "", "T1: int8 | uint8; T2: int8 | uint8"),
+    ("Max", 6, "data_0...: T -> max: T", "", "T: float"),
+    ("Max", 12, "data_0...: T -> max: T", "", "T: numeric"),
+    ("MaxPool", 8, "X: T -> Y: T, Indices?: I", "auto_pad: string = \"NOTSET\"; ceil_mode: int = 0; dilations: ints; kernel_shape: ints!; pads: ints; storage_order: int = 0; strides: ints", "T: float | int8 | uint8; I: int64"),
+    ("MaxRoiPool", 1, "X: T, rois: T -> Y: T", "pooled_shape: ints!; spatial_scale: float = 1.0", "T: float"),
+    ("MaxUnpool", 9, "X: T1, I: int64, output_shape?: int64 -> output: T1", "kernel_shape: ints!; pads: ints; strides: ints", "T1: float"),
+    ("Mean", 6, "data_0...: T -> mean: T", "", "T: float"),
+    ("MeanVarianceNormalization", 9, "X: T -> Y: T", "axes: ints", "T: float"),
+    ("MelWeightMatrix", 17, "num_mel_bins: T1, dft_length: T1, sample_rate: T1, lower_edge_hertz: T2, upper_edge_hertz: T2 -> output: T3", "output_datatype: int = 1", "T1: index; T2: float; T3: numeric"),
+    ("Min", 6, "data_0...: T -> min: T", "", "T: float"),
+    ("Min", 12, "data_0...: T -> min: T", "", "T: numeric"),
+    ("Mish", 18, "X: T -> Y: T", "", "T: float"),
+    ("Mod", 10, "A: T, B: T -> C: T", "fmod: int = 0", "T: numeric"),
+    ("Mul", 7, "A: T, B: T -> C: T", "", "T: numeric"),
+    ("Multinomial", 7, "input: T1 -> output: T2", "dtype: int = 6; sample_size: int = 1; seed: float", "T1: float; T2: index"),
+    ("Neg", 6, "X: T -> Y: T", "", "T: signed"),
+    ("NegativeLogLikelihoodLoss", 12, "input: T, target: Tind, weight?: T -> loss: T", "ignore_index: int; reduction: string = \"mean\"", "T: float; Tind: index"),
+    ("NonMaxSuppression", 10, "boxes: float, scores: float, max_output_boxes_per_class?: int64, iou_threshold?: float, score_threshold?: float -> selected_indices: int64", "center_point_box: int = 0", ""),
+    ("NonZero", 9, "X: T -> Y: int64", "", "T: all"),
+    ("Not", 1, "X: T -> Y: T", "", "T: bool"),
+    ("OneHot", 9, "indices: T1, depth: T2, values: T3 -> output: T3", "axis: int = -1", "T1: numeric; T2: numeric; T3: all"),
+    ("OptionalGetElement", 15, "input: O -> output: V", "", ""),
+    ("OptionalHasElement", 15, "input?: O -> output: bool", "", ""),
+    ("Or", 7, "A: T, B: T -> C: T1", "", "T: bool; T1: bool"),
+    ("PRelu", 6, "X: T, slope: T -> Y: T", "", "T: signed"),
+    ("Pad", 2, "data: T -> output: T", "mode: string = \"constant\"; pads: ints!; value: float = 0.0", "T: float"),
+    ("Pad", 11, "data: T, pads: int64, constant_value?: T -> output: T", "mode: string = \"constant\"", "T: all"),
+    ("Pad", 18, "data: T, pads: int64, constant_value?: T, axes?: Tind -> output: T", "mode: string = \"constant\"", "T: all; Tind: index"),
+    ("Pow", 7, "X: T, Y: T1 -> Z: T", "", "T: numeric; T1: numeric"),
+    ("QLinearConv", 10, "x: T1, x_scale: float, x_zero_point: T1, w: T2, w_scale: float, w_zero_point: T2, y_scale: float, y_zero_point: T3, B?: int32 -> y: T3", CONV, "T1: int8 | uint8; T2: int8 | uint8; T3: int8 | uint8"),
+    ("QLinearMatMul", 10, "a: T1, a_scale: float, a_zero_point: T1, b: T2, b_scale: float, b_zero_point: T2, y_scale: float, y_zero_point: T3 -> y: T3", "", "T1: int8 | uint8; T2: int8 | uint8; T3: int8 | uint8"),
+    ("QuantizeLinear", 10, "x: T1, y_scale: float, y_zero_point?: T2 -> y: T2", "axis: int = 1", "T1: float | int32; T2: int8 | uint8"),
+    ("RNN", 7, "X: T, W: T, R: T, B?: T, sequence_lens?: T1, initial_h?: T -> Y?: T, Y_h?: T", RNN, "T: float; T1: int32"),
+    ("RandomNormal", 1, " -> output: T", "dtype: int = 1; mean: float = 0.0; scale: float = 1.0; seed: float; shape: ints!", "T: float"),
+    ("RandomNormalLike", 1, "input: T1 -> output: T2", RANDOM, "T1: all; T2: float"),
+    ("RandomUniform", 1, " -> output: T", "dtype: int = 1; high: float = 1.0; low: float = 0.0; seed: float; shape: ints!", "T: float"),
+    ("RandomUniformLike", 1, "input: T1 -> output: T2", UNIFORM, "T1: all; T2: float"),
+    ("Range", 11, "start: T, limit: T, delta: T -> output: T", "", "T: float | double | int16 | int32 | int64"),
+    ("Reciprocal", 6, "X: T -> Y: T", "", "T: float"),
+    ("ReduceL1", 1, "data: T -> reduced: T", REDUCE, "T: numeric"),
+    ("ReduceL1", 18, "data: T, axes?: int64 -> reduced: T", REDUCE_INPUT, "T: numeric"),
+    ("ReduceL2", 1, "data: T -> reduced: T", REDUCE, "T: numeric"),
+    ("ReduceL2", 18, "data: T, axes?: int64 -> reduced: T", REDUCE_INPUT, "T: numeric"),
+    ("ReduceLogSum", 1, "data: T -> reduced: T", REDUCE, "T: numeric"),
+    ("ReduceLogSum", 18, "data: T, axes?: int64 -> reduced: T", REDUCE_INPUT, "T: numeric"),
+    ("ReduceLogSumExp", 1, "data: T -> reduced: T", REDUCE, "T: numeric"),
+    ("ReduceLogSumExp", 18, "data: T, axes?: int64 -> reduced: T", REDUCE_INPUT, "T: numeric"),
+    ("ReduceMax", 1, "data: T -> reduced: T", REDUCE, "T: numeric | bool"),
+    ("ReduceMax", 18, "data: T, axes?: int64 -> reduced: T", REDUCE_INPUT, "T: numeric | bool"),
+    ("ReduceMean", 1, "data: T -> reduced: T", REDUCE, "T: numeric"),
+    ("ReduceMean", 18, "data: T, axes?: int64 -> reduced: T", REDUCE_INPUT, "T: numeric"),
+    ("ReduceMin", 1, "data: T -> reduced: T", REDUCE, "T: numeric | bool"),
+    ("ReduceMin", 18, "data: T, axes?: int64 -> reduced: T", REDUCE_INPUT, "T: numeric | bool"),
+    ("ReduceProd", 1, "data: T -> reduced: T", REDUCE, "T: numeric"),
+    ("ReduceProd", 18, "data: T, axes?: int64 -> reduced: T", REDUCE_INPUT, "T: numeric"),
+    ("ReduceSum", 1, "data: T -> reduced: T", REDUCE, "T: numeric"),
+    ("ReduceSum", 13, "data: T, axes?: int64 -> reduced: T", REDUCE_INPUT, "T: numeric"),
+    ("ReduceSumSquare", 1, "data: T -> reduced: T", REDUCE, "T: numeric"),
+    ("ReduceSumSquare", 18, "data: T, axes?: int64 -> reduced: T", REDUCE_INPUT, "T: numeric"),
+    ("Relu", 6, "X: T -> Y: T", "", "T: float"),
+    ("Relu", 14, "X: T -> Y: T", "", "T: signed"),
+    ("Reshape", 5, "data: T, shape: int64 -> reshaped: T", "allowzero: int = 0", "T: all"),
+    ("Resize", 10, "X: T, scales: float -> Y: T", "mode: string = \"nearest\"", "T: all"),
+    ("Resize", 11, "X: T1, roi: T2, scales: float, sizes?: int64 -> Y: T1", "coordinate_transformation_mode: string = \"half_pixel\"; cubic_coeff_a: float = -0.75; exclude_outside: int = 0; extrapolation_value: float = 0.0; mode: string = \"nearest\"; nearest_mode: string = \"round_prefer_floor\"", "T1: all; T2: float"),
+    ("Resize", 13, "X: T1, roi?: T2, scales?: float, sizes?: int64 -> Y: T1", "coordinate_transformation_mode: string = \"half_pixel\"; cubic_coeff_a: float = -0.75; exclude_outside: int = 0; extrapolation_value: float = 0.0; mode: string = \"nearest\"; nearest_mode: string = \"round_prefer_floor\"", "T1: all; T2: float"),
+    ("Resize", 18, "X: T1, roi?: T2, scales?: float, sizes?: int64 -> Y: T1", "antialias: int = 0; axes: ints; coordinate_transformation_mode: string = \"half_pixel\"; cubic_coeff_a: float = -0.75; exclude_outside: int = 0; extrapolation_value: float = 0.0; keep_aspect_ratio_policy: string = \"stretch\"; mode: string = \"nearest\"; nearest_mode: string = \"round_prefer_floor\"", "T1: all; T2: float"),
+    ("ReverseSequence", 10, "input: T, sequence_lens: int64 -> Y: T", "batch_axis: int = 1; time_axis: int = 0", "T: all"),
+    ("RoiAlign", 10, "X: T1, rois: T1, batch_indices: T2 -> Y: T1", "coordinate_transformation_mode: string = \"half_pixel\"; mode: string = \"avg\"; output_height: int = 1; output_width: int = 1; sampling_ratio: int = 0; spatial_scale: float = 1.0", "T1: float; T2: int64"),
+    ("Round", 11, "X: T -> Y: T", "", "T: float"),
+    ("STFT", 17, "signal: T1, frame_step: T2, window?: T1, frame_length?: T2 -> output: T1", "onesided: int = 1", "T1: float; T2: index"),
+    ("Scan", 9, "initial_state_and_scan_inputs...: V -> final_state_and_scan_outputs...: V", "body: graph!; num_scan_inputs: int!; scan_input_axes: ints; scan_input_directions: ints; scan_output_axes: ints; scan_output_directions: ints", ""),
+    ("Scatter", 9, "data: T, indices: Tind, updates: T -> output: T", "axis: int = 0", "T: all; Tind: index"),
+    ("ScatterElements", 11, "data: T, indices: Tind, updates: T -> output: T", "axis: int = 0; reduction: string = \"none\"", "T: all; Tind: index"),
+    ("ScatterND", 11, "data: T, indices: int64, updates: T -> output: T", "reduction: string = \"none\"", "T: all"),
+    ("Selu", 6, "X: T -> Y: T", "alpha: float = 1.67326; gamma: float = 1.0507", "T: float"),
+    ("SequenceAt", 11, "input_sequence: S, position: I -> tensor: T", "", "I: index"),
+    ("SequenceConstruct", 11, "inputs...: T -> output_sequence: S", "", ""),
+    ("SequenceEmpty", 11, " -> output: S", "dtype: int", ""),
+    ("SequenceErase", 11, "input_sequence: S, position?: I -> output_sequence: S", "", "I: index"),
+    ("SequenceInsert", 11, "input_sequence: S, tensor: T, position?: I -> output_sequence: S", "", "I: index"),
+    ("SequenceLength", 11, "input_sequence: S -> length: int64", "", ""),
+    ("SequenceMap", 17, "input_sequence: S, additional_inputs*: V -> out_sequence...: S", "body: graph!", ""),
+    ("Shape", 1, "data: T -> shape: int64", "end: int; start: int = 0", "T: all"),
+    ("Shrink", 9, "input: T -> output: T", "bias: float = 0.0; lambd: float = 0.5", "T: numeric"),
+    ("Sigmoid", 6, "X: T -> Y: T", "", "T: float"),
+    ("Sign", 9, "input: T -> output: T", "", "T: numeric"),
+    ("Sin", 7, "input: T -> output: T", "", "T: float"),
+    ("Sinh", 9, "input: T -> output: T", "", "T: float"),
+    ("Size", 1, "data: T -> size: int64", "", "T: all"),
+    ("Slice", 1, "data: T -> output: T", "axes: ints; ends: ints!; starts: ints!", "T: all"),
+    ("Slice", 10, "data: T, starts: Tind, ends: Tind, axes?: Tind, steps?: Tind -> output: T", "", "T: all; Tind: index"),
+    ("Softmax", 1, "input: T -> output: T", "axis: int = 1", "T: float"),
+    ("Softmax", 13, "input: T -> output: T", "axis: int = -1", "T: float"),
+    ("SoftmaxCrossEntropyLoss", 12, "scores: T, labels: Tind, weights?: T -> output: T, log_prob?: T", "ignore_index: int; reduction: string = \"mean\"", "T: float; Tind: index"),
+    ("Softplus", 1, "X: T -> Y: T", "", "T: float"),
+    ("Softsign", 1, "input: T -> output: T", "", "T: float"),
+    ("SpaceToDepth", 1, "input: T -> output: T", "blocksize: int!", "T: all"),
+    ("Split", 2, "input: T -> outputs...: T", "axis: int = 0; split: ints", "T: all"),
+    ("Split", 13, "input: T, split?: int64 -> outputs...: T", "axis: int = 0", "T: all"),
+    ("Split", 18, "input: T, split?: int64 -> outputs...: T", "axis: int = 0; num_outputs: int", "T: all"),
+    ("SplitToSequence", 11, "input: T, split?: I -> output_sequence: S", "axis: int = 0; keepdims: int = 1", "T: all; I: index"),
+    ("Sqrt", 6, "X: T -> Y: T", "", "T: float"),
+    ("Squeeze", 1, "data: T -> squeezed: T", "axes: ints", "T: all"),
+    ("Squeeze", 13, "data: T, axes?: int64 -> squeezed: T", "", "T: all"),
+    ("StringNormalizer", 10, "X: string -> Y: string", "case_change_action: string = \"NONE\"; is_case_sensitive: int = 0; locale: string; stopwords: strings", ""),
+    ("Sub", 7, "A: T, B: T -> C: T", "", "T: numeric"),
+    ("Sum", 6, "data_0...: T -> sum: T", "", "T: numeric"),
+    ("Tan", 7, "input: T -> output: T", "", "T: float"),
+    ("Tanh", 6, "input: T -> output: T", "", "T: float"),
+    ("ThresholdedRelu", 10, "X: T -> Y: T", "alpha: float = 1.0", "T: float"),
+    ("Tile", 6, "input: T, repeats: int64 -> output: T", "", "T: all"),
+    ("TopK", 1, "X: T -> Values: T, Indices: I", "axis: int = -1; k: int!", "T: numeric; I: int64"),
+    ("TopK", 10, "X: T, K: int64 -> Values: T, Indices: I", "axis: int = -1; largest: int = 1; sorted: int = 1", "T: numeric; I: int64"),
+    ("Transpose", 1, "data: T -> transposed: T", "perm: ints", "T: all"),
+    ("Trilu", 14, "input: T, k?: int64 -> output: T", "upper: int = 1", "T: all"),
+    ("Unique", 11, "X: T -> Y: T, indices?: int64, inverse_indices?: int64, counts?: int64", "axis: int; sorted: int = 1", "T: all"),
+    ("Unsqueeze", 1, "data: T -> expanded: T", "axes: ints!", "T: all"),
+    ("Unsqueeze", 13, "data: T, axes: int64 -> expanded: T", "", "T: all"),
+    ("Upsample", 7, "X: T -> Y: T", "mode: string = \"nearest\"; scales: floats!", "T: all"),
+    ("Upsample", 9, "X: T, scales: float -> Y: T", "mode: string = \"nearest\"", "T: all"),
+    ("Where", 9, "condition: B, X: T, Y: T -> output: T", "", "B: bool; T: all"),
+    ("Xor", 7, "A: T, B: T -> C: T1", "", "T: bool; T1: bool"),
+];
+
+/// Returns schemas of the standard operator set.
+pub(super) fn schemas() -> Vec<OpSchema> {
+    SCHEMAS.iter().map(parse_row).collect()
+}
+
+fn parse_row(&(op_type, since, signature, attributes, constraints): &Row) -> OpSchema {
+    let mut schema = OpSchema::new("", op_type).since_version(since);
+    let (inputs, outputs) = signature
+        .split_once("->")
+        .unwrap_or_else(|| panic!("{}: signature has no outputs", op_type));
+    schema = schema.inputs(0, 0).outputs(0, 0);
+    for (name, type_str, option) in parse_params(inputs) {
+        schema = schema.input(name, type_str, option);
+    }
+    for (name, type_str, option) in parse_params(outputs) {
+        schema = schema.output(name, type_str, option);
+    }
+    for attr in split(attributes) {
+        schema = parse_attribute(schema, attr);
+    }
+    for constraint in split(constraints) {
+        let (param, types) = constraint
+            .split_once(':')
+            .unwrap_or_else(|| panic!("{}: invalid constraint {:?}", op_type, constraint));
+        let types: Vec<DataType> = types
+            .split('|')
+            .flat_map(|group| type_group(op_type, group.trim()))
+            .collect();
+        schema = schema.type_constraint(param.trim(), &types);
+    }
+    schema
+}
+
+fn parse_params(params: &str) -> Vec<(&str, &str, ParameterOption)> {
+    params
+        .split(',')
+        .map(str::trim)
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            let (name, type_str) = param.split_once(':').unwrap_or((param, ""));
+            let (name, type_str) = (name.trim(), type_str.trim());
+            if let Some(name) = name.strip_suffix('?') {
+                (name, type_str, ParameterOption::Optional)
+            } else if let Some(name) = name.strip_suffix("...") {
+                (name, type_str, ParameterOption::Variadic(1))
+            } else if let Some(name) = name.strip_suffix('*') {
+                (name, type_str, ParameterOption::Variadic(0))
+            } else {
+                (name, type_str, ParameterOption::Single)
+            }
+        })
+        .collect()
+}
+
+fn parse_attribute(schema: OpSchema, attr: &str) -> OpSchema {
+    let (name, typ) = attr
+        .split_once(':')
+        .unwrap_or_else(|| panic!("{}: invalid attribute {:?}", schema.name(), attr));
+    let (typ, default) = match typ.split_once('=') {
+        Some((typ, default)) => (typ.trim(), Some(default.trim())),
+        None => (typ.trim(), None),
+    };
+    let (typ, required) = match typ.strip_suffix('!') {
+        Some(typ) => (typ, true),
+        None => (typ, false),
+    };
+    let name = name.trim();
+    let invalid = || -> ! { panic!("{}: invalid attribute {:?}", schema.name(), attr) };
+    if let Some(default) = default {
+        let value: Attribute = match typ {
+            "int" => default.parse::<i64>().unwrap_or_else(|_| invalid()).into(),
+            "float" => default.parse::<f32>().unwrap_or_else(|_| invalid()).into(),
+            "string" => default.trim_matches('"').to_owned().into(),
+            _ => invalid(),
+        };
+        return schema.default_attribute(name, value);
+    }
+    let typ = match typ {
+        "float" => AttributeType::Float,
+        "int" => AttributeType::Int,
+        "string" => AttributeType::String,
+        "tensor" => AttributeType::Tensor,
+        "graph" => AttributeType::Graph,
+        "sparse_tensor" => AttributeType::SparseTensor,
+        "floats" => AttributeType::Floats,
+        "ints" => AttributeType::Ints,
+        "strings" => AttributeType::Strings,
+        "tensors" => AttributeType::Tensors,
+        "graphs" => AttributeType::Graphs,
+        _ => invalid(),
+    };
+    if required {
+        schema.required_attribute(name, typ)
+    } else {
+        schema.attribute(name, typ)
+    }
+}
+
+fn split(list: &str) -> impl Iterator<Item = &str> {
+    list.split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+fn type_group(op_type: &str, group: &str) -> Vec<DataType> {
+    use DataType::*;
+
+    match group {
+        "all" => vec![
+            Float, Uint8, Int8, Uint16, Int16, Int32, Int64, String, Bool, Float16, Double, Uint32,
+            Uint64, Complex64, Complex128, Bfloat16,
+        ],
+        "numeric" => vec![
+            Float, Uint8, Int8, Uint16, Int16, Int32, Int64, Float16, Double, Uint32, Uint64,
+            Bfloat16,
+        ],
+        "signed" => vec![Float, Int8, Int16, Int32, Int64, Float16, Double, Bfloat16],
+        "int" => vec![Uint8, Int8, Uint16, Int16, Int32, Int64, Uint32, Uint64],
+        "float" => vec![Float, Float16, Double, Bfloat16],
+        "index" => vec![Int32, Int64],
+        name => vec![text::data_type_by_name(name)
+            .unwrap_or_else(|| panic!("{}: unknown type {:?}", op_type, name))],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{opset, schema};
+
+    #[test]
+    fn table() {
+        let schemas = schemas();
+        for schema in schemas.iter() {
+            let versions = opset::op_versions(schema.name())
+                .unwrap_or_else(|| panic!("{} is not a standard operator", schema.name()));
+            assert!(schema.version() >= versions[0], "{}", schema.name());
+            for param in schema
+                .formal_inputs()
+                .iter()
+                .chain(schema.formal_outputs().iter())
+            {
+                let typ = param.type_str.as_str();
+                assert!(
+                    !typ.is_empty()
+                        && (text::data_type_by_name(typ).is_some()
+                            || typ.starts_with(|c: char| c.is_ascii_uppercase())),
+                    "{}: {}",
+                    schema.name(),
+                    param.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn version_boundaries() {
+        let schema =
+            |op_type: &str, version: i64| schema::lookup("", op_type, Some(version)).unwrap();
+        let has_attribute = |op_type: &str, version: i64, name: &str| {
+            schema(op_type, version)
+                .attributes()
+                .iter()
+                .any(|attr| attr.name == name)
+        };
+        let accepts = |op_type: &str, version: i64, param: &str, data_type: DataType| {
+            schema(op_type, version)
+                .type_constraint_of(param)
+                .unwrap()
+                .contains(&data_type)
+        };
+        let optional = |op_type: &str, version: i64, index: usize| {
+            schema(op_type, version).formal_inputs()[index].option == ParameterOption::Optional
+        };
+
+        assert!(!accepts("Relu", 13, "T", DataType::Int32));
+        assert!(accepts("Relu", 14, "T", DataType::Int32));
+        for op_type in ["Max", "Min"].iter() {
+            assert!(!accepts(op_type, 11, "T", DataType::Int64));
+            assert!(accepts(op_type, 12, "T", DataType::Int64));
+        }
+        assert!(!accepts("Clip", 11, "T", DataType::Int32));
+        assert!(accepts("Clip", 12, "T", DataType::Int32));
+        for op_type in ["ArgMax", "ArgMin"].iter() {
+            assert!(!has_attribute(op_type, 11, "select_last_index"));
+            assert!(has_attribute(op_type, 12, "select_last_index"));
+        }
+        assert!(!optional("Resize", 11, 1));
+        assert!(optional("Resize", 13, 1));
+        assert!(!has_attribute("Resize", 17, "antialias"));
+        assert!(has_attribute("Resize", 18, "antialias"));
+        assert!(!has_attribute("Split", 17, "num_outputs"));
+        assert!(has_attribute("Split", 18, "num_outputs"));
+        assert!(!accepts("Gemm", 8, "T", DataType::Int32));
+        assert!(accepts("Gemm", 9, "T", DataType::Int32));
+        assert!(!optional("Gemm", 10, 2));
+        assert!(optional("Gemm", 11, 2));
+    }
+}
@@ -0,0 +1,504 @@
+//! Static shape and element type inference.
+//!
+//! Shapes and types are taken from initializers and value declarations of a
+//! graph and propagated through a subset of operators. Values of other
+//! operators have unknown shapes unless declared in `value_info`.
+
+use std::collections::{HashMap, HashSet};
+
+use onnx_pb::{
+    tensor_proto::DataType, tensor_shape_proto::dimension, type_proto, GraphProto, NodeProto,
+    TensorProto,
+};
+
+use crate::{optim, tensor, visit};
+
+/// Dimension of a value.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Dim {
+    Value(i64),
+    Param(String),
+    Unknown,
+}
+
+pub(crate) type Shape = Vec<Dim>;
+
+/// Returns integer attribute of a node.
+pub(crate) fn int(node: &NodeProto, name: &str) -> Option<i64> {
+    node.attribute
+        .iter()
+        .find(|attr| attr.name == name)
+        .map(|attr| attr.i)
+}
+
+/// Returns integer list parameter given as an attribute or a constant input.
+pub(crate) fn ints(
+    node: &NodeProto,
+    name: &str,
+    position: usize,
+    constants: &HashMap<String, Vec<i64>>,
+) -> Option<Vec<i64>> {
+    match node.attribute.iter().find(|attr| attr.name == name) {
+        Some(attr) => Some(attr.ints.clone()),
+        None => node
+            .input
+            .get(position)
+            .and_then(|input| constants.get(input))
+            .cloned(),
+    }
+}
+
+/// Returns integer initializers and constants of a graph.
+pub(crate) fn integer_constants(graph: &GraphProto) -> HashMap<String, Vec<i64>> {
+    let inputs: HashSet<&str> = graph
+        .input
+        .iter()
+        .map(|value| value.name.as_str())
+        .collect();
+    let values = |tensor: &TensorProto| {
+        let data_type = DataType::from_i32(tensor.data_type)?;
+        if !tensor::is_integer(data_type) {
+            return None;
+        }
+        let values = tensor::to_f64_vec(tensor)?;
+        Some(values.into_iter().map(|value| value as i64).collect())
+    };
+    let mut constants = HashMap::new();
+    for tensor in graph.initializer.iter() {
+        if let (false, Some(values)) = (inputs.contains(tensor.name.as_str()), values(tensor)) {
+            constants.insert(tensor.name.clone(), values);
+        }
+    }
+    for node in graph.node.iter().filter(|node| node.op_type == "Constant") {
+        let tensor = node.attribute.iter().find_map(|attr| attr.t.as_ref());
+        if let (Some(output), Some(values)) = (node.output.first(), tensor.and_then(values)) {
+            constants.insert(output.clone(), values);
+        }
+    }
+    constants
+}
+
+/// Returns shapes of values known from the graph and propagated through
+/// elementwise, reduction and shape manipulation operators.
+pub(crate) fn infer_shapes(
+    graph: &GraphProto,
+    constants: &HashMap<String, Vec<i64>>,
+) -> HashMap<String, Shape> {
+    let mut shapes: HashMap<String, Shape> = HashMap::new();
+    for tensor in graph.initializer.iter() {
+        let dims = tensor.dims.iter().map(|dim| Dim::Value(*dim)).collect();
+        shapes.insert(tensor.name.clone(), dims);
+    }
+    for value in graph
+        .input
+        .iter()
+        .chain(graph.value_info.iter())
+        .chain(graph.output.iter())
+    {
+        let tensor = match value.r#type.as_ref().and_then(|typ| typ.value.as_ref()) {
+            Some(type_proto::Value::TensorType(tensor)) => tensor,
+            _ => continue,
+        };
+        if let Some(shape) = tensor.shape.as_ref() {
+            let dims = shape
+                .dim
+                .iter()
+                .map(|dim| match dim.value.as_ref() {
+                    Some(dimension::Value::DimValue(value)) => Dim::Value(*value),
+                    Some(dimension::Value::DimParam(param)) => Dim::Param(param.clone()),
+                    None => Dim::Unknown,
+                })
+                .collect();
+            shapes.insert(value.name.clone(), dims);
+        }
+    }
+    for node in graph.node.iter() {
+        let output = match node.output.first() {
+            Some(output) if !shapes.contains_key(output) => output,
+            _ => continue,
+        };
+        if let Some(shape) = infer_shape(node, &shapes, constants) {
+            shapes.insert(output.clone(), shape);
+        }
+    }
+    shapes
+}
+
+/// Returns element types of values known from the graph and propagated
+/// through nodes.
+///
+/// Outputs of most operators have the type of their first input. Nodes with
+/// subgraphs are skipped.
+pub(crate) fn infer_types(graph: &GraphProto) -> HashMap<String, i32> {
+    let mut types: HashMap<String, i32> = graph
+        .initializer
+        .iter()
+        .map(|tensor| (tensor.name.clone(), tensor.data_type))
+        .collect();
+    for value in graph
+        .input
+        .iter()
+        .chain(graph.value_info.iter())
+        .chain(graph.output.iter())
+    {
+        if let Some(type_proto::Value::TensorType(tensor)) =
+            value.r#type.as_ref().and_then(|typ| typ.value.as_ref())
+        {
+            types.insert(value.name.clone(), tensor.elem_type);
+        }
+    }
+    for node in graph.node.iter() {
+        if !visit::subgraphs(node).is_empty() {
+            continue;
+        }
+        let input = |index: usize| node.input.get(index).and_then(|input| types.get(input));
+        let attribute = |name: &str| node.attribute.iter().find(|attr| attr.name == name);
+        let int64 = DataType::Int64 as i32;
+        let float = DataType::Float as i32;
+        let inferred: Vec<Option<i32>> = match node.op_type.as_str() {
+            "Cast" => vec![attribute("to").map(|attr| attr.i as i32)],
+            "Shape" | "Size" | "ArgMax" | "ArgMin" | "NonZero" | "NonMaxSuppression" => {
+                vec![Some(int64)]
+            }
+            "Equal" | "Greater" | "GreaterOrEqual" | "Less" | "LessOrEqual" | "Not" | "And"
+            | "Or" | "Xor" | "IsNaN" | "IsInf" => vec![Some(DataType::Bool as i32)],
+            "Constant" => vec![optim::constant_value(node).map(|tensor| tensor.data_type)],
+            "ConstantOfShape" => vec![Some(
+                attribute("value")
+                    .and_then(|attr| attr.t.as_ref())
+                    .map_or(float, |tensor| tensor.data_type),
+            )],
+            "QuantizeLinear" => vec![Some(input(2).cloned().unwrap_or(DataType::Uint8 as i32))],
+            "DequantizeLinear" => vec![Some(float)],
+            "DynamicQuantizeLinear" => vec![
+                Some(DataType::Uint8 as i32),
+                Some(float),
+                Some(DataType::Uint8 as i32),
+            ],
+            "TopK" | "MaxPool" => vec![input(0).cloned(), Some(int64)],
+            "LayerNormalization" => vec![input(0).cloned(), Some(float), Some(float)],
+            "Where" => vec![input(1).cloned()],
+            _ => vec![input(0).cloned(); node.output.len()],
+        };
+        for (output, data_type) in node.output.iter().zip(inferred) {
+            if let (false, Some(data_type)) = (output.is_empty(), data_type) {
+                types.entry(output.clone()).or_insert(data_type);
+            }
+        }
+    }
+    types
+}
+
+/// Returns shape of the first node output.
+fn infer_shape(
+    node: &NodeProto,
+    shapes: &HashMap<String, Shape>,
+    constants: &HashMap<String, Vec<i64>>,
+) -> Option<Shape> {
+    let input = |index: usize| node.input.get(index).and_then(|input| shapes.get(input));
+    let normalize = |axis: i64, rank: usize| {
+        let axis = if axis < 0 { axis + rank as i64 } else { axis };
+        Some(axis as usize).filter(|axis| *axis < rank)
+    };
+    Some(match node.op_type.as_str() {
+        "Abs"
+        | "BatchNormalization"
+        | "Cast"
+        | "Ceil"
+        | "Clip"
+        | "Cos"
+        | "Dropout"
+        | "Elu"
+        | "Erf"
+        | "Exp"
+        | "Floor"
+        | "HardSigmoid"
+        | "Identity"
+        | "InstanceNormalization"
+        | "LayerNormalization"
+        | "LeakyRelu"
+        | "Log"
+        | "LogSoftmax"
+        | "Neg"
+        | "Not"
+        | "Reciprocal"
+        | "Relu"
+        | "Round"
+        | "Selu"
+        | "Sigmoid"
+        | "Sign"
+        | "Sin"
+        | "Softmax"
+        | "Softplus"
+        | "Softsign"
+        | "Sqrt"
+        | "Tan"
+        | "Tanh" => input(0)?.clone(),
+        "Add" | "And" | "Div" | "Equal" | "Greater" | "GreaterOrEqual" | "Less" | "LessOrEqual"
+        | "Mod" | "Mul" | "Or" | "Pow" | "PRelu" | "Sub" | "Xor" => broadcast(input(0)?, input(1)?),
+        "Max" | "Min" | "Mean" | "Sum" => {
+            let mut dims = input(0)?.clone();
+            for index in 1..node.input.len() {
+                dims = broadcast(&dims, input(index)?);
+            }
+            dims
+        }
+        "Where" => broadcast(&broadcast(input(0)?, input(1)?), input(2)?),
+        "Size" => Vec::new(),
+        "Shape" => vec![Dim::Value(input(0)?.len() as i64)],
+        "Constant" => node
+            .attribute
+            .iter()
+            .find_map(|attr| attr.t.as_ref())?
+            .dims
+            .iter()
+            .map(|dim| Dim::Value(*dim))
+            .collect(),
+        "ReduceSum" | "ReduceMean" | "ReduceMax" | "ReduceMin" => {
+            let dims = input(0)?;
+            let axes = match ints(node, "axes", 1, constants) {
+                Some(axes) => axes
+                    .into_iter()
+                    .map(|axis| normalize(axis, dims.len()))
+                    .collect::<Option<Vec<usize>>>()?,
+                None if node.input.len() > 1 && !node.input[1].is_empty() => return None,
+                None => (0..dims.len()).collect(),
+            };
+            let keepdims = int(node, "keepdims").unwrap_or(1) != 0;
+            dims.iter()
+                .enumerate()
+                .filter_map(|(axis, dim)| match (axes.contains(&axis), keepdims) {
+                    (false, _) => Some(dim.clone()),
+                    (true, true) => Some(Dim::Value(1)),
+                    (true, false) => None,
+                })
+                .collect()
+        }
+        "Concat" => {
+            let first = input(0)?;
+            let axis = normalize(int(node, "axis")?, first.len())?;
+            let mut size = 0;
+            for index in 0..node.input.len() {
+                match input(index)?.get(axis)? {
+                    Dim::Value(dim) => size += dim,
+                    _ => return None,
+                }
+            }
+            let mut dims = first.clone();
+            dims[axis] = Dim::Value(size);
+            dims
+        }
+        "MatMul" => {
+            let (a, b) = (input(0)?, input(1)?);
+            if a.len() < 2 || b.len() < 2 {
+                return None;
+            }
+            let mut dims = broadcast(&a[..a.len() - 2], &b[..b.len() - 2]);
+            dims.push(a[a.len() - 2].clone());
+            dims.push(b[b.len() - 1].clone());
+            dims
+        }
+        "Transpose" => {
+            let dims = input(0)?;
+            match ints(node, "perm", usize::MAX, constants) {
+                Some(perm) => perm
+                    .iter()
+                    .map(|axis| dims.get(*axis as usize).cloned())
+                    .collect::<Option<Shape>>()?,
+                None => dims.iter().rev().cloned().collect(),
+            }
+        }
+        "Squeeze" => {
+            let dims = input(0)?;
+            let axes = ints(node, "axes", 1, constants)?
+                .into_iter()
+                .map(|axis| normalize(axis, dims.len()))
+                .collect::<Option<Vec<usize>>>()?;
+            dims.iter()
+                .enumerate()
+                .filter(|(axis, _)| !axes.contains(axis))
+                .map(|(_, dim)| dim.clone())
+                .collect()
+        }
+        "Unsqueeze" => {
+            let mut dims = input(0)?.clone();
+            let axes = ints(node, "axes", 1, constants)?;
+            let rank = dims.len() + axes.len();
+            let mut axes = axes
+                .into_iter()
+                .map(|axis| normalize(axis, rank))
+                .collect::<Option<Vec<usize>>>()?;
+            axes.sort_unstable();
+            for axis in axes {
+                dims.insert(axis.min(dims.len()), Dim::Value(1));
+            }
+            dims
+        }
+        "Gemm" => {
+            let (a, b) = (input(0)?, input(1)?);
+            if a.len() != 2 || b.len() != 2 {
+                return None;
+            }
+            let m = a[(int(node, "transA") == Some(1)) as usize].clone();
+            let n = b[(int(node, "transB") != Some(1)) as usize].clone();
+            vec![m, n]
+        }
+        "Conv" | "MaxPool" | "AveragePool" | "LpPool" => {
+            let x = input(0)?;
+            if x.len() < 3 {
+                return None;
+            }
+            let (channels, kernel) = if node.op_type == "Conv" {
+                let w = input(1)?;
+                let kernel = w[2..]
+                    .iter()
+                    .map(|dim| match dim {
+                        Dim::Value(dim) => Some(*dim),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<i64>>>();
+                (
+                    w.first()?.clone(),
+                    ints(node, "kernel_shape", usize::MAX, constants).or(kernel)?,
+                )
+            } else {
+                (
+                    x[1].clone(),
+                    ints(node, "kernel_shape", usize::MAX, constants)?,
+                )
+            };
+            let spatial = x.len() - 2;
+            if kernel.len() != spatial {
+                return None;
+            }
+            let param = |name: &str, default: i64, len: usize| {
+                ints(node, name, usize::MAX, constants)
+                    .filter(|values| values.len() == len)
+                    .unwrap_or_else(|| vec![default; len])
+            };
+            let strides = param("strides", 1, spatial);
+            let dilations = param("dilations", 1, spatial);
+            let pads = param("pads", 0, spatial * 2);
+            let auto_pad = node
+                .attribute
+                .iter()
+                .find(|attr| attr.name == "auto_pad")
+                .map(|attr| String::from_utf8_lossy(&attr.s).into_owned())
+                .unwrap_or_default();
+            let ceil = int(node, "ceil_mode") == Some(1);
+            let mut dims = vec![x[0].clone(), channels];
+            for axis in 0..spatial {
+                let size = match x[axis + 2] {
+                    Dim::Value(size) => size,
+                    _ => {
+                        dims.push(Dim::Unknown);
+                        continue;
+                    }
+                };
+                let extent = dilations[axis] * (kernel[axis] - 1) + 1;
+                let stride = strides[axis].max(1);
+                let size = match auto_pad.as_str() {
+                    "SAME_UPPER" | "SAME_LOWER" => (size + stride - 1) / stride,
+                    "VALID" => (size - extent) / stride + 1,
+                    _ => {
+                        let padded = size + pads[axis] + pads[axis + spatial] - extent;
+                        if ceil {
+                            (padded + stride - 1) / stride + 1
+                        } else {
+                            padded / stride + 1
+                        }
+                    }
+                };
+                dims.push(Dim::Value(size));
+            }
+            dims
+        }
+        "GlobalAveragePool" | "GlobalMaxPool" => {
+            let x = input(0)?;
+            let mut dims = x[..2.min(x.len())].to_vec();
+            dims.resize(x.len(), Dim::Value(1));
+            dims
+        }
+        "Flatten" => {
+            let x = input(0)?;
+            let axis = int(node, "axis").unwrap_or(1);
+            let axis = if axis < 0 {
+                axis + x.len() as i64
+            } else {
+                axis
+            } as usize;
+            if axis > x.len() {
+                return None;
+            }
+            vec![product(&x[..axis]), product(&x[axis..])]
+        }
+        "Reshape" => {
+            let x = input(0)?;
+            let shape = ints(node, "shape", 1, constants)?;
+            let allow_zero = int(node, "allowzero") == Some(1);
+            let mut dims: Shape = shape
+                .iter()
+                .enumerate()
+                .map(|(axis, dim)| match dim {
+                    0 if !allow_zero => x.get(axis).cloned().unwrap_or(Dim::Unknown),
+                    -1 => Dim::Unknown,
+                    dim => Dim::Value(*dim),
+                })
+                .collect();
+            if let Some(axis) = shape.iter().position(|dim| *dim == -1) {
+                let mut rest = dims.clone();
+                rest.remove(axis);
+                if let (Dim::Value(total), Dim::Value(known)) = (product(x), product(&rest)) {
+                    if known != 0 {
+                        dims[axis] = Dim::Value(total / known);
+                    }
+                }
+            }
+            dims
+        }
+        "Gather" => {
+            let (data, indices) = (input(0)?, input(1)?);
+            let axis = normalize(int(node, "axis").unwrap_or(0), data.len())?;
+            data[..axis]
+                .iter()
+                .chain(indices.iter())
+                .chain(data[axis + 1..].iter())
+                .cloned()
+                .collect()
+        }
+        _ => return None,
+    })
+}
+
+/// Returns number of elements of known dimensions.
+pub(crate) fn product(dims: &[Dim]) -> Dim {
+    let mut size = 1;
+    for dim in dims {
+        match dim {
+            Dim::Value(dim) => size *= dim,
+            _ => return Dim::Unknown,
+        }
+    }
+    Dim::Value(size)
+}
+
+/// Returns shape of multidirectional broadcast.
+pub(crate) fn broadcast(left: &[Dim], right: &[Dim]) -> Shape {
+    let rank = left.len().max(right.len());
+    let dim = |dims: &[Dim], axis: usize| {
+        (axis + dims.len())
+            .checked_sub(rank)
+            .map(|axis| dims[axis].clone())
+            .unwrap_or(Dim::Value(1))
+    };
+    (0..rank)
+        .map(|axis| match (dim(left, axis), dim(right, axis)) {
+            (left, right) if left == right => left,
+            (Dim::Value(1), other) | (other, Dim::Value(1)) => other,
+            (Dim::Value(value), Dim::Unknown) | (Dim::Unknown, Dim::Value(value)) => {
+                Dim::Value(value)
+            }
+            _ => Dim::Unknown,
+        })
+        .collect()
+}
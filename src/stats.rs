@@ -0,0 +1,581 @@
+//! Model statistics and cost estimates.
+//!
+//! [`graph_stats`](fn.graph_stats.html) counts operators and parameters of a
+//! graph and estimates its compute and activation memory from static shapes,
+//! declared in the graph or propagated from its inputs. Nodes with unknown
+//! shapes have unknown cost and values of unknown size are not counted in
+//! memory estimates.
+//!
+//! A multiply-accumulate counts as one MAC and two FLOPs. Elementwise
+//! operators count one FLOP per output element, reductions and pooling one
+//! per reduced element and data movement such as `Reshape` or `Gather` none.
+//! Nodes of subgraphs are counted in the histogram only.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use onnx_pb::{tensor_proto::DataType, GraphProto, ModelProto, NodeProto, TensorProto};
+
+use crate::{
+    shape::{self, Dim, Shape},
+    tensor, visit,
+};
+
+/// Operators moving data without arithmetic.
+const MOVEMENT_OPS: &[&str] = &[
+    "Cast",
+    "Concat",
+    "Constant",
+    "ConstantOfShape",
+    "DepthToSpace",
+    "Dropout",
+    "Expand",
+    "Flatten",
+    "Gather",
+    "GatherElements",
+    "GatherND",
+    "Identity",
+    "Pad",
+    "Reshape",
+    "Shape",
+    "Size",
+    "Slice",
+    "SpaceToDepth",
+    "Split",
+    "Squeeze",
+    "Tile",
+    "Transpose",
+    "Unsqueeze",
+];
+
+/// Operators computing one operation per output element.
+const ELEMENTWISE_OPS: &[&str] = &[
+    "Abs",
+    "Add",
+    "And",
+    "Ceil",
+    "Clip",
+    "Cos",
+    "Div",
+    "Elu",
+    "Equal",
+    "Erf",
+    "Exp",
+    "Floor",
+    "Greater",
+    "GreaterOrEqual",
+    "HardSigmoid",
+    "LeakyRelu",
+    "Less",
+    "LessOrEqual",
+    "Log",
+    "Mod",
+    "Mul",
+    "Neg",
+    "Not",
+    "Or",
+    "PRelu",
+    "Pow",
+    "Reciprocal",
+    "Relu",
+    "Round",
+    "Selu",
+    "Sigmoid",
+    "Sign",
+    "Sin",
+    "Softplus",
+    "Softsign",
+    "Sqrt",
+    "Sub",
+    "Tan",
+    "Tanh",
+    "Where",
+    "Xor",
+];
+
+/// Schedule error.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// Schedule does not list every node of the graph exactly once.
+    Incomplete,
+    /// Node is scheduled before the node producing one of its inputs.
+    Order {
+        /// Scheduled node.
+        node: String,
+        /// Input produced later.
+        input: String,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Incomplete => write!(f, "schedule does not list every node exactly once"),
+            Error::Order { node, input } => write!(
+                f,
+                "node {:?} is scheduled before input {:?} is computed",
+                node, input
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Statistics of a node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeStats {
+    /// Node name, or its first output if the node is not named.
+    pub name: String,
+    /// Operator type.
+    pub op_type: String,
+    /// Floating point operations, `None` if shapes are unknown.
+    pub flops: Option<u64>,
+    /// Multiply-accumulate operations, `None` if shapes are unknown.
+    pub macs: Option<u64>,
+    /// Total size of outputs in bytes, `None` if unknown.
+    pub output_bytes: Option<u64>,
+}
+
+/// Statistics of a graph.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// Statistics of nodes in graph order.
+    pub nodes: Vec<NodeStats>,
+    /// Total floating point operations of nodes with known cost.
+    pub flops: u64,
+    /// Total multiply-accumulate operations of nodes with known cost.
+    pub macs: u64,
+    /// Number of initializer elements.
+    pub parameters: u64,
+    /// Size of initializers in bytes.
+    pub initializer_bytes: u64,
+    /// Peak size of graph inputs and node outputs alive at once in bytes.
+    pub peak_memory: u64,
+    /// Number of nodes of each operator, including nodes of subgraphs.
+    ///
+    /// Operators of other domains are prefixed with their domain.
+    pub op_types: BTreeMap<String, usize>,
+}
+
+impl Stats {
+    /// Returns nodes with unknown cost.
+    pub fn unknown_cost(&self) -> impl Iterator<Item = &NodeStats> {
+        self.nodes.iter().filter(|node| node.flops.is_none())
+    }
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "nodes: {}", self.nodes.len())?;
+        writeln!(f, "flops: {}", self.flops)?;
+        writeln!(f, "macs: {}", self.macs)?;
+        writeln!(
+            f,
+            "parameters: {} ({} bytes)",
+            self.parameters, self.initializer_bytes
+        )?;
+        write!(f, "peak memory: {} bytes", self.peak_memory)?;
+        let unknown = self.unknown_cost().count();
+        if unknown > 0 {
+            write!(f, "\nunknown cost: {} nodes", unknown)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns statistics of a model graph.
+pub fn model_stats(model: &ModelProto) -> Stats {
+    match model.graph.as_ref() {
+        Some(graph) => graph_stats(graph),
+        None => Stats::default(),
+    }
+}
+
+/// Returns statistics of a graph executed in graph order.
+pub fn graph_stats(graph: &GraphProto) -> Stats {
+    let schedule: Vec<usize> = (0..graph.node.len()).collect();
+    let analysis = Analysis::new(graph);
+    analysis.stats(&schedule)
+}
+
+/// Returns statistics of a graph executed in order of node indices.
+pub fn scheduled_stats(graph: &GraphProto, schedule: &[usize]) -> Result<Stats, Error> {
+    check_schedule(graph, schedule)?;
+    Ok(Analysis::new(graph).stats(schedule))
+}
+
+/// Returns peak size of graph inputs and node outputs alive at once when
+/// nodes are executed in order of node indices.
+///
+/// Values are allocated when their node runs and freed after their last
+/// use. Graph outputs are never freed.
+pub fn peak_memory(graph: &GraphProto, schedule: &[usize]) -> Result<u64, Error> {
+    check_schedule(graph, schedule)?;
+    Ok(Analysis::new(graph).peak_memory(schedule))
+}
+
+/// Checks that schedule is a topological order of graph nodes.
+pub(crate) fn check_schedule(graph: &GraphProto, schedule: &[usize]) -> Result<(), Error> {
+    let mut seen = vec![false; graph.node.len()];
+    for index in schedule.iter() {
+        match seen.get_mut(*index) {
+            Some(seen) if !*seen => *seen = true,
+            _ => return Err(Error::Incomplete),
+        }
+    }
+    if schedule.len() != graph.node.len() {
+        return Err(Error::Incomplete);
+    }
+    let producers: HashMap<&str, usize> = graph
+        .node
+        .iter()
+        .enumerate()
+        .flat_map(|(index, node)| {
+            node.output
+                .iter()
+                .map(move |output| (output.as_str(), index))
+        })
+        .filter(|(output, _)| !output.is_empty())
+        .collect();
+    let mut done = vec![false; graph.node.len()];
+    for index in schedule.iter() {
+        let node = &graph.node[*index];
+        for input in visit::node_references(node) {
+            match producers.get(input.as_str()) {
+                Some(producer) if !done[*producer] => {
+                    return Err(Error::Order {
+                        node: node_name(node),
+                        input,
+                    })
+                }
+                _ => {}
+            }
+        }
+        done[*index] = true;
+    }
+    Ok(())
+}
+
+/// Shapes and sizes of graph values.
+pub(crate) struct Analysis<'a> {
+    graph: &'a GraphProto,
+    shapes: HashMap<String, Shape>,
+    types: HashMap<String, i32>,
+}
+
+impl<'a> Analysis<'a> {
+    pub(crate) fn new(graph: &'a GraphProto) -> Self {
+        let constants = shape::integer_constants(graph);
+        Analysis {
+            graph,
+            shapes: shape::infer_shapes(graph, &constants),
+            types: shape::infer_types(graph),
+        }
+    }
+
+    /// Returns number of elements of a value.
+    fn elements(&self, name: &str) -> Option<u64> {
+        match shape::product(self.shapes.get(name)?) {
+            Dim::Value(size) if size >= 0 => Some(size as u64),
+            _ => None,
+        }
+    }
+
+    /// Returns size of a value in bytes.
+    pub(crate) fn bytes(&self, name: &str) -> Option<u64> {
+        let data_type = DataType::from_i32(*self.types.get(name)?)?;
+        Some(self.elements(name)? * tensor::element_size(data_type)? as u64)
+    }
+
+    /// Returns names of graph inputs which are not initializers.
+    pub(crate) fn inputs(&self) -> Vec<&'a str> {
+        let initializers: HashSet<&str> = self
+            .graph
+            .initializer
+            .iter()
+            .map(|tensor| tensor.name.as_str())
+            .collect();
+        self.graph
+            .input
+            .iter()
+            .map(|value| value.name.as_str())
+            .filter(|name| !initializers.contains(name))
+            .collect()
+    }
+
+    /// Returns schedule positions of last uses of values.
+    ///
+    /// Graph outputs are used after the last position.
+    pub(crate) fn last_uses(&self, schedule: &[usize]) -> HashMap<String, usize> {
+        let mut last = HashMap::new();
+        for (position, index) in schedule.iter().enumerate() {
+            for name in visit::node_references(&self.graph.node[*index]) {
+                last.insert(name, position);
+            }
+        }
+        for output in self.graph.output.iter() {
+            last.insert(output.name.clone(), schedule.len());
+        }
+        last
+    }
+
+    pub(crate) fn peak_memory(&self, schedule: &[usize]) -> u64 {
+        let last = self.last_uses(schedule);
+        let mut freed: Vec<Vec<&str>> = vec![Vec::new(); schedule.len() + 1];
+        let mut live = 0;
+        for input in self.inputs() {
+            live += self.bytes(input).unwrap_or(0);
+            match last.get(input) {
+                Some(position) => freed[*position].push(input),
+                None => freed[0].push(input),
+            }
+        }
+        let mut peak = live;
+        for (position, index) in schedule.iter().enumerate() {
+            for output in self.graph.node[*index].output.iter() {
+                if output.is_empty() {
+                    continue;
+                }
+                live += self.bytes(output).unwrap_or(0);
+                freed[*last.get(output).unwrap_or(&position)].push(output);
+            }
+            peak = peak.max(live);
+            for name in freed[position].iter() {
+                live -= self.bytes(name).unwrap_or(0);
+            }
+        }
+        peak
+    }
+
+    fn stats(&self, schedule: &[usize]) -> Stats {
+        let nodes: Vec<NodeStats> = self
+            .graph
+            .node
+            .iter()
+            .map(|node| {
+                let cost = self.cost(node);
+                NodeStats {
+                    name: node_name(node),
+                    op_type: node.op_type.clone(),
+                    flops: cost.map(|(flops, _)| flops),
+                    macs: cost.map(|(_, macs)| macs),
+                    output_bytes: node
+                        .output
+                        .iter()
+                        .filter(|output| !output.is_empty())
+                        .map(|output| self.bytes(output))
+                        .sum(),
+                }
+            })
+            .collect();
+        let mut op_types = BTreeMap::new();
+        count_ops(self.graph, &mut op_types);
+        Stats {
+            flops: nodes.iter().filter_map(|node| node.flops).sum(),
+            macs: nodes.iter().filter_map(|node| node.macs).sum(),
+            nodes,
+            parameters: self
+                .graph
+                .initializer
+                .iter()
+                .map(|tensor| tensor.dims.iter().product::<i64>().max(0) as u64)
+                .sum(),
+            initializer_bytes: self.graph.initializer.iter().map(tensor_bytes).sum(),
+            peak_memory: self.peak_memory(schedule),
+            op_types,
+        }
+    }
+
+    /// Returns FLOPs and MACs of a node.
+    fn cost(&self, node: &NodeProto) -> Option<(u64, u64)> {
+        let input = |index: usize| self.elements(node.input.get(index)?);
+        let output = || self.elements(node.output.first()?);
+        let dims = |index: usize| node.input.get(index).and_then(|name| self.shapes.get(name));
+        let size = |dims: &[Dim]| match shape::product(dims) {
+            Dim::Value(size) if size >= 0 => Some(size as u64),
+            _ => None,
+        };
+        let biased = node.input.get(2).is_some_and(|name| !name.is_empty());
+        let op_type = node.op_type.as_str();
+        if !node.domain.is_empty() && node.domain != "ai.onnx" {
+            return None;
+        }
+        Some(match op_type {
+            _ if MOVEMENT_OPS.contains(&op_type) => (0, 0),
+            _ if ELEMENTWISE_OPS.contains(&op_type) => (output()?, 0),
+            "Max" | "Mean" | "Min" | "Sum" => (output()? * (node.input.len() as u64 - 1), 0),
+            "BatchNormalization" => (2 * output()?, 0),
+            "Softmax" | "LogSoftmax" => (3 * output()?, 0),
+            "InstanceNormalization" | "LayerNormalization" => (6 * output()?, 0),
+            "ArgMax" | "ArgMin" | "GlobalAveragePool" | "GlobalMaxPool" | "ReduceL1"
+            | "ReduceL2" | "ReduceLogSumExp" | "ReduceMax" | "ReduceMean" | "ReduceMin"
+            | "ReduceProd" | "ReduceSum" | "ReduceSumSquare" => (input(0)?, 0),
+            "AveragePool" | "LpPool" | "MaxPool" => {
+                let kernel = node
+                    .attribute
+                    .iter()
+                    .find(|attr| attr.name == "kernel_shape")?;
+                (
+                    output()? * kernel.ints.iter().product::<i64>().max(0) as u64,
+                    0,
+                )
+            }
+            "MatMul" => {
+                let a = dims(0)?;
+                let macs = output()? * size(&a[a.len().checked_sub(1)?..])?;
+                (2 * macs, macs)
+            }
+            "Gemm" => {
+                let a = dims(0)?;
+                let axis = (shape::int(node, "transA") != Some(1)) as usize;
+                let macs = output()? * size(a.get(axis..=axis)?)?;
+                (2 * macs + if biased { output()? } else { 0 }, macs)
+            }
+            "Conv" => {
+                let macs = output()? * size(dims(1)?.get(1..)?)?;
+                (2 * macs + if biased { output()? } else { 0 }, macs)
+            }
+            "ConvTranspose" => {
+                let macs = input(0)? * size(dims(1)?.get(1..)?)?;
+                (2 * macs + if biased { output()? } else { 0 }, macs)
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// Returns name of a node, or its first output if the node is not named.
+pub(crate) fn node_name(node: &NodeProto) -> String {
+    if node.name.is_empty() {
+        node.output.first().cloned().unwrap_or_default()
+    } else {
+        node.name.clone()
+    }
+}
+
+/// Returns size of tensor data in bytes.
+fn tensor_bytes(tensor: &TensorProto) -> u64 {
+    if !tensor.raw_data.is_empty() {
+        return tensor.raw_data.len() as u64;
+    }
+    match DataType::from_i32(tensor.data_type) {
+        Some(DataType::String) => tensor.string_data.iter().map(|s| s.len() as u64).sum(),
+        Some(data_type) => {
+            let elements = tensor.dims.iter().product::<i64>().max(0) as u64;
+            elements * tensor::element_size(data_type).unwrap_or(0) as u64
+        }
+        None => 0,
+    }
+}
+
+/// Counts operators in a graph and its subgraphs.
+fn count_ops(graph: &GraphProto, histogram: &mut BTreeMap<String, usize>) {
+    for node in graph.node.iter() {
+        let op = if node.domain.is_empty() {
+            node.op_type.clone()
+        } else {
+            format!("{}.{}", node.domain, node.op_type)
+        };
+        *histogram.entry(op).or_default() += 1;
+        for subgraph in visit::subgraphs(node) {
+            count_ops(subgraph, histogram);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::text::parse_graph;
+
+    #[test]
+    fn linear() {
+        let graph = parse_graph(
+            r#"
+            g (float[1,4] X) => (float[1,3] Y) <float[4,3] W = {0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0}, float[3] B = {0.0, 0.0, 0.0}> {
+                m = MatMul (X, W)
+                a = Add (m, B)
+                Y = Relu (a)
+            }
+            "#,
+        )
+        .unwrap();
+        let stats = graph_stats(&graph);
+        let flops: Vec<Option<u64>> = stats.nodes.iter().map(|node| node.flops).collect();
+        assert_eq!(flops, vec![Some(24), Some(3), Some(3)]);
+        assert_eq!((stats.flops, stats.macs), (30, 12));
+        assert_eq!((stats.parameters, stats.initializer_bytes), (15, 60));
+        assert_eq!(stats.nodes[0].output_bytes, Some(12));
+        assert_eq!(stats.peak_memory, 28);
+        let histogram: Vec<(&str, usize)> = stats
+            .op_types
+            .iter()
+            .map(|(op, count)| (op.as_str(), *count))
+            .collect();
+        assert_eq!(histogram, vec![("Add", 1), ("MatMul", 1), ("Relu", 1)]);
+        assert_eq!(stats.unknown_cost().count(), 0);
+    }
+
+    #[test]
+    fn convolution() {
+        let graph = parse_graph(
+            r#"
+            g (float[1,3,8,8] X, float[16,3,3,3] W, float[16] B) => (float[1,16,4,4] Y) {
+                c = Conv <pads = [1, 1, 1, 1]> (X, W, B)
+                r = Relu (c)
+                Y = MaxPool <kernel_shape = [2, 2], strides = [2, 2]> (r)
+                Z = my.Custom (Y)
+            }
+            "#,
+        )
+        .unwrap();
+        let stats = graph_stats(&graph);
+        assert_eq!(stats.nodes[0].macs, Some(1024 * 27));
+        assert_eq!(stats.nodes[0].flops, Some(2 * 1024 * 27 + 1024));
+        assert_eq!(stats.nodes[2].flops, Some(256 * 4));
+        assert_eq!(stats.nodes[2].output_bytes, Some(1024));
+        let unknown: Vec<&str> = stats
+            .unknown_cost()
+            .map(|node| node.name.as_str())
+            .collect();
+        assert_eq!(unknown, vec!["Z"]);
+        assert_eq!(stats.op_types["my.Custom"], 1);
+    }
+
+    #[test]
+    fn schedules() {
+        let graph = parse_graph(
+            r#"
+            g (float[256] X) => (float[] Y) {
+                a = Neg (X)
+                b = Exp (X)
+                c = ReduceSum <keepdims = 0> (a)
+                d = ReduceSum <keepdims = 0> (b)
+                Y = Add (c, d)
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(graph_stats(&graph).peak_memory, 3 * 1024);
+        assert_eq!(peak_memory(&graph, &[0, 2, 1, 3, 4]), Ok(2 * 1024 + 4));
+        assert_eq!(
+            scheduled_stats(&graph, &[0, 2, 1, 3, 4])
+                .unwrap()
+                .peak_memory,
+            2 * 1024 + 4
+        );
+        assert_eq!(
+            peak_memory(&graph, &[2, 0, 1, 3, 4]),
+            Err(Error::Order {
+                node: "c".to_owned(),
+                input: "a".to_owned()
+            })
+        );
+        assert_eq!(peak_memory(&graph, &[0, 1, 2, 3]), Err(Error::Incomplete));
+        assert_eq!(
+            peak_memory(&graph, &[0, 0, 1, 2, 3]),
+            Err(Error::Incomplete)
+        );
+    }
+}
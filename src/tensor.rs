@@ -0,0 +1,674 @@
+//! Tensor data encoding.
+//!
+//! Converts Rust values to and from tensor data, stored either in
+//! `raw_data` as little-endian bytes or in typed data fields.
+//! Use [`builder::Tensor`](../builder/struct.Tensor.html) to create tensors.
+
+use onnx_pb::{tensor_proto::DataType, TensorProto};
+
+/// Tensor data error.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// Number of values does not match the shape.
+    ShapeMismatch { dims: Vec<i64>, len: usize },
+    /// Tensor element type does not match the requested type.
+    DataType { expected: DataType, found: i32 },
+    /// Tensor data is malformed or not stored in the tensor.
+    InvalidData(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ShapeMismatch { dims, len } => {
+                write!(f, "{} values do not match shape {:?}", len, dims)
+            }
+            Error::DataType { expected, found } => write!(
+                f,
+                "expected tensor of type {:?}, found {:?}",
+                expected,
+                DataType::from_i32(*found).unwrap_or(DataType::Undefined)
+            ),
+            Error::InvalidData(message) => write!(f, "invalid tensor data: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Storage of tensor values.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Encoding {
+    /// Little-endian bytes in `raw_data`.
+    #[default]
+    Raw,
+    /// Typed data fields, e.g. `float_data` or `int32_data`.
+    Typed,
+}
+
+/// Tensor element type.
+pub trait Element: Copy + 'static {
+    /// Tensor element type.
+    const DATA_TYPE: DataType;
+
+    /// Appends little-endian bytes of the value.
+    fn write_le(self, bytes: &mut Vec<u8>);
+
+    /// Reads value from little-endian bytes of its size.
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_element {
+    ( $t:ty, $data_type:ident ) => {
+        impl Element for $t {
+            const DATA_TYPE: DataType = DataType::$data_type;
+
+            #[inline]
+            fn write_le(self, bytes: &mut Vec<u8>) {
+                bytes.extend_from_slice(&self.to_le_bytes());
+            }
+
+            #[inline]
+            fn read_le(bytes: &[u8]) -> Self {
+                let mut buf = [0u8; std::mem::size_of::<$t>()];
+                buf.copy_from_slice(bytes);
+                <$t>::from_le_bytes(buf)
+            }
+        }
+    };
+}
+
+impl_element!(f32, Float);
+impl_element!(f64, Double);
+impl_element!(i8, Int8);
+impl_element!(u8, Uint8);
+impl_element!(i16, Int16);
+impl_element!(u16, Uint16);
+impl_element!(i32, Int32);
+impl_element!(u32, Uint32);
+impl_element!(i64, Int64);
+impl_element!(u64, Uint64);
+#[cfg(feature = "half")]
+impl_element!(half::f16, Float16);
+#[cfg(feature = "half")]
+impl_element!(half::bf16, Bfloat16);
+
+impl Element for bool {
+    const DATA_TYPE: DataType = DataType::Bool;
+
+    #[inline]
+    fn write_le(self, bytes: &mut Vec<u8>) {
+        bytes.push(self as u8);
+    }
+
+    #[inline]
+    fn read_le(bytes: &[u8]) -> Self {
+        bytes[0] != 0
+    }
+}
+
+/// Encodes values as a tensor of given shape.
+pub fn from_slice<T: Element>(
+    shape: &[usize],
+    values: &[T],
+    encoding: Encoding,
+) -> Result<TensorProto, Error> {
+    if shape.iter().product::<usize>() != values.len() {
+        return Err(Error::ShapeMismatch {
+            dims: shape.iter().map(|dim| *dim as i64).collect(),
+            len: values.len(),
+        });
+    }
+    let mut raw = Vec::with_capacity(values.len() * element_size(T::DATA_TYPE).unwrap_or(1));
+    for value in values.iter() {
+        value.write_le(&mut raw);
+    }
+    Ok(encode(T::DATA_TYPE, shape, raw, encoding))
+}
+
+/// Creates tensor from little-endian bytes of its values.
+pub(crate) fn encode(
+    data_type: DataType,
+    shape: &[usize],
+    raw: Vec<u8>,
+    encoding: Encoding,
+) -> TensorProto {
+    let mut tensor = TensorProto {
+        dims: shape.iter().map(|dim| *dim as i64).collect(),
+        data_type: data_type as i32,
+        ..TensorProto::default()
+    };
+    match encoding {
+        Encoding::Raw => tensor.raw_data = raw,
+        Encoding::Typed => raw_to_typed(&mut tensor, data_type, &raw),
+    }
+    tensor
+}
+
+/// Decodes tensor values of an element type.
+pub fn to_vec<T: Element>(tensor: &TensorProto) -> Result<Vec<T>, Error> {
+    if tensor.data_type != T::DATA_TYPE as i32 {
+        return Err(Error::DataType {
+            expected: T::DATA_TYPE,
+            found: tensor.data_type,
+        });
+    }
+    if tensor.data_location == 1 {
+        return Err(Error::InvalidData("data is stored externally".to_owned()));
+    }
+    let size = element_size(T::DATA_TYPE).unwrap_or(1);
+    let typed;
+    let raw = if tensor.raw_data.is_empty() {
+        typed = typed_to_raw(tensor, T::DATA_TYPE);
+        &typed
+    } else {
+        &tensor.raw_data
+    };
+    if raw.len() % size != 0 {
+        return Err(Error::InvalidData(format!(
+            "{} bytes are not a multiple of element size {}",
+            raw.len(),
+            size
+        )));
+    }
+    let values: Vec<T> = raw.chunks_exact(size).map(T::read_le).collect();
+    let count = tensor.dims.iter().product::<i64>();
+    if count < 0 || values.len() != count as usize {
+        return Err(Error::ShapeMismatch {
+            dims: tensor.dims.clone(),
+            len: values.len(),
+        });
+    }
+    Ok(values)
+}
+
+/// Encodes values as an n-dimensional tensor.
+///
+/// This is a function rather than `From<ArrayD<T>> for TensorProto` because
+/// both types are defined in other crates, which the orphan rule forbids.
+/// [`builder::Tensor`](../builder/struct.Tensor.html) implements `From` for
+/// arrays.
+#[cfg(feature = "ndarray")]
+pub fn from_array<S, D>(array: &ndarray::ArrayBase<S, D>, encoding: Encoding) -> TensorProto
+where
+    S: ndarray::Data,
+    S::Elem: Element,
+    D: ndarray::Dimension,
+{
+    let values: Vec<S::Elem> = array.iter().copied().collect();
+    from_slice(array.shape(), &values, encoding).expect("array shape matches its length")
+}
+
+/// Decodes tensor values as an n-dimensional array.
+///
+/// `TryFrom<&TensorProto> for ArrayD<T>` can not be implemented outside of
+/// `ndarray` and `onnx-pb` for the same reason as `From` in
+/// [`from_array`](fn.from_array.html).
+#[cfg(feature = "ndarray")]
+pub fn to_array<T: Element>(tensor: &TensorProto) -> Result<ndarray::ArrayD<T>, Error> {
+    let values = to_vec(tensor)?;
+    let shape: Vec<usize> = tensor.dims.iter().map(|dim| *dim as usize).collect();
+    ndarray::ArrayD::from_shape_vec(shape, values).map_err(|_| Error::ShapeMismatch {
+        dims: tensor.dims.clone(),
+        len: tensor.dims.iter().product::<i64>() as usize,
+    })
+}
+
+/// Stores little-endian bytes in the typed data field of a type.
+fn raw_to_typed(tensor: &mut TensorProto, data_type: DataType, raw: &[u8]) {
+    macro_rules! store {
+        ( $field:ident, $t:ty, $as:ty ) => {
+            tensor.$field = raw
+                .chunks_exact(std::mem::size_of::<$t>())
+                .map(|chunk| <$t as Element>::read_le(chunk) as $as)
+                .collect()
+        };
+    }
+    match data_type {
+        DataType::Float => store!(float_data, f32, f32),
+        DataType::Double => store!(double_data, f64, f64),
+        DataType::Int8 => store!(int32_data, i8, i32),
+        DataType::Uint8 | DataType::Bool => store!(int32_data, u8, i32),
+        DataType::Int16 => store!(int32_data, i16, i32),
+        DataType::Uint16 | DataType::Float16 | DataType::Bfloat16 => {
+            store!(int32_data, u16, i32)
+        }
+        DataType::Int32 => store!(int32_data, i32, i32),
+        DataType::Int64 => store!(int64_data, i64, i64),
+        DataType::Uint32 => store!(uint64_data, u32, u64),
+        DataType::Uint64 => store!(uint64_data, u64, u64),
+        _ => tensor.raw_data = raw.to_vec(),
+    }
+}
+
+/// Returns typed data field of a type as little-endian bytes.
+fn typed_to_raw(tensor: &TensorProto, data_type: DataType) -> Vec<u8> {
+    let mut raw = Vec::new();
+    macro_rules! load {
+        ( $field:ident, $t:ty ) => {
+            for value in tensor.$field.iter() {
+                (*value as $t).write_le(&mut raw);
+            }
+        };
+    }
+    match data_type {
+        DataType::Float => load!(float_data, f32),
+        DataType::Double => load!(double_data, f64),
+        DataType::Int8 => load!(int32_data, i8),
+        DataType::Uint8 | DataType::Bool => load!(int32_data, u8),
+        DataType::Int16 => load!(int32_data, i16),
+        DataType::Uint16 | DataType::Float16 | DataType::Bfloat16 => load!(int32_data, u16),
+        DataType::Int32 => load!(int32_data, i32),
+        DataType::Int64 => load!(int64_data, i64),
+        DataType::Uint32 => load!(uint64_data, u32),
+        DataType::Uint64 => load!(uint64_data, u64),
+        _ => {}
+    }
+    raw
+}
+
+/// Transposes a matrix, keeping the storage of its values.
+///
+/// Returns `None` for tensors which are not 2-D or can not be stored in
+/// `raw_data`.
+pub(crate) fn transpose(tensor: &TensorProto) -> Option<TensorProto> {
+    let data_type = DataType::from_i32(tensor.data_type)?;
+    let size = element_size(data_type)?;
+    let (rows, cols) = match tensor.dims[..] {
+        [rows, cols] => (rows as usize, cols as usize),
+        _ => return None,
+    };
+    let typed = tensor.raw_data.is_empty();
+    let raw = if typed {
+        typed_to_raw(tensor, data_type)
+    } else {
+        tensor.raw_data.clone()
+    };
+    if raw.len() != rows * cols * size {
+        return None;
+    }
+    let mut transposed = Vec::with_capacity(raw.len());
+    for col in 0..cols {
+        for row in 0..rows {
+            let start = (row * cols + col) * size;
+            transposed.extend_from_slice(&raw[start..start + size]);
+        }
+    }
+    let encoding = if typed {
+        Encoding::Typed
+    } else {
+        Encoding::Raw
+    };
+    let mut result = encode(data_type, &[cols, rows], transposed, encoding);
+    result.name = tensor.name.clone();
+    result.doc_string = tensor.doc_string.clone();
+    Some(result)
+}
+
+/// Decodes numeric tensor values as `f64`.
+///
+/// Reads `raw_data` when present and typed data fields otherwise. Returns
+/// `None` for string, complex and undefined tensors.
+pub(crate) fn to_f64_vec(tensor: &TensorProto) -> Option<Vec<f64>> {
+    let raw = &tensor.raw_data;
+    macro_rules! decode {
+        ( $field:ident, $t:ty, $map:expr ) => {
+            if raw.is_empty() {
+                tensor.$field.iter().map(|v| $map(*v as $t)).collect()
+            } else {
+                const SIZE: usize = std::mem::size_of::<$t>();
+                raw.chunks_exact(SIZE)
+                    .map(|chunk| {
+                        let mut bytes = [0u8; SIZE];
+                        bytes.copy_from_slice(chunk);
+                        $map(<$t>::from_le_bytes(bytes))
+                    })
+                    .collect()
+            }
+        };
+    }
+    let values = match DataType::from_i32(tensor.data_type)? {
+        DataType::Float => decode!(float_data, f32, f64::from),
+        DataType::Double => decode!(double_data, f64, |v| v),
+        DataType::Int8 => decode!(int32_data, i8, f64::from),
+        DataType::Uint8 | DataType::Bool => decode!(int32_data, u8, f64::from),
+        DataType::Int16 => decode!(int32_data, i16, f64::from),
+        DataType::Uint16 => decode!(int32_data, u16, f64::from),
+        DataType::Float16 => decode!(int32_data, u16, f16_to_f64),
+        DataType::Bfloat16 => decode!(int32_data, u16, bf16_to_f64),
+        DataType::Int32 => decode!(int32_data, i32, f64::from),
+        DataType::Int64 => decode!(int64_data, i64, |v| v as f64),
+        DataType::Uint32 => decode!(uint64_data, u32, f64::from),
+        DataType::Uint64 => decode!(uint64_data, u64, |v| v as f64),
+        DataType::String | DataType::Complex64 | DataType::Complex128 | DataType::Undefined => {
+            return None
+        }
+    };
+    Some(values)
+}
+
+/// Converts IEEE 754 half precision bits to `f64`.
+fn f16_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let fraction = (bits & 0x3ff) as f64;
+    match exponent {
+        0 => sign * fraction * 2f64.powi(-24),
+        0x1f if fraction == 0.0 => sign * f64::INFINITY,
+        0x1f => f64::NAN,
+        _ => sign * (1.0 + fraction / 1024.0) * 2f64.powi(exponent - 15),
+    }
+}
+
+/// Converts bfloat16 bits to `f64`.
+fn bf16_to_f64(bits: u16) -> f64 {
+    f32::from_bits((bits as u32) << 16) as f64
+}
+
+/// Converts `f64` to IEEE 754 half precision bits, rounding to nearest even.
+fn f64_to_f16(value: f64) -> u16 {
+    let bits = (value as f32).to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    let (half, rest, halfway) = if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        (
+            mantissa >> shift,
+            mantissa & ((1 << shift) - 1),
+            1 << (shift - 1),
+        )
+    } else {
+        (
+            ((exponent as u32) << 10) | (mantissa >> 13),
+            mantissa & 0x1fff,
+            0x1000,
+        )
+    };
+    let round = (rest > halfway || (rest == halfway && half & 1 == 1)) as u32;
+    sign | (half + round) as u16
+}
+
+/// Converts `f64` to bfloat16 bits, rounding to nearest even.
+fn f64_to_bf16(value: f64) -> u16 {
+    let bits = (value as f32).to_bits();
+    if (value as f32).is_nan() {
+        return ((bits >> 16) | 0x40) as u16;
+    }
+    ((bits + 0x7fff + ((bits >> 16) & 1)) >> 16) as u16
+}
+
+/// Encodes numeric values as a tensor of given type and shape.
+///
+/// Values are truncated for integer types and rounded to nearest for half
+/// precision types. Returns `None` for string, complex and undefined types.
+pub(crate) fn from_f64_vec(
+    data_type: DataType,
+    dims: Vec<i64>,
+    values: &[f64],
+) -> Option<TensorProto> {
+    let mut tensor = TensorProto {
+        dims,
+        data_type: data_type as i32,
+        ..TensorProto::default()
+    };
+    match data_type {
+        DataType::Float => tensor.float_data = values.iter().map(|v| *v as f32).collect(),
+        DataType::Double => tensor.double_data = values.to_vec(),
+        DataType::Float16 => {
+            tensor.int32_data = values.iter().map(|v| f64_to_f16(*v) as i32).collect()
+        }
+        DataType::Bfloat16 => {
+            tensor.int32_data = values.iter().map(|v| f64_to_bf16(*v) as i32).collect()
+        }
+        DataType::Int8 | DataType::Uint8 | DataType::Int16 | DataType::Uint16 | DataType::Int32 => {
+            tensor.int32_data = values.iter().map(|v| *v as i32).collect()
+        }
+        DataType::Bool => tensor.int32_data = values.iter().map(|v| (*v != 0.0) as i32).collect(),
+        DataType::Int64 => tensor.int64_data = values.iter().map(|v| *v as i64).collect(),
+        DataType::Uint32 | DataType::Uint64 => {
+            tensor.uint64_data = values.iter().map(|v| *v as u64).collect()
+        }
+        _ => return None,
+    }
+    Some(tensor)
+}
+
+/// Decodes integer tensor values as `i64`.
+///
+/// `Uint64` values keep their bits. Returns `None` for other than integer
+/// tensors.
+pub(crate) fn to_i64_vec(tensor: &TensorProto) -> Option<Vec<i64>> {
+    let raw = &tensor.raw_data;
+    macro_rules! decode {
+        ( $field:ident, $t:ty ) => {
+            if raw.is_empty() {
+                tensor.$field.iter().map(|v| *v as $t as i64).collect()
+            } else {
+                const SIZE: usize = std::mem::size_of::<$t>();
+                raw.chunks_exact(SIZE)
+                    .map(|chunk| {
+                        let mut bytes = [0u8; SIZE];
+                        bytes.copy_from_slice(chunk);
+                        <$t>::from_le_bytes(bytes) as i64
+                    })
+                    .collect()
+            }
+        };
+    }
+    let values = match DataType::from_i32(tensor.data_type)? {
+        DataType::Int8 => decode!(int32_data, i8),
+        DataType::Uint8 | DataType::Bool => decode!(int32_data, u8),
+        DataType::Int16 => decode!(int32_data, i16),
+        DataType::Uint16 => decode!(int32_data, u16),
+        DataType::Int32 => decode!(int32_data, i32),
+        DataType::Int64 => decode!(int64_data, i64),
+        DataType::Uint32 => decode!(uint64_data, u32),
+        DataType::Uint64 => decode!(uint64_data, u64),
+        _ => return None,
+    };
+    Some(values)
+}
+
+/// Encodes integer values as a tensor of given type and shape.
+///
+/// Values wrap around to the range of the type. Returns `None` for other
+/// than integer types.
+pub(crate) fn from_i64_vec(
+    data_type: DataType,
+    dims: Vec<i64>,
+    values: &[i64],
+) -> Option<TensorProto> {
+    let mut tensor = TensorProto {
+        dims,
+        data_type: data_type as i32,
+        ..TensorProto::default()
+    };
+    let wrapped = values.iter().map(|v| wrap_integer(data_type, *v));
+    match data_type {
+        DataType::Int8 | DataType::Uint8 | DataType::Int16 | DataType::Uint16 | DataType::Int32 => {
+            tensor.int32_data = wrapped.map(|v| v as i32).collect()
+        }
+        DataType::Bool => tensor.int32_data = values.iter().map(|v| (*v != 0) as i32).collect(),
+        DataType::Int64 => tensor.int64_data = wrapped.collect(),
+        DataType::Uint32 | DataType::Uint64 => {
+            tensor.uint64_data = wrapped.map(|v| v as u64).collect()
+        }
+        _ => return None,
+    }
+    Some(tensor)
+}
+
+/// Wraps integer value around to the range of a type.
+pub(crate) fn wrap_integer(data_type: DataType, value: i64) -> i64 {
+    match data_type {
+        DataType::Int8 => value as i8 as i64,
+        DataType::Uint8 => value as u8 as i64,
+        DataType::Int16 => value as i16 as i64,
+        DataType::Uint16 => value as u16 as i64,
+        DataType::Int32 => value as i32 as i64,
+        DataType::Uint32 => value as u32 as i64,
+        DataType::Bool => (value != 0) as i64,
+        _ => value,
+    }
+}
+
+/// Returns true if values of a type are integers.
+pub(crate) fn is_integer(data_type: DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Uint8
+            | DataType::Int16
+            | DataType::Uint16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::Uint32
+            | DataType::Uint64
+            | DataType::Bool
+    )
+}
+
+/// Returns size in bytes of an element stored in `raw_data`.
+pub(crate) fn element_size(data_type: DataType) -> Option<usize> {
+    Some(match data_type {
+        DataType::Int8 | DataType::Uint8 | DataType::Bool => 1,
+        DataType::Int16 | DataType::Uint16 | DataType::Float16 | DataType::Bfloat16 => 2,
+        DataType::Float | DataType::Int32 | DataType::Uint32 => 4,
+        DataType::Double | DataType::Int64 | DataType::Uint64 | DataType::Complex64 => 8,
+        DataType::Complex128 => 16,
+        DataType::String | DataType::Undefined => return None,
+    })
+}
+
+/// Returns number of values stored in typed data fields.
+pub(crate) fn typed_data_len(tensor: &TensorProto) -> usize {
+    match DataType::from_i32(tensor.data_type) {
+        Some(DataType::Float) => tensor.float_data.len(),
+        Some(DataType::Complex64) => tensor.float_data.len() / 2,
+        Some(DataType::Double) => tensor.double_data.len(),
+        Some(DataType::Complex128) => tensor.double_data.len() / 2,
+        Some(DataType::Int64) => tensor.int64_data.len(),
+        Some(DataType::Uint32) | Some(DataType::Uint64) => tensor.uint64_data.len(),
+        Some(DataType::String) => tensor.string_data.len(),
+        _ => tensor.int32_data.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transpose_matrix() {
+        let values = [1i64, 2, 3, 4, 5, 6];
+        for encoding in [Encoding::Raw, Encoding::Typed].iter() {
+            let tensor = from_slice(&[2, 3], &values, *encoding).unwrap();
+            let transposed = transpose(&tensor).unwrap();
+            assert_eq!(transposed.dims, vec![3, 2]);
+            assert_eq!(transposed.raw_data.is_empty(), tensor.raw_data.is_empty());
+            assert_eq!(to_vec::<i64>(&transposed).unwrap(), vec![1, 4, 2, 5, 3, 6]);
+        }
+        assert!(transpose(&from_slice(&[6], &values, Encoding::Raw).unwrap()).is_none());
+    }
+
+    #[test]
+    fn f16_conversions() {
+        assert_eq!(f16_to_f64(0x3c00), 1.0);
+        assert_eq!(f16_to_f64(0xc000), -2.0);
+        assert_eq!(f16_to_f64(0x7bff), 65504.0);
+        assert_eq!(f16_to_f64(0x0001), 2f64.powi(-24));
+        assert_eq!(f16_to_f64(0x03ff), 1023.0 * 2f64.powi(-24));
+        assert_eq!(f16_to_f64(0x0400), 2f64.powi(-14));
+        assert_eq!(f16_to_f64(0x7c00), f64::INFINITY);
+        assert_eq!(f16_to_f64(0xfc00), f64::NEG_INFINITY);
+        assert!(f16_to_f64(0x7e00).is_nan());
+        assert!(f16_to_f64(0x8000).is_sign_negative());
+
+        assert_eq!(f64_to_f16(65504.0), 0x7bff);
+        assert_eq!(f64_to_f16(1e6), 0x7c00);
+        assert_eq!(f64_to_f16(-1e6), 0xfc00);
+        // halfway to the next value rounds to even, carrying into infinity
+        assert_eq!(f64_to_f16(65520.0), 0x7c00);
+        assert_eq!(f64_to_f16(1.0 + 2f64.powi(-11)), 0x3c00);
+        assert_eq!(f64_to_f16(1.0 + 3.0 * 2f64.powi(-11)), 0x3c02);
+        // carry from the largest fraction into the exponent
+        assert_eq!(f64_to_f16(1.0 - 2f64.powi(-12)), 0x3c00);
+        // subnormals
+        assert_eq!(f64_to_f16(2f64.powi(-24)), 0x0001);
+        assert_eq!(f64_to_f16(2f64.powi(-25)), 0x0000);
+        assert_eq!(f64_to_f16(1.5 * 2f64.powi(-25)), 0x0001);
+        assert_eq!(f64_to_f16(3.0 * 2f64.powi(-25)), 0x0002);
+        assert_eq!(f64_to_f16(1023.5 * 2f64.powi(-24)), 0x0400);
+        assert_eq!(f64_to_f16(-2f64.powi(-30)), 0x8000);
+        assert_eq!(f64_to_f16(f64::INFINITY), 0x7c00);
+        assert!(f16_to_f64(f64_to_f16(f64::NAN)).is_nan());
+
+        for bits in 0..=u16::MAX {
+            let value = f16_to_f64(bits);
+            if !value.is_nan() {
+                assert_eq!(f64_to_f16(value), bits, "{:#06x}", bits);
+            }
+        }
+    }
+
+    #[test]
+    fn bf16_conversions() {
+        assert_eq!(bf16_to_f64(0x3f80), 1.0);
+        assert_eq!(bf16_to_f64(0xc000), -2.0);
+        assert_eq!(bf16_to_f64(0x0001), f64::from(f32::from_bits(0x0001_0000)));
+        assert_eq!(bf16_to_f64(0x7f80), f64::INFINITY);
+        assert!(bf16_to_f64(0x7fc0).is_nan());
+
+        assert_eq!(f64_to_bf16(1.5), 0x3fc0);
+        // halfway rounds to even
+        assert_eq!(f64_to_bf16(f64::from(f32::from_bits(0x3f80_8000))), 0x3f80);
+        assert_eq!(f64_to_bf16(f64::from(f32::from_bits(0x3f81_8000))), 0x3f82);
+        // carry into the exponent and into infinity
+        assert_eq!(f64_to_bf16(f64::from(f32::from_bits(0x3f7f_ffff))), 0x3f80);
+        assert_eq!(f64_to_bf16(f64::from(f32::MAX)), 0x7f80);
+        assert_eq!(f64_to_bf16(f64::NEG_INFINITY), 0xff80);
+        // subnormals
+        assert_eq!(f64_to_bf16(f64::from(f32::from_bits(0x0000_8001))), 0x0001);
+        assert!(bf16_to_f64(f64_to_bf16(f64::NAN)).is_nan());
+
+        for bits in 0..=u16::MAX {
+            let value = bf16_to_f64(bits);
+            if !value.is_nan() {
+                assert_eq!(f64_to_bf16(value), bits, "{:#06x}", bits);
+            }
+        }
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn half_crate() {
+        for bits in 0..=u16::MAX {
+            let value = half::f16::from_bits(bits).to_f64();
+            if value.is_nan() {
+                assert!(f16_to_f64(bits).is_nan());
+                continue;
+            }
+            assert_eq!(f16_to_f64(bits), value);
+            assert_eq!(bf16_to_f64(bits), half::bf16::from_bits(bits).to_f64());
+            let between = value + value.abs() * 2f64.powi(-12);
+            assert_eq!(
+                f64_to_f16(between),
+                half::f16::from_f64(between).to_bits(),
+                "{}",
+                between
+            );
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! ONNX textual syntax.
+//!
+//! Prints and parses models in the format used by `onnx.parser` and
+//! `onnx.printer`:
+//!
+//! ```text
+//! <
+//!   ir_version: 6,
+//!   opset_import: ["" : 11]
+//! >
+//! add (float[1,10] X, float[1,10] Y) => (float[1,10] Z) {
+//!   Z = Add (X, Y)
+//! }
+//! ```
+//!
+//! Two extensions keep the mapping lossless for models built with this crate:
+//! node names are written in brackets before the outputs (`[name] Z = Add (X, Y)`)
+//! and names which are not plain identifiers are written as quoted strings.
+//!
+//! Tensor values are always written in decoded form, so tensors stored in
+//! `raw_data` are parsed back into their typed data fields. Doc strings of
+//! graphs, nodes and values, denotations and sparse tensors are not represented.
+
+mod parser;
+mod printer;
+
+use onnx_pb::{tensor_proto::DataType, GraphProto, ModelProto, TypeProto};
+
+pub use self::parser::ParseError;
+
+pub(crate) use self::printer::tensor_values;
+
+/// Prints model in textual syntax.
+pub fn print_model(model: &ModelProto) -> String {
+    let mut printer = printer::Printer::default();
+    printer.model(model);
+    printer.finish()
+}
+
+/// Prints graph in textual syntax.
+pub fn print_graph(graph: &GraphProto) -> String {
+    let mut printer = printer::Printer::default();
+    printer.graph(graph);
+    printer.finish()
+}
+
+/// Prints value type in textual syntax, e.g. `float[N,6]`.
+pub fn print_type(typ: &TypeProto) -> String {
+    printer::type_proto(typ)
+}
+
+/// Parses model from textual syntax.
+pub fn parse_model(text: &str) -> Result<ModelProto, ParseError> {
+    parser::Parser::new(text)?.parse_model()
+}
+
+/// Parses graph from textual syntax.
+pub fn parse_graph(text: &str) -> Result<GraphProto, ParseError> {
+    parser::Parser::new(text)?.parse_standalone_graph()
+}
+
+const DATA_TYPES: &[(DataType, &str)] = &[
+    (DataType::Undefined, "undefined"),
+    (DataType::Float, "float"),
+    (DataType::Uint8, "uint8"),
+    (DataType::Int8, "int8"),
+    (DataType::Uint16, "uint16"),
+    (DataType::Int16, "int16"),
+    (DataType::Int32, "int32"),
+    (DataType::Int64, "int64"),
+    (DataType::String, "string"),
+    (DataType::Bool, "bool"),
+    (DataType::Float16, "float16"),
+    (DataType::Double, "double"),
+    (DataType::Uint32, "uint32"),
+    (DataType::Uint64, "uint64"),
+    (DataType::Complex64, "complex64"),
+    (DataType::Complex128, "complex128"),
+    (DataType::Bfloat16, "bfloat16"),
+];
+
+/// Returns textual name of a tensor element type.
+pub(crate) fn data_type_name(data_type: i32) -> Option<&'static str> {
+    DATA_TYPES
+        .iter()
+        .find(|(typ, _)| *typ as i32 == data_type)
+        .map(|(_, name)| *name)
+}
+
+/// Returns tensor element type by textual name.
+pub(crate) fn data_type_by_name(name: &str) -> Option<DataType> {
+    DATA_TYPES
+        .iter()
+        .find(|(_, typ_name)| *typ_name == name)
+        .map(|(typ, _)| *typ)
+}
+
+/// Returns true if name can be written without quotes.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(is_identifier_char)
+        && data_type_by_name(name).is_none()
+        && !matches!(name, "seq" | "map" | "inf" | "nan")
+}
+
+#[inline]
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '/'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use onnx_pb::{tensor_proto::DataType, TensorProto};
+
+    use crate::builder;
+
+    #[test]
+    fn parse_handwritten() {
+        let model = parse_model(
+            r#"
+            <ir_version: 6, opset_import: ["" : 11, "com.example" : 1]>
+            agraph (float[N, 6] X, "input:1") => (float[N,?] Y) <float[1] two = {2.0}> {
+                # comment
+                T = ReduceMean <axes = [1], keepdims = 1> (X)
+                [scale] Y = Mul (T, two)
+                U = com.example.Custom <names: strings = [], t = int64[2] {1, 2}> ("input:1", )
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(model.opset_import.len(), 2);
+        assert_eq!(model.opset_import[1].domain, "com.example");
+        let graph = model.graph.as_ref().unwrap();
+        assert_eq!(graph.input[1].name, "input:1");
+        assert!(graph.input[1].r#type.is_none());
+        assert_eq!(graph.initializer[0].name, "two");
+        assert_eq!(graph.initializer[0].float_data, vec![2.0]);
+        assert_eq!(graph.node[1].name, "scale");
+        assert_eq!(graph.node[2].domain, "com.example");
+        assert_eq!(graph.node[2].op_type, "Custom");
+        assert_eq!(graph.node[2].input, vec!["input:1", ""]);
+        assert_eq!(parse_model(&print_model(&model)).unwrap(), model);
+    }
+
+    #[test]
+    fn roundtrip_subgraph() {
+        let mut then_branch = builder::Graph::new("then");
+        let x = then_branch.input("X").typed(DataType::Float).node();
+        let then_branch = then_branch.outputs(-x).build();
+        let mut graph = builder::Graph::new("cond");
+        let c = graph.input("C").typed(DataType::Bool).node();
+        let out = graph
+            .node("if")
+            .op("Branch")
+            .domain("com.example")
+            .input(c)
+            .attribute("then_branch", then_branch.clone())
+            .attribute("else_branch", then_branch)
+            .attribute("scale", vec![1.5f32, -0.25])
+            .attribute("label", "a \"quoted\"\nlabel")
+            .build();
+        let value = TensorProto {
+            dims: vec![2],
+            data_type: DataType::Float as i32,
+            raw_data: [1.0f32.to_le_bytes(), f32::INFINITY.to_le_bytes()].concat(),
+            ..TensorProto::default()
+        };
+        let graph = graph.outputs(out).initializer(value).build();
+        let text = print_graph(&graph);
+        let parsed = parse_graph(&text).unwrap();
+        assert_eq!(parsed.node, graph.node);
+        assert_eq!(parsed.initializer[0].float_data, vec![1.0, f32::INFINITY]);
+        assert_eq!(print_graph(&parsed), text);
+    }
+
+    #[test]
+    fn parse_error_position() {
+        let err = parse_graph("g (float[1] X) => (Y) {\n  Y = Neg X\n}").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 11));
+    }
+}
@@ -0,0 +1,762 @@
+//! Textual syntax parser.
+
+use onnx_pb::{
+    attribute_proto::AttributeType,
+    tensor_proto::DataType,
+    tensor_shape_proto::{dimension, Dimension},
+    type_proto, AttributeProto, GraphProto, ModelProto, NodeProto, OperatorSetIdProto,
+    StringStringEntryProto, TensorProto, TensorShapeProto, TypeProto, ValueInfoProto,
+};
+
+use super::{data_type_by_name, is_identifier_char};
+
+/// Textual syntax parse error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// Line number starting from 1.
+    pub line: usize,
+    /// Column number starting from 1.
+    pub column: usize,
+    /// Error message.
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(Vec<u8>),
+    Number(String),
+    Punct(char),
+    Arrow,
+    Eof,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "`{}`", ident),
+            Token::Str(_) => write!(f, "string"),
+            Token::Number(number) => write!(f, "`{}`", number),
+            Token::Punct(c) => write!(f, "`{}`", c),
+            Token::Arrow => write!(f, "`=>`"),
+            Token::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+/// Token with its position.
+struct Spanned {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Spanned>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let (mut line, mut column) = (1, 1);
+    macro_rules! bump {
+        () => {{
+            let c = chars.next();
+            if c == Some('\n') {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+            c
+        }};
+    }
+    while let Some(&c) = chars.peek() {
+        let (start_line, start_column) = (line, column);
+        let error = |message: String| ParseError {
+            line: start_line,
+            column: start_column,
+            message,
+        };
+        let token = if c.is_whitespace() {
+            bump!();
+            continue;
+        } else if c == '#' {
+            while !matches!(chars.peek(), None | Some('\n')) {
+                bump!();
+            }
+            continue;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&c) = chars.peek() {
+                if !is_identifier_char(c) {
+                    break;
+                }
+                ident.push(c);
+                bump!();
+            }
+            Token::Ident(ident)
+        } else if c.is_ascii_digit() || c == '-' || c == '+' {
+            let mut number = String::new();
+            number.push(c);
+            bump!();
+            while let Some(&c) = chars.peek() {
+                let exponent_sign = (c == '-' || c == '+') && number.ends_with(['e', 'E']);
+                if !(c.is_ascii_alphanumeric() || c == '.' || exponent_sign) {
+                    break;
+                }
+                number.push(c);
+                bump!();
+            }
+            if number == "-" || number == "+" {
+                return Err(error(format!("unexpected `{}`", number)));
+            }
+            Token::Number(number)
+        } else if c == '"' {
+            bump!();
+            let mut bytes = Vec::new();
+            loop {
+                match bump!() {
+                    None => return Err(error("unterminated string".to_owned())),
+                    Some('"') => break,
+                    Some('\\') => match bump!() {
+                        Some('n') => bytes.push(b'\n'),
+                        Some('t') => bytes.push(b'\t'),
+                        Some('r') => bytes.push(b'\r'),
+                        Some('x') => {
+                            let hex: String = (0..2).filter_map(|_| bump!()).collect();
+                            let byte = u8::from_str_radix(&hex, 16)
+                                .map_err(|_| error(format!("invalid escape `\\x{}`", hex)))?;
+                            bytes.push(byte);
+                        }
+                        Some(c) => {
+                            let mut buf = [0; 4];
+                            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                        }
+                        None => return Err(error("unterminated string".to_owned())),
+                    },
+                    Some(c) => {
+                        let mut buf = [0; 4];
+                        bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                    }
+                }
+            }
+            Token::Str(bytes)
+        } else if c == '=' {
+            bump!();
+            if chars.peek() == Some(&'>') {
+                bump!();
+                Token::Arrow
+            } else {
+                Token::Punct('=')
+            }
+        } else if "<>()[]{},:@?".contains(c) {
+            bump!();
+            Token::Punct(c)
+        } else {
+            return Err(error(format!("unexpected character `{}`", c)));
+        };
+        tokens.push(Spanned {
+            token,
+            line: start_line,
+            column: start_column,
+        });
+    }
+    tokens.push(Spanned {
+        token: Token::Eof,
+        line,
+        column,
+    });
+    Ok(tokens)
+}
+
+/// Textual syntax parser.
+pub(super) struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+impl Parser {
+    pub fn new(text: &str) -> Result<Self, ParseError> {
+        Ok(Parser {
+            tokens: tokenize(text)?,
+            pos: 0,
+        })
+    }
+
+    pub fn parse_model(mut self) -> Result<ModelProto, ParseError> {
+        let mut model = ModelProto::default();
+        self.expect('<')?;
+        loop {
+            let key = self.ident()?;
+            self.expect(':')?;
+            match key.as_str() {
+                "ir_version" => model.ir_version = self.int()?,
+                "model_version" => model.model_version = self.int()?,
+                "producer_name" => model.producer_name = self.string()?,
+                "producer_version" => model.producer_version = self.string()?,
+                "domain" => model.domain = self.string()?,
+                "doc_string" => model.doc_string = self.string()?,
+                "opset_import" => {
+                    model.opset_import = self.pairs(|p| {
+                        Ok(OperatorSetIdProto {
+                            domain: p.string()?,
+                            version: {
+                                p.expect(':')?;
+                                p.int()?
+                            },
+                        })
+                    })?
+                }
+                "metadata_props" => {
+                    model.metadata_props = self.pairs(|p| {
+                        Ok(StringStringEntryProto {
+                            key: p.string()?,
+                            value: {
+                                p.expect(':')?;
+                                p.string()?
+                            },
+                        })
+                    })?
+                }
+                _ => return Err(self.error_before(format!("unknown model field `{}`", key))),
+            }
+            if !self.eat(',') {
+                break;
+            }
+        }
+        self.expect('>')?;
+        if self.peek() != &Token::Eof {
+            model.graph = Some(self.graph()?);
+        }
+        self.expect_eof()?;
+        Ok(model)
+    }
+
+    pub fn parse_standalone_graph(mut self) -> Result<GraphProto, ParseError> {
+        let graph = self.graph()?;
+        self.expect_eof()?;
+        Ok(graph)
+    }
+
+    fn graph(&mut self) -> Result<GraphProto, ParseError> {
+        let mut graph = GraphProto {
+            name: self.name()?,
+            ..GraphProto::default()
+        };
+        self.expect('(')?;
+        graph.input = self.value_infos(')')?;
+        self.expect(')')?;
+        if self.next() != Token::Arrow {
+            return Err(self.error_before("expected `=>`".to_owned()));
+        }
+        self.expect('(')?;
+        graph.output = self.value_infos(')')?;
+        self.expect(')')?;
+        if self.eat('<') {
+            loop {
+                let typ = self.type_proto()?;
+                let name = self.name()?;
+                if self.eat('=') {
+                    let mut tensor = self.tensor_from_type(&typ)?;
+                    tensor.name = name;
+                    self.tensor_values(&mut tensor)?;
+                    graph.initializer.push(tensor);
+                } else {
+                    graph.value_info.push(ValueInfoProto {
+                        name,
+                        r#type: Some(typ),
+                        doc_string: String::new(),
+                    });
+                }
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            self.expect('>')?;
+        }
+        self.expect('{')?;
+        while !self.eat('}') {
+            graph.node.push(self.node()?);
+        }
+        Ok(graph)
+    }
+
+    fn node(&mut self) -> Result<NodeProto, ParseError> {
+        let mut node = NodeProto::default();
+        if self.eat('[') {
+            node.name = self.name()?;
+            self.expect(']')?;
+        }
+        if !self.eat('=') {
+            loop {
+                node.output.push(self.name()?);
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            self.expect('=')?;
+        }
+        let op = self.ident()?;
+        match op.rfind('.') {
+            Some(index) => {
+                node.domain = op[..index].to_owned();
+                node.op_type = op[index + 1..].to_owned();
+            }
+            None => node.op_type = op,
+        }
+        if self.eat('<') {
+            loop {
+                node.attribute.push(self.attribute()?);
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            self.expect('>')?;
+        }
+        self.expect('(')?;
+        if !self.eat(')') {
+            loop {
+                if self.peek() == &Token::Punct(',') || self.peek() == &Token::Punct(')') {
+                    node.input.push(String::new());
+                } else {
+                    node.input.push(self.name()?);
+                }
+                if self.eat(')') {
+                    break;
+                }
+                self.expect(',')?;
+                if self.eat(')') {
+                    node.input.push(String::new());
+                    break;
+                }
+            }
+        }
+        Ok(node)
+    }
+
+    fn attribute(&mut self) -> Result<AttributeProto, ParseError> {
+        let mut attr = AttributeProto {
+            name: self.name()?,
+            ..AttributeProto::default()
+        };
+        let mut typ = None;
+        if self.eat(':') {
+            let name = self.ident()?;
+            typ =
+                Some(attribute_type_by_name(&name).ok_or_else(|| {
+                    self.error_before(format!("unknown attribute type `{}`", name))
+                })?);
+        }
+        self.expect('=')?;
+        if self.eat('@') {
+            attr.ref_attr_name = self.name()?;
+            let typ = typ.ok_or_else(|| {
+                self.error_before("attribute reference requires a type".to_owned())
+            })?;
+            attr.r#type = typ as i32;
+            return Ok(attr);
+        }
+        let typ = if self.eat('[') {
+            let typ = match (self.peek(), typ) {
+                (_, Some(typ)) => typ,
+                (Token::Punct(']'), None) => {
+                    return Err(self.error("empty list requires a type".to_owned()))
+                }
+                (token, None) => match self.scalar_attribute_type(token) {
+                    AttributeType::Float => AttributeType::Floats,
+                    AttributeType::Int => AttributeType::Ints,
+                    AttributeType::String => AttributeType::Strings,
+                    AttributeType::Tensor => AttributeType::Tensors,
+                    AttributeType::Graph => AttributeType::Graphs,
+                    _ => return Err(self.error(format!("unexpected {}", token))),
+                },
+            };
+            if !self.eat(']') {
+                loop {
+                    match typ {
+                        AttributeType::Floats => attr.floats.push(self.float()? as f32),
+                        AttributeType::Ints => attr.ints.push(self.int()?),
+                        AttributeType::Strings => attr.strings.push(self.bytes()?),
+                        AttributeType::Tensors => attr.tensors.push(self.tensor()?),
+                        AttributeType::Graphs => attr.graphs.push(self.graph()?),
+                        _ => return Err(self.error(format!("unexpected {}", self.peek()))),
+                    }
+                    if !self.eat(',') {
+                        break;
+                    }
+                }
+                self.expect(']')?;
+            }
+            typ
+        } else {
+            let typ = typ.unwrap_or_else(|| self.scalar_attribute_type(self.peek()));
+            match typ {
+                AttributeType::Float => attr.f = self.float()? as f32,
+                AttributeType::Int => attr.i = self.int()?,
+                AttributeType::String => attr.s = self.bytes()?,
+                AttributeType::Tensor => attr.t = Some(self.tensor()?),
+                AttributeType::Graph => attr.g = Some(self.graph()?),
+                _ => return Err(self.error(format!("unexpected {}", self.peek()))),
+            }
+            typ
+        };
+        attr.r#type = typ as i32;
+        Ok(attr)
+    }
+
+    fn scalar_attribute_type(&self, token: &Token) -> AttributeType {
+        match token {
+            Token::Number(number) if is_float(number) => AttributeType::Float,
+            Token::Number(_) => AttributeType::Int,
+            Token::Ident(ident) if ident == "inf" || ident == "nan" => AttributeType::Float,
+            Token::Ident(ident) if data_type_by_name(ident).is_some() => AttributeType::Tensor,
+            Token::Ident(_) => AttributeType::Graph,
+            Token::Str(_) => match self.peek_at(1) {
+                Token::Punct('(') => AttributeType::Graph,
+                _ => AttributeType::String,
+            },
+            _ => AttributeType::Undefined,
+        }
+    }
+
+    fn tensor(&mut self) -> Result<TensorProto, ParseError> {
+        let typ = self.type_proto()?;
+        let mut tensor = self.tensor_from_type(&typ)?;
+        if self.peek() != &Token::Punct('{') {
+            tensor.name = self.name()?;
+        }
+        self.tensor_values(&mut tensor)?;
+        Ok(tensor)
+    }
+
+    fn tensor_from_type(&self, typ: &TypeProto) -> Result<TensorProto, ParseError> {
+        let tensor = match typ.value.as_ref() {
+            Some(type_proto::Value::TensorType(tensor)) => tensor,
+            _ => return Err(self.error_before("expected tensor type".to_owned())),
+        };
+        let dims = match tensor.shape.as_ref() {
+            Some(shape) => shape
+                .dim
+                .iter()
+                .map(|dim| match dim.value {
+                    Some(dimension::Value::DimValue(value)) => Ok(value),
+                    _ => Err(self.error_before("tensor dimensions must be known".to_owned())),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
+        Ok(TensorProto {
+            dims,
+            data_type: tensor.elem_type,
+            ..TensorProto::default()
+        })
+    }
+
+    fn tensor_values(&mut self, tensor: &mut TensorProto) -> Result<(), ParseError> {
+        self.expect('{')?;
+        if self.eat('}') {
+            return Ok(());
+        }
+        let typ = DataType::from_i32(tensor.data_type).unwrap_or(DataType::Undefined);
+        loop {
+            match typ {
+                DataType::Float | DataType::Complex64 => {
+                    tensor.float_data.push(self.float()? as f32)
+                }
+                DataType::Double | DataType::Complex128 => tensor.double_data.push(self.float()?),
+                DataType::Int64 => tensor.int64_data.push(self.int()?),
+                DataType::Uint32 | DataType::Uint64 => {
+                    let value = self.number()?;
+                    let value = value
+                        .parse()
+                        .map_err(|_| self.error_before(format!("invalid integer `{}`", value)))?;
+                    tensor.uint64_data.push(value)
+                }
+                DataType::String => tensor.string_data.push(self.bytes()?),
+                DataType::Bool if matches!(self.peek(), Token::Ident(_)) => {
+                    match self.ident()?.as_str() {
+                        "true" => tensor.int32_data.push(1),
+                        "false" => tensor.int32_data.push(0),
+                        ident => {
+                            return Err(self.error_before(format!("invalid boolean `{}`", ident)))
+                        }
+                    }
+                }
+                DataType::Undefined => {
+                    return Err(self.error("values of undefined type".to_owned()));
+                }
+                _ => {
+                    let value = self.int()?;
+                    tensor.int32_data.push(value as i32);
+                }
+            }
+            if !self.eat(',') {
+                break;
+            }
+        }
+        self.expect('}')
+    }
+
+    fn value_infos(&mut self, end: char) -> Result<Vec<ValueInfoProto>, ParseError> {
+        let mut values = Vec::new();
+        if self.peek() == &Token::Punct(end) {
+            return Ok(values);
+        }
+        loop {
+            let typ = if self.is_type_start() {
+                Some(self.type_proto()?)
+            } else {
+                None
+            };
+            values.push(ValueInfoProto {
+                name: self.name()?,
+                r#type: typ,
+                doc_string: String::new(),
+            });
+            if !self.eat(',') {
+                break;
+            }
+        }
+        Ok(values)
+    }
+
+    fn is_type_start(&self) -> bool {
+        match self.peek() {
+            Token::Ident(ident) => {
+                ident == "seq" || ident == "map" || data_type_by_name(ident).is_some()
+            }
+            _ => false,
+        }
+    }
+
+    fn type_proto(&mut self) -> Result<TypeProto, ParseError> {
+        let ident = self.ident()?;
+        let value = match ident.as_str() {
+            "seq" => {
+                self.expect('(')?;
+                let elem_type = self.type_proto()?;
+                self.expect(')')?;
+                type_proto::Value::SequenceType(Box::new(type_proto::Sequence {
+                    elem_type: Some(Box::new(elem_type)),
+                }))
+            }
+            "map" => {
+                self.expect('(')?;
+                let key_type = self.data_type()?;
+                self.expect(',')?;
+                let value_type = self.type_proto()?;
+                self.expect(')')?;
+                type_proto::Value::MapType(Box::new(type_proto::Map {
+                    key_type: key_type as i32,
+                    value_type: Some(Box::new(value_type)),
+                }))
+            }
+            _ => {
+                let elem_type = data_type_by_name(&ident)
+                    .ok_or_else(|| self.error_before(format!("unknown type `{}`", ident)))?;
+                let shape = if self.eat('[') {
+                    let mut dim = Vec::new();
+                    if !self.eat(']') {
+                        loop {
+                            dim.push(self.dimension()?);
+                            if !self.eat(',') {
+                                break;
+                            }
+                        }
+                        self.expect(']')?;
+                    }
+                    Some(TensorShapeProto { dim })
+                } else {
+                    None
+                };
+                type_proto::Value::TensorType(type_proto::Tensor {
+                    elem_type: elem_type as i32,
+                    shape,
+                })
+            }
+        };
+        Ok(TypeProto {
+            value: Some(value),
+            denotation: String::new(),
+        })
+    }
+
+    fn dimension(&mut self) -> Result<Dimension, ParseError> {
+        let value = match self.peek() {
+            Token::Punct('?') => {
+                self.next();
+                None
+            }
+            Token::Number(_) => Some(dimension::Value::DimValue(self.int()?)),
+            _ => Some(dimension::Value::DimParam(self.name()?)),
+        };
+        Ok(Dimension {
+            value,
+            denotation: String::new(),
+        })
+    }
+
+    fn data_type(&mut self) -> Result<DataType, ParseError> {
+        let ident = self.ident()?;
+        data_type_by_name(&ident)
+            .ok_or_else(|| self.error_before(format!("unknown type `{}`", ident)))
+    }
+
+    fn pairs<T, F>(&mut self, mut item: F) -> Result<Vec<T>, ParseError>
+    where
+        F: FnMut(&mut Self) -> Result<T, ParseError>,
+    {
+        let mut items = Vec::new();
+        self.expect('[')?;
+        if self.eat(']') {
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            if !self.eat(',') {
+                break;
+            }
+        }
+        self.expect(']')?;
+        Ok(items)
+    }
+
+    fn name(&mut self) -> Result<String, ParseError> {
+        match self.next() {
+            Token::Ident(ident) => Ok(ident),
+            Token::Str(bytes) => self.utf8(bytes),
+            token => Err(self.error_before(format!("expected name, found {}", token))),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, ParseError> {
+        match self.next() {
+            Token::Ident(ident) => Ok(ident),
+            token => Err(self.error_before(format!("expected identifier, found {}", token))),
+        }
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, ParseError> {
+        match self.next() {
+            Token::Str(bytes) => Ok(bytes),
+            token => Err(self.error_before(format!("expected string, found {}", token))),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let bytes = self.bytes()?;
+        self.utf8(bytes)
+    }
+
+    fn utf8(&self, bytes: Vec<u8>) -> Result<String, ParseError> {
+        String::from_utf8(bytes).map_err(|_| self.error_before("invalid UTF-8 string".to_owned()))
+    }
+
+    fn number(&mut self) -> Result<String, ParseError> {
+        match self.next() {
+            Token::Number(number) => Ok(number),
+            Token::Ident(ident) if ident == "inf" || ident == "nan" => Ok(ident),
+            token => Err(self.error_before(format!("expected number, found {}", token))),
+        }
+    }
+
+    fn int(&mut self) -> Result<i64, ParseError> {
+        let number = self.number()?;
+        number
+            .parse()
+            .map_err(|_| self.error_before(format!("invalid integer `{}`", number)))
+    }
+
+    fn float(&mut self) -> Result<f64, ParseError> {
+        let number = self.number()?;
+        number
+            .parse()
+            .map_err(|_| self.error_before(format!("invalid float `{}`", number)))
+    }
+
+    fn peek(&self) -> &Token {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> &Token {
+        let index = (self.pos + offset).min(self.tokens.len() - 1);
+        &self.tokens[index].token
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].token.clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == &Token::Punct(c) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`, found {}", c, self.peek())))
+        }
+    }
+
+    fn expect_eof(&mut self) -> Result<(), ParseError> {
+        match self.peek() {
+            Token::Eof => Ok(()),
+            token => Err(self.error(format!("expected end of input, found {}", token))),
+        }
+    }
+
+    /// Creates an error at the current token.
+    fn error(&self, message: String) -> ParseError {
+        let token = &self.tokens[self.pos];
+        ParseError {
+            line: token.line,
+            column: token.column,
+            message,
+        }
+    }
+
+    /// Creates an error at the previously consumed token.
+    fn error_before(&self, message: String) -> ParseError {
+        let token = &self.tokens[self.pos.saturating_sub(1)];
+        ParseError {
+            line: token.line,
+            column: token.column,
+            message,
+        }
+    }
+}
+
+fn is_float(number: &str) -> bool {
+    number.contains(['.', 'e', 'E', 'i', 'n'])
+}
+
+fn attribute_type_by_name(name: &str) -> Option<AttributeType> {
+    Some(match name {
+        "float" => AttributeType::Float,
+        "int" => AttributeType::Int,
+        "string" => AttributeType::String,
+        "tensor" => AttributeType::Tensor,
+        "graph" => AttributeType::Graph,
+        "floats" => AttributeType::Floats,
+        "ints" => AttributeType::Ints,
+        "strings" => AttributeType::Strings,
+        "tensors" => AttributeType::Tensors,
+        "graphs" => AttributeType::Graphs,
+        _ => return None,
+    })
+}
@@ -0,0 +1,426 @@
+//! Textual syntax printer.
+
+use std::fmt::Write;
+
+use onnx_pb::{
+    attribute_proto::AttributeType, tensor_proto::DataType, tensor_shape_proto::dimension,
+    type_proto, AttributeProto, GraphProto, ModelProto, NodeProto, TensorProto, TypeProto,
+    ValueInfoProto,
+};
+
+use super::{data_type_name, is_identifier};
+
+/// Textual syntax printer.
+#[derive(Default)]
+pub(super) struct Printer {
+    out: String,
+    indent: usize,
+}
+
+impl Printer {
+    pub fn finish(self) -> String {
+        self.out
+    }
+
+    pub fn model(&mut self, model: &ModelProto) {
+        let mut header = vec![
+            format!("ir_version: {}", model.ir_version),
+            format!(
+                "opset_import: [{}]",
+                model
+                    .opset_import
+                    .iter()
+                    .map(|opset| format!("{} : {}", quote(opset.domain.as_bytes()), opset.version))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ];
+        let strings = [
+            ("producer_name", &model.producer_name),
+            ("producer_version", &model.producer_version),
+            ("domain", &model.domain),
+        ];
+        for (key, value) in strings.iter() {
+            if !value.is_empty() {
+                header.push(format!("{}: {}", key, quote(value.as_bytes())));
+            }
+        }
+        if model.model_version != 0 {
+            header.push(format!("model_version: {}", model.model_version));
+        }
+        if !model.doc_string.is_empty() {
+            header.push(format!(
+                "doc_string: {}",
+                quote(model.doc_string.as_bytes())
+            ));
+        }
+        if !model.metadata_props.is_empty() {
+            header.push(format!(
+                "metadata_props: [{}]",
+                model
+                    .metadata_props
+                    .iter()
+                    .map(|prop| format!(
+                        "{} : {}",
+                        quote(prop.key.as_bytes()),
+                        quote(prop.value.as_bytes())
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        self.out.push_str("<\n");
+        self.out.push_str("  ");
+        self.out.push_str(&header.join(",\n  "));
+        self.out.push_str("\n>\n");
+        if let Some(graph) = model.graph.as_ref() {
+            self.graph(graph);
+        }
+    }
+
+    pub fn graph(&mut self, graph: &GraphProto) {
+        self.out.push_str(&name(&graph.name));
+        self.out.push_str(" (");
+        self.value_infos(&graph.input);
+        self.out.push_str(") => (");
+        self.value_infos(&graph.output);
+        self.out.push(')');
+        if !graph.initializer.is_empty() || !graph.value_info.is_empty() {
+            self.out.push_str(" <");
+            for (index, tensor) in graph.initializer.iter().enumerate() {
+                if index > 0 {
+                    self.out.push_str(", ");
+                }
+                self.tensor_type(tensor);
+                let _ = write!(self.out, " {} = ", name(&tensor.name));
+                self.tensor_values(tensor);
+            }
+            if !graph.initializer.is_empty() && !graph.value_info.is_empty() {
+                self.out.push_str(", ");
+            }
+            self.value_infos(&graph.value_info);
+            self.out.push('>');
+        }
+        self.out.push_str(" {\n");
+        self.indent += 1;
+        for node in graph.node.iter() {
+            self.node(node);
+        }
+        self.indent -= 1;
+        self.write_indent();
+        self.out.push('}');
+        if self.indent == 0 {
+            self.out.push('\n');
+        }
+    }
+
+    fn node(&mut self, node: &NodeProto) {
+        self.write_indent();
+        if !node.name.is_empty() {
+            let _ = write!(self.out, "[{}] ", name(&node.name));
+        }
+        if !node.output.is_empty() {
+            self.out.push_str(&names(&node.output));
+            self.out.push(' ');
+        }
+        self.out.push_str("= ");
+        if !node.domain.is_empty() {
+            self.out.push_str(&node.domain);
+            self.out.push('.');
+        }
+        self.out.push_str(&node.op_type);
+        if !node.attribute.is_empty() {
+            self.out.push_str(" <");
+            for (index, attr) in node.attribute.iter().enumerate() {
+                if index > 0 {
+                    self.out.push_str(", ");
+                }
+                self.attribute(attr);
+            }
+            self.out.push('>');
+        }
+        let _ = writeln!(self.out, " ({})", names(&node.input));
+    }
+
+    fn attribute(&mut self, attr: &AttributeProto) {
+        self.out.push_str(&name(&attr.name));
+        let typ = AttributeType::from_i32(attr.r#type).unwrap_or(AttributeType::Undefined);
+        if !attr.ref_attr_name.is_empty() {
+            let _ = write!(
+                self.out,
+                ": {} = @{}",
+                attribute_type_name(typ),
+                name(&attr.ref_attr_name)
+            );
+            return;
+        }
+        let empty = match typ {
+            AttributeType::Floats => attr.floats.is_empty(),
+            AttributeType::Ints => attr.ints.is_empty(),
+            AttributeType::Strings => attr.strings.is_empty(),
+            AttributeType::Tensors => attr.tensors.is_empty(),
+            AttributeType::Graphs => attr.graphs.is_empty(),
+            _ => false,
+        };
+        if empty {
+            let _ = write!(self.out, ": {}", attribute_type_name(typ));
+        }
+        self.out.push_str(" = ");
+        match typ {
+            AttributeType::Float => self.out.push_str(&float(attr.f)),
+            AttributeType::Int => {
+                let _ = write!(self.out, "{}", attr.i);
+            }
+            AttributeType::String => self.out.push_str(&quote(&attr.s)),
+            AttributeType::Tensor => {
+                let tensor = attr.t.clone().unwrap_or_default();
+                self.tensor(&tensor);
+            }
+            AttributeType::Graph => {
+                let graph = attr.g.clone().unwrap_or_default();
+                self.graph(&graph);
+            }
+            AttributeType::Floats => {
+                let floats = attr.floats.iter().map(|v| float(*v));
+                self.out.push_str(&list(floats));
+            }
+            AttributeType::Ints => {
+                let ints = attr.ints.iter().map(|v| v.to_string());
+                self.out.push_str(&list(ints));
+            }
+            AttributeType::Strings => {
+                let strings = attr.strings.iter().map(|v| quote(v));
+                self.out.push_str(&list(strings));
+            }
+            AttributeType::Tensors => {
+                self.out.push('[');
+                for (index, tensor) in attr.tensors.iter().enumerate() {
+                    if index > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.tensor(tensor);
+                }
+                self.out.push(']');
+            }
+            AttributeType::Graphs => {
+                self.out.push('[');
+                for (index, graph) in attr.graphs.iter().enumerate() {
+                    if index > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.graph(graph);
+                }
+                self.out.push(']');
+            }
+            AttributeType::Undefined
+            | AttributeType::SparseTensor
+            | AttributeType::SparseTensors => self.out.push_str("[]"),
+        }
+    }
+
+    fn tensor(&mut self, tensor: &TensorProto) {
+        self.tensor_type(tensor);
+        self.out.push(' ');
+        if !tensor.name.is_empty() {
+            self.out.push_str(&name(&tensor.name));
+            self.out.push(' ');
+        }
+        self.tensor_values(tensor);
+    }
+
+    fn tensor_type(&mut self, tensor: &TensorProto) {
+        self.out
+            .push_str(data_type_name(tensor.data_type).unwrap_or("undefined"));
+        let dims = tensor.dims.iter().map(|dim| dim.to_string());
+        self.out.push_str(&list(dims).replace(", ", ","));
+    }
+
+    fn tensor_values(&mut self, tensor: &TensorProto) {
+        self.out.push('{');
+        self.out.push_str(&tensor_values(tensor).join(", "));
+        self.out.push('}');
+    }
+
+    fn value_infos(&mut self, values: &[ValueInfoProto]) {
+        for (index, value) in values.iter().enumerate() {
+            if index > 0 {
+                self.out.push_str(", ");
+            }
+            if let Some(typ) = value.r#type.as_ref() {
+                self.out.push_str(&type_proto(typ));
+                self.out.push(' ');
+            }
+            self.out.push_str(&name(&value.name));
+        }
+    }
+
+    fn write_indent(&mut self) {
+        for _ in 0..self.indent {
+            self.out.push_str("  ");
+        }
+    }
+}
+
+/// Formats a value type.
+pub(crate) fn type_proto(typ: &TypeProto) -> String {
+    match typ.value.as_ref() {
+        Some(type_proto::Value::TensorType(tensor)) => {
+            let mut out = data_type_name(tensor.elem_type)
+                .unwrap_or("undefined")
+                .to_owned();
+            if let Some(shape) = tensor.shape.as_ref() {
+                let dims = shape.dim.iter().map(|dim| match dim.value.as_ref() {
+                    Some(dimension::Value::DimValue(value)) => value.to_string(),
+                    Some(dimension::Value::DimParam(param)) => name(param),
+                    None => "?".to_owned(),
+                });
+                out.push_str(&list(dims).replace(", ", ","));
+            }
+            out
+        }
+        Some(type_proto::Value::SequenceType(seq)) => format!(
+            "seq({})",
+            seq.elem_type
+                .as_ref()
+                .map(|typ| type_proto(typ))
+                .unwrap_or_default()
+        ),
+        Some(type_proto::Value::MapType(map)) => format!(
+            "map({}, {})",
+            data_type_name(map.key_type).unwrap_or("undefined"),
+            map.value_type
+                .as_ref()
+                .map(|typ| type_proto(typ))
+                .unwrap_or_default()
+        ),
+        None => String::new(),
+    }
+}
+
+/// Returns decoded tensor values formatted for printing.
+pub(crate) fn tensor_values(tensor: &TensorProto) -> Vec<String> {
+    let raw = &tensor.raw_data;
+    let typ = DataType::from_i32(tensor.data_type).unwrap_or(DataType::Undefined);
+    macro_rules! decode {
+        ( $field:ident, $t:ty, $fmt:expr ) => {
+            if raw.is_empty() {
+                tensor.$field.iter().map(|v| $fmt(*v)).collect()
+            } else {
+                const SIZE: usize = std::mem::size_of::<$t>();
+                raw.chunks_exact(SIZE)
+                    .map(|chunk| {
+                        let mut bytes = [0u8; SIZE];
+                        bytes.copy_from_slice(chunk);
+                        $fmt(<$t>::from_le_bytes(bytes))
+                    })
+                    .collect()
+            }
+        };
+    }
+    match typ {
+        DataType::Float | DataType::Complex64 => decode!(float_data, f32, float),
+        DataType::Double | DataType::Complex128 => decode!(double_data, f64, float),
+        DataType::Int8 => decode!(int32_data, i8, |v| format!("{}", v)),
+        DataType::Uint8 | DataType::Bool => decode!(int32_data, u8, |v| format!("{}", v)),
+        DataType::Int16 => decode!(int32_data, i16, |v| format!("{}", v)),
+        DataType::Uint16 | DataType::Float16 | DataType::Bfloat16 => {
+            decode!(int32_data, u16, |v| format!("{}", v))
+        }
+        DataType::Int32 => decode!(int32_data, i32, |v| format!("{}", v)),
+        DataType::Int64 => decode!(int64_data, i64, |v| format!("{}", v)),
+        DataType::Uint32 => decode!(uint64_data, u32, |v| format!("{}", v)),
+        DataType::Uint64 => decode!(uint64_data, u64, |v| format!("{}", v)),
+        DataType::String => tensor.string_data.iter().map(|s| quote(s)).collect(),
+        DataType::Undefined => Vec::new(),
+    }
+}
+
+fn attribute_type_name(typ: AttributeType) -> &'static str {
+    match typ {
+        AttributeType::Undefined => "undefined",
+        AttributeType::Float => "float",
+        AttributeType::Int => "int",
+        AttributeType::String => "string",
+        AttributeType::Tensor => "tensor",
+        AttributeType::Graph => "graph",
+        AttributeType::SparseTensor => "sparse_tensor",
+        AttributeType::Floats => "floats",
+        AttributeType::Ints => "ints",
+        AttributeType::Strings => "strings",
+        AttributeType::Tensors => "tensors",
+        AttributeType::Graphs => "graphs",
+        AttributeType::SparseTensors => "sparse_tensors",
+    }
+}
+
+fn list<I: Iterator<Item = String>>(items: I) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(", "))
+}
+
+fn names(names: &[String]) -> String {
+    names.iter().map(|s| name(s)).collect::<Vec<_>>().join(", ")
+}
+
+/// Formats a name, quoting it if it's not an identifier.
+pub(crate) fn name(name: &str) -> String {
+    if is_identifier(name) {
+        name.to_owned()
+    } else {
+        quote(name.as_bytes())
+    }
+}
+
+/// Formats a float so that it parses back to the same value.
+fn float<T: Into<f64> + std::fmt::Debug + Copy>(value: T) -> String {
+    let wide: f64 = value.into();
+    if wide.is_nan() {
+        "nan".to_owned()
+    } else if wide.is_infinite() {
+        if wide > 0.0 { "inf" } else { "-inf" }.to_owned()
+    } else {
+        let text = format!("{:?}", value);
+        if text.contains(['.', 'e']) {
+            text
+        } else {
+            format!("{}.0", text)
+        }
+    }
+}
+
+/// Formats bytes as a quoted string literal.
+pub(crate) fn quote(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() + 2);
+    out.push('"');
+    match std::str::from_utf8(bytes) {
+        Ok(text) => {
+            for c in text.chars() {
+                escape(&mut out, c);
+            }
+        }
+        Err(_) => {
+            for b in bytes {
+                if b.is_ascii() {
+                    escape(&mut out, *b as char);
+                } else {
+                    let _ = write!(out, "\\x{:02x}", b);
+                }
+            }
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn escape(out: &mut String, c: char) {
+    match c {
+        '"' => out.push_str("\\\""),
+        '\\' => out.push_str("\\\\"),
+        '\n' => out.push_str("\\n"),
+        '\t' => out.push_str("\\t"),
+        '\r' => out.push_str("\\r"),
+        c if (c as u32) < 0x20 || c as u32 == 0x7f => {
+            let _ = write!(out, "\\x{:02x}", c as u32);
+        }
+        c => out.push(c),
+    }
+}
@@ -0,0 +1,166 @@
+//! Graph traversal and editing helpers.
+
+use std::collections::HashSet;
+
+use onnx_pb::{GraphProto, NodeProto};
+
+/// Returns graph attributes of a node.
+pub fn subgraphs(node: &NodeProto) -> Vec<&GraphProto> {
+    node.attribute
+        .iter()
+        .flat_map(|attr| attr.g.iter().chain(attr.graphs.iter()))
+        .collect()
+}
+
+/// Returns mutable graph attributes of a node.
+pub fn subgraphs_mut(node: &mut NodeProto) -> Vec<&mut GraphProto> {
+    node.attribute
+        .iter_mut()
+        .flat_map(|attr| attr.g.iter_mut().chain(attr.graphs.iter_mut()))
+        .collect()
+}
+
+/// Returns names of values used in a graph which are not defined in it.
+///
+/// These are values captured from an outer scope by subgraphs of nodes such
+/// as `If` and `Loop`, or graph inputs missing from a graph declaration.
+pub fn outer_references(graph: &GraphProto) -> HashSet<String> {
+    let mut defined: HashSet<&str> = graph
+        .input
+        .iter()
+        .map(|value| value.name.as_str())
+        .chain(graph.initializer.iter().map(|tensor| tensor.name.as_str()))
+        .collect();
+    let mut references = HashSet::new();
+    for node in graph.node.iter() {
+        for name in node_references(node) {
+            if !defined.contains(name.as_str()) {
+                references.insert(name);
+            }
+        }
+        defined.extend(node.output.iter().map(String::as_str));
+    }
+    for output in graph.output.iter() {
+        if !defined.contains(output.name.as_str()) {
+            references.insert(output.name.clone());
+        }
+    }
+    references
+}
+
+/// Returns names of values declared, produced or used in a graph, not
+/// including its subgraphs.
+pub(crate) fn value_names(graph: &GraphProto) -> HashSet<String> {
+    let mut names: HashSet<String> = graph
+        .input
+        .iter()
+        .chain(graph.output.iter())
+        .chain(graph.value_info.iter())
+        .map(|value| value.name.clone())
+        .chain(graph.initializer.iter().map(|tensor| tensor.name.clone()))
+        .collect();
+    for node in graph.node.iter() {
+        names.extend(node.input.iter().chain(node.output.iter()).cloned());
+    }
+    names
+}
+
+/// Returns names of values used by a node, including values used by its
+/// subgraphs from an outer scope.
+pub fn node_references(node: &NodeProto) -> Vec<String> {
+    let mut names: Vec<String> = node
+        .input
+        .iter()
+        .filter(|name| !name.is_empty())
+        .cloned()
+        .collect();
+    for graph in subgraphs(node) {
+        names.extend(outer_references(graph));
+    }
+    names
+}
+
+/// Renames a value everywhere in a graph, including nested subgraphs.
+///
+/// Subgraphs which declare a value of the same name shadow it and are left
+/// unchanged from that point on.
+pub fn rename_value(graph: &mut GraphProto, from: &str, to: &str) {
+    for value in graph
+        .input
+        .iter_mut()
+        .chain(graph.output.iter_mut())
+        .chain(graph.value_info.iter_mut())
+    {
+        if value.name == from {
+            value.name = to.to_owned();
+        }
+    }
+    for tensor in graph.initializer.iter_mut() {
+        if tensor.name == from {
+            tensor.name = to.to_owned();
+        }
+    }
+    for node in graph.node.iter_mut() {
+        rename_node_inputs(node, from, to);
+        for output in node.output.iter_mut() {
+            if output == from {
+                *output = to.to_owned();
+            }
+        }
+    }
+}
+
+/// Renames a value used by a node and its subgraphs.
+pub(crate) fn rename_node_inputs(node: &mut NodeProto, from: &str, to: &str) {
+    for input in node.input.iter_mut() {
+        if input == from {
+            *input = to.to_owned();
+        }
+    }
+    for graph in subgraphs_mut(node) {
+        let shadowed = graph.input.iter().any(|value| value.name == from)
+            || graph.initializer.iter().any(|tensor| tensor.name == from)
+            || graph
+                .node
+                .iter()
+                .any(|node| node.output.iter().any(|output| output == from));
+        if !shadowed {
+            rename_value(graph, from, to);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::text::parse_graph;
+
+    #[test]
+    fn rename_captured_value() {
+        let mut graph = parse_graph(
+            r#"
+            cond (bool C, float X) => (float Z) {
+                Z = If <
+                    then_branch = then (float Y) => (float T) { T = Add (Y, X) },
+                    else_branch = else () => (float X) { X = Neg (C) }
+                > (C)
+            }
+            "#,
+        )
+        .unwrap();
+        assert!(outer_references(&graph).is_empty());
+        let references: Vec<_> = subgraphs(&graph.node[0])
+            .into_iter()
+            .map(outer_references)
+            .collect();
+        assert_eq!(references[0], vec!["X".to_owned()].into_iter().collect());
+        assert_eq!(references[1], vec!["C".to_owned()].into_iter().collect());
+
+        rename_value(&mut graph, "X", "input");
+        assert_eq!(graph.input[1].name, "input");
+        let branches = subgraphs(&graph.node[0]);
+        assert_eq!(branches[0].node[0].input, vec!["Y", "input"]);
+        assert_eq!(branches[1].output[0].name, "X");
+    }
+}
@@ -0,0 +1,575 @@
+//! Weight loading.
+//!
+//! Fills initializers of a [`builder::Graph`](../builder/struct.Graph.html)
+//! with tensors read from `.safetensors` files, with the `safetensors`
+//! feature, or `.npy` and `.npz` files, with the `npy` feature.
+//!
+//! Declared values are initializers inserted into the graph, e.g. by
+//! [`nn`](../nn/index.html) layers, and typed graph inputs. Loaded tensors
+//! replace initializers of the same name and have to match their element
+//! type and shape.
+
+use std::collections::HashSet;
+#[cfg(any(feature = "safetensors", feature = "npy"))]
+use std::path::Path;
+
+use onnx_pb::{tensor_proto::DataType, tensor_shape_proto::dimension, type_proto, TensorProto};
+
+use crate::builder::Graph;
+
+/// Weight loading error.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// File could not be read.
+    Io(String),
+    /// File is malformed or holds unsupported tensors.
+    Format(String),
+    /// Element type of a tensor does not match the declared value.
+    DataType {
+        /// Name of the declared value.
+        name: String,
+        /// Element type of the declared value.
+        expected: DataType,
+        /// Element type of the loaded tensor.
+        found: DataType,
+    },
+    /// Shape of a tensor does not match the declared value.
+    Shape {
+        /// Name of the declared value.
+        name: String,
+        /// Dimensions of the declared value, `None` if unknown.
+        expected: Vec<Option<i64>>,
+        /// Dimensions of the loaded tensor.
+        found: Vec<i64>,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(message) => write!(f, "can not read weights: {}", message),
+            Error::Format(message) => write!(f, "invalid weights: {}", message),
+            Error::DataType {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{}: expected tensor of type {:?}, found {:?}",
+                name, expected, found
+            ),
+            Error::Shape {
+                name,
+                expected,
+                found,
+            } => {
+                let expected: Vec<String> = expected
+                    .iter()
+                    .map(|dim| dim.map_or_else(|| "?".to_owned(), |dim| dim.to_string()))
+                    .collect();
+                write!(
+                    f,
+                    "{}: expected tensor of shape [{}], found {:?}",
+                    name,
+                    expected.join(", "),
+                    found
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err.to_string())
+    }
+}
+
+/// Outcome of loading weights into a graph.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    /// Declared values filled with loaded tensors.
+    pub loaded: Vec<String>,
+    /// Declared initializers which were not loaded.
+    pub missing: Vec<String>,
+    /// Names of loaded tensors which do not match any declared value.
+    pub unused: Vec<String>,
+}
+
+impl Report {
+    /// Returns true if all initializers were loaded and all tensors used.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty() && self.unused.is_empty()
+    }
+}
+
+/// Weight loader.
+///
+/// Collects named tensors from files and loads them into a graph:
+///
+/// ```ignore
+/// let report = weights::Loader::new()
+///     .rename(|name| name.strip_prefix("model.").map(String::from))
+///     .safetensors("model.safetensors")?
+///     .load(&mut graph)?;
+/// assert!(report.is_complete());
+/// ```
+#[derive(Default)]
+pub struct Loader {
+    tensors: Vec<TensorProto>,
+    rename: Option<Rename>,
+}
+
+/// Mapping of tensor names to names of declared values.
+type Rename = Box<dyn Fn(&str) -> Option<String>>;
+
+impl Loader {
+    /// Creates a new loader.
+    #[inline]
+    pub fn new() -> Self {
+        Loader::default()
+    }
+
+    /// Inserts named tensor.
+    #[inline]
+    pub fn tensor<T: Into<TensorProto>>(mut self, tensor: T) -> Self {
+        self.tensors.push(tensor.into());
+        self
+    }
+
+    /// Sets mapping of tensor names to names of declared values.
+    ///
+    /// Tensors mapped to `None` are skipped and reported as unused.
+    #[inline]
+    pub fn rename<F: Fn(&str) -> Option<String> + 'static>(mut self, rename: F) -> Self {
+        self.rename = Some(Box::new(rename));
+        self
+    }
+
+    /// Inserts tensors of a `.safetensors` file.
+    #[cfg(feature = "safetensors")]
+    pub fn safetensors<P: AsRef<Path>>(mut self, path: P) -> Result<Self, Error> {
+        let bytes = std::fs::read(path)?;
+        self.tensors.extend(read_safetensors(&bytes)?);
+        Ok(self)
+    }
+
+    /// Inserts tensor of a `.npy` file named after the file stem.
+    #[cfg(feature = "npy")]
+    pub fn npy<P: AsRef<Path>>(mut self, path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| Error::Format(format!("{:?} is not a valid tensor name", path)))?;
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        self.tensors.push(read_npy(name, file)?);
+        Ok(self)
+    }
+
+    /// Inserts tensors of a `.npz` archive.
+    #[cfg(feature = "npy")]
+    pub fn npz<P: AsRef<Path>>(mut self, path: P) -> Result<Self, Error> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        self.tensors.extend(read_npz(file)?);
+        Ok(self)
+    }
+
+    /// Loads tensors into initializers of declared values of a graph.
+    ///
+    /// Fails on the first tensor which does not match its declared value,
+    /// leaving the graph unchanged.
+    pub fn load(self, graph: &mut Graph) -> Result<Report, Error> {
+        let mut declared: Vec<Declared> = graph
+            .initializers_mut()
+            .iter()
+            .map(|tensor| Declared {
+                name: tensor.name.clone(),
+                data_type: DataType::from_i32(tensor.data_type).unwrap_or_default(),
+                shape: Some(tensor.dims.iter().map(|dim| Some(*dim)).collect()),
+                initializer: true,
+            })
+            .collect();
+        declared.extend(graph.declared_inputs().into_iter().filter_map(|input| {
+            match input.r#type.and_then(|typ| typ.value) {
+                Some(type_proto::Value::TensorType(tensor)) => Some(Declared {
+                    name: input.name,
+                    data_type: DataType::from_i32(tensor.elem_type).unwrap_or_default(),
+                    shape: tensor.shape.map(|shape| {
+                        shape
+                            .dim
+                            .into_iter()
+                            .map(|dim| match dim.value {
+                                Some(dimension::Value::DimValue(value)) => Some(value),
+                                _ => None,
+                            })
+                            .collect()
+                    }),
+                    initializer: false,
+                }),
+                _ => None,
+            }
+        }));
+
+        let mut report = Report::default();
+        let mut matched = Vec::new();
+        for mut tensor in self.tensors {
+            let name = match self.rename.as_ref() {
+                Some(rename) => rename(&tensor.name),
+                None => Some(tensor.name.clone()),
+            };
+            let value = name.and_then(|name| declared.iter().find(|value| value.name == name));
+            let value = match value {
+                Some(value) => value,
+                None => {
+                    report.unused.push(tensor.name);
+                    continue;
+                }
+            };
+            value.check(&tensor)?;
+            tensor.name = value.name.clone();
+            matched.push(tensor);
+        }
+
+        let mut loaded = HashSet::new();
+        let initializers = graph.initializers_mut();
+        for tensor in matched {
+            if loaded.insert(tensor.name.clone()) {
+                report.loaded.push(tensor.name.clone());
+            }
+            match initializers
+                .iter_mut()
+                .find(|init| init.name == tensor.name)
+            {
+                Some(init) => *init = tensor,
+                None => initializers.push(tensor),
+            }
+        }
+        report.missing = declared
+            .into_iter()
+            .filter(|value| value.initializer && !loaded.contains(&value.name))
+            .map(|value| value.name)
+            .collect();
+        Ok(report)
+    }
+}
+
+/// Value declared in a graph.
+struct Declared {
+    name: String,
+    data_type: DataType,
+    shape: Option<Vec<Option<i64>>>,
+    initializer: bool,
+}
+
+impl Declared {
+    /// Checks that tensor matches element type and known dimensions.
+    fn check(&self, tensor: &TensorProto) -> Result<(), Error> {
+        let data_type = DataType::from_i32(tensor.data_type).unwrap_or_default();
+        if self.data_type != DataType::Undefined && self.data_type != data_type {
+            return Err(Error::DataType {
+                name: self.name.clone(),
+                expected: self.data_type,
+                found: data_type,
+            });
+        }
+        if let Some(shape) = self.shape.as_ref() {
+            let matches = shape.len() == tensor.dims.len()
+                && shape
+                    .iter()
+                    .zip(tensor.dims.iter())
+                    .all(|(expected, found)| expected.map_or(true, |dim| dim == *found));
+            if !matches {
+                return Err(Error::Shape {
+                    name: self.name.clone(),
+                    expected: shape.clone(),
+                    found: tensor.dims.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads tensors of a `.safetensors` file, sorted by name.
+#[cfg(feature = "safetensors")]
+pub fn read_safetensors(bytes: &[u8]) -> Result<Vec<TensorProto>, Error> {
+    use safetensors::{tensor::Dtype, SafeTensors};
+
+    let file = SafeTensors::deserialize(bytes).map_err(|err| Error::Format(err.to_string()))?;
+    let mut tensors = Vec::with_capacity(file.len());
+    for (name, view) in file.tensors() {
+        let data_type = match view.dtype() {
+            Dtype::BOOL => DataType::Bool,
+            Dtype::U8 => DataType::Uint8,
+            Dtype::I8 => DataType::Int8,
+            Dtype::I16 => DataType::Int16,
+            Dtype::U16 => DataType::Uint16,
+            Dtype::F16 => DataType::Float16,
+            Dtype::BF16 => DataType::Bfloat16,
+            Dtype::I32 => DataType::Int32,
+            Dtype::U32 => DataType::Uint32,
+            Dtype::F32 => DataType::Float,
+            Dtype::F64 => DataType::Double,
+            Dtype::I64 => DataType::Int64,
+            Dtype::U64 => DataType::Uint64,
+            dtype => {
+                return Err(Error::Format(format!(
+                    "{}: unsupported type {:?}",
+                    name, dtype
+                )))
+            }
+        };
+        let mut tensor = crate::tensor::encode(
+            data_type,
+            view.shape(),
+            view.data().to_vec(),
+            crate::tensor::Encoding::Raw,
+        );
+        tensor.name = name;
+        tensors.push(tensor);
+    }
+    tensors.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(tensors)
+}
+
+/// Reads tensor of a `.npy` file stored in C order.
+#[cfg(feature = "npy")]
+pub fn read_npy<S: Into<String>, R: std::io::Read>(
+    name: S,
+    mut reader: R,
+) -> Result<TensorProto, Error> {
+    use std::io::Read;
+
+    use npyz::{DType, Endianness, NpyHeader, Order, TypeChar};
+
+    let name = name.into();
+    let header = NpyHeader::from_reader(&mut reader)?;
+    let format = |message: &str| Error::Format(format!("{}: {}", name, message));
+    if header.order() != Order::C {
+        return Err(format("fortran order is not supported"));
+    }
+    let type_str = match header.dtype() {
+        DType::Plain(type_str) => type_str,
+        _ => return Err(format("structured arrays are not supported")),
+    };
+    let data_type = match (type_str.type_char(), type_str.size_field()) {
+        (TypeChar::Bool, 1) => DataType::Bool,
+        (TypeChar::Int, 1) => DataType::Int8,
+        (TypeChar::Int, 2) => DataType::Int16,
+        (TypeChar::Int, 4) => DataType::Int32,
+        (TypeChar::Int, 8) => DataType::Int64,
+        (TypeChar::Uint, 1) => DataType::Uint8,
+        (TypeChar::Uint, 2) => DataType::Uint16,
+        (TypeChar::Uint, 4) => DataType::Uint32,
+        (TypeChar::Uint, 8) => DataType::Uint64,
+        (TypeChar::Float, 2) => DataType::Float16,
+        (TypeChar::Float, 4) => DataType::Float,
+        (TypeChar::Float, 8) => DataType::Double,
+        _ => return Err(format(&format!("unsupported type {}", type_str))),
+    };
+    let size = type_str.size_field() as usize;
+    let len = header.len() as usize * size;
+    let mut raw = Vec::with_capacity(len);
+    reader.take(len as u64).read_to_end(&mut raw)?;
+    if raw.len() != len {
+        return Err(format("data is truncated"));
+    }
+    if type_str.endianness() == Endianness::Big {
+        raw.chunks_mut(size).for_each(|value| value.reverse());
+    }
+    let shape: Vec<usize> = header.shape().iter().map(|dim| *dim as usize).collect();
+    let mut tensor = crate::tensor::encode(data_type, &shape, raw, crate::tensor::Encoding::Raw);
+    tensor.name = name;
+    Ok(tensor)
+}
+
+/// Reads tensors of a `.npz` archive, in order of the archive.
+#[cfg(feature = "npy")]
+pub fn read_npz<R: std::io::Read + std::io::Seek>(reader: R) -> Result<Vec<TensorProto>, Error> {
+    use npyz::npz::{file_name_from_array_name, NpzArchive};
+
+    let mut archive = NpzArchive::new(reader)?;
+    let names: Vec<String> = archive.array_names().map(String::from).collect();
+    let mut tensors = Vec::with_capacity(names.len());
+    for name in names {
+        let file = archive
+            .zip_archive()
+            .by_name(&file_name_from_array_name(&name))
+            .map_err(|err| Error::Format(err.to_string()))?;
+        tensors.push(read_npy(name, file)?);
+    }
+    Ok(tensors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{builder::Tensor, tensor};
+
+    fn tensor_named(name: &str, dims: &[usize], values: &[f32]) -> TensorProto {
+        Tensor::from_slice(dims, values).unwrap().name(name).build()
+    }
+
+    #[test]
+    fn load_tensors() {
+        let mut graph = Graph::new("weights");
+        graph.parameter("encoder.weight", tensor_named("", &[2, 2], &[0.0; 4]));
+        graph.parameter("encoder.scale", tensor_named("", &[2], &[1.0; 2]));
+        graph
+            .input("bias")
+            .typed(DataType::Float)
+            .dim_param("n")
+            .node();
+        let report = Loader::new()
+            .tensor(tensor_named(
+                "model.encoder.weight",
+                &[2, 2],
+                &[1.0, 2.0, 3.0, 4.0],
+            ))
+            .tensor(tensor_named("model.bias", &[3], &[0.5; 3]))
+            .tensor(tensor_named("model.extra", &[1], &[0.0]))
+            .tensor(tensor_named("other", &[1], &[0.0]))
+            .rename(|name| {
+                name.strip_prefix("model.")
+                    .filter(|name| *name != "extra")
+                    .map(String::from)
+            })
+            .load(&mut graph)
+            .unwrap();
+        assert_eq!(
+            report,
+            Report {
+                loaded: vec!["encoder.weight".to_owned(), "bias".to_owned()],
+                missing: vec!["encoder.scale".to_owned()],
+                unused: vec!["model.extra".to_owned(), "other".to_owned()],
+            }
+        );
+        assert!(!report.is_complete());
+        let graph = graph.build();
+        assert_eq!(graph.initializer.len(), 3);
+        assert_eq!(graph.initializer[0].name, "encoder.weight");
+        assert_eq!(
+            tensor::to_vec::<f32>(&graph.initializer[0]).unwrap(),
+            vec![1.0, 2.0, 3.0, 4.0]
+        );
+        assert_eq!(graph.initializer[2].name, "bias");
+    }
+
+    #[test]
+    fn mismatch() {
+        let mut graph = Graph::new("weights");
+        graph.parameter("w", tensor_named("", &[2, 2], &[0.0; 4]));
+        let err = Loader::new()
+            .tensor(Tensor::from_slice(&[2, 2], &[0i64; 4]).unwrap().name("w"))
+            .load(&mut graph)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "w: expected tensor of type Float, found Int64"
+        );
+        graph
+            .input("x")
+            .typed(DataType::Float)
+            .dim_param("n")
+            .dim(3)
+            .node();
+        let err = Loader::new()
+            .tensor(tensor_named("x", &[2, 2], &[0.0; 4]))
+            .load(&mut graph)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "x: expected tensor of shape [?, 3], found [2, 2]"
+        );
+
+        // nothing is loaded unless all tensors match
+        let err = Loader::new()
+            .tensor(tensor_named("w", &[2, 2], &[1.0; 4]))
+            .tensor(tensor_named("x", &[2, 2], &[0.0; 4]))
+            .load(&mut graph)
+            .unwrap_err();
+        assert!(matches!(err, Error::Shape { .. }));
+        let graph = graph.build();
+        assert_eq!(graph.initializer.len(), 1);
+        assert_eq!(
+            tensor::to_vec::<f32>(&graph.initializer[0]).unwrap(),
+            vec![0.0; 4]
+        );
+    }
+
+    #[cfg(feature = "safetensors")]
+    #[test]
+    fn safetensors() {
+        use safetensors::tensor::{Dtype, TensorView};
+
+        let weight: Vec<u8> = [1.5f32, -2.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let ids: Vec<u8> = [7i64].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let bytes = safetensors::serialize(
+            vec![
+                (
+                    "w",
+                    TensorView::new(Dtype::F32, vec![1, 2], &weight).unwrap(),
+                ),
+                ("ids", TensorView::new(Dtype::I64, vec![1], &ids).unwrap()),
+            ],
+            &None,
+        )
+        .unwrap();
+        let tensors = read_safetensors(&bytes).unwrap();
+        assert_eq!(tensors[0].name, "ids");
+        assert_eq!(tensor::to_vec::<i64>(&tensors[0]).unwrap(), vec![7]);
+        assert_eq!(tensors[1].name, "w");
+        assert_eq!(tensors[1].dims, vec![1, 2]);
+        assert_eq!(tensor::to_vec::<f32>(&tensors[1]).unwrap(), vec![1.5, -2.0]);
+        assert!(read_safetensors(&bytes[..12]).is_err());
+    }
+
+    #[cfg(feature = "npy")]
+    #[test]
+    fn npy() {
+        use npyz::WriterBuilder;
+
+        let mut header = "{'descr': '>i4', 'fortran_order': False, 'shape': (3,), }".to_owned();
+        while (header.len() + 11) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend((header.len() as u16).to_le_bytes().iter());
+        bytes.extend(header.bytes());
+        for value in [1i32, -2, 300].iter() {
+            bytes.extend(value.to_be_bytes().iter());
+        }
+        let tensor = read_npy("counts", bytes.as_slice()).unwrap();
+        assert_eq!(tensor.name, "counts");
+        assert_eq!(tensor::to_vec::<i32>(&tensor).unwrap(), vec![1, -2, 300]);
+        assert!(read_npy("counts", &bytes[..bytes.len() - 1]).is_err());
+
+        let mut archive = std::io::Cursor::new(Vec::new());
+        {
+            let mut npz = npyz::npz::NpzWriter::new(&mut archive);
+            let mut writer = npz
+                .array::<f64>("scale", Default::default())
+                .unwrap()
+                .default_dtype()
+                .shape(&[2, 1])
+                .begin_nd()
+                .unwrap();
+            writer.extend(vec![0.25, 4.0]).unwrap();
+            writer.finish().unwrap();
+        }
+        archive.set_position(0);
+        let tensors = read_npz(archive).unwrap();
+        assert_eq!(tensors.len(), 1);
+        assert_eq!(tensors[0].name, "scale");
+        assert_eq!(tensors[0].dims, vec![2, 1]);
+        assert_eq!(tensor::to_vec::<f64>(&tensors[0]).unwrap(), vec![0.25, 4.0]);
+    }
+}
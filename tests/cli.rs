@@ -0,0 +1,190 @@
+//! Runs the command-line tool on a sample model.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use onnx_helpers::{checker, text};
+use onnx_pb::ModelProto;
+
+const MODEL: &str = "tests/mean-reverse.onnx";
+const OUTPUT: &str =
+    "SNeg_X_Sub_SReduceMean_X_ReduceMean_axes_ints_1_keepdims_int_1EO_O_Neg_EO_Mul_twoO_O_Add_X_O";
+const CENTERED: &str = "X_Sub_SReduceMean_X_ReduceMean_axes_ints_1_keepdims_int_1EO_O";
+
+fn execute(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_onnx-helpers"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Runs the tool expecting success and returns its standard output.
+fn run(args: &[&str]) -> String {
+    let output = execute(args);
+    assert!(
+        output.status.success(),
+        "{:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Returns path of an output file in textual syntax.
+fn output_path(name: &str) -> String {
+    let path: PathBuf = [env!("CARGO_TARGET_TMPDIR"), &format!("{}.txt", name)]
+        .iter()
+        .collect();
+    path.to_str().unwrap().to_owned()
+}
+
+/// Reads a model written by the tool and checks that it is valid.
+fn read(path: &str) -> ModelProto {
+    let model = text::parse_model(&std::fs::read_to_string(path).unwrap()).unwrap();
+    assert_eq!(checker::check_model(&model), Ok(()));
+    model
+}
+
+#[test]
+fn info() {
+    let stdout = run(&["info", MODEL]);
+    assert!(stdout.contains("opset_import: \"\" 11"));
+    assert!(stdout.contains("X: float[1,6]"));
+    assert!(stdout.contains("nodes: 6"));
+}
+
+#[test]
+fn print() {
+    let stdout = run(&["print", MODEL]);
+    let model = text::parse_model(&stdout).unwrap();
+    assert_eq!(model.graph.unwrap().node.len(), 6);
+
+    let path = output_path("print");
+    assert!(run(&["print", MODEL, "-o", &path]).is_empty());
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap().trim(),
+        stdout.trim()
+    );
+}
+
+#[test]
+fn dot() {
+    let stdout = run(&["dot", MODEL]);
+    assert!(stdout.starts_with("digraph"));
+    assert!(stdout.contains("ReduceMean"));
+}
+
+#[test]
+fn check() {
+    assert_eq!(run(&["check", MODEL]).trim(), format!("{}: ok", MODEL));
+
+    let path = output_path("check_invalid");
+    std::fs::write(
+        &path,
+        "<ir_version: 6, opset_import: [\"\" : 11]>\ng (float[2] X) => (float[2] Y) {\n  Y = Neg (Z)\n}\n",
+    )
+    .unwrap();
+    assert!(!execute(&["check", &path]).status.success());
+}
+
+#[test]
+fn optimize() {
+    let path = output_path("optimize");
+    run(&["optimize", MODEL, "-o", &path]);
+    let graph = read(&path).graph.unwrap();
+    assert_eq!(graph.output[0].name, OUTPUT);
+
+    let path = output_path("optimize_fuse");
+    run(&["optimize", MODEL, "-o", &path, "--fuse", "--no-cse"]);
+    assert_eq!(read(&path).graph.unwrap().output[0].name, OUTPUT);
+}
+
+#[test]
+fn convert() {
+    let path = output_path("convert");
+    run(&["convert", MODEL, "-o", &path, "--opset", "13"]);
+    let model = read(&path);
+    assert_eq!(model.opset_import[0].version, 13);
+    assert_eq!(model.ir_version, 7);
+
+    let path = output_path("convert_unknown");
+    assert!(!execute(&["convert", MODEL, "-o", &path, "--opset", "99"])
+        .status
+        .success());
+}
+
+#[test]
+fn rename_io() {
+    let path = output_path("rename_io");
+    let rename = format!("{}=Y", OUTPUT);
+    run(&[
+        "rename-io",
+        MODEL,
+        "-o",
+        &path,
+        "-r",
+        "X=input",
+        "-r",
+        &rename,
+    ]);
+    let graph = read(&path).graph.unwrap();
+    assert_eq!(graph.input[0].name, "input");
+    assert_eq!(graph.output[0].name, "Y");
+}
+
+#[test]
+fn set_metadata() {
+    let path = output_path("set_metadata");
+    run(&[
+        "set-metadata",
+        MODEL,
+        "-o",
+        &path,
+        "--producer-name",
+        "tests",
+        "--model-version",
+        "3",
+        "--prop",
+        "license=MIT",
+    ]);
+    let model = read(&path);
+    assert_eq!(model.producer_name, "tests");
+    assert_eq!(model.model_version, 3);
+    assert_eq!(model.metadata_props[0].key, "license");
+    assert_eq!(model.metadata_props[0].value, "MIT");
+}
+
+#[test]
+fn extract() {
+    let path = output_path("extract");
+    run(&[
+        "extract",
+        MODEL,
+        "-o",
+        &path,
+        "--inputs",
+        "X",
+        "--outputs",
+        CENTERED,
+    ]);
+    let graph = read(&path).graph.unwrap();
+    let mut ops: Vec<&str> = graph
+        .node
+        .iter()
+        .map(|node| node.op_type.as_str())
+        .collect();
+    ops.sort_unstable();
+    assert_eq!(ops, vec!["ReduceMean", "Sub"]);
+    assert_eq!(graph.output[0].name, CENTERED);
+}
+
+#[test]
+fn stats() {
+    let stdout = run(&["stats", MODEL, "--nodes"]);
+    assert!(stdout.contains("flops: 30"));
+    assert!(stdout.contains("ReduceMean"));
+    run(&["stats", MODEL, "--max-flops", "30"]);
+    assert!(!execute(&["stats", MODEL, "--max-flops", "29"])
+        .status
+        .success());
+}